@@ -150,6 +150,13 @@ pub trait Application: Send + Clone + 'static {
     fn finalize_block(&self, _request: RequestFinalizeBlock) -> ResponseFinalizeBlock {
         Default::default()
     }
+
+    /// Called once the [`Server`] has been shut down and its connections have
+    /// been drained, so that the application can flush its committed state to
+    /// persistent storage.
+    ///
+    /// [`Server`]: crate::Server
+    fn shutdown(&self) {}
 }
 
 /// Provides a mechanism for the [`Server`] to execute incoming requests while
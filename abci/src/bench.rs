@@ -0,0 +1,317 @@
+//! Load generation for ABCI applications.
+//!
+//! [`run`] drives an [`Application`] in-process through a workload of
+//! `CheckTx` requests, followed by the execution of the accepted transactions
+//! in blocks of the configured size (`FinalizeBlock` and `Commit`), and
+//! reports the latencies of these requests along with the throughput of the
+//! application. This helps sizing the mempool and storage settings of a node
+//! before running the application on a real network.
+
+use std::{
+    fmt, thread,
+    time::{Duration, Instant},
+};
+
+use bytes::Bytes;
+use tendermint_proto::{
+    google::protobuf::Timestamp,
+    v0_38::abci::{CheckTxType, RequestCheckTx, RequestFinalizeBlock},
+};
+
+use crate::Application;
+
+/// The workload to drive an application through.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Workload {
+    /// The number of blocks to execute.
+    pub blocks: u64,
+    /// The number of transactions submitted to `CheckTx` for each block.
+    pub txs_per_block: usize,
+    /// The size of the generated transactions, in bytes.
+    pub tx_size: usize,
+    /// The rate at which transactions are submitted to `CheckTx`, in
+    /// transactions per second, or `None` to submit them as fast as the
+    /// application accepts them.
+    pub check_tx_rate: Option<u32>,
+    /// The time between the start of consecutive blocks, or `None` to
+    /// execute the blocks back to back.
+    pub block_interval: Option<Duration>,
+    /// The height of the first block.
+    pub initial_height: i64,
+}
+
+impl Default for Workload {
+    fn default() -> Self {
+        Self {
+            blocks: 100,
+            txs_per_block: 100,
+            tx_size: 256,
+            check_tx_rate: None,
+            block_interval: None,
+            initial_height: 1,
+        }
+    }
+}
+
+/// Statistics about the latencies of a type of request.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LatencyStats {
+    /// The number of requests.
+    pub count: usize,
+    /// The minimum latency.
+    pub min: Duration,
+    /// The mean latency.
+    pub mean: Duration,
+    /// The median latency.
+    pub p50: Duration,
+    /// The 90th percentile of the latencies.
+    pub p90: Duration,
+    /// The 99th percentile of the latencies.
+    pub p99: Duration,
+    /// The maximum latency.
+    pub max: Duration,
+}
+
+impl LatencyStats {
+    /// Compute the statistics of the given latencies.
+    pub fn from_samples(mut samples: Vec<Duration>) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+        samples.sort_unstable();
+        let count = samples.len();
+        let total: Duration = samples.iter().sum();
+        // Nearest-rank percentiles.
+        let percentile = |p: usize| samples[(count * p).div_ceil(100).max(1) - 1];
+        Self {
+            count,
+            min: samples[0],
+            mean: match u32::try_from(count) {
+                Ok(count) => total / count,
+                Err(_) => Duration::from_secs_f64(total.as_secs_f64() / count as f64),
+            },
+            p50: percentile(50),
+            p90: percentile(90),
+            p99: percentile(99),
+            max: samples[count - 1],
+        }
+    }
+}
+
+impl fmt::Display for LatencyStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "count={} min={:?} mean={:?} p50={:?} p90={:?} p99={:?} max={:?}",
+            self.count, self.min, self.mean, self.p50, self.p90, self.p99, self.max
+        )
+    }
+}
+
+/// The results of a benchmark.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Report {
+    /// The latencies of the `CheckTx` requests.
+    pub check_tx: LatencyStats,
+    /// The latencies of the `FinalizeBlock` requests.
+    pub finalize_block: LatencyStats,
+    /// The latencies of the `Commit` requests.
+    pub commit: LatencyStats,
+    /// The number of transactions submitted to `CheckTx`.
+    pub submitted_txs: u64,
+    /// The number of transactions rejected by `CheckTx`.
+    pub rejected_txs: u64,
+    /// The number of transactions executed in blocks.
+    pub executed_txs: u64,
+    /// The total duration of the benchmark.
+    pub elapsed: Duration,
+}
+
+impl Report {
+    /// The number of transactions executed per second.
+    pub fn throughput(&self) -> f64 {
+        let secs = self.elapsed.as_secs_f64();
+        if secs == 0.0 {
+            0.0
+        } else {
+            self.executed_txs as f64 / secs
+        }
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "CheckTx:       {}", self.check_tx)?;
+        writeln!(f, "FinalizeBlock: {}", self.finalize_block)?;
+        writeln!(f, "Commit:        {}", self.commit)?;
+        writeln!(
+            f,
+            "Transactions:  submitted={} rejected={} executed={}",
+            self.submitted_txs, self.rejected_txs, self.executed_txs
+        )?;
+        write!(
+            f,
+            "Throughput:    {:.1} tx/s over {:?}",
+            self.throughput(),
+            self.elapsed
+        )
+    }
+}
+
+/// Generate a `key=value` transaction of the given size, as understood by
+/// the key/value store application, with a key unique to the given index.
+pub fn key_value_tx(index: u64, size: usize) -> Vec<u8> {
+    let mut tx = format!("bench-{index}=").into_bytes();
+    // Cheap deterministic filler, varying across transactions.
+    let mut state = index.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1;
+    while tx.len() < size {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        tx.push(b'a' + (state % 26) as u8);
+    }
+    tx
+}
+
+/// Drive the application through the given workload, with transactions
+/// generated by [`key_value_tx`].
+pub fn run<A: Application>(app: &A, workload: &Workload) -> Report {
+    run_with(app, workload, |index| key_value_tx(index, workload.tx_size))
+}
+
+/// Drive the application through the given workload, with transactions
+/// generated by the given function from their index.
+pub fn run_with<A, G>(app: &A, workload: &Workload, mut generate_tx: G) -> Report
+where
+    A: Application,
+    G: FnMut(u64) -> Vec<u8>,
+{
+    let mut check_tx = Vec::new();
+    let mut finalize_block = Vec::new();
+    let mut commit = Vec::new();
+    let mut report = Report::default();
+
+    let check_tx_interval = workload
+        .check_tx_rate
+        .filter(|rate| *rate > 0)
+        .map(|rate| Duration::from_secs(1) / rate);
+    let start = Instant::now();
+    let mut next_tx = start;
+    let mut next_block = start;
+
+    for block in 0..workload.blocks {
+        let height = workload.initial_height + block as i64;
+        let mut txs = Vec::with_capacity(workload.txs_per_block);
+
+        for _ in 0..workload.txs_per_block {
+            if let Some(interval) = check_tx_interval {
+                sleep_until(next_tx);
+                next_tx += interval;
+            }
+            let tx: Bytes = generate_tx(report.submitted_txs).into();
+            report.submitted_txs += 1;
+
+            let request = RequestCheckTx {
+                tx: tx.clone(),
+                r#type: CheckTxType::New as i32,
+            };
+            let started = Instant::now();
+            let response = app.check_tx(request);
+            check_tx.push(started.elapsed());
+
+            if response.code == 0 {
+                txs.push(tx);
+            } else {
+                report.rejected_txs += 1;
+            }
+        }
+
+        if let Some(interval) = workload.block_interval {
+            sleep_until(next_block);
+            next_block += interval;
+        }
+        report.executed_txs += txs.len() as u64;
+        let elapsed = start.elapsed();
+        let request = RequestFinalizeBlock {
+            txs,
+            height,
+            time: Some(Timestamp {
+                seconds: elapsed.as_secs() as i64,
+                nanos: elapsed.subsec_nanos() as i32,
+            }),
+            ..Default::default()
+        };
+        let started = Instant::now();
+        app.finalize_block(request);
+        finalize_block.push(started.elapsed());
+
+        let started = Instant::now();
+        app.commit();
+        commit.push(started.elapsed());
+    }
+
+    report.elapsed = start.elapsed();
+    report.check_tx = LatencyStats::from_samples(check_tx);
+    report.finalize_block = LatencyStats::from_samples(finalize_block);
+    report.commit = LatencyStats::from_samples(commit);
+    report
+}
+
+fn sleep_until(deadline: Instant) {
+    let now = Instant::now();
+    if deadline > now {
+        thread::sleep(deadline - now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tendermint_proto::v0_38::abci::ResponseCheckTx;
+
+    use super::*;
+
+    #[derive(Clone)]
+    struct OddRejectingApp;
+
+    impl Application for OddRejectingApp {
+        fn check_tx(&self, request: RequestCheckTx) -> ResponseCheckTx {
+            let odd = request.tx.last().is_some_and(|b| b % 2 == 1);
+            ResponseCheckTx {
+                code: odd.into(),
+                ..Default::default()
+            }
+        }
+    }
+
+    #[test]
+    fn runs_workload() {
+        let workload = Workload {
+            blocks: 5,
+            txs_per_block: 10,
+            ..Default::default()
+        };
+        let report = run_with(&OddRejectingApp, &workload, |index| vec![index as u8]);
+        assert_eq!(report.submitted_txs, 50);
+        assert_eq!(report.rejected_txs, 25);
+        assert_eq!(report.executed_txs, 25);
+        assert_eq!(report.check_tx.count, 50);
+        assert_eq!(report.finalize_block.count, 5);
+        assert_eq!(report.commit.count, 5);
+    }
+
+    #[test]
+    fn computes_percentiles() {
+        let samples = (1..=100).rev().map(Duration::from_millis).collect();
+        let stats = LatencyStats::from_samples(samples);
+        assert_eq!(stats.count, 100);
+        assert_eq!(stats.min, Duration::from_millis(1));
+        assert_eq!(stats.p50, Duration::from_millis(50));
+        assert_eq!(stats.p90, Duration::from_millis(90));
+        assert_eq!(stats.p99, Duration::from_millis(99));
+        assert_eq!(stats.max, Duration::from_millis(100));
+        assert_eq!(stats.mean, Duration::from_micros(50_500));
+
+        assert_eq!(key_value_tx(3, 32).len(), 32);
+        assert!(key_value_tx(3, 32).starts_with(b"bench-3="));
+    }
+}
@@ -0,0 +1,62 @@
+//! Benchmark of the in-memory key/value store application for Tendermint.
+
+use std::time::Duration;
+
+use structopt::StructOpt;
+use tendermint_abci::{
+    bench::{self, Workload},
+    KeyValueStoreApp,
+};
+use tracing_subscriber::filter::LevelFilter;
+
+#[derive(Debug, StructOpt)]
+struct Opt {
+    /// The number of blocks to execute.
+    #[structopt(short, long, default_value = "100")]
+    blocks: u64,
+
+    /// The number of transactions in each block.
+    #[structopt(short, long, default_value = "100")]
+    txs_per_block: usize,
+
+    /// The size of the transactions, in bytes.
+    #[structopt(short = "s", long, default_value = "256")]
+    tx_size: usize,
+
+    /// The rate at which transactions are submitted to CheckTx, in
+    /// transactions per second (unlimited by default).
+    #[structopt(short = "r", long)]
+    check_tx_rate: Option<u32>,
+
+    /// The time between consecutive blocks, in milliseconds (back to back by
+    /// default).
+    #[structopt(short = "i", long)]
+    block_interval_ms: Option<u64>,
+
+    /// Increase output logging verbosity to DEBUG level.
+    #[structopt(short, long)]
+    verbose: bool,
+}
+
+fn main() {
+    let opt: Opt = Opt::from_args();
+    let log_level = if opt.verbose {
+        LevelFilter::DEBUG
+    } else {
+        LevelFilter::WARN
+    };
+    tracing_subscriber::fmt().with_max_level(log_level).init();
+
+    let (app, driver) = KeyValueStoreApp::new();
+    std::thread::spawn(move || driver.run());
+
+    let workload = Workload {
+        blocks: opt.blocks,
+        txs_per_block: opt.txs_per_block,
+        tx_size: opt.tx_size,
+        check_tx_rate: opt.check_tx_rate,
+        block_interval: opt.block_interval_ms.map(Duration::from_millis),
+        ..Default::default()
+    };
+    println!("{}", bench::run(&app, &workload));
+}
@@ -39,6 +39,38 @@ define_error! {
         ChannelRecv
             [ DisplayError<std::sync::mpsc::RecvError> ]
             | _ | { "channel recv error" },
+
+        UnexpectedInitChain
+            { last_height: i64 }
+            | e | {
+                format_args!("unexpected InitChain request: the application already committed height {}",
+                    e.last_height)
+            },
+
+        UnexpectedRequest
+            { request: String }
+            | e | { format_args!("unexpected {} request in the handshake sequence", e.request) },
+
+        UnexpectedBlockHeight
+            {
+                expected: i64,
+                got: i64,
+            }
+            | e | {
+                format_args!("unexpected block height: expected {0}, but got {1}",
+                    e.expected, e.got)
+            },
+
+        InvalidSnapshotMetadata
+            { detail: String }
+            | e | { format_args!("invalid snapshot metadata: {}", e.detail) },
+
+        InvalidChunk
+            {
+                index: u32,
+                detail: String,
+            }
+            | e | { format_args!("invalid snapshot chunk {}: {}", e.index, e.detail) },
     }
 }
 
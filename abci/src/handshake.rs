@@ -0,0 +1,215 @@
+//! Validation of the handshake through which CometBFT resynchronizes with
+//! an application when it starts.
+//!
+//! On startup, CometBFT queries the last block committed by the application
+//! with an `Info` request. If the application has not committed any block
+//! yet, it is initialized with `InitChain`, unless it restores its state from
+//! a snapshot through state sync instead. Then, the blocks CometBFT has
+//! stored above the last height committed by the application, if any, are
+//! replayed to it with `FinalizeBlock` and `Commit` requests, after which
+//! regular consensus resumes with the same sequence of requests.
+//!
+//! An application recovering from a crash can track this sequence with a
+//! [`HandshakeState`], which rejects requests that diverge from it, e.g. a
+//! replay starting below or above the height the application reported.
+
+use bytes::Bytes;
+use tendermint_proto::v0_38::abci::{
+    RequestFinalizeBlock, RequestInfo, RequestInitChain, ResponseFinalizeBlock, ResponseInfo,
+};
+use tracing::debug;
+
+use crate::Error;
+
+/// The requests an application expects after answering an `Info` request.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Expectation {
+    /// The application has not committed any block yet, and expects to be
+    /// initialized with `InitChain`, or restored from a snapshot.
+    InitChain,
+    /// The application expects the block at the given height to be
+    /// finalized next, whether it is replayed by CometBFT or decided anew.
+    FinalizeBlock { height: i64 },
+    /// The application finalized the block at the given height, and expects
+    /// it to be committed next.
+    Commit { height: i64 },
+}
+
+/// The next request expected in the handshake sequence.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Next {
+    /// No `Info` request was received yet.
+    Info,
+    /// The application expects to be initialized with `InitChain`.
+    InitChain,
+    /// The application expects the block at the given height to be
+    /// finalized.
+    FinalizeBlock { height: i64 },
+    /// The block at the given height was finalized, and the application
+    /// expects it to be committed.
+    Commit { height: i64, app_hash: Bytes },
+}
+
+/// Tracks the last block committed by an application, and validates the
+/// sequence of requests through which CometBFT replays blocks to it.
+///
+/// ## Examples
+///
+/// ```rust,ignore
+/// let mut handshake = HandshakeState::new(store.last_height(), store.last_app_hash());
+///
+/// fn info(&self, request: RequestInfo) -> ResponseInfo {
+///     let mut handshake = self.handshake.lock().unwrap();
+///     match handshake.on_info(&request) {
+///         Expectation::InitChain => info!("starting from genesis"),
+///         Expectation::FinalizeBlock { height } => info!("resuming at height {height}"),
+///         Expectation::Commit { height } => info!("committing height {height}"),
+///     }
+///     handshake.info_response(ResponseInfo { data: "my-app".into(), ..Default::default() })
+/// }
+///
+/// fn finalize_block(&self, request: RequestFinalizeBlock) -> ResponseFinalizeBlock {
+///     let response = self.execute(&request);
+///     self.handshake.lock().unwrap().on_finalize_block(&request, &response).unwrap();
+///     response
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HandshakeState {
+    last_height: i64,
+    last_app_hash: Bytes,
+    next: Next,
+}
+
+impl HandshakeState {
+    /// Start tracking the handshake of an application which last committed
+    /// the block at the given height, resulting in the given app hash.
+    ///
+    /// An application which has not committed any block yet has a height
+    /// of 0.
+    pub fn new(last_height: i64, last_app_hash: impl Into<Bytes>) -> Self {
+        Self {
+            last_height,
+            last_app_hash: last_app_hash.into(),
+            next: Next::Info,
+        }
+    }
+
+    /// The height of the last block committed by the application.
+    pub fn last_height(&self) -> i64 {
+        self.last_height
+    }
+
+    /// The app hash resulting from the last block committed by the
+    /// application.
+    pub fn last_app_hash(&self) -> &Bytes {
+        &self.last_app_hash
+    }
+
+    /// Handle an `Info` request, returning the requests which the
+    /// application expects next.
+    ///
+    /// CometBFT may query the application again at any time, on the same or
+    /// on another connection, which does not restart the handshake.
+    pub fn on_info(&mut self, request: &RequestInfo) -> Expectation {
+        debug!(
+            "Handshake with CometBFT {} (ABCI {}), at height {}",
+            request.version, request.abci_version, self.last_height
+        );
+        if self.next == Next::Info {
+            self.next = if self.last_height == 0 {
+                Next::InitChain
+            } else {
+                Next::FinalizeBlock {
+                    height: self.last_height + 1,
+                }
+            };
+        }
+        match self.next {
+            Next::InitChain => Expectation::InitChain,
+            Next::FinalizeBlock { height } => Expectation::FinalizeBlock { height },
+            Next::Commit { height, .. } => Expectation::Commit { height },
+            Next::Info => unreachable!("the next request is set above"),
+        }
+    }
+
+    /// Fill in the last committed block of the application in the given
+    /// response to an `Info` request.
+    pub fn info_response(&self, response: ResponseInfo) -> ResponseInfo {
+        ResponseInfo {
+            last_block_height: self.last_height,
+            last_block_app_hash: self.last_app_hash.clone(),
+            ..response
+        }
+    }
+
+    /// Handle an `InitChain` request, which is only expected before the
+    /// application commits its first block.
+    pub fn on_init_chain(&mut self, request: &RequestInitChain) -> Result<(), Error> {
+        if self.next != Next::InitChain {
+            return Err(Error::unexpected_init_chain(self.last_height));
+        }
+        // CometBFT starts chains at height 1 unless configured otherwise.
+        let height = request.initial_height.max(1);
+        self.next = Next::FinalizeBlock { height };
+        Ok(())
+    }
+
+    /// Record the restoration of the state of the application at the given
+    /// height from a snapshot, resulting in the given app hash.
+    ///
+    /// State sync replaces the initialization of an application which has not
+    /// committed any block yet, so after the last `ApplySnapshotChunk`
+    /// request, the block following the snapshot is expected to be finalized.
+    pub fn on_snapshot_restored(
+        &mut self,
+        height: i64,
+        app_hash: impl Into<Bytes>,
+    ) -> Result<(), Error> {
+        if self.next != Next::InitChain {
+            return Err(Error::unexpected_request("ApplySnapshotChunk".to_string()));
+        }
+        self.last_height = height;
+        self.last_app_hash = app_hash.into();
+        self.next = Next::FinalizeBlock { height: height + 1 };
+        Ok(())
+    }
+
+    /// Handle a `FinalizeBlock` request, with the response the application
+    /// produced for it, which must be for the block following the last
+    /// committed one.
+    pub fn on_finalize_block(
+        &mut self,
+        request: &RequestFinalizeBlock,
+        response: &ResponseFinalizeBlock,
+    ) -> Result<(), Error> {
+        let expected = match self.next {
+            Next::FinalizeBlock { height } => height,
+            Next::Info | Next::InitChain | Next::Commit { .. } => {
+                return Err(Error::unexpected_request("FinalizeBlock".to_string()))
+            },
+        };
+        if request.height != expected {
+            return Err(Error::unexpected_block_height(expected, request.height));
+        }
+        self.next = Next::Commit {
+            height: request.height,
+            app_hash: response.app_hash.clone(),
+        };
+        Ok(())
+    }
+
+    /// Handle a `Commit` request, which must follow the finalization of a
+    /// block.
+    pub fn on_commit(&mut self) -> Result<(), Error> {
+        let Next::Commit { height, app_hash } = &self.next else {
+            return Err(Error::unexpected_request("Commit".to_string()));
+        };
+        self.last_height = *height;
+        self.last_app_hash = app_hash.clone();
+        self.next = Next::FinalizeBlock {
+            height: self.last_height + 1,
+        };
+        Ok(())
+    }
+}
@@ -3,11 +3,16 @@
 //! [Tendermint]: https://tendermint.com
 
 mod application;
+#[cfg(feature = "bench")]
+pub mod bench;
 #[cfg(feature = "client")]
 mod client;
 mod codec;
 pub mod error;
+pub mod handshake;
 mod server;
+#[cfg(feature = "snapshot")]
+pub mod snapshot;
 
 // Common exports
 // Example applications
@@ -19,4 +24,5 @@ pub use application::Application;
 #[cfg(feature = "client")]
 pub use client::{Client, ClientBuilder};
 pub use error::Error;
-pub use server::{Server, ServerBuilder};
+pub use handshake::HandshakeState;
+pub use server::{Server, ServerBuilder, ShutdownHandle};
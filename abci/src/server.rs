@@ -1,11 +1,17 @@
 //! ABCI application server interface.
 
 use std::{
-    net::{TcpListener, TcpStream, ToSocketAddrs},
-    thread,
+    io,
+    net::{Ipv4Addr, Ipv6Addr, Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
 };
 
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 use crate::{application::RequestDispatcher, codec::ServerCodec, error::Error, Application};
 
@@ -13,9 +19,14 @@ use crate::{application::RequestDispatcher, codec::ServerCodec, error::Error, Ap
 /// server (1MB).
 pub const DEFAULT_SERVER_READ_BUF_SIZE: usize = 1024 * 1024;
 
+/// The time given to the connections to the ABCI server to finish processing
+/// their in-flight requests once it has been shut down (10 seconds).
+pub const DEFAULT_SERVER_DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
+
 /// Allows us to configure and construct an ABCI server.
 pub struct ServerBuilder {
     read_buf_size: usize,
+    drain_timeout: Duration,
 }
 
 impl ServerBuilder {
@@ -25,7 +36,19 @@ impl ServerBuilder {
     /// incoming data from the client. This needs to be tuned for your
     /// application.
     pub fn new(read_buf_size: usize) -> Self {
-        Self { read_buf_size }
+        Self {
+            read_buf_size,
+            drain_timeout: DEFAULT_SERVER_DRAIN_TIMEOUT,
+        }
+    }
+
+    /// Set the time given to the connections to finish processing their
+    /// in-flight requests once the server has been shut down through its
+    /// [`ShutdownHandle`]. The threads of the connections which do not
+    /// finish in time are detached (see [`Server::listen`]).
+    pub fn drain_timeout(mut self, drain_timeout: Duration) -> Self {
+        self.drain_timeout = drain_timeout;
+        self
     }
 
     /// Constructor for an ABCI server.
@@ -39,22 +62,23 @@ impl ServerBuilder {
         App: Application,
     {
         let listener = TcpListener::bind(addr).map_err(Error::io)?;
-        let local_addr = listener.local_addr().map_err(Error::io)?.to_string();
+        let socket_addr = listener.local_addr().map_err(Error::io)?;
+        let local_addr = socket_addr.to_string();
         info!("ABCI server running at {}", local_addr);
         Ok(Server {
             app,
             listener,
             local_addr,
             read_buf_size: self.read_buf_size,
+            drain_timeout: self.drain_timeout,
+            shutdown: Arc::new(ShutdownState::new(socket_addr)),
         })
     }
 }
 
 impl Default for ServerBuilder {
     fn default() -> Self {
-        Self {
-            read_buf_size: DEFAULT_SERVER_READ_BUF_SIZE,
-        }
+        Self::new(DEFAULT_SERVER_READ_BUF_SIZE)
     }
 }
 
@@ -64,22 +88,54 @@ impl Default for ServerBuilder {
 /// application is cloned for access in each thread. It is up to the
 /// application developer to manage shared state across these different
 /// threads.
+///
+/// The server runs until it is shut down through a [`ShutdownHandle`],
+/// obtained with [`Server::shutdown_handle`].
 pub struct Server<App> {
     app: App,
     listener: TcpListener,
     local_addr: String,
     read_buf_size: usize,
+    drain_timeout: Duration,
+    shutdown: Arc<ShutdownState>,
 }
 
 impl<App: Application> Server<App> {
     /// Initiate a blocking listener for incoming connections.
+    ///
+    /// Once the server is shut down, it stops accepting connections and
+    /// gives the open connections until the drain timeout to finish
+    /// processing their in-flight requests, closing those which do not
+    /// finish in time. The [`Application::shutdown`] method of the
+    /// application is then called, before returning.
+    ///
+    /// The threads of the connections closed at the deadline are detached,
+    /// rather than joined: a request still being processed by the
+    /// application keeps its thread running, possibly concurrently with
+    /// [`Application::shutdown`] and past the return of this method, until
+    /// the application returns its response.
     pub fn listen(self) -> Result<(), Error> {
+        let mut connections = Vec::new();
         loop {
             let (stream, addr) = self.listener.accept().map_err(Error::io)?;
+            if self.shutdown.is_requested() {
+                break;
+            }
+            connections.retain(|connection: &Connection| !connection.handle.is_finished());
             let addr = addr.to_string();
             info!("Incoming connection from: {}", addr);
-            self.spawn_client_handler(stream, addr);
+            match self.spawn_client_handler(stream, addr) {
+                Ok(connection) => connections.push(connection),
+                Err(e) => error!("Failed to handle incoming connection: {:?}", e),
+            }
         }
+
+        info!("ABCI server shutting down");
+        drop(self.listener);
+        Self::drain(connections, self.drain_timeout);
+        self.app.shutdown();
+        info!("ABCI server shut down");
+        Ok(())
     }
 
     /// Getter for this server's local address.
@@ -87,10 +143,59 @@ impl<App: Application> Server<App> {
         self.local_addr.clone()
     }
 
-    fn spawn_client_handler(&self, stream: TcpStream, addr: String) {
+    /// A handle to shut down this server once it is listening.
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        ShutdownHandle {
+            state: self.shutdown.clone(),
+        }
+    }
+
+    fn spawn_client_handler(&self, stream: TcpStream, addr: String) -> io::Result<Connection> {
         let app = self.app.clone();
         let read_buf_size = self.read_buf_size;
-        let _ = thread::spawn(move || Self::handle_client(stream, addr, app, read_buf_size));
+        let shutdown_stream = stream.try_clone()?;
+        let handle = thread::spawn(move || Self::handle_client(stream, addr, app, read_buf_size));
+        Ok(Connection {
+            stream: shutdown_stream,
+            handle,
+        })
+    }
+
+    // Stops reading from the connections, so that they terminate once their
+    // in-flight requests have been processed, and waits for them to do so
+    // until the deadline. The connections left are closed, and their threads
+    // detached by dropping their handles, as a thread blocked in the
+    // application cannot be interrupted.
+    fn drain(connections: Vec<Connection>, timeout: Duration) {
+        let connections: Vec<_> = connections
+            .into_iter()
+            .filter(|connection| !connection.handle.is_finished())
+            .collect();
+        if connections.is_empty() {
+            return;
+        }
+        info!("Draining {} connections", connections.len());
+        for connection in &connections {
+            let _ = connection.stream.shutdown(Shutdown::Read);
+        }
+
+        let deadline = Instant::now() + timeout;
+        while Instant::now() < deadline
+            && connections
+                .iter()
+                .any(|connection| !connection.handle.is_finished())
+        {
+            thread::sleep(DRAIN_POLL_INTERVAL);
+        }
+
+        for connection in connections {
+            if connection.handle.is_finished() {
+                let _ = connection.handle.join();
+            } else {
+                warn!("Connection did not drain before the deadline, closing it and detaching its thread");
+                let _ = connection.stream.shutdown(Shutdown::Both);
+            }
+        }
     }
 
     fn handle_client(stream: TcpStream, addr: String, app: App, read_buf_size: usize) {
@@ -121,3 +226,64 @@ impl<App: Application> Server<App> {
         }
     }
 }
+
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// An open connection to the server, along with the thread handling it.
+struct Connection {
+    stream: TcpStream,
+    handle: JoinHandle<()>,
+}
+
+#[derive(Debug)]
+struct ShutdownState {
+    requested: AtomicBool,
+    wake_addr: SocketAddr,
+}
+
+impl ShutdownState {
+    fn new(local_addr: SocketAddr) -> Self {
+        // Connections to unspecified addresses are not portable.
+        let mut wake_addr = local_addr;
+        if wake_addr.ip().is_unspecified() {
+            wake_addr.set_ip(match wake_addr {
+                SocketAddr::V4(_) => Ipv4Addr::LOCALHOST.into(),
+                SocketAddr::V6(_) => Ipv6Addr::LOCALHOST.into(),
+            });
+        }
+        Self {
+            requested: AtomicBool::new(false),
+            wake_addr,
+        }
+    }
+
+    fn is_requested(&self) -> bool {
+        self.requested.load(Ordering::SeqCst)
+    }
+}
+
+/// A handle to shut down a [`Server`].
+#[derive(Clone, Debug)]
+pub struct ShutdownHandle {
+    state: Arc<ShutdownState>,
+}
+
+impl ShutdownHandle {
+    /// Shut down the server: it stops accepting new connections, and
+    /// [`Server::listen`] returns once the open connections have been
+    /// drained.
+    pub fn shutdown(&self) {
+        if self.state.requested.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        // Wake up the listener, which is blocked accepting connections.
+        if let Err(e) = TcpStream::connect(self.state.wake_addr) {
+            error!("Failed to wake up the ABCI server to shut it down: {:?}", e);
+        }
+    }
+
+    /// Whether the server has been shut down.
+    pub fn is_shutdown(&self) -> bool {
+        self.state.is_requested()
+    }
+}
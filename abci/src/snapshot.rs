@@ -0,0 +1,252 @@
+//! Fetching of the chunks of state sync snapshots.
+//!
+//! During state sync, CometBFT discovers the snapshots of its peers with
+//! `ListSnapshots`, offers one of them to the application with
+//! `OfferSnapshot`, then fetches its chunks from the peers, which load them
+//! from their application with `LoadSnapshotChunk`, and applies them with
+//! `ApplySnapshotChunk`.
+//!
+//! CometBFT does not interpret the chunks, so a provider of snapshots must
+//! verify them itself. A [`SnapshotFetcher`] loads the chunks of a snapshot
+//! from a [`ChunkSource`], e.g. an ABCI [`Client`], and checks them against
+//! the SHA-256 chunk hashes of the metadata of the snapshot, in the
+//! [`SnapshotMetadata`] format of the Cosmos SDK, and the reassembled content
+//! of the snapshot against its hash.
+//!
+//! The snapshot, along with its hash and metadata, is advertised by the peer
+//! serving it, so this verification only protects against chunks corrupted
+//! in transport or served by peers disagreeing on the snapshot. A malicious
+//! peer can advertise a snapshot of a bogus state whose hashes are
+//! consistent, which only the application can detect, by checking the state
+//! it restored against the app hash of the height of the snapshot, trusted
+//! e.g. through the light client.
+//!
+//! [`Client`]: crate::Client
+
+use bytes::{Buf, BufMut, Bytes};
+use prost::{
+    encoding::{self, DecodeContext, WireType},
+    DecodeError, Message,
+};
+use sha2::{Digest, Sha256};
+use tendermint_proto::v0_38::abci::{RequestApplySnapshotChunk, RequestOfferSnapshot, Snapshot};
+
+use crate::Error;
+
+/// The metadata of a snapshot, holding the SHA-256 hashes of its chunks,
+/// in order.
+///
+/// This is the format of the metadata of the snapshots of the Cosmos SDK, a
+/// Protobuf message with a repeated `bytes chunk_hashes = 1` field.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SnapshotMetadata {
+    pub chunk_hashes: Vec<Bytes>,
+}
+
+impl Message for SnapshotMetadata {
+    fn encode_raw<B: BufMut>(&self, buf: &mut B) {
+        encoding::bytes::encode_repeated(1, &self.chunk_hashes, buf);
+    }
+
+    fn merge_field<B: Buf>(
+        &mut self,
+        tag: u32,
+        wire_type: WireType,
+        buf: &mut B,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError> {
+        match tag {
+            1 => encoding::bytes::merge_repeated(wire_type, &mut self.chunk_hashes, buf, ctx),
+            _ => encoding::skip_field(wire_type, tag, buf, ctx),
+        }
+    }
+
+    fn encoded_len(&self) -> usize {
+        encoding::bytes::encoded_len_repeated(1, &self.chunk_hashes)
+    }
+
+    fn clear(&mut self) {
+        self.chunk_hashes.clear();
+    }
+}
+
+/// A verified chunk of a snapshot.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Chunk {
+    /// The index of the chunk.
+    pub index: u32,
+    /// The content of the chunk.
+    pub data: Bytes,
+}
+
+impl Chunk {
+    /// The request applying the chunk, received from the given peer, to the
+    /// application.
+    pub fn apply_request(&self, sender: String) -> RequestApplySnapshotChunk {
+        RequestApplySnapshotChunk {
+            index: self.index,
+            chunk: self.data.clone(),
+            sender,
+        }
+    }
+}
+
+/// A source of the chunks of snapshots.
+pub trait ChunkSource {
+    /// Load the chunk with the given index of the snapshot taken at the
+    /// given height in the given format, returning an empty chunk if it is
+    /// not available.
+    fn load_chunk(&mut self, height: u64, format: u32, index: u32) -> Result<Bytes, Error>;
+}
+
+#[cfg(feature = "client")]
+impl ChunkSource for crate::Client {
+    fn load_chunk(&mut self, height: u64, format: u32, index: u32) -> Result<Bytes, Error> {
+        let request = tendermint_proto::v0_38::abci::RequestLoadSnapshotChunk {
+            height,
+            format,
+            chunk: index,
+        };
+        Ok(self.load_snapshot_chunk(request)?.chunk)
+    }
+}
+
+/// Loads the chunks of a snapshot, verifying them against their hashes.
+///
+/// The fetcher is an iterator over the chunks of the snapshot, in order. A
+/// chunk which cannot be loaded or verified is reported as an error, and is
+/// loaded again at the next iteration, e.g. after switching to another
+/// source with [`SnapshotFetcher::source_mut`].
+///
+/// ## Examples
+///
+/// ```rust,ignore
+/// use tendermint_abci::{snapshot::SnapshotFetcher, ClientBuilder};
+///
+/// let mut client = ClientBuilder::default().connect("127.0.0.1:26658")?;
+/// let snapshot = client.list_snapshots()?.snapshots.remove(0);
+/// let fetcher = SnapshotFetcher::new(client, snapshot)?;
+/// let chunks = fetcher.collect::<Result<Vec<_>, _>>()?;
+/// ```
+#[derive(Debug)]
+pub struct SnapshotFetcher<S> {
+    source: S,
+    snapshot: Snapshot,
+    chunk_hashes: Vec<Bytes>,
+    /// The hash of the content of the chunks fetched so far, if it is checked
+    /// against the hash of the snapshot.
+    content: Option<Sha256>,
+    next: u32,
+}
+
+impl<S: ChunkSource> SnapshotFetcher<S> {
+    /// Fetch the chunks of the given snapshot from the given source,
+    /// verifying them against the hashes of its [`SnapshotMetadata`].
+    ///
+    /// As in the Cosmos SDK, the hash of the snapshot is expected to be the
+    /// SHA-256 hash of the content of all its chunks, in order, which is
+    /// checked when iterating over the last chunk.
+    pub fn new(source: S, snapshot: Snapshot) -> Result<Self, Error> {
+        let metadata = SnapshotMetadata::decode(snapshot.metadata.clone())
+            .map_err(|e| Error::invalid_snapshot_metadata(e.to_string()))?;
+        let mut fetcher = Self::with_chunk_hashes(source, snapshot, metadata.chunk_hashes)?;
+        fetcher.content = Some(Sha256::new());
+        Ok(fetcher)
+    }
+
+    /// Fetch the chunks of the given snapshot from the given source,
+    /// verifying them against the given SHA-256 hashes, for snapshots whose
+    /// metadata is in another format.
+    ///
+    /// The hash of the snapshot is not checked, as its format is then
+    /// unknown.
+    pub fn with_chunk_hashes(
+        source: S,
+        snapshot: Snapshot,
+        chunk_hashes: Vec<Bytes>,
+    ) -> Result<Self, Error> {
+        if chunk_hashes.len() != snapshot.chunks as usize {
+            return Err(Error::invalid_snapshot_metadata(format!(
+                "{} chunk hashes for a snapshot of {} chunks",
+                chunk_hashes.len(),
+                snapshot.chunks
+            )));
+        }
+        Ok(Self {
+            source,
+            snapshot,
+            chunk_hashes,
+            content: None,
+            next: 0,
+        })
+    }
+
+    /// The snapshot whose chunks are fetched.
+    pub fn snapshot(&self) -> &Snapshot {
+        &self.snapshot
+    }
+
+    /// The source of the chunks.
+    pub fn source_mut(&mut self) -> &mut S {
+        &mut self.source
+    }
+
+    /// The request offering the snapshot to the application, for the given
+    /// trusted app hash of the height of the snapshot.
+    pub fn offer_request(&self, app_hash: Bytes) -> RequestOfferSnapshot {
+        RequestOfferSnapshot {
+            snapshot: Some(self.snapshot.clone()),
+            app_hash,
+        }
+    }
+
+    /// Load the chunk with the given index, and verify it against its hash.
+    pub fn fetch_chunk(&mut self, index: u32) -> Result<Chunk, Error> {
+        let expected = self
+            .chunk_hashes
+            .get(index as usize)
+            .ok_or_else(|| Error::invalid_chunk(index, "no such chunk".to_string()))?;
+        let data = self
+            .source
+            .load_chunk(self.snapshot.height, self.snapshot.format, index)?;
+        if data.is_empty() {
+            return Err(Error::invalid_chunk(
+                index,
+                "chunk not available".to_string(),
+            ));
+        }
+        if Sha256::digest(&data).as_slice() != expected.as_ref() {
+            return Err(Error::invalid_chunk(index, "hash mismatch".to_string()));
+        }
+        Ok(Chunk { index, data })
+    }
+}
+
+impl<S: ChunkSource> Iterator for SnapshotFetcher<S> {
+    type Item = Result<Chunk, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next >= self.snapshot.chunks {
+            return None;
+        }
+        let chunk = self.fetch_chunk(self.next).and_then(|chunk| {
+            if let Some(content) = &mut self.content {
+                let mut updated = content.clone();
+                updated.update(&chunk.data);
+                if self.next + 1 == self.snapshot.chunks
+                    && updated.clone().finalize().as_slice() != self.snapshot.hash.as_ref()
+                {
+                    return Err(Error::invalid_snapshot_metadata(
+                        "the chunks do not match the hash of the snapshot".to_string(),
+                    ));
+                }
+                *content = updated;
+            }
+            Ok(chunk)
+        });
+        if chunk.is_ok() {
+            self.next += 1;
+        }
+        Some(chunk)
+    }
+}
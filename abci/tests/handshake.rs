@@ -0,0 +1,122 @@
+//! Tests of the validation of the handshake sequence.
+
+use tendermint_abci::handshake::{Expectation, HandshakeState};
+use tendermint_proto::v0_38::abci::{
+    RequestFinalizeBlock, RequestInfo, RequestInitChain, ResponseFinalizeBlock, ResponseInfo,
+};
+
+fn finalize(state: &mut HandshakeState, height: i64, app_hash: &'static [u8]) {
+    let request = RequestFinalizeBlock {
+        height,
+        ..Default::default()
+    };
+    let response = ResponseFinalizeBlock {
+        app_hash: app_hash.into(),
+        ..Default::default()
+    };
+    state.on_finalize_block(&request, &response).unwrap();
+}
+
+#[test]
+fn initializes_chain_at_genesis() {
+    let mut state = HandshakeState::new(0, Vec::new());
+    assert_eq!(
+        state.on_info(&RequestInfo::default()),
+        Expectation::InitChain
+    );
+    // A block cannot be finalized before the chain is initialized.
+    assert!(state
+        .on_finalize_block(&Default::default(), &Default::default())
+        .is_err());
+
+    state
+        .on_init_chain(&RequestInitChain {
+            initial_height: 100,
+            ..Default::default()
+        })
+        .unwrap();
+    assert_eq!(
+        state.on_info(&RequestInfo::default()),
+        Expectation::FinalizeBlock { height: 100 }
+    );
+    finalize(&mut state, 100, b"hash100");
+    state.on_commit().unwrap();
+    assert_eq!(state.last_height(), 100);
+    assert_eq!(state.last_app_hash().as_ref(), b"hash100");
+}
+
+#[test]
+fn replays_blocks_above_last_committed_height() {
+    let mut state = HandshakeState::new(7, b"hash7".to_vec());
+    assert_eq!(
+        state.on_info(&RequestInfo::default()),
+        Expectation::FinalizeBlock { height: 8 }
+    );
+    let response = state.info_response(ResponseInfo {
+        data: "app".to_string(),
+        ..Default::default()
+    });
+    assert_eq!(response.data, "app");
+    assert_eq!(response.last_block_height, 7);
+    assert_eq!(response.last_block_app_hash.as_ref(), b"hash7");
+
+    // The chain was already initialized.
+    assert!(state.on_init_chain(&RequestInitChain::default()).is_err());
+    // The replay must start right above the last committed height.
+    for height in [7, 9] {
+        let request = RequestFinalizeBlock {
+            height,
+            ..Default::default()
+        };
+        let err = state
+            .on_finalize_block(&request, &Default::default())
+            .unwrap_err();
+        assert!(err.to_string().contains("expected 8"));
+    }
+
+    // Each finalized block must be committed before the next one.
+    assert!(state.on_commit().is_err());
+    finalize(&mut state, 8, b"hash8");
+    assert_eq!(
+        state.on_info(&RequestInfo::default()),
+        Expectation::Commit { height: 8 }
+    );
+    let request = RequestFinalizeBlock {
+        height: 9,
+        ..Default::default()
+    };
+    assert!(state
+        .on_finalize_block(&request, &Default::default())
+        .is_err());
+    state.on_commit().unwrap();
+    finalize(&mut state, 9, b"hash9");
+    state.on_commit().unwrap();
+    assert_eq!(state.last_height(), 9);
+    assert_eq!(state.last_app_hash().as_ref(), b"hash9");
+}
+
+#[test]
+fn resumes_after_state_sync() {
+    let mut state = HandshakeState::new(0, Vec::new());
+    // The state can only be restored from a snapshot as part of the handshake.
+    assert!(state.on_snapshot_restored(50, b"hash50".to_vec()).is_err());
+    assert_eq!(
+        state.on_info(&RequestInfo::default()),
+        Expectation::InitChain
+    );
+
+    state.on_snapshot_restored(50, b"hash50".to_vec()).unwrap();
+    assert_eq!(state.last_height(), 50);
+    assert_eq!(state.last_app_hash().as_ref(), b"hash50");
+    assert_eq!(
+        state.on_info(&RequestInfo::default()),
+        Expectation::FinalizeBlock { height: 51 }
+    );
+    // The chain is not initialized once the state is restored.
+    assert!(state.on_init_chain(&RequestInitChain::default()).is_err());
+    assert!(state.on_snapshot_restored(60, b"hash60".to_vec()).is_err());
+
+    finalize(&mut state, 51, b"hash51");
+    state.on_commit().unwrap();
+    assert_eq!(state.last_height(), 51);
+}
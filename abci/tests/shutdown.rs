@@ -0,0 +1,83 @@
+//! Integration tests for the graceful shutdown of the ABCI server.
+
+#[cfg(feature = "client")]
+mod shutdown_integration {
+    use std::{
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            mpsc, Arc, Mutex,
+        },
+        time::Duration,
+    };
+
+    use tendermint_abci::{Application, ClientBuilder, ServerBuilder};
+    use tendermint_proto::v0_38::abci::{RequestEcho, ResponseEcho};
+
+    /// An application signaling when it receives an echo request, and only
+    /// answering it once released.
+    #[derive(Clone)]
+    struct SlowApp {
+        started: Arc<Mutex<mpsc::Sender<()>>>,
+        release: Arc<Mutex<mpsc::Receiver<()>>>,
+        flushed: Arc<AtomicBool>,
+    }
+
+    impl Application for SlowApp {
+        fn echo(&self, request: RequestEcho) -> ResponseEcho {
+            self.started.lock().unwrap().send(()).unwrap();
+            self.release.lock().unwrap().recv().unwrap();
+            ResponseEcho {
+                message: request.message,
+            }
+        }
+
+        fn shutdown(&self) {
+            self.flushed.store(true, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn drains_in_flight_requests() {
+        let (started_tx, started) = mpsc::channel();
+        let (release, release_rx) = mpsc::channel();
+        let app = SlowApp {
+            started: Arc::new(Mutex::new(started_tx)),
+            release: Arc::new(Mutex::new(release_rx)),
+            flushed: Arc::default(),
+        };
+        let server = ServerBuilder::default()
+            .drain_timeout(Duration::from_secs(30))
+            .bind("127.0.0.1:0", app.clone())
+            .unwrap();
+        let server_addr = server.local_addr();
+        let shutdown = server.shutdown_handle();
+        let server = std::thread::spawn(move || server.listen());
+
+        let mut client = ClientBuilder::default().connect(&server_addr).unwrap();
+        let request = std::thread::spawn(move || {
+            let response = client.echo(RequestEcho {
+                message: "in flight".to_string(),
+            });
+            (client, response)
+        });
+
+        // Shut down while the request is being processed.
+        started.recv().unwrap();
+        shutdown.shutdown();
+        assert!(shutdown.is_shutdown());
+        release.send(()).unwrap();
+
+        let (mut client, response) = request.join().unwrap();
+        assert_eq!(response.unwrap().message, "in flight");
+        server.join().unwrap().unwrap();
+        assert!(app.flushed.load(Ordering::SeqCst));
+
+        // The connection has been closed, and no new connection is accepted.
+        assert!(client
+            .echo(RequestEcho {
+                message: "too late".to_string(),
+            })
+            .is_err());
+        assert!(ClientBuilder::default().connect(&server_addr).is_err());
+    }
+}
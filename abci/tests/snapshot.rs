@@ -0,0 +1,121 @@
+//! Tests of the fetching of snapshot chunks.
+
+#[cfg(feature = "snapshot")]
+mod snapshot_fetching {
+    use std::collections::HashMap;
+
+    use bytes::Bytes;
+    use prost::Message;
+    use sha2::{Digest, Sha256};
+    use tendermint_abci::{
+        snapshot::{ChunkSource, SnapshotFetcher, SnapshotMetadata},
+        Error,
+    };
+    use tendermint_proto::v0_38::abci::Snapshot;
+
+    #[derive(Default)]
+    struct MemorySource {
+        chunks: HashMap<u32, Bytes>,
+        loads: usize,
+    }
+
+    impl ChunkSource for MemorySource {
+        fn load_chunk(&mut self, height: u64, format: u32, index: u32) -> Result<Bytes, Error> {
+            assert_eq!((height, format), (10, 1));
+            self.loads += 1;
+            Ok(self.chunks.get(&index).cloned().unwrap_or_default())
+        }
+    }
+
+    fn snapshot(chunks: &[&'static [u8]]) -> Snapshot {
+        let metadata = SnapshotMetadata {
+            chunk_hashes: chunks
+                .iter()
+                .map(|chunk| Bytes::copy_from_slice(&Sha256::digest(chunk)))
+                .collect(),
+        };
+        Snapshot {
+            height: 10,
+            format: 1,
+            chunks: chunks.len() as u32,
+            hash: Bytes::copy_from_slice(&Sha256::digest(chunks.concat())),
+            metadata: metadata.encode_to_vec().into(),
+        }
+    }
+
+    fn source(chunks: &[&'static [u8]]) -> MemorySource {
+        MemorySource {
+            chunks: (0..)
+                .zip(chunks)
+                .map(|(index, chunk)| (index, Bytes::from_static(chunk)))
+                .collect(),
+            loads: 0,
+        }
+    }
+
+    #[test]
+    fn fetches_verified_chunks() {
+        let chunks: [&[u8]; 3] = [b"first", b"second", b"third"];
+        let fetcher = SnapshotFetcher::new(source(&chunks), snapshot(&chunks)).unwrap();
+        let fetched = fetcher.collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(fetched.len(), 3);
+        for (index, chunk) in fetched.iter().enumerate() {
+            assert_eq!(chunk.index, index as u32);
+            assert_eq!(chunk.data, chunks[index]);
+        }
+        let request = fetched[1].apply_request("peer".to_string());
+        assert_eq!((request.index, request.sender.as_str()), (1, "peer"));
+    }
+
+    #[test]
+    fn retries_invalid_chunks() {
+        let chunks: [&[u8]; 2] = [b"first", b"second"];
+        let mut source = source(&chunks);
+        source.chunks.insert(1, Bytes::from_static(b"corrupted"));
+        let mut fetcher = SnapshotFetcher::new(source, snapshot(&chunks)).unwrap();
+
+        assert!(fetcher.next().unwrap().is_ok());
+        assert!(fetcher.next().unwrap().is_err());
+        // The chunk is loaded again once the source is fixed.
+        fetcher
+            .source_mut()
+            .chunks
+            .insert(1, Bytes::from_static(b"second"));
+        assert_eq!(fetcher.next().unwrap().unwrap().index, 1);
+        assert!(fetcher.next().is_none());
+        assert_eq!(fetcher.source_mut().loads, 3);
+
+        // Missing chunks are reported as empty.
+        let mut fetcher = SnapshotFetcher::new(MemorySource::default(), snapshot(&chunks)).unwrap();
+        assert!(fetcher.fetch_chunk(0).is_err());
+        assert!(fetcher.fetch_chunk(2).is_err());
+    }
+
+    #[test]
+    fn rejects_inconsistent_metadata() {
+        let mut snapshot = snapshot(&[b"first", b"second"]);
+        snapshot.chunks = 3;
+        assert!(SnapshotFetcher::new(MemorySource::default(), snapshot.clone()).is_err());
+
+        snapshot.metadata = Bytes::from_static(&[0x0a, 0xff]);
+        assert!(SnapshotFetcher::new(MemorySource::default(), snapshot).is_err());
+    }
+
+    #[test]
+    fn verifies_the_snapshot_hash() {
+        let chunks: [&[u8]; 2] = [b"first", b"second"];
+        let mut snapshot = snapshot(&chunks);
+        snapshot.hash = Bytes::from_static(b"snapshot");
+        let mut fetcher = SnapshotFetcher::new(source(&chunks), snapshot.clone()).unwrap();
+        assert!(fetcher.next().unwrap().is_ok());
+        assert!(fetcher.next().unwrap().is_err());
+
+        // The hash of the snapshot is not checked against chunk hashes of other
+        // formats.
+        let metadata = SnapshotMetadata::decode(snapshot.metadata.clone()).unwrap();
+        let fetcher =
+            SnapshotFetcher::with_chunk_hashes(source(&chunks), snapshot, metadata.chunk_hashes)
+                .unwrap();
+        assert_eq!(fetcher.collect::<Result<Vec<_>, _>>().unwrap().len(), 2);
+    }
+}
@@ -8,7 +8,7 @@ use color_eyre::{
     Report,
 };
 use futures::future::join_all;
-use tendermint::{crypto::default::Sha256, evidence::Evidence, Time};
+use tendermint::{crypto::default::Sha256, Time};
 use tendermint_light_client::{
     builder::LightClientBuilder,
     instance::Instance,
@@ -17,8 +17,8 @@ use tendermint_light_client::{
     types::{Hash, Height, LightBlock, TrustThreshold},
 };
 use tendermint_light_client_detector::{
-    compare_new_header_with_witness, detect_divergence, gather_evidence_from_conflicting_headers,
-    CompareError, Error, ErrorDetail, Provider, Trace,
+    compare_new_header_with_witness, detect_misbehavior, gather_evidence_from_conflicting_headers,
+    CompareError, Error, ErrorDetail, Provider, ReportingHandler,
 };
 use tendermint_rpc::{Client, HttpClient, HttpClientUrl, Url};
 use tracing::{debug, error, info, metadata::LevelFilter, warn};
@@ -104,6 +104,10 @@ struct Cli {
     #[clap(long, default_value = "5")]
     max_clock_drift: u64,
 
+    /// Initial height of the chain, for chains restarted after a hard fork
+    #[clap(long)]
+    initial_height: Option<Height>,
+
     /// Maximum block lag, in seconds
     #[clap(long, default_value = "5")]
     max_block_lag: u64,
@@ -133,6 +137,7 @@ async fn main() -> Result<()> {
         trust_threshold: args.trust_threshold,
         trusting_period: Duration::from_secs(args.trusting_period),
         clock_drift: Duration::from_secs(args.max_clock_drift),
+        initial_height: args.initial_height,
     };
 
     let mut primary = make_provider(
@@ -197,64 +202,15 @@ async fn run_detector(
     max_block_lag: Duration,
     now: Time,
 ) -> Result<(), Report> {
-    if witnesses.is_empty() {
-        return Err(Error::no_witnesses().into());
-    }
-
-    info!(
-        "Running misbehavior detection against {} witnesses...",
-        witnesses.len()
-    );
-
-    let primary_trace = Trace::new(primary_trace)?;
-
-    let last_verified_block = primary_trace.last();
-    let last_verified_header = &last_verified_block.signed_header;
-
-    for witness in witnesses {
-        let divergence = detect_divergence::<Sha256>(
-            Some(primary),
-            witness,
-            primary_trace.clone().into_vec(),
-            max_clock_drift,
-            max_block_lag,
-        )
-        .await;
-
-        let evidence = match divergence {
-            Ok(Some(divergence)) => divergence.evidence,
-            Ok(None) => {
-                info!(
-                    "no divergence found between primary and witness {}",
-                    witness.peer_id()
-                );
-
-                continue;
-            },
-            Err(e) => {
-                error!(
-                    "failed to run attack detector against witness {}: {e}",
-                    witness.peer_id()
-                );
-
-                continue;
-            },
-        };
-
-        // Report the evidence to the witness
-        witness
-            .report_evidence(Evidence::from(evidence.against_primary))
-            .await
-            .map_err(|e| eyre!("failed to report evidence to witness: {}", e))?;
-
-        if let Some(against_witness) = evidence.against_witness {
-            // Report the evidence to the primary
-            primary
-                .report_evidence(Evidence::from(against_witness))
-                .await
-                .map_err(|e| eyre!("failed to report evidence to primary: {}", e))?;
-        }
-    }
+    detect_misbehavior::<Sha256>(
+        primary,
+        witnesses,
+        primary_trace,
+        max_clock_drift,
+        max_block_lag,
+        &ReportingHandler,
+    )
+    .await?;
 
     Ok(())
 }
@@ -1,7 +1,8 @@
-use tendermint::{block::Height, Hash, Time};
+use tendermint::{block::Height, node::Id as PeerId, Hash, Time};
 use tendermint_light_client::components::io::IoError;
 use tendermint_light_client::errors::Error as LightClientError;
 use tendermint_light_client::verifier::types::LightBlock;
+use tendermint_rpc::Error as RpcError;
 
 use crate::conflict::GatheredEvidence;
 
@@ -84,5 +85,12 @@ flex_error::define_error! {
 
         FailedHeaderCrossReferencing
             |_| { format_args!("failed to cross-reference header with witness") },
+
+        ReportEvidence
+            {
+                peer_id: PeerId,
+            }
+            [ RpcError ]
+            |e| { format_args!("failed to report evidence to {}", e.peer_id) },
     }
 }
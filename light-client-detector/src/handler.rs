@@ -0,0 +1,194 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tendermint::{
+    crypto::Sha256, evidence::Evidence, evidence::LightClientAttackEvidence, merkle::MerkleHash,
+    node::Id as PeerId,
+};
+use tendermint_light_client::{relayer::Misbehaviour, verifier::types::LightBlock};
+use tracing::{error, info, warn};
+
+use super::{detect::detect_divergence, error::Error, provider::Provider, trace::Trace};
+
+/// A misbehavior detected by [`detect_misbehavior`], passed to the [`MisbehaviorHandler`].
+#[derive(Clone, Debug)]
+pub struct Misbehavior<'a> {
+    /// The peer ID of the primary
+    pub primary: PeerId,
+    /// The peer ID of the witness that diverged from the primary
+    pub witness: PeerId,
+    /// The trace of the light blocks verified against the primary
+    pub primary_trace: &'a Trace,
+    /// The trace of the light blocks verified against the witness, up to the
+    /// bifurcation point
+    pub witness_trace: &'a Trace,
+    /// The conflicting light block that was returned by the witness
+    pub challenging_block: &'a LightBlock,
+    /// The evidence of the attack, to be reported to the witness
+    pub against_primary: &'a LightClientAttackEvidence,
+    /// The evidence of the attack, to be reported to the primary, if the
+    /// examination of the trace of the witness against the primary succeeded
+    pub against_witness: Option<&'a LightClientAttackEvidence>,
+}
+
+impl Misbehavior<'_> {
+    /// Package the conflicting headers of the primary and the witness into a
+    /// [`Misbehaviour`] to submit to the IBC light clients tracking the chain,
+    /// both verified against the common block of the witness trace.
+    pub fn to_ibc_misbehaviour(&self) -> Result<Misbehaviour, Error> {
+        Misbehaviour::from_attack_evidence(
+            self.against_primary,
+            self.witness_trace.first(),
+            self.witness_trace.last(),
+        )
+        .map_err(Error::light_client)
+    }
+}
+
+/// The action to take once a misbehavior has been handled by a [`MisbehaviorHandler`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MisbehaviorAction {
+    /// Report the evidence against the primary to the witness, and the evidence against
+    /// the witness to the primary, then keep running the detection against the other witnesses
+    ReportEvidence,
+    /// Keep running the detection against the other witnesses, without reporting the evidence
+    Continue,
+    /// Stop running the detection, so that the caller replaces the primary with the witness
+    SwitchPrimary,
+    /// Stop running the detection, so that the caller halts the light client
+    Halt,
+}
+
+/// Callback interface invoked by [`detect_misbehavior`] for every divergence detected between
+/// the primary and a witness, which decides how the detection proceeds.
+///
+/// Handlers may, for example, alert an operator, or forward the evidence to the
+/// counterparty chain of a relayer.
+#[async_trait]
+pub trait MisbehaviorHandler: Send + Sync {
+    /// Handle the given misbehavior
+    async fn handle(&self, misbehavior: &Misbehavior<'_>) -> MisbehaviorAction;
+}
+
+/// A [`MisbehaviorHandler`] which always reports the evidence to the nodes, and continues.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ReportingHandler;
+
+#[async_trait]
+impl MisbehaviorHandler for ReportingHandler {
+    async fn handle(&self, _misbehavior: &Misbehavior<'_>) -> MisbehaviorAction {
+        MisbehaviorAction::ReportEvidence
+    }
+}
+
+/// The outcome of [`detect_misbehavior`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DetectionOutcome {
+    /// The detection ran against all the witnesses, and found the given number of divergences
+    Completed { divergences: usize },
+    /// The handler requested to replace the primary with the witness at the given index
+    SwitchPrimary { witness: usize },
+    /// The handler requested to halt when handling a divergence with the witness at the given
+    /// index
+    Halted { witness: usize },
+}
+
+/// Run the detection of misbehaviors against every witness with [`detect_divergence`], invoking
+/// the given handler with every divergence found.
+///
+/// Witnesses for which the detection fails are skipped.
+pub async fn detect_misbehavior<H>(
+    primary: &Provider,
+    witnesses: &mut [Provider],
+    primary_trace: Vec<LightBlock>,
+    max_clock_drift: Duration,
+    max_block_lag: Duration,
+    handler: &dyn MisbehaviorHandler,
+) -> Result<DetectionOutcome, Error>
+where
+    H: Sha256 + MerkleHash + Default,
+{
+    if witnesses.is_empty() {
+        return Err(Error::no_witnesses());
+    }
+
+    info!(
+        "Running misbehavior detection against {} witnesses...",
+        witnesses.len()
+    );
+
+    let primary_trace = Trace::new(primary_trace)?;
+    let mut divergences = 0;
+
+    for (index, witness) in witnesses.iter_mut().enumerate() {
+        let divergence = detect_divergence::<H>(
+            Some(primary),
+            witness,
+            primary_trace.clone().into_vec(),
+            max_clock_drift,
+            max_block_lag,
+        )
+        .await;
+
+        let divergence = match divergence {
+            Ok(Some(divergence)) => divergence,
+            Ok(None) => {
+                info!(
+                    "no divergence found between primary and witness {}",
+                    witness.peer_id()
+                );
+
+                continue;
+            },
+            Err(e) => {
+                error!(
+                    "failed to run attack detector against witness {}: {e}",
+                    witness.peer_id()
+                );
+
+                continue;
+            },
+        };
+
+        divergences += 1;
+
+        let evidence = divergence.evidence;
+        let misbehavior = Misbehavior {
+            primary: *primary.peer_id(),
+            witness: *witness.peer_id(),
+            primary_trace: &primary_trace,
+            witness_trace: &evidence.witness_trace,
+            challenging_block: &divergence.challenging_block,
+            against_primary: &evidence.against_primary,
+            against_witness: evidence.against_witness.as_ref(),
+        };
+
+        let action = handler.handle(&misbehavior).await;
+        warn!(witness = %witness.peer_id(), ?action, "Handled misbehavior");
+
+        match action {
+            MisbehaviorAction::ReportEvidence => {
+                // Report the evidence to the witness
+                witness
+                    .report_evidence(Evidence::from(evidence.against_primary))
+                    .await
+                    .map_err(|e| Error::report_evidence(*witness.peer_id(), e))?;
+
+                if let Some(against_witness) = evidence.against_witness {
+                    // Report the evidence to the primary
+                    primary
+                        .report_evidence(Evidence::from(against_witness))
+                        .await
+                        .map_err(|e| Error::report_evidence(*primary.peer_id(), e))?;
+                }
+            },
+            MisbehaviorAction::Continue => {},
+            MisbehaviorAction::SwitchPrimary => {
+                return Ok(DetectionOutcome::SwitchPrimary { witness: index })
+            },
+            MisbehaviorAction::Halt => return Ok(DetectionOutcome::Halted { witness: index }),
+        }
+    }
+
+    Ok(DetectionOutcome::Completed { divergences })
+}
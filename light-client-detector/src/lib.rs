@@ -1,18 +1,24 @@
 //! The detector component of the light client detects and handles attacks on the light client.
 //!
-//! See [`detect_divergence`] for the main entry point.
+//! See [`detect_divergence`] for the main entry point, and [`detect_misbehavior`] to run the
+//! detection against several witnesses and handle misbehaviors with a [`MisbehaviorHandler`].
 
 mod conflict;
 mod detect;
 mod error;
 mod evidence;
 mod examine;
+mod handler;
 mod provider;
 mod trace;
 
 pub use conflict::gather_evidence_from_conflicting_headers;
 pub use detect::{compare_new_header_with_witness, detect_divergence, CompareError, Divergence};
 pub use error::{Error, ErrorDetail};
+pub use handler::{
+    detect_misbehavior, DetectionOutcome, Misbehavior, MisbehaviorAction, MisbehaviorHandler,
+    ReportingHandler,
+};
 pub use provider::Provider;
 pub use tendermint::evidence::{Evidence, LightClientAttackEvidence};
 pub use trace::Trace;
@@ -3,7 +3,7 @@ use tendermint::evidence::Evidence;
 use tendermint::hash::Hash;
 use tendermint_light_client::errors::Error;
 use tendermint_light_client::instance::Instance;
-use tendermint_light_client::light_client::TargetOrLatest;
+use tendermint_light_client::light_client::{Options, TargetOrLatest};
 use tendermint_light_client::state::State;
 use tendermint_light_client::store::memory::MemoryStore;
 use tendermint_light_client::verifier::types::LightBlock;
@@ -50,6 +50,10 @@ impl Provider {
             .map(|(lb, _)| lb)
     }
 
+    pub fn update_options(&mut self, options: Options) -> Result<(), Error> {
+        self.instance.update_options(options)
+    }
+
     pub fn verify_to_highest(&mut self) -> Result<LightBlock, Error> {
         self.instance
             .light_client
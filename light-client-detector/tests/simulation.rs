@@ -0,0 +1,211 @@
+use std::{sync::Mutex, time::Duration};
+
+use async_trait::async_trait;
+
+use tendermint::{crypto::default::Sha256, node::Id as PeerId};
+use tendermint_light_client::{
+    relayer::Misbehaviour,
+    simulation::{SimNetwork, SimNode},
+    verifier::{
+        options::Options,
+        types::{Height, LightBlock},
+    },
+};
+use tendermint_light_client_detector::{
+    detect_divergence, detect_misbehavior, DetectionOutcome, Misbehavior, MisbehaviorAction,
+    MisbehaviorHandler, Provider,
+};
+use tendermint_rpc::HttpClient;
+use tendermint_testgen::{
+    helpers::get_time, light_block::TmLightBlock, AttackKind, Generator, LightClientAttack,
+};
+
+fn to_light_block(lb: TmLightBlock) -> LightBlock {
+    LightBlock {
+        signed_header: lb.signed_header,
+        validators: lb.validators,
+        next_validators: lb.next_validators,
+        provider: lb.provider,
+    }
+}
+
+fn provider(instance: tendermint_light_client::instance::Instance) -> Provider {
+    // Evidence is never reported in these tests, the RPC client is never used.
+    let rpc_client = HttpClient::new("http://127.0.0.1:26657").unwrap();
+    Provider::new("test-chain".to_string(), instance, rpc_client)
+}
+
+#[tokio::test]
+async fn detects_lunatic_primary() {
+    let fixture = LightClientAttack::new(AttackKind::Lunatic)
+        .generate()
+        .unwrap();
+    let trusted = to_light_block(fixture.trusted);
+    let honest = to_light_block(fixture.honest);
+    let conflicting = to_light_block(fixture.conflicting);
+    let target_height = honest.height();
+
+    let primary = SimNode::new(PeerId::new([1; 20]), [trusted.clone(), conflicting.clone()]);
+    let witness = SimNode::new(PeerId::new([2; 20]), [trusted, honest.clone()]);
+    let network = SimNetwork::new(primary, vec![witness]);
+
+    let options = Options {
+        trust_threshold: Default::default(),
+        trusting_period: Duration::from_secs(60 * 60),
+        clock_drift: Duration::from_secs(10),
+        initial_height: None,
+    };
+    let (primary, mut witnesses) =
+        network.instances(Height::from(1_u32), options, get_time(100).unwrap());
+
+    let mut primary = provider(primary);
+    let mut witness = provider(witnesses.remove(0));
+
+    primary.verify_to_height(target_height).unwrap();
+    let primary_trace = primary.get_trace(target_height);
+
+    let divergence = detect_divergence::<Sha256>(
+        Some(&primary),
+        &mut witness,
+        primary_trace,
+        Duration::from_secs(10),
+        Duration::from_secs(10),
+    )
+    .await
+    .unwrap()
+    .expect("divergence should have been detected");
+
+    assert_eq!(
+        divergence.challenging_block.signed_header,
+        honest.signed_header
+    );
+    let evidence = divergence.evidence.against_primary;
+    assert_eq!(
+        evidence.conflicting_block.signed_header,
+        conflicting.signed_header
+    );
+    assert_eq!(evidence.common_height.value(), 1);
+}
+
+#[tokio::test]
+async fn no_divergence_between_honest_nodes() {
+    let fixture = LightClientAttack::new(AttackKind::Lunatic)
+        .generate()
+        .unwrap();
+    let trusted = to_light_block(fixture.trusted);
+    let honest = to_light_block(fixture.honest);
+    let target_height = honest.height();
+
+    let blocks = [trusted, honest];
+    let network = SimNetwork::new(
+        SimNode::new(PeerId::new([1; 20]), blocks.clone()),
+        vec![SimNode::new(PeerId::new([2; 20]), blocks)],
+    );
+    let options = Options {
+        trust_threshold: Default::default(),
+        trusting_period: Duration::from_secs(60 * 60),
+        clock_drift: Duration::from_secs(10),
+        initial_height: None,
+    };
+    let (primary, mut witnesses) =
+        network.instances(Height::from(1_u32), options, get_time(100).unwrap());
+
+    let mut primary = provider(primary);
+    let mut witness = provider(witnesses.remove(0));
+
+    primary.verify_to_height(target_height).unwrap();
+    let divergence = detect_divergence::<Sha256>(
+        Some(&primary),
+        &mut witness,
+        primary.get_trace(target_height),
+        Duration::from_secs(10),
+        Duration::from_secs(10),
+    )
+    .await
+    .unwrap();
+    assert!(divergence.is_none());
+    assert!(!network.witnesses()[0].requests().is_empty());
+}
+
+#[derive(Default)]
+struct HaltingHandler {
+    handled: Mutex<Vec<(PeerId, Height)>>,
+    misbehaviours: Mutex<Vec<Misbehaviour>>,
+}
+
+#[async_trait]
+impl MisbehaviorHandler for HaltingHandler {
+    async fn handle(&self, misbehavior: &Misbehavior<'_>) -> MisbehaviorAction {
+        self.handled.lock().unwrap().push((
+            misbehavior.witness,
+            misbehavior
+                .against_primary
+                .conflicting_block
+                .signed_header
+                .header
+                .height,
+        ));
+        self.misbehaviours
+            .lock()
+            .unwrap()
+            .push(misbehavior.to_ibc_misbehaviour().unwrap());
+        MisbehaviorAction::Halt
+    }
+}
+
+#[tokio::test]
+async fn misbehavior_handler_halts_detection() {
+    let fixture = LightClientAttack::new(AttackKind::Lunatic)
+        .generate()
+        .unwrap();
+    let trusted = to_light_block(fixture.trusted);
+    let honest = to_light_block(fixture.honest);
+    let conflicting = to_light_block(fixture.conflicting);
+    let target_height = honest.height();
+
+    let primary = SimNode::new(PeerId::new([1; 20]), [trusted.clone(), conflicting]);
+    let witnesses = vec![
+        SimNode::new(PeerId::new([2; 20]), [trusted.clone(), honest.clone()]),
+        SimNode::new(PeerId::new([3; 20]), [trusted.clone(), honest.clone()]),
+    ];
+    let network = SimNetwork::new(primary, witnesses);
+
+    let options = Options {
+        trust_threshold: Default::default(),
+        trusting_period: Duration::from_secs(60 * 60),
+        clock_drift: Duration::from_secs(10),
+        initial_height: None,
+    };
+    let (primary, witnesses) =
+        network.instances(Height::from(1_u32), options, get_time(100).unwrap());
+
+    let mut primary = provider(primary);
+    let mut witnesses: Vec<_> = witnesses.into_iter().map(provider).collect();
+
+    primary.verify_to_height(target_height).unwrap();
+
+    let handler = HaltingHandler::default();
+    let outcome = detect_misbehavior::<Sha256>(
+        &primary,
+        &mut witnesses,
+        primary.get_trace(target_height),
+        Duration::from_secs(10),
+        Duration::from_secs(10),
+        &handler,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(outcome, DetectionOutcome::Halted { witness: 0 });
+    assert_eq!(
+        *handler.handled.lock().unwrap(),
+        vec![(PeerId::new([2; 20]), target_height)]
+    );
+    let misbehaviours = handler.misbehaviours.lock().unwrap();
+    assert_eq!(misbehaviours[0].header1.height(), target_height);
+    assert_eq!(misbehaviours[0].header2.height(), target_height);
+    assert_eq!(misbehaviours[0].header2.signed_header, honest.signed_header);
+    assert_eq!(misbehaviours[0].header1.trusted_height, trusted.height());
+    // The detection did not run against the second witness.
+    assert!(network.witnesses()[1].requests().is_empty());
+}
@@ -61,11 +61,11 @@ fn deserialize_params(
     })?;
 
     let options = serde_wasm_bindgen::from_value::<JsOptions>(options)
-        .map(Into::into)
         .map_err(|e| Error::Serialization {
             param: "options".into(),
             msg: e.to_string(),
-        })?;
+        })?
+        .try_into()?;
 
     let now = serde_wasm_bindgen::from_value(now).map_err(|e| Error::Serialization {
         param: "now".into(),
@@ -90,15 +90,28 @@ pub struct JsOptions {
     pub trust_threshold: (u64, u64),
     pub trusting_period: u64,
     pub clock_drift: u64,
+    #[serde(default)]
+    pub initial_height: Option<u64>,
 }
 
-impl From<JsOptions> for Options {
-    fn from(o: JsOptions) -> Self {
+impl TryFrom<JsOptions> for Options {
+    type Error = Error;
+
+    fn try_from(o: JsOptions) -> Result<Self, Error> {
         let (num, den) = o.trust_threshold;
-        Self {
+        let initial_height = o
+            .initial_height
+            .map(TryInto::try_into)
+            .transpose()
+            .map_err(|e: tendermint::Error| Error::Serialization {
+                param: "options.initial_height".into(),
+                msg: e.to_string(),
+            })?;
+        Ok(Self {
             trust_threshold: TrustThreshold::new(num, den).unwrap(),
             trusting_period: Duration::from_secs(o.trusting_period),
             clock_drift: Duration::from_secs(o.clock_drift),
-        }
+            initial_height,
+        })
     }
 }
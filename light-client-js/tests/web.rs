@@ -207,6 +207,7 @@ fn test_options() -> JsValue {
         trust_threshold: (1, 3),
         trusting_period: 1209600, // 2 weeks
         clock_drift: 5,           // 5 seconds
+        initial_height: None,
     })
     .unwrap()
 }
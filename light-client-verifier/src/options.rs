@@ -7,7 +7,7 @@ use core::time::Duration;
 use derive_more::Display;
 use serde::{Deserialize, Serialize};
 
-use crate::types::TrustThreshold;
+use crate::types::{Height, TrustThreshold};
 
 /// Verification parameters
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Display, Serialize, Deserialize)]
@@ -27,4 +27,13 @@ pub struct Options {
     /// is the maximum amount that the local clock may drift behind a timestamp from the
     /// blockchain.
     pub clock_drift: Duration,
+
+    /// The initial height of the chain, if known, which is greater than 1 for
+    /// chains restarted after a hard fork.
+    ///
+    /// When set, the untrusted headers are checked against the rules
+    /// specific to the first block of the chain, and the headers below the
+    /// initial height are rejected.
+    #[serde(default)]
+    pub initial_height: Option<Height>,
 }
@@ -22,6 +22,11 @@ pub struct ProdPredicates;
 #[cfg(feature = "rust-crypto")]
 impl VerificationPredicates for ProdPredicates {
     type Sha256 = tendermint::crypto::default::Sha256;
+
+    /// Use the memoized hash of the validator set.
+    fn validators_hash(&self, validators: &ValidatorSet) -> Hash {
+        validators.hash()
+    }
 }
 
 /// Defines the various predicates used to validate and verify light blocks.
@@ -34,6 +39,11 @@ pub trait VerificationPredicates: Send + Sync {
     /// The implementation of SHA256 digest
     type Sha256: MerkleHash + Sha256 + Default;
 
+    /// The hash of the given validator set.
+    fn validators_hash(&self, validators: &ValidatorSet) -> Hash {
+        validators.hash_with::<Self::Sha256>()
+    }
+
     /// Compare the provided validator_set_hash against the hash produced from hashing the validator
     /// set.
     fn validator_sets_match(
@@ -41,7 +51,7 @@ pub trait VerificationPredicates: Send + Sync {
         validators: &ValidatorSet,
         header_validators_hash: Hash,
     ) -> Result<(), VerificationError> {
-        let validators_hash = validators.hash_with::<Self::Sha256>();
+        let validators_hash = self.validators_hash(validators);
         if header_validators_hash == validators_hash {
             Ok(())
         } else {
@@ -58,7 +68,7 @@ pub trait VerificationPredicates: Send + Sync {
         next_validators: &ValidatorSet,
         header_next_validators_hash: Hash,
     ) -> Result<(), VerificationError> {
-        let next_validators_hash = next_validators.hash_with::<Self::Sha256>();
+        let next_validators_hash = self.validators_hash(next_validators);
         if header_next_validators_hash == next_validators_hash {
             Ok(())
         } else {
@@ -168,6 +178,18 @@ pub trait VerificationPredicates: Send + Sync {
         }
     }
 
+    /// Check that the untrusted header satisfies the rules depending on the
+    /// initial height of the chain.
+    fn is_valid_at_initial_height(
+        &self,
+        untrusted_header: &Header,
+        initial_height: Height,
+    ) -> Result<(), VerificationError> {
+        untrusted_header
+            .validate_initial_height(initial_height)
+            .map_err(VerificationError::tendermint)
+    }
+
     /// Check that the chain-ids of the trusted header and the untrusted one are the same
     fn is_matching_chain_id(
         &self,
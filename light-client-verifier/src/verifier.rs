@@ -204,6 +204,22 @@ where
         Verdict::Success
     }
 
+    /// Check the untrusted header against the initial height of the chain, if
+    /// set in the options.
+    pub fn check_initial_height(
+        &self,
+        untrusted: &UntrustedBlockState<'_>,
+        options: &Options,
+    ) -> Verdict {
+        if let Some(initial_height) = options.initial_height {
+            verdict!(self
+                .predicates
+                .is_valid_at_initial_height(&untrusted.signed_header.header, initial_height));
+        }
+
+        Verdict::Success
+    }
+
     /// Ensure the header isn't from a future time
     pub fn check_header_is_from_past(
         &self,
@@ -258,6 +274,8 @@ where
     ///     - Ensure the header next validator hashes match the given next validators
     ///     - Ensure the header matches the commit
     ///     - Ensure commit is valid
+    ///     - If the initial height of the chain is set in the options, check the header
+    ///       against it
     /// - Validate the untrusted header against the trusted header
     ///     - Ensure the latest trusted header hasn't expired
     ///     - Ensure the header isn't from a future time
@@ -286,6 +304,7 @@ where
         now: Time,
     ) -> Verdict {
         ensure_verdict_success!(self.verify_validator_sets(&untrusted));
+        ensure_verdict_success!(self.check_initial_height(&untrusted, options));
         ensure_verdict_success!(self.validate_against_trusted(&untrusted, &trusted, options, now));
         ensure_verdict_success!(self.check_header_is_from_past(&untrusted, options, now));
         ensure_verdict_success!(self.verify_commit_against_trusted(&untrusted, &trusted, options));
@@ -305,6 +324,7 @@ where
         now: Time,
     ) -> Verdict {
         ensure_verdict_success!(self.verify_validator_sets(&untrusted));
+        ensure_verdict_success!(self.check_initial_height(&untrusted, options));
         ensure_verdict_success!(self.validate_against_trusted(&untrusted, &trusted, options, now));
         ensure_verdict_success!(self.verify_commit_against_trusted(&untrusted, &trusted, options));
         ensure_verdict_success!(self.verify_commit(&untrusted));
@@ -323,7 +343,10 @@ mod tests {
     use core::{ops::Sub, time::Duration};
 
     use tendermint::Time;
-    use tendermint_testgen::{light_block::LightBlock as TestgenLightBlock, Generator};
+    use tendermint_testgen::{
+        helpers::get_time, light_block::LightBlock as TestgenLightBlock, AttackKind, BlockTimes,
+        Chain, Generator, LightClientAttack,
+    };
 
     use crate::{
         errors::VerificationErrorDetail, options::Options, types::LightBlock, ProdVerifier,
@@ -367,6 +390,7 @@ mod tests {
             trust_threshold: Default::default(),
             trusting_period: Duration::from_secs(60),
             clock_drift: Default::default(),
+            initial_height: None,
         };
 
         let verdict = vp.verify_update_header(
@@ -386,4 +410,129 @@ mod tests {
             v => panic!("expected ChainIdMismatch error, got: {:?}", v),
         }
     }
+
+    #[test]
+    fn test_verification_against_initial_height() {
+        let now = Time::now();
+        let light_block = |height: u64, age: u64| -> LightBlock {
+            TestgenLightBlock::new_default_with_time_and_chain_id(
+                "chain-1".to_owned(),
+                now.sub(Duration::from_secs(age)).unwrap(),
+                height,
+            )
+            .generate()
+            .unwrap()
+            .into()
+        };
+        // The generated headers do not refer to the previous block.
+        let trusted = light_block(1, 20);
+        let untrusted = light_block(2, 10);
+
+        let verify = |initial_height: Option<u64>| {
+            let opt = Options {
+                trust_threshold: Default::default(),
+                trusting_period: Duration::from_secs(60),
+                clock_drift: Default::default(),
+                initial_height: initial_height.map(|h| h.try_into().unwrap()),
+            };
+            ProdVerifier::default().verify_update_header(
+                untrusted.as_untrusted_state(),
+                trusted.as_trusted_state(),
+                &opt,
+                now,
+            )
+        };
+
+        assert_eq!(verify(None), Verdict::Success);
+        // The untrusted block is the first one of a chain restarted at height 2.
+        assert_eq!(verify(Some(2)), Verdict::Success);
+        for initial_height in [1, 3] {
+            match verify(Some(initial_height)) {
+                Verdict::Invalid(VerificationErrorDetail::Tendermint(_)) => {},
+                v => panic!("expected Tendermint error, got: {:?}", v),
+            }
+        }
+    }
+
+    #[test]
+    fn test_attack_fixtures_pass_verification() {
+        let vp = ProdVerifier::default();
+        let opt = Options {
+            trust_threshold: Default::default(),
+            trusting_period: Duration::from_secs(60),
+            clock_drift: Default::default(),
+            initial_height: None,
+        };
+        let now = Time::from_unix_timestamp(10, 0).unwrap();
+
+        for kind in [
+            AttackKind::Equivocation,
+            AttackKind::Lunatic,
+            AttackKind::Amnesia,
+        ] {
+            let fixture = LightClientAttack::new(kind).generate().unwrap();
+            let trusted: LightBlock = fixture.trusted.into();
+
+            for untrusted in [fixture.honest, fixture.conflicting] {
+                let untrusted: LightBlock = untrusted.into();
+                let verdict = vp.verify_update_header(
+                    untrusted.as_untrusted_state(),
+                    trusted.as_trusted_state(),
+                    &opt,
+                    now,
+                );
+                assert_eq!(verdict, Verdict::Success, "{kind} attack");
+            }
+        }
+    }
+
+    fn verify_with_times(times: BlockTimes, now: u64, trusting_period: u64) -> Verdict {
+        let blocks = Chain::new(3).times(times).generate().unwrap();
+        let trusted: LightBlock = blocks[0].clone().into();
+        let untrusted: LightBlock = blocks[2].clone().into();
+
+        let opt = Options {
+            trust_threshold: Default::default(),
+            trusting_period: Duration::from_secs(trusting_period),
+            clock_drift: Duration::from_secs(10),
+            initial_height: None,
+        };
+        ProdVerifier::default().verify_update_header(
+            untrusted.as_untrusted_state(),
+            trusted.as_trusted_state(),
+            &opt,
+            get_time(now).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_verification_with_generated_block_times() {
+        let times = BlockTimes::new(100, 10);
+        assert_eq!(verify_with_times(times.clone(), 130, 60), Verdict::Success);
+
+        // the trusted block expires exactly at `now`
+        match verify_with_times(times.clone(), 160, 60) {
+            Verdict::Invalid(VerificationErrorDetail::NotWithinTrustPeriod(_)) => {},
+            v => panic!("expected NotWithinTrustPeriod error, got: {:?}", v),
+        }
+
+        // slowing block production produces the untrusted block after the trusted one expired
+        match verify_with_times(times.clone().drift(50), 165, 60) {
+            Verdict::Invalid(VerificationErrorDetail::NotWithinTrustPeriod(_)) => {},
+            v => panic!("expected NotWithinTrustPeriod error, got: {:?}", v),
+        }
+
+        // the untrusted block is exactly one clock drift ahead of `now`
+        match verify_with_times(times.clone(), 110, 60) {
+            Verdict::Invalid(VerificationErrorDetail::HeaderFromTheFuture(_)) => {},
+            v => panic!("expected HeaderFromTheFuture error, got: {:?}", v),
+        }
+        assert_eq!(verify_with_times(times.clone(), 111, 60), Verdict::Success);
+
+        // the untrusted block has the same time as the trusted one
+        match verify_with_times(times.shift(3, -20), 130, 60) {
+            Verdict::Invalid(VerificationErrorDetail::NonMonotonicBftTime(_)) => {},
+            v => panic!("expected NonMonotonicBftTime error, got: {:?}", v),
+        }
+    }
 }
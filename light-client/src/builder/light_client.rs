@@ -7,6 +7,7 @@ use {
     crate::components::clock::SystemClock,
     crate::components::io::ProdIo,
     crate::components::scheduler,
+    crate::components::source::{LightBlockSource, SourceIo},
     crate::verifier::{predicates::ProdPredicates, ProdVerifier},
     core::time::Duration,
     tendermint_rpc as rpc,
@@ -127,6 +128,22 @@ where
         }
     }
 
+    /// Replace the component used to fetch light blocks from the primary.
+    pub fn io(mut self, io: Box<dyn Io>) -> Self {
+        self.io = io;
+        self
+    }
+
+    /// Fetch light blocks from the primary through the given source, with
+    /// the given timeout for each request.
+    #[cfg(feature = "rpc-client")]
+    pub fn light_block_source<S>(self, source: S, timeout: Option<Duration>) -> Self
+    where
+        S: LightBlockSource + 'static,
+    {
+        self.io(Box::new(SourceIo::new(source, timeout)))
+    }
+
     /// Set the given light block as the initial trusted state.
     pub fn trust_light_block(
         mut self,
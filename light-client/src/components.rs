@@ -3,6 +3,7 @@
 pub mod clock;
 pub mod io;
 pub mod scheduler;
+pub mod source;
 
 // Re-export for backward compatibility
 pub use tendermint_light_client_verifier as verifier;
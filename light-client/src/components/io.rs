@@ -4,8 +4,6 @@ use std::time::Duration;
 
 use flex_error::{define_error, TraceError};
 use tendermint_rpc as rpc;
-#[cfg(feature = "rpc-client")]
-use tendermint_rpc::Client;
 
 use crate::verifier::types::{Height, LightBlock};
 
@@ -71,6 +69,36 @@ define_error! {
             [ TraceError<std::io::Error> ]
             | _ | { "failed to initialize runtime" },
 
+        LightBlockNotFound
+            { height: Height }
+            | e | {
+                format_args!("no light block at height {0}", e.height)
+            },
+
+        EmptyArchive
+            | _ | { "the light block archive is empty" },
+
+        ArchiveRead
+            { path: String }
+            [ TraceError<std::io::Error> ]
+            | e | {
+                format_args!("failed to read light block archive {0}", e.path)
+            },
+
+        ArchiveWrite
+            { path: String }
+            [ TraceError<std::io::Error> ]
+            | e | {
+                format_args!("failed to write light block archive {0}", e.path)
+            },
+
+        InvalidArchive
+            { path: String }
+            [ TraceError<serde_json::Error> ]
+            | e | {
+                format_args!("invalid light block archive {0}", e.path)
+            },
+
     }
 }
 
@@ -137,37 +165,32 @@ mod prod {
         account::Id as TMAccountId, block::signed_header::SignedHeader as TMSignedHeader,
         validator::Set as TMValidatorSet,
     };
-    use tendermint_rpc::Paging;
+    use tendermint_rpc::ValidatorSetCache;
 
     use super::*;
-    use crate::{utils::block_on, verifier::types::PeerId};
+    use crate::{
+        components::source::{LightBlockSource, RpcSource},
+        utils::block_on,
+        verifier::types::PeerId,
+    };
 
     /// Production implementation of the Io component, which fetches
     /// light blocks from full nodes via RPC.
+    ///
+    /// The light blocks are fetched by an [`RpcSource`], whose requests are
+    /// run to completion with the given timeout.
     #[derive(Clone, Debug)]
     pub struct ProdIo {
-        peer_id: PeerId,
-        rpc_client: rpc::HttpClient,
+        source: RpcSource<rpc::HttpClient>,
         timeout: Option<Duration>,
     }
 
     impl Io for ProdIo {
         fn fetch_light_block(&self, height: AtHeight) -> Result<LightBlock, IoError> {
-            let signed_header = self.fetch_signed_header(height)?;
-            let height = signed_header.header.height;
-            let proposer_address = signed_header.header.proposer_address;
-
-            let validator_set = self.fetch_validator_set(height.into(), Some(proposer_address))?;
-            let next_validator_set = self.fetch_validator_set(height.increment().into(), None)?;
-
-            let light_block = LightBlock::new(
-                signed_header,
-                validator_set,
-                next_validator_set,
-                self.peer_id,
-            );
-
-            Ok(light_block)
+            let source = self.source.clone();
+            block_on(self.timeout, async move {
+                source.fetch_light_block(height).await
+            })?
         }
     }
 
@@ -182,37 +205,46 @@ mod prod {
             timeout: Option<Duration>,
         ) -> Self {
             Self {
-                peer_id,
-                rpc_client,
+                source: RpcSource::new(peer_id, rpc_client),
                 timeout,
             }
         }
 
+        /// Consult the given cache before fetching validator sets, and cache
+        /// the validator sets fetched.
+        ///
+        /// The cache can be shared with other components, such as the
+        /// [`ProdIo`] components of the witnesses.
+        pub fn with_validator_set_cache(mut self, cache: ValidatorSetCache) -> Self {
+            self.source = self.source.with_validator_set_cache(cache);
+            self
+        }
+
         pub fn peer_id(&self) -> PeerId {
-            self.peer_id
+            self.source.peer_id()
         }
 
         pub fn rpc_client(&self) -> &rpc::HttpClient {
-            &self.rpc_client
+            self.source.client()
         }
 
         pub fn timeout(&self) -> Option<Duration> {
             self.timeout
         }
 
+        pub fn validator_set_cache(&self) -> Option<&ValidatorSetCache> {
+            self.source.validator_set_cache()
+        }
+
+        pub fn source(&self) -> &RpcSource<rpc::HttpClient> {
+            &self.source
+        }
+
         pub fn fetch_signed_header(&self, height: AtHeight) -> Result<TMSignedHeader, IoError> {
-            let client = self.rpc_client.clone();
-            let res = block_on(self.timeout, async move {
-                match height {
-                    AtHeight::Highest => client.latest_commit().await,
-                    AtHeight::At(height) => client.commit(height).await,
-                }
-            })?;
-
-            match res {
-                Ok(response) => Ok(response.signed_header),
-                Err(err) => Err(IoError::from_rpc(err)),
-            }
+            let source = self.source.clone();
+            block_on(self.timeout, async move {
+                source.fetch_signed_header(height).await
+            })?
         }
 
         pub fn fetch_validator_set(
@@ -227,21 +259,10 @@ mod prod {
                 AtHeight::At(height) => height,
             };
 
-            let client = self.rpc_client.clone();
-            let response = block_on(self.timeout, async move {
-                client.validators(height, Paging::All).await
+            let source = self.source.clone();
+            block_on(self.timeout, async move {
+                source.fetch_validator_set(height, proposer_address).await
             })?
-            .map_err(IoError::rpc)?;
-
-            let validator_set = match proposer_address {
-                Some(proposer_address) => {
-                    TMValidatorSet::with_proposer(response.validators, proposer_address)
-                        .map_err(IoError::invalid_validator_set)?
-                },
-                None => TMValidatorSet::without_proposer(response.validators),
-            };
-
-            Ok(validator_set)
         }
     }
 }
@@ -0,0 +1,339 @@
+//! Provides an async interface for sources of light blocks, along with
+//! implementations fetching light blocks from a full node or replaying them
+//! from an archive.
+//!
+//! Any [`LightBlockSource`] can back a light client, through the
+//! [`LightClientBuilder::light_block_source`] method of the builder.
+//!
+//! No source fetches light blocks through the gRPC services of CometBFT 1.x
+//! yet, as their definitions are not part of `tendermint-proto`.
+//!
+//! [`LightClientBuilder::light_block_source`]: crate::builder::LightClientBuilder::light_block_source
+
+use std::{collections::BTreeMap, fs, path::Path};
+
+use async_trait::async_trait;
+
+use crate::{
+    components::io::{AtHeight, IoError},
+    verifier::types::{Height, LightBlock},
+};
+
+/// Async interface for fetching light blocks.
+#[async_trait]
+pub trait LightBlockSource: Send + Sync {
+    /// Fetch the light block at the given height
+    async fn fetch_light_block(&self, height: AtHeight) -> Result<LightBlock, IoError>;
+}
+
+#[cfg(feature = "rpc-client")]
+pub use self::rpc_source::{RpcSource, SourceIo};
+
+#[cfg(feature = "rpc-client")]
+mod rpc_source {
+    use std::{sync::Arc, time::Duration};
+
+    use tendermint::{account, block::signed_header::SignedHeader, chain, validator, Hash};
+    use tendermint_rpc::{Client, Paging, ValidatorSetCache};
+
+    use super::*;
+    use crate::{components::io::Io, utils::block_on, verifier::types::PeerId};
+
+    /// A source fetching light blocks from a full node, through any RPC
+    /// [`Client`].
+    #[derive(Clone, Debug)]
+    pub struct RpcSource<C> {
+        peer_id: PeerId,
+        client: C,
+        validator_set_cache: Option<ValidatorSetCache>,
+    }
+
+    impl<C> RpcSource<C> {
+        /// Constructs a source fetching light blocks from the given peer,
+        /// through the given client.
+        pub fn new(peer_id: PeerId, client: C) -> Self {
+            Self {
+                peer_id,
+                client,
+                validator_set_cache: None,
+            }
+        }
+
+        /// Consult the given cache before fetching validator sets, and cache
+        /// the validator sets fetched.
+        ///
+        /// The cache can be shared with other components, such as the
+        /// sources of the witnesses.
+        pub fn with_validator_set_cache(mut self, cache: ValidatorSetCache) -> Self {
+            self.validator_set_cache = Some(cache);
+            self
+        }
+
+        pub fn peer_id(&self) -> PeerId {
+            self.peer_id
+        }
+
+        pub fn client(&self) -> &C {
+            &self.client
+        }
+
+        pub fn validator_set_cache(&self) -> Option<&ValidatorSetCache> {
+            self.validator_set_cache.as_ref()
+        }
+    }
+
+    impl<C: Client + Send + Sync> RpcSource<C> {
+        pub async fn fetch_signed_header(&self, height: AtHeight) -> Result<SignedHeader, IoError> {
+            let response = match height {
+                AtHeight::Highest => self.client.latest_commit().await,
+                AtHeight::At(height) => self.client.commit(height).await,
+            }
+            .map_err(IoError::from_rpc)?;
+
+            Ok(response.signed_header)
+        }
+
+        pub async fn fetch_validator_set(
+            &self,
+            height: Height,
+            proposer_address: Option<account::Id>,
+        ) -> Result<validator::Set, IoError> {
+            let response = self
+                .client
+                .validators(height, Paging::All)
+                .await
+                .map_err(IoError::rpc)?;
+
+            match proposer_address {
+                Some(proposer_address) => {
+                    validator::Set::with_proposer(response.validators, proposer_address)
+                        .map_err(IoError::invalid_validator_set)
+                },
+                None => Ok(validator::Set::without_proposer(response.validators)),
+            }
+        }
+
+        /// Fetch the validator set expected to have the given hash at the
+        /// given height, serving it from the validator set cache if it holds
+        /// it.
+        async fn fetch_validator_set_with_hash(
+            &self,
+            chain_id: &chain::Id,
+            height: Height,
+            hash: Hash,
+            proposer_address: Option<account::Id>,
+        ) -> Result<validator::Set, IoError> {
+            let Some(cache) = &self.validator_set_cache else {
+                return self.fetch_validator_set(height, proposer_address).await;
+            };
+
+            let validator_set = self
+                .client
+                .validator_set(chain_id, height, hash, cache)
+                .await
+                .map_err(IoError::rpc)?;
+
+            match proposer_address {
+                Some(proposer_address) => validator_set
+                    .into_with_proposer(proposer_address)
+                    .map_err(IoError::invalid_validator_set),
+                None => Ok(validator_set),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl<C: Client + Send + Sync> LightBlockSource for RpcSource<C> {
+        async fn fetch_light_block(&self, height: AtHeight) -> Result<LightBlock, IoError> {
+            let signed_header = self.fetch_signed_header(height).await?;
+            let header = &signed_header.header;
+            let height = header.height;
+
+            let validator_set = self
+                .fetch_validator_set_with_hash(
+                    &header.chain_id,
+                    height,
+                    header.validators_hash,
+                    Some(header.proposer_address),
+                )
+                .await?;
+            let next_validator_set = self
+                .fetch_validator_set_with_hash(
+                    &header.chain_id,
+                    height.increment(),
+                    header.next_validators_hash,
+                    None,
+                )
+                .await?;
+
+            Ok(LightBlock::new(
+                signed_header,
+                validator_set,
+                next_validator_set,
+                self.peer_id,
+            ))
+        }
+    }
+
+    /// Implementation of the [`Io`] component on top of a [`LightBlockSource`],
+    /// which runs the requests to the source to completion with the given
+    /// timeout.
+    pub struct SourceIo<S> {
+        source: Arc<S>,
+        timeout: Option<Duration>,
+    }
+
+    impl<S> SourceIo<S> {
+        /// Constructs a new `SourceIo` component.
+        pub fn new(source: S, timeout: Option<Duration>) -> Self {
+            Self {
+                source: Arc::new(source),
+                timeout,
+            }
+        }
+
+        pub fn source(&self) -> &S {
+            &self.source
+        }
+
+        pub fn timeout(&self) -> Option<Duration> {
+            self.timeout
+        }
+    }
+
+    impl<S> Clone for SourceIo<S> {
+        fn clone(&self) -> Self {
+            Self {
+                source: self.source.clone(),
+                timeout: self.timeout,
+            }
+        }
+    }
+
+    impl<S: LightBlockSource + 'static> Io for SourceIo<S> {
+        fn fetch_light_block(&self, height: AtHeight) -> Result<LightBlock, IoError> {
+            let source = self.source.clone();
+            block_on(self.timeout, async move {
+                source.fetch_light_block(height).await
+            })?
+        }
+    }
+}
+
+/// A source replaying light blocks from an archive, for example to test a
+/// light client against light blocks recorded from a live network.
+///
+/// Archives are stored as JSON documents, holding either a single light block
+/// or an array of light blocks. An archive can also be read from a directory,
+/// in which case every file with a `.json` extension is part of the archive.
+#[derive(Clone, Debug, Default)]
+pub struct ArchiveSource {
+    light_blocks: BTreeMap<Height, LightBlock>,
+}
+
+impl ArchiveSource {
+    /// Constructs an archive holding the given light blocks.
+    pub fn new(light_blocks: impl IntoIterator<Item = LightBlock>) -> Self {
+        let mut archive = Self::default();
+        archive.extend(light_blocks);
+        archive
+    }
+
+    /// Reads the archive at the given path, which is either a file or a
+    /// directory.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, IoError> {
+        let path = path.as_ref();
+        let mut archive = Self::default();
+        if path.is_dir() {
+            let entries =
+                fs::read_dir(path).map_err(|e| IoError::archive_read(display(path), e))?;
+            for entry in entries {
+                let entry = entry.map_err(|e| IoError::archive_read(display(path), e))?;
+                let file = entry.path();
+                if file.extension().is_some_and(|ext| ext == "json") {
+                    archive.extend(read_file(&file)?);
+                }
+            }
+        } else {
+            archive.extend(read_file(path)?);
+        }
+        Ok(archive)
+    }
+
+    /// Writes the light blocks of the archive to the given file, as a JSON
+    /// array sorted by height.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), IoError> {
+        let path = path.as_ref();
+        let light_blocks: Vec<&LightBlock> = self.light_blocks.values().collect();
+        let json = serde_json::to_vec_pretty(&light_blocks)
+            .map_err(|e| IoError::invalid_archive(display(path), e))?;
+        fs::write(path, json).map_err(|e| IoError::archive_write(display(path), e))
+    }
+
+    /// Adds a light block to the archive, replacing any light block at the
+    /// same height.
+    pub fn insert(&mut self, light_block: LightBlock) {
+        self.light_blocks.insert(light_block.height(), light_block);
+    }
+
+    /// The light block at the given height, if any.
+    pub fn get(&self, height: Height) -> Option<&LightBlock> {
+        self.light_blocks.get(&height)
+    }
+
+    /// The height of the highest light block of the archive, if any.
+    pub fn latest_height(&self) -> Option<Height> {
+        self.light_blocks.keys().next_back().copied()
+    }
+
+    /// The number of light blocks of the archive.
+    pub fn len(&self) -> usize {
+        self.light_blocks.len()
+    }
+
+    /// Whether the archive holds no light block.
+    pub fn is_empty(&self) -> bool {
+        self.light_blocks.is_empty()
+    }
+}
+
+impl Extend<LightBlock> for ArchiveSource {
+    fn extend<T: IntoIterator<Item = LightBlock>>(&mut self, light_blocks: T) {
+        for light_block in light_blocks {
+            self.insert(light_block);
+        }
+    }
+}
+
+#[async_trait]
+impl LightBlockSource for ArchiveSource {
+    async fn fetch_light_block(&self, height: AtHeight) -> Result<LightBlock, IoError> {
+        let latest_height = self.latest_height().ok_or_else(IoError::empty_archive)?;
+        let height = match height {
+            AtHeight::At(height) => height,
+            AtHeight::Highest => latest_height,
+        };
+        if height > latest_height {
+            return Err(IoError::height_too_high(height, latest_height));
+        }
+        self.get(height)
+            .cloned()
+            .ok_or_else(|| IoError::light_block_not_found(height))
+    }
+}
+
+fn display(path: &Path) -> String {
+    path.display().to_string()
+}
+
+fn read_file(path: &Path) -> Result<Vec<LightBlock>, IoError> {
+    let json = fs::read(path).map_err(|e| IoError::archive_read(display(path), e))?;
+    let value: serde_json::Value =
+        serde_json::from_slice(&json).map_err(|e| IoError::invalid_archive(display(path), e))?;
+    if value.is_array() {
+        serde_json::from_value(value)
+    } else {
+        serde_json::from_value(value).map(|light_block| vec![light_block])
+    }
+    .map_err(|e| IoError::invalid_archive(display(path), e))
+}
@@ -13,7 +13,7 @@ use crate::{
         errors::VerificationErrorDetail,
         operations::voting_power::VotingPowerTally,
         options::Options,
-        types::{Hash, Height, LightBlock, PeerId, Status},
+        types::{Hash, Height, LightBlock, PeerId, Status, Time},
     },
 };
 
@@ -85,6 +85,16 @@ define_error! {
                 format_args!("trusted state outside of trusting period")
             },
 
+        InvalidOptions
+            {
+                options: Options,
+                reason: String,
+            }
+            | e | {
+                format_args!("invalid options {0}: {1}",
+                    e.options, e.reason)
+            },
+
         BisectionFailed
             {
                 target_height: Height,
@@ -116,6 +126,45 @@ define_error! {
                     e.height)
             },
 
+        MisbehaviourChainIdMismatch
+            {
+                chain_id1: String,
+                chain_id2: String,
+            }
+            | e | {
+                format_args!("conflicting headers belong to different chains: {0} != {1}",
+                    e.chain_id1, e.chain_id2)
+            },
+
+        MisbehaviourHeightOrder
+            {
+                height1: Height,
+                height2: Height,
+            }
+            | e | {
+                format_args!("first conflicting header at height {0} is lower than second conflicting header at height {1}",
+                    e.height1, e.height2)
+            },
+
+        MisbehaviourNoTimeViolation
+            {
+                height1: Height,
+                time1: Time,
+                height2: Height,
+                time2: Time,
+            }
+            | e | {
+                format_args!("first conflicting header at height {0} is later in time ({1}) than second conflicting header at height {2} ({3})",
+                    e.height1, e.time1, e.height2, e.time2)
+            },
+
+        MisbehaviourIdenticalHeaders
+            { height: Height }
+            | e | {
+                format_args!("headers at height {0} are identical and do not conflict",
+                    e.height)
+            },
+
         ChannelDisconnected
             | _ | { "internal channel disconnected" },
 
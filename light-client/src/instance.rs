@@ -4,7 +4,7 @@ use tendermint::block::Height;
 
 use crate::{
     errors::Error,
-    light_client::LightClient,
+    light_client::{LightClient, Options},
     state::State,
     verifier::types::{LightBlock, Status},
 };
@@ -33,6 +33,13 @@ impl Instance {
         &self.light_client.peer
     }
 
+    /// Replace the options of the light client of this instance.
+    ///
+    /// See [`LightClient::update_options`].
+    pub fn update_options(&mut self, options: Options) -> Result<(), Error> {
+        self.light_client.update_options(options)
+    }
+
     /// Get the latest trusted block.
     pub fn latest_trusted(&self) -> Option<LightBlock> {
         self.state.light_store.highest(Status::Trusted)
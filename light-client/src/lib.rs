@@ -21,6 +21,10 @@ pub mod contracts;
 pub mod errors;
 pub mod instance;
 pub mod light_client;
+pub mod relayer;
+#[cfg(any(test, feature = "simulation"))]
+#[cfg_attr(docsrs, doc(cfg(feature = "simulation")))]
+pub mod simulation;
 pub mod state;
 pub mod store;
 
@@ -89,6 +89,28 @@ impl LightClient {
         }
     }
 
+    /// Replace the options of the light client, e.g. to follow a change of
+    /// the unbonding period of the chain, after checking that they are valid.
+    ///
+    /// The new options apply to the verifications started afterwards. The
+    /// trusting period must be non-zero and longer than the clock drift.
+    pub fn update_options(&mut self, options: Options) -> Result<(), Error> {
+        if options.trusting_period.is_zero() {
+            return Err(Error::invalid_options(
+                options,
+                "the trusting period is zero".to_string(),
+            ));
+        }
+        if options.clock_drift >= options.trusting_period {
+            return Err(Error::invalid_options(
+                options,
+                "the clock drift is not shorter than the trusting period".to_string(),
+            ));
+        }
+        self.options = options;
+        Ok(())
+    }
+
     /// Attempt to update the light client to the highest block of the primary node.
     ///
     /// Note: This function delegates the actual work to `verify_to_target`.
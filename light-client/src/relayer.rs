@@ -0,0 +1,257 @@
+//! Packaging of the outputs of the light client into the artifacts submitted
+//! by relayers to the IBC light clients tracking the chain on counterparty
+//! chains.
+//!
+//! An IBC light client is updated with [`UpdateHeader`]s, each carrying a
+//! header along with the height of the consensus state it was verified
+//! against, and is frozen with a [`Misbehaviour`] made of two conflicting
+//! headers.
+
+use serde::{Deserialize, Serialize};
+use tendermint::{block::signed_header::SignedHeader, evidence::LightClientAttackEvidence};
+
+use crate::{
+    errors::Error,
+    state::State,
+    verifier::types::{Height, LightBlock, ValidatorSet},
+};
+
+/// A header to update an IBC light client with, verified against the
+/// consensus state of the client at the trusted height.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UpdateHeader {
+    /// The new header, with the commit for it
+    pub signed_header: SignedHeader,
+    /// The validator set which signed the new header
+    pub validator_set: ValidatorSet,
+    /// The height of the consensus state the new header is verified against
+    pub trusted_height: Height,
+    /// The validator set of the block following the one at the trusted height,
+    /// ie. the validator set expected to sign the block after it
+    pub trusted_next_validator_set: ValidatorSet,
+}
+
+impl UpdateHeader {
+    /// Package the given untrusted light block into an update verified
+    /// against the given trusted light block.
+    pub fn new(untrusted: &LightBlock, trusted: &LightBlock) -> Result<Self, Error> {
+        if untrusted.height() < trusted.height() {
+            return Err(Error::target_lower_than_trusted_state(
+                untrusted.height(),
+                trusted.height(),
+            ));
+        }
+
+        Ok(Self {
+            signed_header: untrusted.signed_header.clone(),
+            validator_set: untrusted.validators.clone(),
+            trusted_height: trusted.height(),
+            trusted_next_validator_set: trusted.next_validators.clone(),
+        })
+    }
+
+    /// Package the given verification trace, sorted by height, into the
+    /// sequence of updates which brings an IBC light client from the first
+    /// light block of the trace to the last one.
+    ///
+    /// Every light block of the trace is verified against the previous one,
+    /// matching the traces recorded by the light client in its [`State`].
+    pub fn from_trace(trace: &[LightBlock]) -> Result<Vec<Self>, Error> {
+        trace
+            .windows(2)
+            .map(|pair| Self::new(&pair[1], &pair[0]))
+            .collect()
+    }
+
+    /// The height of the new header.
+    pub fn height(&self) -> Height {
+        self.signed_header.header.height
+    }
+}
+
+/// Two conflicting headers, which freeze an IBC light client.
+///
+/// Either both headers are at the same height with different hashes (a fork),
+/// or the first header is at a higher height than the second one while not
+/// being later in time (a violation of the monotonicity of BFT time).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Misbehaviour {
+    /// The first header
+    pub header1: UpdateHeader,
+    /// The second header
+    pub header2: UpdateHeader,
+}
+
+impl Misbehaviour {
+    /// Constructs a misbehaviour from the given conflicting headers.
+    pub fn new(header1: UpdateHeader, header2: UpdateHeader) -> Result<Self, Error> {
+        let (chain_id1, chain_id2) = (
+            &header1.signed_header.header.chain_id,
+            &header2.signed_header.header.chain_id,
+        );
+        if chain_id1 != chain_id2 {
+            return Err(Error::misbehaviour_chain_id_mismatch(
+                chain_id1.to_string(),
+                chain_id2.to_string(),
+            ));
+        }
+
+        if header1.height() < header2.height() {
+            return Err(Error::misbehaviour_height_order(
+                header1.height(),
+                header2.height(),
+            ));
+        }
+
+        if header1.height() == header2.height()
+            && header1.signed_header.header.hash() == header2.signed_header.header.hash()
+        {
+            return Err(Error::misbehaviour_identical_headers(header1.height()));
+        }
+
+        // Headers at different heights only conflict if the higher one is
+        // not later in time, otherwise consecutive honest headers would be
+        // accepted as evidence.
+        let (time1, time2) = (
+            header1.signed_header.header.time,
+            header2.signed_header.header.time,
+        );
+        if header1.height() > header2.height() && time1 > time2 {
+            return Err(Error::misbehaviour_no_time_violation(
+                header1.height(),
+                time1,
+                header2.height(),
+                time2,
+            ));
+        }
+
+        Ok(Self { header1, header2 })
+    }
+
+    /// Constructs a misbehaviour from two conflicting light blocks, both
+    /// verified against the same common light block.
+    pub fn from_conflicting_blocks(
+        common: &LightBlock,
+        block1: &LightBlock,
+        block2: &LightBlock,
+    ) -> Result<Self, Error> {
+        Self::new(
+            UpdateHeader::new(block1, common)?,
+            UpdateHeader::new(block2, common)?,
+        )
+    }
+
+    /// Constructs a misbehaviour from the evidence of a light client attack,
+    /// as gathered by the detector, along with the common light block the
+    /// evidence refers to, and the light block conflicting with the one of the
+    /// evidence.
+    pub fn from_attack_evidence(
+        evidence: &LightClientAttackEvidence,
+        common: &LightBlock,
+        conflicting: &LightBlock,
+    ) -> Result<Self, Error> {
+        let attacking = &evidence.conflicting_block;
+        let header1 = UpdateHeader {
+            signed_header: attacking.signed_header.clone(),
+            validator_set: attacking.validator_set.clone(),
+            trusted_height: common.height(),
+            trusted_next_validator_set: common.next_validators.clone(),
+        };
+
+        Self::new(header1, UpdateHeader::new(conflicting, common)?)
+    }
+}
+
+impl State {
+    /// Package the verification trace of the block at the given height into
+    /// the sequence of updates which brings an IBC light client from the
+    /// lowest trusted block of the trace to the target block.
+    ///
+    /// See [`UpdateHeader::from_trace`].
+    pub fn get_update_headers(&self, target_height: Height) -> Result<Vec<UpdateHeader>, Error> {
+        UpdateHeader::from_trace(&self.get_trace(target_height))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tendermint_testgen::{
+        helpers::get_time, light_block::LightBlock as TestgenLightBlock, Generator,
+    };
+
+    use super::*;
+    use crate::errors::ErrorDetail;
+
+    fn light_block(height: u64, time: u64, chain_id: &str) -> LightBlock {
+        let lb = TestgenLightBlock::new_default_with_time_and_chain_id(
+            chain_id.to_owned(),
+            get_time(time).unwrap(),
+            height,
+        )
+        .generate()
+        .unwrap();
+
+        LightBlock::new(
+            lb.signed_header,
+            lb.validators,
+            lb.next_validators,
+            lb.provider,
+        )
+    }
+
+    #[test]
+    fn update_headers_from_trace() {
+        let trace: Vec<_> = [1, 3, 7]
+            .into_iter()
+            .map(|height| light_block(height, height, "test-chain"))
+            .collect();
+
+        let updates = UpdateHeader::from_trace(&trace).unwrap();
+        assert_eq!(updates.len(), 2);
+        assert_eq!(updates[0].height(), trace[1].height());
+        assert_eq!(updates[0].trusted_height, trace[0].height());
+        assert_eq!(updates[1].height(), trace[2].height());
+        assert_eq!(updates[1].trusted_height, trace[1].height());
+        assert_eq!(
+            updates[1].trusted_next_validator_set,
+            trace[1].next_validators
+        );
+
+        assert!(UpdateHeader::new(&trace[0], &trace[1]).is_err());
+    }
+
+    #[test]
+    fn misbehaviour_from_conflicting_blocks() {
+        let common = light_block(1, 1, "test-chain");
+        let block1 = light_block(5, 5, "test-chain");
+        let block2 = light_block(5, 6, "test-chain");
+
+        let misbehaviour =
+            Misbehaviour::from_conflicting_blocks(&common, &block1, &block2).unwrap();
+        assert_eq!(misbehaviour.header1.trusted_height, common.height());
+        assert_eq!(misbehaviour.header2.trusted_height, common.height());
+
+        // Identical headers
+        assert!(Misbehaviour::from_conflicting_blocks(&common, &block1, &block1).is_err());
+        // Headers of different chains
+        let other_chain = light_block(5, 6, "other-chain");
+        assert!(Misbehaviour::from_conflicting_blocks(&common, &block1, &other_chain).is_err());
+        // Second header higher than the first one
+        let higher = light_block(6, 4, "test-chain");
+        assert!(Misbehaviour::from_conflicting_blocks(&common, &block1, &higher).is_err());
+        assert!(Misbehaviour::from_conflicting_blocks(&common, &higher, &block1).is_ok());
+    }
+
+    #[test]
+    fn misbehaviour_refuses_consecutive_honest_headers() {
+        let common = light_block(1, 1, "test-chain");
+        let block1 = light_block(5, 5, "test-chain");
+        let block2 = light_block(6, 6, "test-chain");
+
+        let err = Misbehaviour::from_conflicting_blocks(&common, &block2, &block1).unwrap_err();
+        assert!(matches!(
+            err.detail(),
+            ErrorDetail::MisbehaviourNoTimeViolation(_)
+        ));
+    }
+}
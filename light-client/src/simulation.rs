@@ -0,0 +1,457 @@
+//! Deterministic simulation of a network of nodes serving light blocks.
+//!
+//! Every [`SimNode`] serves the light blocks of a chain, typically generated with
+//! `tendermint-testgen`, and implements [`Io`] so that it can back a [`LightClient`].
+//! Nodes can be forked to serve conflicting light blocks, and scripted to fail requests
+//! at given heights or at given points in time, measured in number of requests served.
+//! No actual networking or waiting is involved, so that end-to-end scenarios involving
+//! a primary and several witnesses are reproducible.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    ops::Range,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use tendermint::node::Id as PeerId;
+use tendermint_rpc as rpc;
+
+use crate::{
+    components::io::{AtHeight, Io, IoError},
+    verifier::types::{Height, LightBlock, Time},
+};
+#[cfg(feature = "rust-crypto")]
+use crate::{
+    components::scheduler, instance::Instance, light_client::LightClient, state::State,
+    store::memory::MemoryStore, store::LightStore, tests::MockClock, verifier::options::Options,
+    verifier::types::Status, verifier::ProdVerifier,
+};
+
+/// A fault a [`SimNode`] exhibits when serving a request.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Fault {
+    /// The request times out after the given duration.
+    Timeout(Duration),
+    /// The node is unreachable.
+    Unavailable,
+    /// The node lags behind, and does not know of any block above the given height.
+    Stale(Height),
+    /// The node serves the given light block instead of the requested one.
+    Serve(Box<LightBlock>),
+}
+
+#[derive(Debug, Default)]
+struct NodeState {
+    blocks: BTreeMap<Height, LightBlock>,
+    faults_at_height: HashMap<Height, Fault>,
+    faults_on_requests: Vec<(Range<usize>, Fault)>,
+    requests: Vec<Option<Height>>,
+}
+
+/// A synthetic node serving the light blocks of a chain.
+///
+/// Clones of a node share their state, so that a node handed over to a light client
+/// can still be forked, scripted, and inspected by the test driving the simulation.
+#[derive(Clone, Debug)]
+pub struct SimNode {
+    peer_id: PeerId,
+    state: Arc<Mutex<NodeState>>,
+}
+
+impl SimNode {
+    /// Creates a node with the given peer id, serving the given light blocks.
+    ///
+    /// The provider of the light blocks is set to the peer id of the node.
+    pub fn new(peer_id: PeerId, light_blocks: impl IntoIterator<Item = LightBlock>) -> Self {
+        let node = Self {
+            peer_id,
+            state: Default::default(),
+        };
+        node.fork(light_blocks);
+        node
+    }
+
+    /// The peer id of this node.
+    pub fn peer_id(&self) -> PeerId {
+        self.peer_id
+    }
+
+    /// Replaces the light blocks served at the heights of the given light blocks,
+    /// e.g. to serve a conflicting chain from some height on.
+    pub fn fork(&self, light_blocks: impl IntoIterator<Item = LightBlock>) {
+        let mut state = self.state.lock().unwrap();
+        for mut light_block in light_blocks {
+            light_block.provider = self.peer_id;
+            state.blocks.insert(light_block.height(), light_block);
+        }
+    }
+
+    /// Stops serving the light blocks above the given height.
+    pub fn truncate(&self, height: Height) {
+        let mut state = self.state.lock().unwrap();
+        state.blocks.retain(|h, _| *h <= height);
+    }
+
+    /// Fails all requests for the light block at the given height with the given fault.
+    pub fn fault_at_height(&self, height: Height, fault: Fault) {
+        let mut state = self.state.lock().unwrap();
+        state.faults_at_height.insert(height, fault);
+    }
+
+    /// Fails the requests with the given sequence numbers, starting from 0,
+    /// with the given fault.
+    pub fn fault_on_requests(&self, requests: Range<usize>, fault: Fault) {
+        let mut state = self.state.lock().unwrap();
+        state.faults_on_requests.push((requests, fault));
+    }
+
+    /// Removes all scripted faults.
+    pub fn heal(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.faults_at_height.clear();
+        state.faults_on_requests.clear();
+    }
+
+    /// The heights requested from this node so far, `None` standing for the latest height.
+    pub fn requests(&self) -> Vec<Option<Height>> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    /// The latest height served by this node.
+    pub fn latest_height(&self) -> Option<Height> {
+        let state = self.state.lock().unwrap();
+        state.blocks.keys().next_back().copied()
+    }
+
+    /// The light block served at the given height, disregarding any fault.
+    pub fn light_block(&self, height: Height) -> Option<LightBlock> {
+        self.state.lock().unwrap().blocks.get(&height).cloned()
+    }
+
+    /// Creates a light client instance fetching from this node, trusting the
+    /// light block the node serves at the given height.
+    #[cfg(feature = "rust-crypto")]
+    pub fn instance(&self, trusted_height: Height, options: Options, now: Time) -> Instance {
+        let trusted = self
+            .light_block(trusted_height)
+            .expect("no light block at the trusted height");
+        self.instance_trusting(trusted, options, now)
+    }
+
+    /// Creates a light client instance fetching from this node, trusting the given light block.
+    #[cfg(feature = "rust-crypto")]
+    pub fn instance_trusting(
+        &self,
+        mut trusted: LightBlock,
+        options: Options,
+        now: Time,
+    ) -> Instance {
+        trusted.provider = self.peer_id;
+        let mut light_store = MemoryStore::new();
+        light_store.insert(trusted, Status::Trusted);
+
+        let light_client = LightClient::new(
+            self.peer_id,
+            options,
+            MockClock { now },
+            scheduler::basic_bisecting_schedule,
+            ProdVerifier::default(),
+            self.clone(),
+        );
+        Instance::new(light_client, State::new(light_store))
+    }
+
+    fn fault(state: &NodeState, request: usize, height: Option<Height>) -> Option<Fault> {
+        let on_request = state
+            .faults_on_requests
+            .iter()
+            .find(|(requests, _)| requests.contains(&request))
+            .map(|(_, fault)| fault);
+        let at_height = height.and_then(|height| state.faults_at_height.get(&height));
+        on_request.or(at_height).cloned()
+    }
+}
+
+impl Io for SimNode {
+    fn fetch_light_block(&self, height: AtHeight) -> Result<LightBlock, IoError> {
+        let mut state = self.state.lock().unwrap();
+
+        let latest_height = state.blocks.keys().next_back().copied();
+        let height = match height {
+            AtHeight::At(height) => Some(height),
+            AtHeight::Highest => None,
+        };
+        let request = state.requests.len();
+        state.requests.push(height);
+
+        let latest_height = match Self::fault(&state, request, height) {
+            None => latest_height,
+            Some(Fault::Timeout(duration)) => {
+                return Err(IoError::rpc(rpc::Error::timeout(duration)))
+            },
+            Some(Fault::Unavailable) => {
+                return Err(IoError::rpc(rpc::Error::client_internal(format!(
+                    "node {} is unavailable",
+                    self.peer_id
+                ))))
+            },
+            Some(Fault::Serve(light_block)) => return Ok(*light_block),
+            Some(Fault::Stale(stale_height)) => latest_height.map(|h| h.min(stale_height)),
+        };
+
+        let latest_height = latest_height.ok_or_else(|| {
+            IoError::rpc(rpc::Error::client_internal(format!(
+                "node {} has no blocks",
+                self.peer_id
+            )))
+        })?;
+        let height = height.unwrap_or(latest_height);
+        if height > latest_height {
+            return Err(IoError::height_too_high(height, latest_height));
+        }
+        state.blocks.get(&height).cloned().ok_or_else(|| {
+            IoError::rpc(rpc::Error::client_internal(format!(
+                "node {} has no block at height {}",
+                self.peer_id, height
+            )))
+        })
+    }
+}
+
+/// A set of [`SimNode`]s, with one of them acting as the primary.
+#[derive(Clone, Debug)]
+pub struct SimNetwork {
+    primary: SimNode,
+    witnesses: Vec<SimNode>,
+}
+
+impl SimNetwork {
+    /// Creates a network of the given primary and witnesses.
+    pub fn new(primary: SimNode, witnesses: Vec<SimNode>) -> Self {
+        Self { primary, witnesses }
+    }
+
+    /// The primary node.
+    pub fn primary(&self) -> &SimNode {
+        &self.primary
+    }
+
+    /// The witness nodes.
+    pub fn witnesses(&self) -> &[SimNode] {
+        &self.witnesses
+    }
+
+    /// The node with the given peer id.
+    pub fn node(&self, peer_id: PeerId) -> Option<&SimNode> {
+        core::iter::once(&self.primary)
+            .chain(&self.witnesses)
+            .find(|node| node.peer_id == peer_id)
+    }
+
+    /// Creates light client instances for the primary and the witnesses, in this order,
+    /// all trusting the light block the primary serves at the given height.
+    #[cfg(feature = "rust-crypto")]
+    pub fn instances(
+        &self,
+        trusted_height: Height,
+        options: Options,
+        now: Time,
+    ) -> (Instance, Vec<Instance>) {
+        let trusted = self
+            .primary
+            .light_block(trusted_height)
+            .expect("no light block at the trusted height");
+
+        let instance = |node: &SimNode| node.instance_trusting(trusted.clone(), options, now);
+        (
+            instance(&self.primary),
+            self.witnesses.iter().map(instance).collect(),
+        )
+    }
+}
+
+#[cfg(all(test, feature = "rust-crypto"))]
+mod tests {
+    use std::time::Duration;
+
+    use tendermint_testgen::{light_block::TmLightBlock, Chain, Generator, LightClientAttack};
+
+    use super::*;
+    use crate::{components::io::IoErrorDetail, errors::ErrorDetail};
+
+    fn to_light_block(lb: TmLightBlock) -> LightBlock {
+        LightBlock {
+            signed_header: lb.signed_header,
+            validators: lb.validators,
+            next_validators: lb.next_validators,
+            provider: lb.provider,
+        }
+    }
+
+    fn peer(n: u8) -> PeerId {
+        PeerId::new([n; 20])
+    }
+
+    fn chain(length: u64) -> Vec<LightBlock> {
+        Chain::new(length)
+            .generate()
+            .unwrap()
+            .into_iter()
+            .map(to_light_block)
+            .collect()
+    }
+
+    fn options() -> Options {
+        Options {
+            trust_threshold: Default::default(),
+            trusting_period: Duration::from_secs(60 * 60),
+            clock_drift: Duration::from_secs(10),
+            initial_height: None,
+        }
+    }
+
+    fn now() -> Time {
+        tendermint_testgen::helpers::get_time(100).unwrap()
+    }
+
+    #[test]
+    fn serves_chain() {
+        let node = SimNode::new(peer(1), chain(5));
+        let lb = node.fetch_light_block(AtHeight::Highest).unwrap();
+        assert_eq!(lb.height().value(), 5);
+        assert_eq!(lb.provider, peer(1));
+
+        let err = node
+            .fetch_light_block(AtHeight::At(Height::from(6_u32)))
+            .unwrap_err();
+        assert!(matches!(err.detail(), IoErrorDetail::HeightTooHigh(_)));
+        assert_eq!(node.requests(), vec![None, Some(Height::from(6_u32))]);
+    }
+
+    #[test]
+    fn scripted_faults() {
+        let node = SimNode::new(peer(1), chain(5));
+        node.fault_on_requests(0..1, Fault::Timeout(Duration::from_secs(5)));
+        node.fault_at_height(Height::from(3_u32), Fault::Unavailable);
+
+        let err = node.fetch_light_block(AtHeight::Highest).unwrap_err();
+        assert!(matches!(err.detail(), IoErrorDetail::Rpc(_)));
+        assert!(node.fetch_light_block(AtHeight::Highest).is_ok());
+        assert!(node
+            .fetch_light_block(AtHeight::At(Height::from(3_u32)))
+            .is_err());
+
+        node.heal();
+        node.fault_on_requests(0..usize::MAX, Fault::Stale(Height::from(2_u32)));
+        let lb = node.fetch_light_block(AtHeight::Highest).unwrap();
+        assert_eq!(lb.height().value(), 2);
+    }
+
+    #[test]
+    fn light_client_over_faulty_node() {
+        let node = SimNode::new(peer(1), chain(10));
+        let mut instance = node.instance(Height::from(1_u32), options(), now());
+
+        node.fault_at_height(Height::from(10_u32), Fault::Unavailable);
+        let err = instance
+            .light_client
+            .verify_to_target(Height::from(10_u32), &mut instance.state)
+            .unwrap_err();
+        assert!(matches!(err.detail(), ErrorDetail::Io(_)));
+
+        node.heal();
+        let lb = instance
+            .light_client
+            .verify_to_target(Height::from(10_u32), &mut instance.state)
+            .unwrap();
+        assert_eq!(lb.height().value(), 10);
+    }
+
+    #[test]
+    fn updates_options_between_verifications() {
+        let node = SimNode::new(peer(1), chain(10));
+        let mut instance = node.instance(Height::from(1_u32), options(), now());
+
+        // The trusted block expires with a shorter trusting period.
+        instance
+            .update_options(Options {
+                trusting_period: Duration::from_secs(20),
+                ..options()
+            })
+            .unwrap();
+        let err = instance
+            .light_client
+            .verify_to_target(Height::from(10_u32), &mut instance.state)
+            .unwrap_err();
+        assert!(matches!(
+            err.detail(),
+            ErrorDetail::TrustedStateOutsideTrustingPeriod(_)
+        ));
+
+        for invalid in [
+            Options {
+                trusting_period: Duration::ZERO,
+                ..options()
+            },
+            Options {
+                clock_drift: Duration::from_secs(60 * 60),
+                ..options()
+            },
+        ] {
+            let err = instance.update_options(invalid).unwrap_err();
+            assert!(matches!(err.detail(), ErrorDetail::InvalidOptions(_)));
+        }
+        assert_eq!(
+            instance.light_client.options.trusting_period,
+            Duration::from_secs(20)
+        );
+
+        instance.update_options(options()).unwrap();
+        let lb = instance
+            .light_client
+            .verify_to_target(Height::from(10_u32), &mut instance.state)
+            .unwrap();
+        assert_eq!(lb.height().value(), 10);
+    }
+
+    #[test]
+    fn forked_witness() {
+        let fixture = LightClientAttack::new(tendermint_testgen::AttackKind::Lunatic)
+            .generate()
+            .unwrap();
+        let trusted = to_light_block(fixture.trusted);
+        let honest = to_light_block(fixture.honest);
+        let conflicting = to_light_block(fixture.conflicting);
+        let height = honest.height();
+
+        let primary = SimNode::new(peer(1), [trusted.clone(), honest.clone()]);
+        let witness = SimNode::new(peer(2), [trusted, honest]);
+        witness.fork([conflicting.clone()]);
+
+        let network = SimNetwork::new(primary, vec![witness]);
+        let (mut primary, mut witnesses) = network.instances(Height::from(1_u32), options(), now());
+
+        let from_primary = primary
+            .light_client
+            .verify_to_target(height, &mut primary.state)
+            .unwrap();
+        let witness = &mut witnesses[0];
+        let from_witness = witness
+            .light_client
+            .verify_to_target(height, &mut witness.state)
+            .unwrap();
+
+        assert_eq!(from_primary.provider, peer(1));
+        assert_eq!(from_witness.provider, peer(2));
+        assert_ne!(
+            from_primary.signed_header.header.hash(),
+            from_witness.signed_header.header.hash()
+        );
+        assert_eq!(
+            from_witness.signed_header.header.hash(),
+            conflicting.signed_header.header.hash()
+        );
+        assert!(!network.node(peer(2)).unwrap().requests().is_empty());
+    }
+}
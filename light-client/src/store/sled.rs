@@ -3,6 +3,8 @@
 pub mod utils;
 use std::path::Path;
 
+#[cfg(feature = "rpc-client")]
+use tendermint_rpc::client::sink::{Checkpoint, CheckpointStore};
 use utils::HeightIndexedDb;
 
 use super::{LightStore, Status};
@@ -12,6 +14,8 @@ const UNVERIFIED: &str = "unverified";
 const VERIFIED: &str = "verified";
 const TRUSTED: &str = "trusted";
 const FAILED: &str = "failed";
+#[cfg(feature = "rpc-client")]
+const CHECKPOINTS: &str = "checkpoints";
 
 /// Persistent store backed by an on-disk `sled` database.
 #[derive(Debug, Clone)]
@@ -92,6 +96,58 @@ impl LightStore for SledStore {
     }
 }
 
+/// Stores the checkpoint of an event [`Pipeline`] in the sled database of a
+/// [`SledStore`], so that indexing resumes along with the light client.
+///
+/// The checkpoints of several pipelines are kept apart by their name.
+///
+/// [`Pipeline`]: tendermint_rpc::client::sink::Pipeline
+#[cfg(feature = "rpc-client")]
+#[derive(Debug, Clone)]
+pub struct SledCheckpointStore {
+    tree: sled::Tree,
+    name: String,
+}
+
+#[cfg(feature = "rpc-client")]
+impl SledCheckpointStore {
+    /// Store the checkpoint of the pipeline with the given name in the given
+    /// sled database.
+    pub fn new(db: &sled::Db, name: impl Into<String>) -> Result<Self, sled::Error> {
+        Ok(Self {
+            tree: db.open_tree(CHECKPOINTS)?,
+            name: name.into(),
+        })
+    }
+}
+
+#[cfg(feature = "rpc-client")]
+impl CheckpointStore for SledCheckpointStore {
+    fn load(&self) -> Result<Option<Checkpoint>, tendermint_rpc::Error> {
+        match self.tree.get(&self.name).map_err(checkpoint_error)? {
+            Some(bytes) => serde_cbor::from_slice(&bytes)
+                .map(Some)
+                .map_err(checkpoint_error),
+            None => Ok(None),
+        }
+    }
+
+    fn save(&mut self, checkpoint: Checkpoint) -> Result<(), tendermint_rpc::Error> {
+        let bytes = serde_cbor::to_vec(&checkpoint).map_err(checkpoint_error)?;
+        self.tree
+            .insert(&self.name, bytes)
+            .map_err(checkpoint_error)?;
+        // The checkpoint must be durable before more events are delivered.
+        self.tree.flush().map_err(checkpoint_error)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "rpc-client")]
+fn checkpoint_error(e: impl std::fmt::Display) -> tendermint_rpc::Error {
+    tendermint_rpc::Error::checkpoint(e.to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use tempfile::tempdir;
@@ -141,6 +197,27 @@ mod tests {
         })
     }
 
+    #[cfg(feature = "rpc-client")]
+    #[test]
+    fn checkpoints_are_persisted() {
+        let tmp_dir = tempdir().unwrap();
+        let db = sled::open(&tmp_dir).unwrap();
+        let mut store = SledCheckpointStore::new(&db, "events").unwrap();
+        assert_eq!(store.load().unwrap(), None);
+
+        let checkpoint = Checkpoint {
+            height: Height::from(7_u32),
+        };
+        store.save(checkpoint).unwrap();
+        let other = SledCheckpointStore::new(&db, "other").unwrap();
+        assert_eq!(other.load().unwrap(), None);
+        drop((store, other, db));
+
+        let db = sled::open(&tmp_dir).unwrap();
+        let store = SledCheckpointStore::new(&db, "events").unwrap();
+        assert_eq!(store.load().unwrap(), Some(checkpoint));
+    }
+
     fn with_blocks(height: u64, f: impl FnOnce(SledStore, Vec<LightBlock>)) {
         let tmp_dir = tempdir().unwrap();
         let db = SledStore::open(tmp_dir).unwrap();
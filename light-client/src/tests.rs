@@ -142,6 +142,7 @@ pub fn verify_single(
         trust_threshold,
         trusting_period,
         clock_drift,
+        initial_height: None,
     };
 
     let result = verifier.verify_update_header(
@@ -54,6 +54,7 @@ fn make(chain: LightChain, trusted_height: Height) -> (LightClient, State) {
         trust_threshold: Default::default(),
         trusting_period: Duration::from_secs(60 * 60 * 24 * 10),
         clock_drift: Duration::from_secs(10),
+        initial_height: None,
     };
 
     let light_blocks = chain
@@ -0,0 +1,95 @@
+#![cfg(feature = "rpc-client")]
+
+use std::time::Duration;
+
+use tendermint_light_client::{
+    builder::LightClientBuilder,
+    components::{
+        io::{AtHeight, IoError},
+        scheduler,
+        source::{ArchiveSource, LightBlockSource},
+    },
+    store::memory::MemoryStore,
+    tests::MockClock,
+    verifier::{
+        options::Options,
+        predicates::ProdPredicates,
+        types::{Height, LightBlock},
+        ProdVerifier,
+    },
+};
+use tendermint_testgen::{helpers::get_time, light_block::default_peer_id, Chain, Generator};
+
+fn light_blocks(length: u64) -> Vec<LightBlock> {
+    Chain::new(length)
+        .generate()
+        .unwrap()
+        .into_iter()
+        .map(|lb| LightBlock {
+            signed_header: lb.signed_header,
+            validators: lb.validators,
+            next_validators: lb.next_validators,
+            provider: lb.provider,
+        })
+        .collect()
+}
+
+#[test]
+fn archive_round_trip() {
+    let light_blocks = light_blocks(5);
+    let dir = tempfile::tempdir().unwrap();
+    ArchiveSource::new(light_blocks[..3].to_vec())
+        .save(dir.path().join("first.json"))
+        .unwrap();
+    ArchiveSource::new(light_blocks[3..].to_vec())
+        .save(dir.path().join("second.json"))
+        .unwrap();
+    std::fs::write(dir.path().join("README"), "not part of the archive").unwrap();
+
+    let archive = ArchiveSource::load(dir.path()).unwrap();
+    assert_eq!(archive.len(), 5);
+    assert_eq!(archive.latest_height(), Some(Height::from(5_u32)));
+
+    let highest = futures::executor::block_on(archive.fetch_light_block(AtHeight::Highest));
+    assert_eq!(highest.unwrap(), light_blocks[4]);
+    let too_high =
+        futures::executor::block_on(archive.fetch_light_block(Height::from(6_u32).into()));
+    assert!(too_high.is_err());
+}
+
+#[test]
+fn light_client_over_archive() {
+    let light_blocks = light_blocks(10);
+    let trusted_hash = light_blocks[0].signed_header.header.hash();
+
+    let options = Options {
+        trust_threshold: Default::default(),
+        trusting_period: Duration::from_secs(60 * 60 * 24 * 10),
+        clock_drift: Duration::from_secs(10),
+        initial_height: None,
+    };
+    let clock = MockClock {
+        now: get_time(11).unwrap(),
+    };
+
+    let mut instance = LightClientBuilder::custom(
+        default_peer_id(),
+        options,
+        Box::new(MemoryStore::new()),
+        Box::new(|_: AtHeight| Err(IoError::empty_archive())),
+        Box::new(clock),
+        Box::new(ProdVerifier::default()),
+        Box::new(scheduler::basic_bisecting_schedule),
+        Box::new(ProdPredicates),
+    )
+    .light_block_source(ArchiveSource::new(light_blocks.clone()), None)
+    .trust_primary_at(Height::from(1_u32), trusted_hash)
+    .unwrap()
+    .build();
+
+    let verified = instance
+        .light_client
+        .verify_to_highest(&mut instance.state)
+        .unwrap();
+    assert_eq!(verified, light_blocks[9]);
+}
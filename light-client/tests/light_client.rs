@@ -44,6 +44,7 @@ fn run_test(tc: LightClientTest<LightBlock>) -> BisectionTestResult {
         trust_threshold,
         trusting_period: trusting_period.into(),
         clock_drift,
+        initial_height: None,
     };
 
     let provider = tc.primary;
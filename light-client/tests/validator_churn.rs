@@ -0,0 +1,107 @@
+use std::{collections::HashMap, time::Duration};
+
+use tendermint_light_client::{
+    components::{
+        io::{AtHeight, Io},
+        scheduler,
+    },
+    errors::Error,
+    light_client::LightClient,
+    state::State,
+    store::{memory::MemoryStore, LightStore},
+    tests::{MockClock, MockIo},
+    verifier::{
+        options::Options,
+        types::{Height, LightBlock, Status},
+        ProdVerifier,
+    },
+};
+use tendermint_testgen::{
+    helpers::get_time, light_block::default_peer_id, Chain, Generator, ValidatorChange,
+};
+
+fn verify(chain: Chain, target_height: u64) -> (Result<LightBlock, Error>, State) {
+    let length = chain.length.unwrap();
+    let light_blocks: Vec<LightBlock> = chain
+        .generate()
+        .unwrap()
+        .into_iter()
+        .map(|lb| LightBlock {
+            signed_header: lb.signed_header,
+            validators: lb.validators,
+            next_validators: lb.next_validators,
+            provider: lb.provider,
+        })
+        .collect();
+    let io = MockIo::new(light_blocks);
+
+    let trusted_state = io
+        .fetch_light_block(AtHeight::At(Height::from(1_u32)))
+        .expect("could not find trusted light block");
+    let mut light_store = MemoryStore::new();
+    light_store.insert(trusted_state, Status::Trusted);
+    let mut state = State {
+        light_store: Box::new(light_store),
+        verification_trace: HashMap::new(),
+    };
+
+    let options = Options {
+        trust_threshold: Default::default(),
+        trusting_period: Duration::from_secs(60 * 60 * 24 * 10),
+        clock_drift: Duration::from_secs(10),
+        initial_height: None,
+    };
+    let clock = MockClock {
+        now: get_time(length + 1).unwrap(),
+    };
+    let light_client = LightClient::new(
+        default_peer_id(),
+        options,
+        clock,
+        scheduler::basic_bisecting_schedule,
+        ProdVerifier::default(),
+        io,
+    );
+
+    let result =
+        light_client.verify_to_target(Height::try_from(target_height).unwrap(), &mut state);
+    (result, state)
+}
+
+#[test]
+fn skipping_verification_over_gradual_churn() {
+    // A single validator out of four is replaced every other block,
+    // so that every pair of adjacent sets shares more than 1/3 of the voting power.
+    let chain = Chain::new(10)
+        .change(ValidatorChange::new(3).leave("a").join("e", 50))
+        .change(ValidatorChange::new(5).leave("b").join("f", 50))
+        .change(ValidatorChange::new(7).power("c", 100))
+        .change(ValidatorChange::new(9).leave("d").join("g", 50));
+
+    let (result, _) = verify(chain, 10);
+    assert_eq!(result.unwrap().height().value(), 10);
+}
+
+#[test]
+fn bisection_over_full_rotation() {
+    // The whole validator set is replaced halfway, which forces the light client
+    // to bisect down to the last block signed by the original validators.
+    let full_rotation = ["a", "b", "c", "d"]
+        .iter()
+        .fold(ValidatorChange::new(6), |change, id| change.leave(id))
+        .join("w", 10)
+        .join("x", 20)
+        .join("y", 30)
+        .join("z", 40);
+    let chain = Chain::new(10).change(full_rotation);
+
+    let (result, state) = verify(chain, 10);
+    assert_eq!(result.unwrap().height().value(), 10);
+
+    let trace: Vec<u64> = state
+        .get_trace(Height::from(10_u32))
+        .iter()
+        .map(|lb| lb.height().value())
+        .collect();
+    assert!(trace.contains(&5), "{trace:?}");
+}
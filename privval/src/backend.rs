@@ -0,0 +1,348 @@
+//! Integration points for signing backends holding keys outside of the
+//! process, such as HSMs or cloud KMS services.
+//!
+//! Such backends typically expose nothing more than a raw signing operation
+//! over arbitrary bytes. [`RemoteSignerBackend`] captures that operation,
+//! and [`BackendSigner`] adapts any backend into a [`Signer`] usable with the
+//! privval [`Server`], computing the canonical sign bytes of the votes and
+//! proposals it is asked to sign.
+//!
+//! [`Server`]: crate::Server
+
+use core::future::Future;
+
+use async_trait::async_trait;
+use tendermint::{
+    chain, privval::RemoteSignerError, proposal::SignProposalRequest, vote::SignVoteRequest,
+    Proposal, PublicKey, Signature, Vote,
+};
+use tendermint_proto::v0_38::types::CanonicalVoteExtension as RawCanonicalVoteExtension;
+
+use crate::{Error, Signer};
+
+/// A backend producing raw signatures over canonical sign bytes.
+#[async_trait]
+pub trait RemoteSignerBackend: Send + Sync {
+    /// Return the consensus public key used to sign on the given chain.
+    async fn public_key(&self, chain_id: &chain::Id) -> Result<PublicKey, RemoteSignerError>;
+
+    /// Sign the given canonical sign bytes of a message for the given chain.
+    async fn sign(
+        &self,
+        chain_id: &chain::Id,
+        sign_bytes: &[u8],
+    ) -> Result<Signature, RemoteSignerError>;
+}
+
+/// Compute the canonical bytes to be signed for the vote in the given
+/// request.
+pub fn vote_sign_bytes(request: &SignVoteRequest) -> Vec<u8> {
+    request.clone().into_signable_vec()
+}
+
+/// Compute the canonical bytes to be signed for the extension of the given
+/// vote, as introduced in CometBFT 0.38.
+pub fn vote_extension_sign_bytes(vote: &Vote, chain_id: &chain::Id) -> Vec<u8> {
+    use prost::Message as _;
+
+    RawCanonicalVoteExtension {
+        extension: vote.extension.clone(),
+        height: vote.height.into(),
+        round: i32::from(vote.round).into(),
+        chain_id: chain_id.to_string(),
+    }
+    .encode_length_delimited_to_vec()
+}
+
+/// Whether the given vote has to carry a signature of its extension, which
+/// is the case for non-nil precommits.
+pub fn vote_has_extension_signature(vote: &Vote) -> bool {
+    vote.is_precommit() && vote.block_id.is_some()
+}
+
+/// Compute the canonical bytes to be signed for the proposal in the given
+/// request.
+pub fn proposal_sign_bytes(request: &SignProposalRequest) -> Vec<u8> {
+    request.clone().into_signable_vec()
+}
+
+/// A [`RemoteSignerBackend`] producing Ed25519 signatures through any
+/// implementation of [`signature::Signer`], which is the interface exposed by
+/// e.g. HSM client libraries.
+pub struct Ed25519Backend<S> {
+    signer: S,
+    public_key: PublicKey,
+}
+
+impl<S> Ed25519Backend<S>
+where
+    S: signature::Signer<ed25519::Signature> + Send + Sync,
+{
+    /// Construct a backend from the given signer and its public key.
+    pub fn new(signer: S, public_key: PublicKey) -> Self {
+        Self { signer, public_key }
+    }
+}
+
+#[async_trait]
+impl<S> RemoteSignerBackend for Ed25519Backend<S>
+where
+    S: signature::Signer<ed25519::Signature> + Send + Sync,
+{
+    async fn public_key(&self, _chain_id: &chain::Id) -> Result<PublicKey, RemoteSignerError> {
+        Ok(self.public_key)
+    }
+
+    async fn sign(
+        &self,
+        _chain_id: &chain::Id,
+        sign_bytes: &[u8],
+    ) -> Result<Signature, RemoteSignerError> {
+        self.signer
+            .try_sign(sign_bytes)
+            .map(Into::into)
+            .map_err(|e| RemoteSignerError {
+                code: 0,
+                description: format!("signing failed: {e}"),
+            })
+    }
+}
+
+/// A [`RemoteSignerBackend`] producing Secp256k1 ECDSA signatures through any
+/// implementation of [`signature::Signer`].
+///
+/// The signer is expected to hash the sign bytes with SHA-256 and to produce
+/// normalized (low-S) signatures, as CometBFT only accepts these, which is
+/// the behavior of the `k256` signing keys.
+#[cfg(feature = "secp256k1")]
+pub struct Secp256k1Backend<S> {
+    signer: S,
+    public_key: PublicKey,
+}
+
+#[cfg(feature = "secp256k1")]
+impl<S> Secp256k1Backend<S>
+where
+    S: signature::Signer<k256::ecdsa::Signature> + Send + Sync,
+{
+    /// Construct a backend from the given signer and its public key.
+    pub fn new(signer: S, public_key: PublicKey) -> Self {
+        Self { signer, public_key }
+    }
+}
+
+#[cfg(feature = "secp256k1")]
+#[async_trait]
+impl<S> RemoteSignerBackend for Secp256k1Backend<S>
+where
+    S: signature::Signer<k256::ecdsa::Signature> + Send + Sync,
+{
+    async fn public_key(&self, _chain_id: &chain::Id) -> Result<PublicKey, RemoteSignerError> {
+        Ok(self.public_key)
+    }
+
+    async fn sign(
+        &self,
+        _chain_id: &chain::Id,
+        sign_bytes: &[u8],
+    ) -> Result<Signature, RemoteSignerError> {
+        let signature = self
+            .signer
+            .try_sign(sign_bytes)
+            .map_err(|e| RemoteSignerError {
+                code: 0,
+                description: format!("signing failed: {e}"),
+            })?;
+        Ok(signature.normalize_s().unwrap_or(signature).into())
+    }
+}
+
+/// Adapts a [`RemoteSignerBackend`] into a [`Signer`], driving the backend's
+/// futures to completion on a dedicated single-threaded runtime.
+///
+/// The signer can be used from within an asynchronous context, in which case
+/// the backend's futures are driven on a separate thread, blocking the
+/// calling thread until they complete.
+///
+/// This adapter performs no double signing protection of its own.
+pub struct BackendSigner<B> {
+    backend: B,
+    // Only taken on drop.
+    runtime: Option<tokio::runtime::Runtime>,
+}
+
+impl<B: RemoteSignerBackend> BackendSigner<B> {
+    /// Construct a signer delegating to the given backend.
+    pub fn new(backend: B) -> Result<Self, Error> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(Error::io)?;
+        Ok(Self {
+            backend,
+            runtime: Some(runtime),
+        })
+    }
+
+    /// Borrow the underlying backend.
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    fn block_on<F>(&self, future: F) -> F::Output
+    where
+        F: Future + Send,
+        F::Output: Send,
+    {
+        let runtime = self
+            .runtime
+            .as_ref()
+            .expect("runtime is only taken on drop");
+        // A runtime cannot be entered from a thread already driving one.
+        if tokio::runtime::Handle::try_current().is_err() {
+            return runtime.block_on(future);
+        }
+        std::thread::scope(|scope| {
+            scope
+                .spawn(|| runtime.block_on(future))
+                .join()
+                .unwrap_or_else(|e| std::panic::resume_unwind(e))
+        })
+    }
+}
+
+impl<B> Drop for BackendSigner<B> {
+    fn drop(&mut self) {
+        // Dropping a runtime blocks, which is not allowed from within an
+        // asynchronous context, but no task is left running on this one.
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+impl<B: RemoteSignerBackend> Signer for BackendSigner<B> {
+    fn public_key(&self, chain_id: &chain::Id) -> Result<PublicKey, RemoteSignerError> {
+        self.block_on(self.backend.public_key(chain_id))
+    }
+
+    fn sign_vote(&mut self, request: SignVoteRequest) -> Result<Vote, RemoteSignerError> {
+        let sign_bytes = vote_sign_bytes(&request);
+        let SignVoteRequest { mut vote, chain_id } = request;
+        vote.signature = Some(self.block_on(self.backend.sign(&chain_id, &sign_bytes))?);
+        if vote_has_extension_signature(&vote) {
+            let sign_bytes = vote_extension_sign_bytes(&vote, &chain_id);
+            vote.extension_signature =
+                Some(self.block_on(self.backend.sign(&chain_id, &sign_bytes))?);
+        }
+        Ok(vote)
+    }
+
+    fn sign_proposal(
+        &mut self,
+        request: SignProposalRequest,
+    ) -> Result<Proposal, RemoteSignerError> {
+        let sign_bytes = proposal_sign_bytes(&request);
+        let SignProposalRequest {
+            mut proposal,
+            chain_id,
+        } = request;
+        proposal.signature = Some(self.block_on(self.backend.sign(&chain_id, &sign_bytes))?);
+        Ok(proposal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tendermint::{
+        account,
+        block::{self, parts::Header as PartSetHeader, Round},
+        crypto::{default::signature::Verifier, signature::Verifier as _},
+        hash::{Algorithm, Hash},
+        vote::{self, ValidatorIndex},
+        Time,
+    };
+
+    use super::*;
+
+    /// Stands in for an HSM-backed key.
+    struct TestKey(ed25519_consensus::SigningKey);
+
+    impl signature::Signer<ed25519::Signature> for TestKey {
+        fn try_sign(&self, msg: &[u8]) -> Result<ed25519::Signature, signature::Error> {
+            Ok(ed25519::Signature::from_bytes(&self.0.sign(msg).to_bytes()))
+        }
+    }
+
+    fn signer() -> BackendSigner<Ed25519Backend<TestKey>> {
+        let key = ed25519_consensus::SigningKey::from([7u8; 32]);
+        let public_key = PublicKey::from_raw_ed25519(key.verification_key().as_bytes()).unwrap();
+        BackendSigner::new(Ed25519Backend::new(TestKey(key), public_key)).unwrap()
+    }
+
+    fn vote_request(chain_id: &chain::Id) -> SignVoteRequest {
+        let hash = Hash::from_bytes(Algorithm::Sha256, &[1; 32]).unwrap();
+        SignVoteRequest {
+            vote: Vote {
+                vote_type: vote::Type::Precommit,
+                height: block::Height::from(3_u32),
+                round: Round::default(),
+                block_id: Some(block::Id {
+                    hash,
+                    part_set_header: PartSetHeader::new(1, hash).unwrap(),
+                }),
+                timestamp: Some(Time::unix_epoch()),
+                validator_address: account::Id::new([1; 20]),
+                validator_index: ValidatorIndex::try_from(0_u32).unwrap(),
+                signature: None,
+                extension: b"extension".to_vec(),
+                extension_signature: None,
+            },
+            chain_id: chain_id.clone(),
+        }
+    }
+
+    fn assert_signs_vote_and_extension<B: RemoteSignerBackend>(mut signer: BackendSigner<B>) {
+        let chain_id: chain::Id = "test-chain".parse().unwrap();
+        let request = vote_request(&chain_id);
+        let public_key = signer.public_key(&chain_id).unwrap();
+
+        let vote = signer.sign_vote(request.clone()).unwrap();
+        Verifier::verify(
+            public_key,
+            &vote_sign_bytes(&request),
+            vote.signature.as_ref().unwrap(),
+        )
+        .unwrap();
+        Verifier::verify(
+            public_key,
+            &vote_extension_sign_bytes(&vote, &chain_id),
+            vote.extension_signature.as_ref().unwrap(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn signs_vote_and_extension() {
+        assert_signs_vote_and_extension(signer());
+    }
+
+    #[test]
+    fn signs_from_within_a_runtime() {
+        let signer = signer();
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(async { assert_signs_vote_and_extension(signer) });
+    }
+
+    #[cfg(feature = "secp256k1")]
+    #[test]
+    fn signs_vote_and_extension_with_secp256k1() {
+        let key = k256::ecdsa::SigningKey::from_slice(&[7u8; 32]).unwrap();
+        let public_key = PublicKey::from(*key.verifying_key());
+        assert_signs_vote_and_extension(
+            BackendSigner::new(Secp256k1Backend::new(key, public_key)).unwrap(),
+        );
+    }
+}
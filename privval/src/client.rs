@@ -0,0 +1,131 @@
+//! Blocking remote signer client, playing the role of the validator node.
+//!
+//! This is primarily useful for testing signer implementations without
+//! running a full node.
+
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::{
+    io::{Read, Write},
+    net::{TcpListener, TcpStream},
+};
+
+use tendermint::{
+    chain,
+    proposal::{SignProposalRequest, SignedProposalResponse},
+    public_key::{PubKeyRequest, PubKeyResponse},
+    vote::{SignVoteRequest, SignedVoteResponse},
+};
+use tendermint_p2p::secret_connection::{SecretConnection, Version};
+
+use crate::{codec::Codec, Error, Request, Response};
+
+/// The size of the read buffer for the client in its receiving of responses
+/// from the signer.
+pub const DEFAULT_CLIENT_READ_BUF_SIZE: usize = 1024;
+
+/// Builder for a blocking remote signer client.
+pub struct ClientBuilder {
+    read_buf_size: usize,
+}
+
+impl ClientBuilder {
+    /// Builder constructor.
+    pub fn new(read_buf_size: usize) -> Self {
+        Self { read_buf_size }
+    }
+
+    /// Wait for a signer to dial in on the given TCP listener, and secure
+    /// the connection with a [`SecretConnection`] authenticated by the given
+    /// identity key.
+    pub fn accept_tcp(
+        self,
+        listener: &TcpListener,
+        identity_key: ed25519_consensus::SigningKey,
+    ) -> Result<Client<SecretConnection<TcpStream>>, Error> {
+        let (stream, _) = listener.accept().map_err(Error::io)?;
+        let conn = SecretConnection::new(stream, identity_key, Version::V0_34)
+            .map_err(Error::secret_connection)?;
+        Ok(self.build(conn))
+    }
+
+    /// Wait for a signer to dial in on the given Unix domain socket listener.
+    #[cfg(unix)]
+    pub fn accept_unix(self, listener: &UnixListener) -> Result<Client<UnixStream>, Error> {
+        let (stream, _) = listener.accept().map_err(Error::io)?;
+        Ok(self.build(stream))
+    }
+
+    /// Construct a client communicating over an already established stream.
+    pub fn build<Io: Read + Write>(self, stream: Io) -> Client<Io> {
+        Client {
+            codec: Codec::new(stream, self.read_buf_size),
+        }
+    }
+}
+
+impl Default for ClientBuilder {
+    fn default() -> Self {
+        Self {
+            read_buf_size: DEFAULT_CLIENT_READ_BUF_SIZE,
+        }
+    }
+}
+
+/// Blocking remote signer client.
+pub struct Client<Io> {
+    codec: Codec<Io>,
+}
+
+macro_rules! perform {
+    ($self:expr, $type:ident, $req:expr) => {
+        match $self.perform($req)? {
+            Response::$type(r) => Ok(r),
+            r => Err(Error::unexpected_message_type(
+                stringify!($type).to_string(),
+                format!("{:?}", r),
+            )),
+        }
+    };
+}
+
+impl<Io: Read + Write> Client<Io> {
+    /// Request the consensus public key used by the signer for the given
+    /// chain.
+    pub fn pub_key(&mut self, chain_id: chain::Id) -> Result<PubKeyResponse, Error> {
+        perform!(self, PubKey, Request::PubKey(PubKeyRequest { chain_id }))
+    }
+
+    /// Request a signature for the given vote.
+    pub fn sign_vote(&mut self, req: SignVoteRequest) -> Result<SignedVoteResponse, Error> {
+        perform!(self, SignedVote, Request::SignVote(req))
+    }
+
+    /// Request a signature for the given proposal.
+    pub fn sign_proposal(
+        &mut self,
+        req: SignProposalRequest,
+    ) -> Result<SignedProposalResponse, Error> {
+        perform!(self, SignedProposal, Request::SignProposal(req))
+    }
+
+    /// Check that the signer is alive.
+    pub fn ping(&mut self) -> Result<(), Error> {
+        match self.perform(Request::Ping)? {
+            Response::Ping => Ok(()),
+            r => Err(Error::unexpected_message_type(
+                "Ping".to_string(),
+                format!("{:?}", r),
+            )),
+        }
+    }
+
+    fn perform(&mut self, req: Request) -> Result<Response, Error> {
+        self.codec.send(req.into())?;
+        let res = self
+            .codec
+            .next()
+            .ok_or_else(Error::connection_terminated)??;
+        Response::try_from(res)
+    }
+}
@@ -0,0 +1,187 @@
+//! Encoding/decoding mechanisms for privval messages.
+//!
+//! Each message is a protobuf-encoded `privval.Message`, prefixed with its
+//! length encoded as an unsigned varint.
+
+use std::io::{Read, Write};
+
+use bytes::{Buf, BufMut, BytesMut};
+use prost::Message as _;
+use tendermint_proto::v0_38::privval::Message;
+
+use crate::error::Error;
+
+/// The maximum number of bytes we expect in a varint. We use this to check if
+/// we're encountering a decoding error for a varint.
+pub const MAX_VARINT_LENGTH: usize = 16;
+
+/// The maximum length of a privval message, in bytes. Longer frames are
+/// rejected before they are buffered.
+pub const MAX_MSG_SIZE_BYTES: usize = 1024 * 1024;
+
+/// Allows for iteration over `S` to produce incoming privval messages, as
+/// well as sending outgoing ones.
+pub struct Codec<S> {
+    stream: S,
+    // Long-running read buffer
+    read_buf: BytesMut,
+    // Fixed-length read window
+    read_window: Vec<u8>,
+    write_buf: BytesMut,
+}
+
+impl<S> Codec<S>
+where
+    S: Read + Write,
+{
+    /// Constructor.
+    pub fn new(stream: S, read_buf_size: usize) -> Self {
+        Self {
+            stream,
+            read_buf: BytesMut::new(),
+            read_window: vec![0_u8; read_buf_size],
+            write_buf: BytesMut::new(),
+        }
+    }
+}
+
+// Iterating over a codec produces instances of `Result<Message>`.
+impl<S> Iterator for Codec<S>
+where
+    S: Read,
+{
+    type Item = Result<Message, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            // Try to decode an incoming message from our buffer first
+            match decode_length_delimited(&mut self.read_buf) {
+                Ok(Some(incoming)) => return Some(Ok(incoming)),
+                Err(e) => return Some(Err(e)),
+                _ => (), // not enough data to decode a message, let's continue.
+            }
+
+            // If we don't have enough data to decode a message, try to read
+            // more
+            let bytes_read = match self.stream.read(self.read_window.as_mut()) {
+                Ok(br) => br,
+                // A `SecretConnection` reads whole frames, and reports a
+                // closed connection as an unexpected EOF
+                Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return None,
+                Err(e) => return Some(Err(Error::io(e))),
+            };
+            if bytes_read == 0 {
+                // The underlying stream terminated
+                return None;
+            }
+            self.read_buf
+                .extend_from_slice(&self.read_window[..bytes_read]);
+        }
+    }
+}
+
+impl<S> Codec<S>
+where
+    S: Write,
+{
+    /// Send a message using this codec.
+    pub fn send(&mut self, message: Message) -> Result<(), Error> {
+        encode_length_delimited(message, &mut self.write_buf)?;
+        while !self.write_buf.is_empty() {
+            let bytes_written = self
+                .stream
+                .write(self.write_buf.as_ref())
+                .map_err(Error::io)?;
+
+            if bytes_written == 0 {
+                return Err(Error::io(std::io::Error::new(
+                    std::io::ErrorKind::WriteZero,
+                    "failed to write to underlying stream",
+                )));
+            }
+            self.write_buf.advance(bytes_written);
+        }
+
+        self.stream.flush().map_err(Error::io)?;
+
+        Ok(())
+    }
+}
+
+/// Encode the given message with a length prefix.
+pub fn encode_length_delimited<B>(message: Message, mut dst: &mut B) -> Result<(), Error>
+where
+    B: BufMut,
+{
+    let mut buf = BytesMut::new();
+    message.encode(&mut buf).map_err(Error::encode)?;
+
+    let buf = buf.freeze();
+    prost::encoding::encode_varint(buf.len() as u64, &mut dst);
+    dst.put(buf);
+    Ok(())
+}
+
+/// Attempt to decode a message from the given source buffer.
+///
+/// Fails on messages longer than [`MAX_MSG_SIZE_BYTES`], as soon as their
+/// length prefix is received.
+pub fn decode_length_delimited(src: &mut BytesMut) -> Result<Option<Message>, Error> {
+    let src_len = src.len();
+    let mut tmp = src.clone().freeze();
+    let encoded_len = match prost::encoding::decode_varint(&mut tmp) {
+        Ok(len) => len,
+        // We've potentially only received a partial length delimiter
+        Err(_) if src_len <= MAX_VARINT_LENGTH => return Ok(None),
+        Err(e) => return Err(Error::decode(e)),
+    };
+    if encoded_len > MAX_MSG_SIZE_BYTES as u64 {
+        return Err(Error::message_too_large(encoded_len, MAX_MSG_SIZE_BYTES));
+    }
+    let remaining = tmp.remaining() as u64;
+    if remaining < encoded_len {
+        // We don't have enough data yet to decode the entire message
+        Ok(None)
+    } else {
+        let delim_len = src_len - tmp.remaining();
+        // We only advance the source buffer once we're sure we have enough
+        // data to try to decode the result.
+        src.advance(delim_len + (encoded_len as usize));
+
+        let mut result_bytes = BytesMut::from(tmp.split_to(encoded_len as usize).as_ref());
+        let res = Message::decode(&mut result_bytes).map_err(Error::decode)?;
+
+        Ok(Some(res))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tendermint_proto::v0_38::privval::{message::Sum, PingRequest};
+
+    use super::*;
+
+    #[test]
+    fn decodes_length_delimited_messages() {
+        let message = Message {
+            sum: Some(Sum::PingRequest(PingRequest {})),
+        };
+        let mut buf = BytesMut::new();
+        encode_length_delimited(message.clone(), &mut buf).unwrap();
+        let mut partial = BytesMut::from(&buf[..buf.len() - 1]);
+        assert!(decode_length_delimited(&mut partial).unwrap().is_none());
+        assert_eq!(decode_length_delimited(&mut buf).unwrap(), Some(message));
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn rejects_oversized_frames() {
+        let mut buf = BytesMut::new();
+        prost::encoding::encode_varint(MAX_MSG_SIZE_BYTES as u64 + 1, &mut buf);
+        let err = decode_length_delimited(&mut buf).unwrap_err();
+        assert!(matches!(
+            err.detail(),
+            crate::error::ErrorDetail::MessageTooLarge(_)
+        ));
+    }
+}
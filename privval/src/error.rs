@@ -0,0 +1,71 @@
+//! tendermint-privval errors
+
+use flex_error::{define_error, DisplayError};
+
+define_error! {
+    Error {
+        Io
+            [ DisplayError<std::io::Error> ]
+            | _ | { "I/O error" },
+
+        Encode
+            [ DisplayError<prost::EncodeError> ]
+            | _ | { "error encoding protocol buffer" },
+
+        Decode
+            [ DisplayError<prost::DecodeError> ]
+            | _ | { "error decoding protocol buffer" },
+
+        Tendermint
+            [ tendermint::Error ]
+            | _ | { "invalid privval message" },
+
+        Config
+            [ tendermint_config::Error ]
+            | _ | { "failed to load validator key" },
+
+        InvalidKey
+            { detail: String }
+            | e | { format_args!("invalid key: {}", e.detail) },
+
+        FileIo
+            { path: String }
+            [ DisplayError<std::io::Error> ]
+            | e | { format_args!("I/O error on file: {}", e.path) },
+
+        SerdeJson
+            [ DisplayError<serde_json::Error> ]
+            | _ | { "malformed signing state" },
+
+        DoubleSign
+            { detail: String }
+            | e | { format_args!("double signing protection: {}", e.detail) },
+
+        SecretConnection
+            [ tendermint_p2p::error::Error ]
+            | _ | { "failed to establish secret connection" },
+
+        ConnectionTerminated
+            | _ | { "connection terminated" },
+
+        MalformedMessage
+            | _ | { "malformed privval message" },
+
+        MessageTooLarge
+            { length: u64, max: usize }
+            | e | {
+                format_args!("privval message of {} bytes exceeds the maximum of {} bytes",
+                    e.length, e.max)
+            },
+
+        UnexpectedMessageType
+            {
+                expected: String,
+                got: String,
+            }
+            | e | {
+                format_args!("unexpected privval message type: expected {0}, but got {1}",
+                    e.expected, e.got)
+            },
+    }
+}
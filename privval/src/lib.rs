@@ -0,0 +1,31 @@
+//! Remote signer (privval) framework for building signing services for
+//! [Tendermint] validators in Rust.
+//!
+//! Implements the wire protocol that CometBFT uses to talk to a remote
+//! signer configured through `priv_validator_laddr`. The validator node
+//! listens on the configured address, and the signer dials into it, either
+//! via TCP (secured by a [`SecretConnection`]) or via a Unix domain socket.
+//!
+//! [Tendermint]: https://tendermint.com
+//! [`SecretConnection`]: tendermint_p2p::secret_connection::SecretConnection
+
+pub mod backend;
+#[cfg(feature = "client")]
+mod client;
+mod codec;
+pub mod error;
+mod message;
+mod server;
+mod signer;
+mod software;
+pub mod state;
+
+pub use backend::{BackendSigner, RemoteSignerBackend};
+#[cfg(feature = "client")]
+pub use client::{Client, ClientBuilder};
+pub use error::Error;
+pub use message::{Request, Response};
+pub use server::{Server, ServerBuilder};
+pub use signer::Signer;
+pub use software::SoftwareSigner;
+pub use state::ValidatorSigningState;
@@ -0,0 +1,123 @@
+//! Domain types for the messages exchanged over the privval protocol.
+
+use tendermint::{
+    proposal::{SignProposalRequest, SignedProposalResponse},
+    public_key::{PubKeyRequest, PubKeyResponse},
+    vote::{SignVoteRequest, SignedVoteResponse},
+};
+use tendermint_proto::v0_38::privval::{message::Sum, Message, PingRequest, PingResponse};
+
+use crate::Error;
+
+/// A request sent by the validator node to the remote signer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Request {
+    /// Request for the signer's consensus public key.
+    PubKey(PubKeyRequest),
+    /// Request to sign a vote.
+    SignVote(SignVoteRequest),
+    /// Request to sign a proposal.
+    SignProposal(SignProposalRequest),
+    /// Keep-alive request.
+    Ping,
+}
+
+/// A response sent by the remote signer back to the validator node.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Response {
+    /// The signer's consensus public key, or an error.
+    PubKey(PubKeyResponse),
+    /// A signed vote, or an error.
+    SignedVote(SignedVoteResponse),
+    /// A signed proposal, or an error.
+    SignedProposal(SignedProposalResponse),
+    /// Keep-alive response.
+    Ping,
+}
+
+/// Returns a human-readable name for the kind of message, for diagnostics.
+pub(crate) fn message_kind(message: &Message) -> &'static str {
+    match message.sum {
+        Some(Sum::PubKeyRequest(_)) => "PubKeyRequest",
+        Some(Sum::PubKeyResponse(_)) => "PubKeyResponse",
+        Some(Sum::SignVoteRequest(_)) => "SignVoteRequest",
+        Some(Sum::SignedVoteResponse(_)) => "SignedVoteResponse",
+        Some(Sum::SignProposalRequest(_)) => "SignProposalRequest",
+        Some(Sum::SignedProposalResponse(_)) => "SignedProposalResponse",
+        Some(Sum::PingRequest(_)) => "PingRequest",
+        Some(Sum::PingResponse(_)) => "PingResponse",
+        None => "empty message",
+    }
+}
+
+impl TryFrom<Message> for Request {
+    type Error = Error;
+
+    fn try_from(message: Message) -> Result<Self, Self::Error> {
+        match message.sum {
+            Some(Sum::PubKeyRequest(req)) => {
+                Ok(Request::PubKey(req.try_into().map_err(Error::tendermint)?))
+            },
+            Some(Sum::SignVoteRequest(req)) => Ok(Request::SignVote(
+                req.try_into().map_err(Error::tendermint)?,
+            )),
+            Some(Sum::SignProposalRequest(req)) => Ok(Request::SignProposal(
+                req.try_into().map_err(Error::tendermint)?,
+            )),
+            Some(Sum::PingRequest(_)) => Ok(Request::Ping),
+            None => Err(Error::malformed_message()),
+            Some(_) => Err(Error::unexpected_message_type(
+                "request".to_string(),
+                message_kind(&message).to_string(),
+            )),
+        }
+    }
+}
+
+impl From<Request> for Message {
+    fn from(request: Request) -> Self {
+        let sum = match request {
+            Request::PubKey(req) => Sum::PubKeyRequest(req.into()),
+            Request::SignVote(req) => Sum::SignVoteRequest(req.into()),
+            Request::SignProposal(req) => Sum::SignProposalRequest(req.into()),
+            Request::Ping => Sum::PingRequest(PingRequest {}),
+        };
+        Message { sum: Some(sum) }
+    }
+}
+
+impl TryFrom<Message> for Response {
+    type Error = Error;
+
+    fn try_from(message: Message) -> Result<Self, Self::Error> {
+        match message.sum {
+            Some(Sum::PubKeyResponse(res)) => {
+                Ok(Response::PubKey(res.try_into().map_err(Error::tendermint)?))
+            },
+            Some(Sum::SignedVoteResponse(res)) => Ok(Response::SignedVote(
+                res.try_into().map_err(Error::tendermint)?,
+            )),
+            Some(Sum::SignedProposalResponse(res)) => Ok(Response::SignedProposal(
+                res.try_into().map_err(Error::tendermint)?,
+            )),
+            Some(Sum::PingResponse(_)) => Ok(Response::Ping),
+            None => Err(Error::malformed_message()),
+            Some(_) => Err(Error::unexpected_message_type(
+                "response".to_string(),
+                message_kind(&message).to_string(),
+            )),
+        }
+    }
+}
+
+impl From<Response> for Message {
+    fn from(response: Response) -> Self {
+        let sum = match response {
+            Response::PubKey(res) => Sum::PubKeyResponse(res.into()),
+            Response::SignedVote(res) => Sum::SignedVoteResponse(res.into()),
+            Response::SignedProposal(res) => Sum::SignedProposalResponse(res.into()),
+            Response::Ping => Sum::PingResponse(PingResponse {}),
+        };
+        Message { sum: Some(sum) }
+    }
+}
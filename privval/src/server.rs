@@ -0,0 +1,183 @@
+//! Remote signer server interface.
+
+use std::{
+    io::{Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+};
+#[cfg(unix)]
+use std::{os::unix::net::UnixStream, path::Path};
+
+use tendermint::{
+    proposal::SignedProposalResponse, public_key::PubKeyResponse, vote::SignedVoteResponse,
+};
+use tendermint_p2p::secret_connection::{SecretConnection, Version};
+use tracing::{debug, error, info};
+
+use crate::{codec::Codec, error::Error, Request, Response, Signer};
+
+/// The size of the read buffer for the connection to the validator node
+/// (64KB).
+pub const DEFAULT_SERVER_READ_BUF_SIZE: usize = 64 * 1024;
+
+/// Allows us to configure and construct a remote signer server.
+pub struct ServerBuilder {
+    read_buf_size: usize,
+}
+
+impl ServerBuilder {
+    /// Builder constructor.
+    ///
+    /// Allows you to specify the read buffer size used when reading chunks of
+    /// incoming data from the validator node.
+    pub fn new(read_buf_size: usize) -> Self {
+        Self { read_buf_size }
+    }
+
+    /// Dial the validator node listening at the given TCP address, and
+    /// secure the connection with a [`SecretConnection`] authenticated by
+    /// the given identity key.
+    ///
+    /// The identity key is unrelated to the consensus key held by the
+    /// signer: it only identifies the signer to the validator node.
+    pub fn connect_tcp<Addr, S>(
+        self,
+        addr: Addr,
+        identity_key: ed25519_consensus::SigningKey,
+        signer: S,
+    ) -> Result<Server<S, SecretConnection<TcpStream>>, Error>
+    where
+        Addr: ToSocketAddrs,
+        S: Signer,
+    {
+        let stream = TcpStream::connect(addr).map_err(Error::io)?;
+        let peer_addr = stream.peer_addr().map_err(Error::io)?;
+        let conn = SecretConnection::new(stream, identity_key, Version::V0_34)
+            .map_err(Error::secret_connection)?;
+        info!(
+            "Connected to validator {} at {}",
+            conn.remote_pubkey(),
+            peer_addr
+        );
+        Ok(self.build(conn, signer))
+    }
+
+    /// Dial the validator node listening on the Unix domain socket at the
+    /// given path.
+    #[cfg(unix)]
+    pub fn connect_unix<P, S>(self, path: P, signer: S) -> Result<Server<S, UnixStream>, Error>
+    where
+        P: AsRef<Path>,
+        S: Signer,
+    {
+        let stream = UnixStream::connect(path.as_ref()).map_err(Error::io)?;
+        info!("Connected to validator at {}", path.as_ref().display());
+        Ok(self.build(stream, signer))
+    }
+
+    /// Construct a server communicating over an already established stream.
+    pub fn build<S, Io>(self, stream: Io, signer: S) -> Server<S, Io>
+    where
+        S: Signer,
+        Io: Read + Write,
+    {
+        Server {
+            signer,
+            codec: Codec::new(stream, self.read_buf_size),
+        }
+    }
+}
+
+impl Default for ServerBuilder {
+    fn default() -> Self {
+        Self {
+            read_buf_size: DEFAULT_SERVER_READ_BUF_SIZE,
+        }
+    }
+}
+
+/// A remote signer server, answering the requests of a single validator node
+/// by delegating them to a [`Signer`].
+pub struct Server<S, Io> {
+    signer: S,
+    codec: Codec<Io>,
+}
+
+impl<S, Io> Server<S, Io>
+where
+    S: Signer,
+    Io: Read + Write,
+{
+    /// Handle incoming requests until the validator node terminates the
+    /// connection.
+    ///
+    /// Returns an error if the connection fails, or if the node sends a
+    /// message that is not a valid request.
+    pub fn serve(mut self) -> Result<(), Error> {
+        loop {
+            let request = match self.codec.next() {
+                Some(result) => result?,
+                None => {
+                    info!("Validator terminated connection");
+                    return Ok(());
+                },
+            };
+            let request = Request::try_from(request).map_err(|e| {
+                error!("Received invalid request from validator: {}", e);
+                e
+            })?;
+            let response = self.handle(request);
+            self.codec.send(response.into())?;
+        }
+    }
+
+    /// Consume the server, returning the underlying signer.
+    pub fn into_signer(self) -> S {
+        self.signer
+    }
+
+    fn handle(&mut self, request: Request) -> Response {
+        debug!("Handling request: {:?}", request);
+        match request {
+            Request::PubKey(req) => {
+                let response = match self.signer.public_key(&req.chain_id) {
+                    Ok(pub_key) => PubKeyResponse {
+                        pub_key: Some(pub_key),
+                        error: None,
+                    },
+                    Err(e) => PubKeyResponse {
+                        pub_key: None,
+                        error: Some(e),
+                    },
+                };
+                Response::PubKey(response)
+            },
+            Request::SignVote(req) => {
+                let response = match self.signer.sign_vote(req) {
+                    Ok(vote) => SignedVoteResponse {
+                        vote: Some(vote),
+                        error: None,
+                    },
+                    Err(e) => SignedVoteResponse {
+                        vote: None,
+                        error: Some(e),
+                    },
+                };
+                Response::SignedVote(response)
+            },
+            Request::SignProposal(req) => {
+                let response = match self.signer.sign_proposal(req) {
+                    Ok(proposal) => SignedProposalResponse {
+                        proposal: Some(proposal),
+                        error: None,
+                    },
+                    Err(e) => SignedProposalResponse {
+                        proposal: None,
+                        error: Some(e),
+                    },
+                };
+                Response::SignedProposal(response)
+            },
+            Request::Ping => Response::Ping,
+        }
+    }
+}
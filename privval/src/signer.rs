@@ -0,0 +1,30 @@
+//! Signer interface.
+
+use tendermint::{
+    chain, privval::RemoteSignerError, proposal::SignProposalRequest, vote::SignVoteRequest,
+    Proposal, PublicKey, Vote,
+};
+
+/// A signing backend for the remote signer protocol.
+///
+/// The [`Server`] decodes incoming requests from the validator node and
+/// delegates them to an implementation of this trait. Any
+/// [`RemoteSignerError`] returned here is relayed back to the node in the
+/// corresponding response message.
+///
+/// [`Server`]: crate::Server
+pub trait Signer: Send {
+    /// Return the consensus public key used to sign on the given chain.
+    fn public_key(&self, chain_id: &chain::Id) -> Result<PublicKey, RemoteSignerError>;
+
+    /// Sign the vote in the given request, returning the vote with its
+    /// `signature` field populated.
+    fn sign_vote(&mut self, request: SignVoteRequest) -> Result<Vote, RemoteSignerError>;
+
+    /// Sign the proposal in the given request, returning the proposal with
+    /// its `signature` field populated.
+    fn sign_proposal(
+        &mut self,
+        request: SignProposalRequest,
+    ) -> Result<Proposal, RemoteSignerError>;
+}
@@ -0,0 +1,153 @@
+//! Software signer backed by CometBFT's `priv_validator_key.json` and
+//! `priv_validator_state.json` files.
+
+use std::path::{Path, PathBuf};
+
+use tendermint::{
+    block::{self, Round},
+    chain,
+    private_key::PrivateKey,
+    privval::RemoteSignerError,
+    proposal::SignProposalRequest,
+    vote::SignVoteRequest,
+    Proposal, PublicKey, Signature, Vote,
+};
+use tendermint_config::PrivValidatorKey;
+use tracing::{info, warn};
+
+use crate::{
+    backend::{vote_extension_sign_bytes, vote_has_extension_signature},
+    state::{ValidatorSigningState, STEP_PRECOMMIT, STEP_PREVOTE, STEP_PROPOSE},
+    Error, Signer,
+};
+
+/// A [`Signer`] holding a consensus key in memory, compatible with the
+/// file-based private validator of CometBFT.
+///
+/// Ed25519 keys are always supported, and Secp256k1 keys with the
+/// `secp256k1` feature. The public key returned to the validator node
+/// advertises the scheme of the key.
+///
+/// The last signed height/round/step is persisted to the state file before
+/// any signature is released, and requests that would regress it are
+/// refused. A request for the same height/round/step as the last signed one
+/// is only answered, with the previously produced signature, if it differs
+/// from the last signed payload at most in its timestamp.
+pub struct SoftwareSigner {
+    private_key: PrivateKey,
+    public_key: PublicKey,
+    state: ValidatorSigningState,
+    state_path: PathBuf,
+}
+
+impl SoftwareSigner {
+    /// Load the consensus key from the given `priv_validator_key.json` file,
+    /// and the last signed state from the given `priv_validator_state.json`
+    /// file, which is created if it does not exist.
+    pub fn load<K, S>(key_path: K, state_path: S) -> Result<Self, Error>
+    where
+        K: AsRef<Path>,
+        S: AsRef<Path>,
+    {
+        let key = PrivValidatorKey::load_json_file(&key_path).map_err(Error::config)?;
+        Self::new(key.priv_key, state_path)
+    }
+
+    /// Construct a signer from the given private key, with the last signed
+    /// state stored in the given file, which is created if it does not exist.
+    pub fn new<S: AsRef<Path>>(private_key: PrivateKey, state_path: S) -> Result<Self, Error> {
+        let public_key = private_key.public_key();
+        let state_path = state_path.as_ref().to_path_buf();
+        let state = ValidatorSigningState::load_or_init(&state_path)?;
+        info!(
+            "Loaded {} key, with signing state {}/{}/{} from {}",
+            public_key.algorithm(),
+            state.height,
+            state.round,
+            state.step,
+            state_path.display()
+        );
+        Ok(Self {
+            private_key,
+            public_key,
+            state,
+            state_path,
+        })
+    }
+
+    /// The last height signed by this signer.
+    pub fn last_height(&self) -> block::Height {
+        self.state.height
+    }
+}
+
+impl Signer for SoftwareSigner {
+    fn public_key(&self, _chain_id: &chain::Id) -> Result<PublicKey, RemoteSignerError> {
+        Ok(self.public_key)
+    }
+
+    fn sign_vote(&mut self, request: SignVoteRequest) -> Result<Vote, RemoteSignerError> {
+        let SignVoteRequest { mut vote, chain_id } = request;
+        let step = if vote.is_precommit() {
+            STEP_PRECOMMIT
+        } else {
+            STEP_PREVOTE
+        };
+
+        let private_key = &self.private_key;
+        let signature = self
+            .state
+            .check_and_update_vote(&self.state_path, &mut vote, &chain_id, |bytes| {
+                private_key.sign(bytes).as_bytes().to_vec()
+            })
+            .map_err(|e| refused(vote.height, vote.round, step, e))?;
+        vote.signature = Some(signature_from_bytes(&signature)?);
+        // The extension is only signed once the vote is, so that refused
+        // requests cost no signing operation.
+        if vote_has_extension_signature(&vote) {
+            let sign_bytes = vote_extension_sign_bytes(&vote, &chain_id);
+            vote.extension_signature = Some(self.private_key.sign(&sign_bytes));
+        }
+        Ok(vote)
+    }
+
+    fn sign_proposal(
+        &mut self,
+        request: SignProposalRequest,
+    ) -> Result<Proposal, RemoteSignerError> {
+        let SignProposalRequest {
+            mut proposal,
+            chain_id,
+        } = request;
+
+        let private_key = &self.private_key;
+        let signature = self
+            .state
+            .check_and_update_proposal(&self.state_path, &mut proposal, &chain_id, |bytes| {
+                private_key.sign(bytes).as_bytes().to_vec()
+            })
+            .map_err(|e| refused(proposal.height, proposal.round, STEP_PROPOSE, e))?;
+        proposal.signature = Some(signature_from_bytes(&signature)?);
+        Ok(proposal)
+    }
+}
+
+fn refused(height: block::Height, round: Round, step: i8, e: Error) -> RemoteSignerError {
+    warn!("Refused to sign at {}/{}/{}: {}", height, round, step, e);
+    remote_signer_error(e.to_string())
+}
+
+fn signature_from_bytes(bytes: &[u8]) -> Result<Signature, RemoteSignerError> {
+    Signature::new(bytes)
+        .ok()
+        .flatten()
+        .ok_or_else(|| remote_signer_error("missing last signature".to_string()))
+}
+
+fn remote_signer_error(description: String) -> RemoteSignerError {
+    // CometBFT does not assign meaningful codes to remote signer errors
+    RemoteSignerError {
+        code: 0,
+        description,
+    }
+}
@@ -0,0 +1,440 @@
+//! Double-signing protection state, persisted in the format of CometBFT's
+//! `priv_validator_state.json`.
+//!
+//! [`ValidatorSigningState`] can be used by any [`Signer`] to refuse the
+//! signature of conflicting votes and proposals, as the software signer of
+//! this crate and the file-based private validator of CometBFT do.
+//!
+//! [`Signer`]: crate::Signer
+
+use std::{
+    cmp::Ordering,
+    fs,
+    io::{ErrorKind, Write},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+use tendermint::{
+    block::{self, Round},
+    chain,
+    proposal::CanonicalProposal,
+    serializers::bytes::{base64string, hexstring},
+    vote::CanonicalVote,
+    Proposal, Vote,
+};
+use tendermint_proto::{
+    v0_38::types::{CanonicalProposal as RawCanonicalProposal, CanonicalVote as RawCanonicalVote},
+    Protobuf,
+};
+
+use crate::Error;
+
+/// Signing step of a proposal.
+pub const STEP_PROPOSE: i8 = 1;
+/// Signing step of a prevote.
+pub const STEP_PREVOTE: i8 = 2;
+/// Signing step of a precommit.
+pub const STEP_PRECOMMIT: i8 = 3;
+
+/// The last height/round/step signed by a validator, together with the
+/// signature and the bytes that were signed at that step.
+///
+/// Steps are ordered by height, then round, then step, and a validator must
+/// never sign a step preceding the last signed one, nor sign different data
+/// at the last signed step, except for the timestamp of a vote or a proposal.
+/// [`ValidatorSigningState::check_and_update_vote`] and
+/// [`ValidatorSigningState::check_and_update_proposal`] enforce these rules,
+/// and persist the updated state before the signature is released.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidatorSigningState {
+    /// The last signed height
+    pub height: block::Height,
+    /// The last signed round
+    #[serde(with = "round_number")]
+    pub round: Round,
+    /// The last signed step, one of [`STEP_PROPOSE`], [`STEP_PREVOTE`] and
+    /// [`STEP_PRECOMMIT`], or 0 if nothing was signed yet
+    pub step: i8,
+    /// The signature produced at the last signed step
+    #[serde(default, skip_serializing_if = "Vec::is_empty", with = "base64string")]
+    pub signature: Vec<u8>,
+    /// The bytes signed at the last signed step
+    #[serde(default, skip_serializing_if = "Vec::is_empty", with = "hexstring")]
+    pub signbytes: Vec<u8>,
+}
+
+impl Default for ValidatorSigningState {
+    fn default() -> Self {
+        // `block::Height` defaults to 1, whereas nothing has been signed yet
+        Self {
+            height: 0_u32.into(),
+            round: Round::default(),
+            step: 0,
+            signature: Vec::new(),
+            signbytes: Vec::new(),
+        }
+    }
+}
+
+impl ValidatorSigningState {
+    /// Load the state from the given file, initializing it to the zero state
+    /// if the file does not exist yet.
+    pub fn load_or_init(path: &Path) -> Result<Self, Error> {
+        match fs::read_to_string(path) {
+            Ok(json) => serde_json::from_str(&json).map_err(Error::serde_json),
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                let state = Self::default();
+                state.save(path)?;
+                Ok(state)
+            },
+            Err(e) => Err(Error::file_io(path.display().to_string(), e)),
+        }
+    }
+
+    /// Atomically persist the state to the given file.
+    ///
+    /// The state is written to a temporary file in the same directory, which
+    /// is then renamed over the target, so that the file on disk is never
+    /// left partially written. The directory is synced after the rename, so
+    /// that the new state survives a crash.
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let json = serde_json::to_string_pretty(self).map_err(Error::serde_json)?;
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let tmp_path = path.with_file_name(format!(".{file_name}.tmp"));
+        let io_err = |e| Error::file_io(tmp_path.display().to_string(), e);
+
+        let mut file = fs::File::create(&tmp_path).map_err(io_err)?;
+        file.write_all(json.as_bytes()).map_err(io_err)?;
+        file.sync_all().map_err(io_err)?;
+        fs::rename(&tmp_path, path).map_err(|e| Error::file_io(path.display().to_string(), e))?;
+        #[cfg(unix)]
+        {
+            let dir = match path.parent() {
+                Some(dir) if !dir.as_os_str().is_empty() => dir,
+                _ => Path::new("."),
+            };
+            fs::File::open(dir)
+                .and_then(|dir| dir.sync_all())
+                .map_err(|e| Error::file_io(dir.display().to_string(), e))?;
+        }
+        Ok(())
+    }
+
+    /// Compare the given height/round/step against the last signed one.
+    ///
+    /// Returns an error describing the regression if the given step precedes
+    /// the last signed one, and otherwise whether the step is the same as the
+    /// last signed one.
+    pub fn check_hrs(&self, height: block::Height, round: Round, step: i8) -> Result<bool, Error> {
+        match (height, round, step).cmp(&(self.height, self.round, self.step)) {
+            Ordering::Less => Err(Error::double_sign(format!(
+                "{}/{}/{} precedes last signed {}/{}/{}",
+                height, round, step, self.height, self.round, self.step
+            ))),
+            Ordering::Equal => Ok(true),
+            Ordering::Greater => Ok(false),
+        }
+    }
+
+    /// Sign the given bytes at the given height/round/step with `sign`,
+    /// unless this would amount to double signing, and return the signature.
+    ///
+    /// If the step follows the last signed one, the bytes are signed, and the
+    /// updated state is persisted to the given file before the signature is
+    /// returned. If the step is the last signed one, the signature produced
+    /// then is returned, provided the sign bytes are the same. Otherwise, an
+    /// error is returned and nothing is signed.
+    ///
+    /// Signers whose signing operation may fail can use
+    /// [`ValidatorSigningState::check_hrs`] and [`ValidatorSigningState::save`]
+    /// to the same effect.
+    ///
+    /// Unlike [`ValidatorSigningState::check_and_update_vote`] and
+    /// [`ValidatorSigningState::check_and_update_proposal`], this refuses
+    /// sign bytes which only differ in their timestamp at the last signed
+    /// step.
+    pub fn check_and_update<F>(
+        &mut self,
+        path: &Path,
+        height: block::Height,
+        round: Round,
+        step: i8,
+        sign_bytes: Vec<u8>,
+        sign: F,
+    ) -> Result<Vec<u8>, Error>
+    where
+        F: FnOnce(&[u8]) -> Vec<u8>,
+    {
+        if self.check_hrs(height, round, step)? {
+            if sign_bytes != self.signbytes {
+                return Err(Error::double_sign(format!(
+                    "conflicting data at already signed step {}/{}/{}",
+                    self.height, self.round, self.step
+                )));
+            }
+            return Ok(self.signature.clone());
+        }
+
+        let signature = sign(&sign_bytes);
+        let state = Self {
+            height,
+            round,
+            step,
+            signature,
+            signbytes: sign_bytes,
+        };
+        state.save(path)?;
+        *self = state;
+        Ok(self.signature.clone())
+    }
+
+    /// Sign the given vote with `sign`, unless this would amount to double
+    /// signing, and return the signature, as
+    /// [`ValidatorSigningState::check_and_update`] does.
+    ///
+    /// As in CometBFT, a vote for the last signed step which only differs
+    /// from the signed one in its timestamp is answered with the signature
+    /// produced then, and its timestamp is replaced with the signed one.
+    pub fn check_and_update_vote<F>(
+        &mut self,
+        path: &Path,
+        vote: &mut Vote,
+        chain_id: &chain::Id,
+        sign: F,
+    ) -> Result<Vec<u8>, Error>
+    where
+        F: FnOnce(&[u8]) -> Vec<u8>,
+    {
+        let step = if vote.is_precommit() {
+            STEP_PRECOMMIT
+        } else {
+            STEP_PREVOTE
+        };
+        if self.check_hrs(vote.height, vote.round, step)? {
+            let last = <CanonicalVote as Protobuf<RawCanonicalVote>>::decode_length_delimited_vec(
+                &self.signbytes,
+            )
+            .map_err(|e| Error::double_sign(format!("malformed last sign bytes: {e}")))?;
+            vote.timestamp = last.timestamp;
+        }
+        let sign_bytes = vote.clone().into_signable_vec(chain_id.clone());
+        self.check_and_update(path, vote.height, vote.round, step, sign_bytes, sign)
+    }
+
+    /// Sign the given proposal with `sign`, unless this would amount to
+    /// double signing, and return the signature, as
+    /// [`ValidatorSigningState::check_and_update`] does.
+    ///
+    /// As in CometBFT, a proposal for the last signed step which only differs
+    /// from the signed one in its timestamp is answered with the signature
+    /// produced then, and its timestamp is replaced with the signed one.
+    pub fn check_and_update_proposal<F>(
+        &mut self,
+        path: &Path,
+        proposal: &mut Proposal,
+        chain_id: &chain::Id,
+        sign: F,
+    ) -> Result<Vec<u8>, Error>
+    where
+        F: FnOnce(&[u8]) -> Vec<u8>,
+    {
+        if self.check_hrs(proposal.height, proposal.round, STEP_PROPOSE)? {
+            let last =
+                <CanonicalProposal as Protobuf<RawCanonicalProposal>>::decode_length_delimited_vec(
+                    &self.signbytes,
+                )
+                .map_err(|e| Error::double_sign(format!("malformed last sign bytes: {e}")))?;
+            proposal.timestamp = last.timestamp;
+        }
+        let sign_bytes = proposal.clone().into_signable_vec(chain_id.clone());
+        self.check_and_update(
+            path,
+            proposal.height,
+            proposal.round,
+            STEP_PROPOSE,
+            sign_bytes,
+            sign,
+        )
+    }
+}
+
+/// CometBFT serializes the round of the last signed state as a JSON number,
+/// unlike the string encoding used for [`Round`] elsewhere.
+mod round_number {
+    use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+    use tendermint::block::Round;
+
+    pub fn serialize<S: Serializer>(round: &Round, serializer: S) -> Result<S::Ok, S::Error> {
+        i32::from(*round).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Round, D::Error> {
+        Round::try_from(i32::deserialize(deserializer)?).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cometbft_state_file_format() {
+        let json = r#"{
+  "height": "1234",
+  "round": 1,
+  "step": 3,
+  "signature": "AQID",
+  "signbytes": "0A0B"
+}"#;
+        let state: ValidatorSigningState = serde_json::from_str(json).unwrap();
+        assert_eq!(state.height.value(), 1234);
+        assert_eq!(state.round.value(), 1);
+        assert_eq!(state.step, STEP_PRECOMMIT);
+        assert_eq!(state.signature, vec![1, 2, 3]);
+        assert_eq!(state.signbytes, vec![0x0a, 0x0b]);
+        assert_eq!(serde_json::to_string_pretty(&state).unwrap(), json);
+
+        let initial: ValidatorSigningState =
+            serde_json::from_str(r#"{"height":"0","round":0,"step":0}"#).unwrap();
+        assert_eq!(initial, ValidatorSigningState::default());
+        assert_eq!(
+            serde_json::to_string(&initial).unwrap(),
+            r#"{"height":"0","round":0,"step":0}"#
+        );
+    }
+
+    #[test]
+    fn regressions_are_refused() {
+        let state = ValidatorSigningState {
+            height: 10_u32.into(),
+            round: 1_u16.into(),
+            step: STEP_PREVOTE,
+            ..Default::default()
+        };
+        assert!(state
+            .check_hrs(9_u32.into(), 5_u16.into(), STEP_PRECOMMIT)
+            .is_err());
+        assert!(state
+            .check_hrs(10_u32.into(), 0_u16.into(), STEP_PRECOMMIT)
+            .is_err());
+        assert!(state
+            .check_hrs(10_u32.into(), 1_u16.into(), STEP_PROPOSE)
+            .is_err());
+        assert!(state
+            .check_hrs(10_u32.into(), 1_u16.into(), STEP_PREVOTE)
+            .unwrap());
+        assert!(!state
+            .check_hrs(10_u32.into(), 1_u16.into(), STEP_PRECOMMIT)
+            .unwrap());
+        assert!(!state
+            .check_hrs(11_u32.into(), 0_u16.into(), STEP_PROPOSE)
+            .unwrap());
+    }
+
+    #[test]
+    fn check_and_update_persists_signed_steps() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("priv_validator_state.json");
+        let mut state = ValidatorSigningState::load_or_init(&path).unwrap();
+        let sign = |bytes: &[u8]| bytes.iter().rev().copied().collect::<Vec<_>>();
+
+        let signature = state
+            .check_and_update(
+                &path,
+                5_u32.into(),
+                0_u16.into(),
+                STEP_PREVOTE,
+                vec![1, 2],
+                sign,
+            )
+            .unwrap();
+        assert_eq!(signature, vec![2, 1]);
+        assert_eq!(ValidatorSigningState::load_or_init(&path).unwrap(), state);
+
+        // The same step is answered with the same signature, without signing.
+        let signature = state
+            .check_and_update(
+                &path,
+                5_u32.into(),
+                0_u16.into(),
+                STEP_PREVOTE,
+                vec![1, 2],
+                |_| unreachable!(),
+            )
+            .unwrap();
+        assert_eq!(signature, vec![2, 1]);
+
+        let err = state
+            .check_and_update(
+                &path,
+                5_u32.into(),
+                0_u16.into(),
+                STEP_PREVOTE,
+                vec![3],
+                sign,
+            )
+            .unwrap_err();
+        assert!(err.to_string().contains("conflicting data"));
+        let err = state
+            .check_and_update(
+                &path,
+                4_u32.into(),
+                2_u16.into(),
+                STEP_PRECOMMIT,
+                vec![3],
+                sign,
+            )
+            .unwrap_err();
+        assert!(err.to_string().contains("precedes last signed"));
+        assert_eq!(state.signbytes, vec![1, 2]);
+        assert_eq!(ValidatorSigningState::load_or_init(&path).unwrap(), state);
+    }
+
+    #[test]
+    fn check_and_update_vote_accepts_timestamp_only_changes() {
+        use tendermint::{account, vote, Time};
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("priv_validator_state.json");
+        let mut state = ValidatorSigningState::load_or_init(&path).unwrap();
+        let chain_id: chain::Id = "test-chain".parse().unwrap();
+        let sign = |bytes: &[u8]| bytes.iter().rev().copied().collect::<Vec<_>>();
+        let vote = |timestamp: i64, round: u16| Vote {
+            vote_type: vote::Type::Prevote,
+            height: 5_u32.into(),
+            round: round.into(),
+            block_id: None,
+            timestamp: Some(Time::from_unix_timestamp(timestamp, 0).unwrap()),
+            validator_address: account::Id::new([0xab; 20]),
+            validator_index: 0_u32.try_into().unwrap(),
+            signature: None,
+            extension: vec![],
+            extension_signature: None,
+        };
+
+        let mut first = vote(1, 0);
+        let signature = state
+            .check_and_update_vote(&path, &mut first, &chain_id, sign)
+            .unwrap();
+
+        // The same vote with another timestamp gets the signed timestamp back.
+        let mut again = vote(2, 0);
+        let resigned = state
+            .check_and_update_vote(&path, &mut again, &chain_id, |_| unreachable!())
+            .unwrap();
+        assert_eq!(resigned, signature);
+        assert_eq!(again.timestamp, first.timestamp);
+
+        // The timestamp of a vote for the next step is kept.
+        let mut next = vote(2, 1);
+        state
+            .check_and_update_vote(&path, &mut next, &chain_id, sign)
+            .unwrap();
+        assert_eq!(next.timestamp, vote(2, 1).timestamp);
+        assert_eq!(ValidatorSigningState::load_or_init(&path).unwrap(), state);
+    }
+}
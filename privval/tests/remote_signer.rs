@@ -0,0 +1,219 @@
+//! Integration tests for the remote signer client/server.
+
+#[cfg(feature = "client")]
+mod remote_signer_integration {
+    use std::net::TcpListener;
+
+    use tendermint::{
+        account,
+        block::{self, Round},
+        chain,
+        crypto::{default::signature::Verifier, signature::Verifier as _},
+        privval::RemoteSignerError,
+        proposal::SignProposalRequest,
+        vote::{self, SignVoteRequest, ValidatorIndex},
+        Proposal, PublicKey, Signature, Time, Vote,
+    };
+    use tendermint_privval::{ClientBuilder, ServerBuilder, Signer};
+
+    struct TestSigner {
+        key: ed25519_consensus::SigningKey,
+        chain_id: chain::Id,
+    }
+
+    impl TestSigner {
+        fn new(chain_id: &str) -> Self {
+            Self {
+                key: ed25519_consensus::SigningKey::from([1u8; 32]),
+                chain_id: chain_id.parse().unwrap(),
+            }
+        }
+
+        fn check_chain_id(&self, chain_id: &chain::Id) -> Result<(), RemoteSignerError> {
+            if chain_id != &self.chain_id {
+                return Err(RemoteSignerError {
+                    code: 1,
+                    description: format!("unknown chain id: {}", chain_id),
+                });
+            }
+            Ok(())
+        }
+    }
+
+    impl Signer for TestSigner {
+        fn public_key(&self, chain_id: &chain::Id) -> Result<PublicKey, RemoteSignerError> {
+            self.check_chain_id(chain_id)?;
+            Ok(PublicKey::from_raw_ed25519(self.key.verification_key().as_bytes()).unwrap())
+        }
+
+        fn sign_vote(&mut self, request: SignVoteRequest) -> Result<Vote, RemoteSignerError> {
+            self.check_chain_id(&request.chain_id)?;
+            let mut vote = request.vote.clone();
+            let signature = self.key.sign(&request.into_signable_vec());
+            vote.signature = Some(signature.into());
+            Ok(vote)
+        }
+
+        fn sign_proposal(
+            &mut self,
+            request: SignProposalRequest,
+        ) -> Result<Proposal, RemoteSignerError> {
+            self.check_chain_id(&request.chain_id)?;
+            let mut proposal = request.proposal.clone();
+            let signature = self.key.sign(&request.into_signable_vec());
+            proposal.signature = Some(signature.into());
+            Ok(proposal)
+        }
+    }
+
+    fn vote() -> Vote {
+        Vote {
+            vote_type: vote::Type::Prevote,
+            height: block::Height::from(10_u32),
+            round: Round::from(2_u16),
+            block_id: None,
+            timestamp: Some(Time::unix_epoch()),
+            validator_address: account::Id::new([0xab; 20]),
+            validator_index: ValidatorIndex::try_from(1_u32).unwrap(),
+            signature: None,
+            extension: vec![],
+            extension_signature: None,
+        }
+    }
+
+    fn proposal() -> Proposal {
+        Proposal {
+            msg_type: tendermint::proposal::Type::Proposal,
+            height: block::Height::from(10_u32),
+            round: Round::from(2_u16),
+            pol_round: None,
+            block_id: None,
+            timestamp: Some(Time::unix_epoch()),
+            signature: None,
+        }
+    }
+
+    fn verify(pub_key: &PublicKey, msg: &[u8], signature: &Signature) {
+        Verifier::verify(*pub_key, msg, signature).expect("signature must be valid");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unix_socket() {
+        let (node_side, signer_side) = std::os::unix::net::UnixStream::pair().unwrap();
+        let server = ServerBuilder::default().build(signer_side, TestSigner::new("test-chain"));
+        let handle = std::thread::spawn(move || server.serve());
+        let mut client = ClientBuilder::default().build(node_side);
+
+        client.ping().unwrap();
+
+        let pub_key = client
+            .pub_key("test-chain".parse().unwrap())
+            .unwrap()
+            .pub_key
+            .unwrap();
+
+        let request = SignVoteRequest {
+            vote: vote(),
+            chain_id: "test-chain".parse().unwrap(),
+        };
+        let signed = client.sign_vote(request.clone()).unwrap();
+        assert!(signed.error.is_none());
+        let signed_vote = signed.vote.unwrap();
+        verify(
+            &pub_key,
+            &request.into_signable_vec(),
+            signed_vote.signature.as_ref().unwrap(),
+        );
+
+        let request = SignProposalRequest {
+            proposal: proposal(),
+            chain_id: "test-chain".parse().unwrap(),
+        };
+        let signed = client.sign_proposal(request.clone()).unwrap();
+        let signed_proposal = signed.proposal.unwrap();
+        verify(
+            &pub_key,
+            &request.into_signable_vec(),
+            signed_proposal.signature.as_ref().unwrap(),
+        );
+
+        let response = client.pub_key("other-chain".parse().unwrap()).unwrap();
+        assert!(response.pub_key.is_none());
+        assert_eq!(response.error.unwrap().code, 1);
+
+        drop(client);
+        handle.join().unwrap().unwrap();
+    }
+
+    #[cfg(all(unix, feature = "secp256k1"))]
+    #[test]
+    fn advertises_secp256k1_key() {
+        use tendermint::{private_key::PrivateKey, public_key::Algorithm};
+        use tendermint_privval::SoftwareSigner;
+
+        let dir = tempfile::tempdir().unwrap();
+        let key = PrivateKey::from_secp256k1_bytes(&[5u8; 32]).unwrap();
+        let expected_key = key.public_key();
+        let signer =
+            SoftwareSigner::new(key, dir.path().join("priv_validator_state.json")).unwrap();
+
+        let (node_side, signer_side) = std::os::unix::net::UnixStream::pair().unwrap();
+        let server = ServerBuilder::default().build(signer_side, signer);
+        let handle = std::thread::spawn(move || server.serve());
+        let mut client = ClientBuilder::default().build(node_side);
+
+        let pub_key = client
+            .pub_key("test-chain".parse().unwrap())
+            .unwrap()
+            .pub_key
+            .unwrap();
+        assert_eq!(pub_key, expected_key);
+        assert_eq!(pub_key.algorithm(), Algorithm::Secp256k1);
+
+        let request = SignVoteRequest {
+            vote: vote(),
+            chain_id: "test-chain".parse().unwrap(),
+        };
+        let signed_vote = client.sign_vote(request.clone()).unwrap().vote.unwrap();
+        verify(
+            &pub_key,
+            &request.into_signable_vec(),
+            signed_vote.signature.as_ref().unwrap(),
+        );
+
+        drop(client);
+        handle.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn tcp_secret_connection() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let handle = std::thread::spawn(move || {
+            let server = ServerBuilder::default()
+                .connect_tcp(
+                    addr,
+                    ed25519_consensus::SigningKey::from([2u8; 32]),
+                    TestSigner::new("test-chain"),
+                )
+                .unwrap();
+            server.serve()
+        });
+        let mut client = ClientBuilder::default()
+            .accept_tcp(&listener, ed25519_consensus::SigningKey::from([3u8; 32]))
+            .unwrap();
+
+        client.ping().unwrap();
+        let signed = client
+            .sign_vote(SignVoteRequest {
+                vote: vote(),
+                chain_id: "test-chain".parse().unwrap(),
+            })
+            .unwrap();
+        assert!(signed.vote.unwrap().signature.is_some());
+
+        drop(client);
+        handle.join().unwrap().unwrap();
+    }
+}
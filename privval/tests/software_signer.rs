@@ -0,0 +1,157 @@
+//! Tests for the file-based software signer.
+
+use tendermint::{
+    account,
+    block::{self, parts::Header as PartSetHeader, Round},
+    crypto::{default::signature::Verifier, signature::Verifier as _},
+    hash::{Algorithm, Hash},
+    proposal::{self, SignProposalRequest},
+    vote::{self, SignVoteRequest, ValidatorIndex},
+    Proposal, Time, Vote,
+};
+use tendermint_privval::{Signer, SoftwareSigner};
+
+const KEY_PATH: &str = "tests/support/priv_validator_key.json";
+
+fn chain_id() -> tendermint::chain::Id {
+    "test-chain".parse().unwrap()
+}
+
+fn block_id(byte: u8) -> block::Id {
+    block::Id {
+        hash: Hash::from_bytes(Algorithm::Sha256, &[byte; 32]).unwrap(),
+        part_set_header: PartSetHeader::new(
+            1,
+            Hash::from_bytes(Algorithm::Sha256, &[byte; 32]).unwrap(),
+        )
+        .unwrap(),
+    }
+}
+
+fn vote(vote_type: vote::Type, height: u32, round: u16, timestamp: Time) -> SignVoteRequest {
+    SignVoteRequest {
+        vote: Vote {
+            vote_type,
+            height: height.into(),
+            round: Round::from(round),
+            block_id: Some(block_id(1)),
+            timestamp: Some(timestamp),
+            validator_address: account::Id::new([0xab; 20]),
+            validator_index: ValidatorIndex::try_from(0_u32).unwrap(),
+            signature: None,
+            extension: vec![],
+            extension_signature: None,
+        },
+        chain_id: chain_id(),
+    }
+}
+
+fn proposal(height: u32, round: u16) -> SignProposalRequest {
+    SignProposalRequest {
+        proposal: Proposal {
+            msg_type: proposal::Type::Proposal,
+            height: height.into(),
+            round: Round::from(round),
+            pol_round: None,
+            block_id: Some(block_id(1)),
+            timestamp: Some(Time::unix_epoch()),
+            signature: None,
+        },
+        chain_id: chain_id(),
+    }
+}
+
+#[test]
+fn signs_and_persists_state() {
+    let dir = tempfile::tempdir().unwrap();
+    let state_path = dir.path().join("priv_validator_state.json");
+    let mut signer = SoftwareSigner::load(KEY_PATH, &state_path).unwrap();
+    let pub_key = signer.public_key(&chain_id()).unwrap();
+
+    let request = vote(vote::Type::Precommit, 5, 0, Time::unix_epoch());
+    let signed = signer.sign_vote(request.clone()).unwrap();
+    Verifier::verify(
+        pub_key,
+        &request.into_signable_vec(),
+        signed.signature.as_ref().unwrap(),
+    )
+    .unwrap();
+    assert!(signed.extension_signature.is_some());
+
+    let state: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&state_path).unwrap()).unwrap();
+    assert_eq!(state["height"], "5");
+    assert_eq!(state["round"], 0);
+    assert_eq!(state["step"], 3);
+
+    // A reloaded signer must keep refusing regressions
+    let mut signer = SoftwareSigner::load(KEY_PATH, &state_path).unwrap();
+    assert_eq!(signer.last_height().value(), 5);
+    assert!(signer
+        .sign_vote(vote(vote::Type::Prevote, 5, 0, Time::unix_epoch()))
+        .is_err());
+    assert!(signer.sign_proposal(proposal(4, 3)).is_err());
+    assert!(signer.sign_proposal(proposal(6, 0)).is_ok());
+}
+
+#[test]
+fn same_step_is_only_resigned_for_identical_payloads() {
+    let dir = tempfile::tempdir().unwrap();
+    let state_path = dir.path().join("priv_validator_state.json");
+    let mut signer = SoftwareSigner::load(KEY_PATH, &state_path).unwrap();
+
+    let first = signer
+        .sign_vote(vote(vote::Type::Prevote, 7, 1, Time::unix_epoch()))
+        .unwrap();
+
+    // Differing only in the timestamp yields the previous vote and signature
+    let later = (Time::unix_epoch() + core::time::Duration::from_secs(3)).unwrap();
+    let again = signer
+        .sign_vote(vote(vote::Type::Prevote, 7, 1, later))
+        .unwrap();
+    assert_eq!(again.timestamp, first.timestamp);
+    assert_eq!(again.signature, first.signature);
+
+    // Conflicting block IDs are refused
+    let mut conflicting = vote(vote::Type::Prevote, 7, 1, Time::unix_epoch());
+    conflicting.vote.block_id = Some(block_id(2));
+    let err = signer.sign_vote(conflicting).unwrap_err();
+    assert!(err.description.contains("conflicting data"));
+}
+
+#[cfg(feature = "secp256k1")]
+#[test]
+fn signs_with_secp256k1_key() {
+    use tendermint::{private_key::PrivateKey, public_key::Algorithm as KeyAlgorithm};
+
+    let dir = tempfile::tempdir().unwrap();
+    let state_path = dir.path().join("priv_validator_state.json");
+    let key = PrivateKey::from_secp256k1_bytes(&[3; 32]).unwrap();
+    let mut signer = SoftwareSigner::new(key, &state_path).unwrap();
+    let pub_key = signer.public_key(&chain_id()).unwrap();
+    assert_eq!(pub_key.algorithm(), KeyAlgorithm::Secp256k1);
+
+    let request = vote(vote::Type::Prevote, 2, 0, Time::unix_epoch());
+    let signed = signer.sign_vote(request.clone()).unwrap();
+    Verifier::verify(
+        pub_key,
+        &request.into_signable_vec(),
+        signed.signature.as_ref().unwrap(),
+    )
+    .unwrap();
+
+    // The signature persisted in the state is released again for the same payload
+    let resigned = signer
+        .sign_vote(vote(vote::Type::Prevote, 2, 0, Time::unix_epoch()))
+        .unwrap();
+    assert_eq!(resigned.signature, signed.signature);
+
+    let request = proposal(3, 0);
+    let signed = signer.sign_proposal(request.clone()).unwrap();
+    Verifier::verify(
+        pub_key,
+        &request.into_signable_vec(),
+        signed.signature.as_ref().unwrap(),
+    )
+    .unwrap();
+}
@@ -44,6 +44,8 @@
 //! * Any type that has the "FromStr" trait can be serialized into a string with
 //!   serializers::primitives::string.
 //! * serializers::bytes::* deserializes a null value into an empty vec![].
+//! * [`from_str`] and the other serializers of integers also accept JSON
+//!   numbers, see [`str_or_int`].
 //!
 //! [`Duration`]: core::time::Duration
 //! [`hexstring`]: bytes::hexstring
@@ -62,6 +64,7 @@ pub mod nullable;
 pub mod optional;
 pub mod optional_from_str;
 pub mod part_set_header_total;
+pub mod str_or_int;
 pub mod time_duration;
 pub mod timestamp;
 pub mod txs;
@@ -1,13 +1,17 @@
 //! Serialize and deserialize any `T` that implements [`FromStr`]
 //! and [`Display`] to convert from or into string. Note this can be used for
 //! all primitive data types.
+//!
+//! Integers encoded as JSON numbers are also accepted, see [`str_or_int`].
+//!
+//! [`str_or_int`]: super::str_or_int
 
-use alloc::borrow::Cow;
 use core::fmt::Display;
 use core::str::FromStr;
 
 use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
 
+use super::str_or_int::StrOrInt;
 use crate::prelude::*;
 
 /// Deserialize string into T
@@ -17,7 +21,8 @@ where
     T: FromStr,
     <T as FromStr>::Err: Display,
 {
-    <Cow<'_, str>>::deserialize(deserializer)?
+    StrOrInt::deserialize(deserializer)?
+        .as_str()
         .parse::<T>()
         .map_err(D::Error::custom)
 }
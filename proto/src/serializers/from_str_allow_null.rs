@@ -11,12 +11,12 @@
 //! [`from_str`]: super::from_str
 //! [`allow_null`]: super::allow_null
 
-use alloc::borrow::Cow;
 use core::fmt::Display;
 use core::str::FromStr;
 
 use serde::{de::Error as _, Deserialize, Deserializer, Serializer};
 
+use super::str_or_int::StrOrInt;
 use crate::prelude::*;
 
 /// Deserialize a nullable string into T
@@ -26,8 +26,8 @@ where
     T: FromStr + Default,
     <T as FromStr>::Err: Display,
 {
-    match <Option<Cow<'_, str>>>::deserialize(deserializer)? {
-        Some(s) => s.parse::<T>().map_err(D::Error::custom),
+    match <Option<StrOrInt<'_>>>::deserialize(deserializer)? {
+        Some(s) => s.as_str().parse::<T>().map_err(D::Error::custom),
         None => Ok(T::default()),
     }
 }
@@ -1,10 +1,10 @@
 //! De/serialize an optional type that must be converted from/to a string.
 
-use alloc::borrow::Cow;
 use core::{fmt::Display, str::FromStr};
 
 use serde::{de::Error, Deserialize, Deserializer, Serializer};
 
+use super::str_or_int::StrOrInt;
 use crate::prelude::*;
 
 pub fn serialize<S, T>(value: &Option<T>, serializer: S) -> Result<S::Ok, S::Error>
@@ -24,11 +24,11 @@ where
     T: FromStr,
     T::Err: Display,
 {
-    let s = match Option::<Cow<'_, str>>::deserialize(deserializer)? {
+    let s = match Option::<StrOrInt<'_>>::deserialize(deserializer)? {
         Some(s) => s,
         None => return Ok(None),
     };
-    Ok(Some(s.parse().map_err(D::Error::custom)?))
+    Ok(Some(s.as_str().parse().map_err(D::Error::custom)?))
 }
 
 #[cfg(test)]
@@ -0,0 +1,151 @@
+//! Deserialize integers encoded either as strings or as numbers.
+//!
+//! CometBFT encodes 64-bit integers as JSON strings, as they exceed the
+//! range of the integers represented exactly by JavaScript numbers, but
+//! some fields and versions of the nodes encode them as JSON numbers.
+//! [`StrOrInt`] accepts both, and keeps the digits of the integer as a
+//! string, so that they are parsed into the target type without a loss of
+//! precision, and an integer out of the range of the target type is
+//! rejected by its parser rather than truncated.
+//!
+//! Numbers out of the range of 64-bit integers, which `serde_json`
+//! deserializes as floating point numbers, and fractional numbers are
+//! rejected.
+//!
+//! Telling strings from numbers requires a self-describing format, so
+//! [`StrOrInt`] only accepts both from human-readable formats, such as JSON.
+//! From the other formats, which may not be self-describing, it only
+//! accepts strings, as the serializers using it did before.
+
+use alloc::borrow::Cow;
+use core::{fmt, marker::PhantomData};
+
+use serde::{
+    de::{Error, Visitor},
+    Deserialize, Deserializer,
+};
+
+use crate::prelude::*;
+
+/// The digits of an integer, deserialized from either a string or a number.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StrOrInt<'a>(pub Cow<'a, str>);
+
+impl StrOrInt<'_> {
+    /// The digits of the integer, or the string it was deserialized from.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl<'de: 'a, 'a> Deserialize<'de> for StrOrInt<'a> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(StrOrIntVisitor(PhantomData))
+        } else {
+            deserializer.deserialize_str(StrOrIntVisitor(PhantomData))
+        }
+    }
+}
+
+struct StrOrIntVisitor<'a>(PhantomData<StrOrInt<'a>>);
+
+impl<'de: 'a, 'a> Visitor<'de> for StrOrIntVisitor<'a> {
+    type Value = StrOrInt<'a>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("an integer, or a string")
+    }
+
+    fn visit_borrowed_str<E: Error>(self, v: &'de str) -> Result<Self::Value, E> {
+        Ok(StrOrInt(Cow::Borrowed(v)))
+    }
+
+    fn visit_str<E: Error>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(StrOrInt(Cow::Owned(v.to_owned())))
+    }
+
+    fn visit_string<E: Error>(self, v: String) -> Result<Self::Value, E> {
+        Ok(StrOrInt(Cow::Owned(v)))
+    }
+
+    fn visit_u64<E: Error>(self, v: u64) -> Result<Self::Value, E> {
+        Ok(StrOrInt(Cow::Owned(v.to_string())))
+    }
+
+    fn visit_i64<E: Error>(self, v: i64) -> Result<Self::Value, E> {
+        Ok(StrOrInt(Cow::Owned(v.to_string())))
+    }
+
+    fn visit_u128<E: Error>(self, v: u128) -> Result<Self::Value, E> {
+        Ok(StrOrInt(Cow::Owned(v.to_string())))
+    }
+
+    fn visit_i128<E: Error>(self, v: i128) -> Result<Self::Value, E> {
+        Ok(StrOrInt(Cow::Owned(v.to_string())))
+    }
+
+    fn visit_f64<E: Error>(self, v: f64) -> Result<Self::Value, E> {
+        // All the floating point numbers from 2^53 are integers, and the
+        // smaller ones are exactly converted to and from 64-bit integers.
+        const EXACT: f64 = 9_007_199_254_740_992.0;
+        let integral = v.is_finite() && (v >= EXACT || v <= -EXACT || (v as i64) as f64 == v);
+        if integral {
+            Err(E::custom(format_args!(
+                "integer {v} is out of the range of 64-bit integers, and would lose precision"
+            )))
+        } else {
+            Err(E::custom(format_args!("expected an integer, got {v}")))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::StrOrInt;
+    use crate::prelude::*;
+
+    #[derive(Deserialize)]
+    struct Foo<'a> {
+        #[serde(borrow)]
+        value: StrOrInt<'a>,
+    }
+
+    fn parse(json: &str) -> Result<String, String> {
+        serde_json::from_str::<Foo<'_>>(json)
+            .map(|foo| foo.value.as_str().to_owned())
+            .map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn accepts_strings_and_integers() {
+        assert_eq!(
+            parse(r#"{"value":"9007199254740993"}"#).unwrap(),
+            "9007199254740993"
+        );
+        assert_eq!(
+            parse(r#"{"value":9007199254740993}"#).unwrap(),
+            "9007199254740993"
+        );
+        assert_eq!(parse(r#"{"value":-42}"#).unwrap(), "-42");
+        assert_eq!(
+            parse(r#"{"value":18446744073709551615}"#).unwrap(),
+            "18446744073709551615"
+        );
+    }
+
+    #[test]
+    fn rejects_imprecise_numbers() {
+        let err = parse(r#"{"value":18446744073709551616}"#).unwrap_err();
+        assert!(err.contains("out of the range of 64-bit integers"), "{err}");
+        let err = parse(r#"{"value":-9223372036854775809}"#).unwrap_err();
+        assert!(err.contains("out of the range of 64-bit integers"), "{err}");
+        let err = parse(r#"{"value":1.5}"#).unwrap_err();
+        assert!(err.contains("expected an integer"), "{err}");
+        let err = parse(r#"{"value":-0.5}"#).unwrap_err();
+        assert!(err.contains("expected an integer"), "{err}");
+        assert!(parse(r#"{"value":true}"#).is_err());
+    }
+}
@@ -3,6 +3,7 @@ use core::time::Duration;
 
 use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
 
+use super::str_or_int::StrOrInt;
 use crate::prelude::*;
 
 /// Deserialize string into Duration
@@ -10,7 +11,8 @@ pub fn deserialize<'de, D>(deserializer: D) -> Result<Duration, D::Error>
 where
     D: Deserializer<'de>,
 {
-    let value = String::deserialize(deserializer)?
+    let value = StrOrInt::deserialize(deserializer)?
+        .as_str()
         .parse::<u64>()
         .map_err(|e| D::Error::custom(format!("{e}")))?;
 
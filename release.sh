@@ -25,6 +25,7 @@ DEFAULT_CRATES="tendermint-proto \
   tendermint-abci \
   tendermint-rpc \
   tendermint-p2p \
+  tendermint-privval \
   tendermint-light-client-verifier \
   tendermint-light-client \
   tendermint-light-client-detector \
@@ -0,0 +1,397 @@
+//! Mapping of the error codes returned by the application for transactions
+//! to typed errors.
+//!
+//! The responses of the `/broadcast_tx_*` and `/tx` endpoints report the
+//! outcome of a transaction through the raw `code`, `codespace` and `log`
+//! fields of the ABCI responses. An [`ErrorRegistry`] maps the
+//! `(codespace, code)` pairs defined by an application to the error type of
+//! the user's choice, so that these responses can be converted into a
+//! `Result<TxAccepted, AppError<E>>`:
+//!
+//! ```
+//! use tendermint_rpc::app_error::{AppError, ErrorRegistry};
+//!
+//! #[derive(Clone, Debug, PartialEq)]
+//! enum BankError {
+//!     InsufficientFunds,
+//!     UnknownAddress,
+//! }
+//!
+//! let registry = ErrorRegistry::new()
+//!     .register("bank", 5, BankError::InsufficientFunds)
+//!     .register("bank", 9, BankError::UnknownAddress);
+//! # let _ = registry;
+//! ```
+
+use alloc::{collections::BTreeMap, sync::Arc};
+use core::{fmt, num::NonZeroU32};
+
+use bytes::Bytes;
+use tendermint::{
+    abci::{self, Code},
+    block, Hash,
+};
+
+use crate::{
+    endpoint::{
+        broadcast::{tx_async, tx_commit, tx_sync},
+        tx,
+    },
+    prelude::*,
+};
+
+/// The stage of the processing of a transaction at which it was rejected.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TxStage {
+    /// The transaction was rejected from the mempool by `CheckTx`.
+    CheckTx,
+    /// The execution of the transaction in a block failed.
+    DeliverTx,
+}
+
+impl fmt::Display for TxStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TxStage::CheckTx => write!(f, "CheckTx"),
+            TxStage::DeliverTx => write!(f, "DeliverTx"),
+        }
+    }
+}
+
+/// A transaction rejected by the application, as reported by the node.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TxRejection {
+    /// The hash of the transaction.
+    pub hash: Hash,
+    /// The stage at which the transaction was rejected.
+    pub stage: TxStage,
+    /// The namespace of the error code.
+    pub codespace: String,
+    /// The error code.
+    pub code: NonZeroU32,
+    /// The output of the application's logger.
+    pub log: String,
+}
+
+impl fmt::Display for TxRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "transaction {} rejected in {} with code {} (codespace: '{}'): {}",
+            self.hash, self.stage, self.code, self.codespace, self.log
+        )
+    }
+}
+
+/// A transaction accepted by the application.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TxAccepted {
+    /// The hash of the transaction.
+    pub hash: Hash,
+    /// The height of the block including the transaction, if it was
+    /// executed already.
+    pub height: Option<block::Height>,
+    /// The data returned by the application.
+    pub data: Bytes,
+    /// The output of the application's logger.
+    pub log: String,
+    /// Amount of gas requested for the transaction, when reported.
+    pub gas_wanted: Option<i64>,
+    /// Amount of gas consumed by the transaction, when reported.
+    pub gas_used: Option<i64>,
+    /// Events that occurred while processing the transaction.
+    pub events: Vec<abci::Event>,
+}
+
+/// The error of a transaction rejected by the application.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AppError<E> {
+    /// The rejection maps to a registered error.
+    Registered(E, TxRejection),
+    /// No error is registered for the codespace and code of the rejection.
+    Unregistered(TxRejection),
+}
+
+impl<E> AppError<E> {
+    /// The registered error, if any.
+    pub fn error(&self) -> Option<&E> {
+        match self {
+            AppError::Registered(e, _) => Some(e),
+            AppError::Unregistered(_) => None,
+        }
+    }
+
+    /// The rejection of the transaction, as reported by the node.
+    pub fn rejection(&self) -> &TxRejection {
+        match self {
+            AppError::Registered(_, rejection) | AppError::Unregistered(rejection) => rejection,
+        }
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for AppError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::Registered(e, rejection) => write!(f, "{e}: {rejection}"),
+            AppError::Unregistered(rejection) => rejection.fmt(f),
+        }
+    }
+}
+
+type ErrorConstructor<E> = Arc<dyn Fn(&TxRejection) -> E + Send + Sync>;
+
+/// The typed errors registered for the `(codespace, code)` pairs of an
+/// application.
+pub struct ErrorRegistry<E> {
+    errors: BTreeMap<(String, u32), ErrorConstructor<E>>,
+}
+
+impl<E> ErrorRegistry<E> {
+    /// Create a registry without any error.
+    pub fn new() -> Self {
+        Self {
+            errors: BTreeMap::new(),
+        }
+    }
+
+    /// Register the error to report for the given codespace and code.
+    pub fn register(self, codespace: impl Into<String>, code: u32, error: E) -> Self
+    where
+        E: Clone + Send + Sync + 'static,
+    {
+        self.register_with(codespace, code, move |_| error.clone())
+    }
+
+    /// Register a function building the error to report for the given
+    /// codespace and code from the rejection, for example to parse its log.
+    pub fn register_with<F>(mut self, codespace: impl Into<String>, code: u32, f: F) -> Self
+    where
+        F: Fn(&TxRejection) -> E + Send + Sync + 'static,
+    {
+        self.errors.insert((codespace.into(), code), Arc::new(f));
+        self
+    }
+
+    /// Map a rejection to its registered error, if any.
+    pub fn map(&self, rejection: TxRejection) -> AppError<E> {
+        let key = (rejection.codespace.clone(), rejection.code.get());
+        match self.errors.get(&key) {
+            Some(f) => AppError::Registered(f(&rejection), rejection),
+            None => AppError::Unregistered(rejection),
+        }
+    }
+
+    fn check(
+        &self,
+        hash: Hash,
+        stage: TxStage,
+        code: Code,
+        codespace: &str,
+        log: &str,
+    ) -> Result<(), AppError<E>> {
+        match code {
+            Code::Ok => Ok(()),
+            Code::Err(code) => Err(self.map(TxRejection {
+                hash,
+                stage,
+                codespace: codespace.to_owned(),
+                code,
+                log: log.to_owned(),
+            })),
+        }
+    }
+}
+
+impl<E> Default for ErrorRegistry<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E> Clone for ErrorRegistry<E> {
+    fn clone(&self) -> Self {
+        Self {
+            errors: self.errors.clone(),
+        }
+    }
+}
+
+impl<E> fmt::Debug for ErrorRegistry<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ErrorRegistry")
+            .field("errors", &self.errors.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl tx_sync::Response {
+    /// Convert the `CheckTx` result of the transaction into a `Result`,
+    /// mapping its error code with the given registry.
+    pub fn into_result<E>(self, registry: &ErrorRegistry<E>) -> Result<TxAccepted, AppError<E>> {
+        registry.check(
+            self.hash,
+            TxStage::CheckTx,
+            self.code,
+            &self.codespace,
+            &self.log,
+        )?;
+        Ok(TxAccepted {
+            hash: self.hash,
+            height: None,
+            data: self.data,
+            log: self.log,
+            gas_wanted: None,
+            gas_used: None,
+            events: Vec::new(),
+        })
+    }
+}
+
+impl tx_async::Response {
+    /// Convert the response into a `Result`, mapping its error code with the
+    /// given registry.
+    ///
+    /// As the node does not wait for the `CheckTx` result before responding,
+    /// errors are only reported for transactions it rejected right away,
+    /// for example because they were already in its cache.
+    pub fn into_result<E>(self, registry: &ErrorRegistry<E>) -> Result<TxAccepted, AppError<E>> {
+        registry.check(
+            self.hash,
+            TxStage::CheckTx,
+            self.code,
+            &self.codespace,
+            &self.log,
+        )?;
+        Ok(TxAccepted {
+            hash: self.hash,
+            height: None,
+            data: self.data,
+            log: self.log,
+            gas_wanted: None,
+            gas_used: None,
+            events: Vec::new(),
+        })
+    }
+}
+
+impl tx_commit::Response {
+    /// Convert the `CheckTx` and execution results of the transaction into
+    /// a `Result`, mapping their error codes with the given registry.
+    pub fn into_result<E>(self, registry: &ErrorRegistry<E>) -> Result<TxAccepted, AppError<E>> {
+        let check_tx = &self.check_tx;
+        registry.check(
+            self.hash,
+            TxStage::CheckTx,
+            check_tx.code,
+            &check_tx.codespace,
+            &check_tx.log,
+        )?;
+        exec_tx_result(registry, self.hash, self.height, self.tx_result)
+    }
+}
+
+impl tx::Response {
+    /// Convert the execution result of the transaction into a `Result`,
+    /// mapping its error code with the given registry.
+    pub fn into_result<E>(self, registry: &ErrorRegistry<E>) -> Result<TxAccepted, AppError<E>> {
+        exec_tx_result(registry, self.hash, self.height, self.tx_result)
+    }
+}
+
+fn exec_tx_result<E>(
+    registry: &ErrorRegistry<E>,
+    hash: Hash,
+    height: block::Height,
+    result: abci::types::ExecTxResult,
+) -> Result<TxAccepted, AppError<E>> {
+    registry.check(
+        hash,
+        TxStage::DeliverTx,
+        result.code,
+        &result.codespace,
+        &result.log,
+    )?;
+    Ok(TxAccepted {
+        hash,
+        height: Some(height),
+        data: result.data,
+        log: result.log,
+        gas_wanted: Some(result.gas_wanted),
+        gas_used: Some(result.gas_used),
+        events: result.events,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Response;
+
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    enum TestError {
+        Unauthorized,
+        InvalidKey(String),
+    }
+
+    fn registry() -> ErrorRegistry<TestError> {
+        ErrorRegistry::new()
+            .register("sdk", 4, TestError::Unauthorized)
+            .register_with("kv", 2, |rejection| {
+                TestError::InvalidKey(rejection.log.clone())
+            })
+    }
+
+    fn sync_response(code: u32, codespace: &str, log: &str) -> tx_sync::Response {
+        tx_sync::Response {
+            codespace: codespace.to_owned(),
+            code: code.into(),
+            data: Bytes::new(),
+            log: log.to_owned(),
+            hash: Hash::None,
+        }
+    }
+
+    #[test]
+    fn maps_registered_errors() {
+        let registry = registry();
+
+        let err = sync_response(4, "sdk", "signature verification failed")
+            .into_result(&registry)
+            .unwrap_err();
+        assert_eq!(err.error(), Some(&TestError::Unauthorized));
+        assert_eq!(err.rejection().stage, TxStage::CheckTx);
+
+        let err = sync_response(2, "kv", "empty key")
+            .into_result(&registry)
+            .unwrap_err();
+        assert_eq!(
+            err.error(),
+            Some(&TestError::InvalidKey("empty key".to_owned()))
+        );
+
+        // Codes are scoped to their codespace.
+        let err = sync_response(4, "kv", "")
+            .into_result(&registry)
+            .unwrap_err();
+        assert!(matches!(err, AppError::Unregistered(_)));
+        assert_eq!(err.rejection().code.get(), 4);
+
+        assert!(sync_response(0, "", "").into_result(&registry).is_ok());
+    }
+
+    #[test]
+    fn converts_commit_responses() {
+        let fixture =
+            include_str!("../tests/kvstore_fixtures/v0_37/incoming/broadcast_tx_commit.json");
+        let mut response = tx_commit::Response::from_string(fixture).unwrap();
+        let accepted = response.clone().into_result(&registry()).unwrap();
+        assert_eq!(accepted.height, Some(response.height));
+        assert_eq!(accepted.events, response.tx_result.events);
+
+        response.tx_result.code = 2.into();
+        response.tx_result.codespace = "kv".to_owned();
+        let err = response.into_result(&registry()).unwrap_err();
+        assert_eq!(err.rejection().stage, TxStage::DeliverTx);
+        assert!(matches!(err.error(), Some(TestError::InvalidKey(_))));
+    }
+}
@@ -3,26 +3,71 @@
 mod compat;
 pub use compat::CompatMode;
 
+#[cfg(feature = "verifier")]
+mod verify;
+#[cfg(feature = "verifier")]
+pub use verify::verify_commit;
+
 #[cfg(any(feature = "http-client", feature = "websocket-client"))]
 mod subscription;
 #[cfg(any(feature = "http-client", feature = "websocket-client"))]
 pub use subscription::{Subscription, SubscriptionClient};
 
+#[cfg(feature = "chain-registry")]
+pub mod chain_registry;
+
+#[cfg(any(feature = "http-client", feature = "websocket-client"))]
+pub mod decoding;
+
+#[cfg(any(feature = "http-client", feature = "websocket-client"))]
+pub mod estimate;
+
+#[cfg(any(feature = "http-client", feature = "websocket-client"))]
+pub mod lag;
+
+#[cfg(all(
+    any(feature = "http-client", feature = "websocket-client"),
+    feature = "rust-crypto"
+))]
+pub mod mempool;
+
+#[cfg(any(feature = "http-client", feature = "websocket-client"))]
+pub mod monitor;
+
+#[cfg(any(feature = "http-client", feature = "websocket-client"))]
+mod poller;
+
+#[cfg(any(feature = "http-client", feature = "websocket-client"))]
+pub mod sink;
+
 #[cfg(any(feature = "http-client", feature = "websocket-client"))]
 pub mod sync;
 
+#[cfg(any(feature = "http-client", feature = "websocket-client"))]
+pub mod uptime;
+
 #[cfg(any(feature = "http-client", feature = "websocket-client"))]
 mod transport;
 
+#[cfg(any(feature = "http-client", feature = "websocket-client"))]
+pub use transport::dns;
 #[cfg(feature = "http-client")]
 pub use transport::http::{self, HttpClient, HttpClientUrl};
+#[cfg(feature = "http-client")]
+pub use transport::sse::SseClient;
 #[cfg(feature = "websocket-client")]
 pub use transport::websocket::{
     self, WebSocketClient, WebSocketClientDriver, WebSocketClientUrl, WebSocketConfig,
 };
 
+#[cfg(any(feature = "http-client", feature = "websocket-client"))]
+pub use transport::faulty::{FaultConfig, FaultyClient};
 #[cfg(any(feature = "http-client", feature = "websocket-client"))]
 pub use transport::mock::{MockClient, MockRequestMatcher, MockRequestMethodMatcher};
+#[cfg(any(feature = "http-client", feature = "websocket-client"))]
+pub use transport::polling::{PollingClient, PollingClientDriver};
+#[cfg(any(feature = "http-client", feature = "websocket-client"))]
+pub use transport::retry::{FailoverClient, RetryConfig, RetryingClient};
 
 use core::fmt;
 
@@ -245,6 +290,60 @@ pub trait Client {
         }
     }
 
+    /// `/validators`: get the validator set with the given hash at the given
+    /// height of the given chain, from the given cache if it holds it, or
+    /// else by fetching all the pages of validators at that height and
+    /// caching the result.
+    ///
+    /// The validator set is returned without a proposer. Its hash is not
+    /// checked against the given one, which the caller is expected to do.
+    #[cfg(feature = "rust-crypto")]
+    async fn validator_set<H>(
+        &self,
+        chain_id: &tendermint::chain::Id,
+        height: H,
+        hash: Hash,
+        cache: &crate::ValidatorSetCache,
+    ) -> Result<tendermint::validator::Set, Error>
+    where
+        H: Into<Height> + Send,
+    {
+        let height = height.into();
+        if let Some(validators) = cache.get(chain_id, hash) {
+            return Ok(validators);
+        }
+
+        let validators = self.validators(height, Paging::All).await?.validators;
+        let validators = tendermint::validator::Set::without_proposer(validators);
+        cache.insert(chain_id, validators.clone());
+        Ok(validators)
+    }
+
+    /// Fetch the commit and the validators at the given height, and verify the
+    /// commit against the given, trusted, validator set.
+    ///
+    /// Returns the signed header only if it carries a valid commit by the
+    /// validators at that height, signed by more than 2/3 of the voting power
+    /// of `trusted`. See [`verify_commit`] for details.
+    #[cfg(feature = "verifier")]
+    async fn verify_commit<H>(
+        &self,
+        height: H,
+        trusted: &tendermint::validator::Set,
+    ) -> Result<tendermint::block::signed_header::SignedHeader, Error>
+    where
+        H: Into<Height> + Send,
+    {
+        let height = height.into();
+        let signed_header = self.commit(height).await?.signed_header;
+        let validators = self.validators(height, Paging::All).await?.validators;
+        let validators = tendermint::validator::Set::without_proposer(validators);
+
+        verify_commit(&signed_header, &validators, trusted)
+            .map_err(|e| Error::invalid_commit(height, e))?;
+        Ok(signed_header)
+    }
+
     /// `/consensus_params`: get the latest consensus parameters.
     async fn latest_consensus_params(&self) -> Result<consensus_params::Response, Error> {
         self.perform(consensus_params::Request::new(None)).await
@@ -276,6 +375,21 @@ pub trait Client {
         self.perform(net_info::Request).await
     }
 
+    /// `/num_unconfirmed_txs`: get the number and total size of the
+    /// transactions in the mempool.
+    async fn num_unconfirmed_txs(&self) -> Result<num_unconfirmed_txs::Response, Error> {
+        self.perform(num_unconfirmed_txs::Request).await
+    }
+
+    /// `/unconfirmed_txs`: list the transactions in the mempool, up to the
+    /// given limit, along with their number and total size.
+    async fn unconfirmed_txs(
+        &self,
+        limit: Option<u32>,
+    ) -> Result<unconfirmed_txs::Response, Error> {
+        self.perform(unconfirmed_txs::Request::new(limit)).await
+    }
+
     /// `/status`: get Tendermint status including node info, pubkey, latest
     /// block hash, app hash, block height and time.
     async fn status(&self) -> Result<status::Response, Error> {
@@ -148,6 +148,8 @@ enum ClientRequest {
     LatestCommit,
     /// Obtain information about the P2P stack and other network connections.
     NetInfo,
+    /// Get the number and total size of the transactions in the mempool.
+    NumUnconfirmedTxs,
     /// Get Tendermint status (node info, public key, latest block hash, etc.).
     Status,
     /// Fetch a transaction by way of its hash.
@@ -173,6 +175,12 @@ enum ClientRequest {
         #[structopt(long)]
         prove: bool,
     },
+    /// List the transactions in the mempool.
+    UnconfirmedTxs {
+        /// The maximum number of transactions to list.
+        #[structopt(long)]
+        limit: Option<u32>,
+    },
     /// Get the validators at the given height.
     Validators {
         /// The height at which to query the validators.
@@ -419,6 +427,10 @@ where
         ClientRequest::NetInfo => {
             serde_json::to_string_pretty(&client.net_info().await?).map_err(Error::serde)?
         },
+        ClientRequest::NumUnconfirmedTxs => {
+            serde_json::to_string_pretty(&client.num_unconfirmed_txs().await?)
+                .map_err(Error::serde)?
+        },
         ClientRequest::Status => {
             serde_json::to_string_pretty(&client.status().await?).map_err(Error::serde)?
         },
@@ -443,6 +455,10 @@ where
                 .await?,
         )
         .map_err(Error::serde)?,
+        ClientRequest::UnconfirmedTxs { limit } => {
+            serde_json::to_string_pretty(&client.unconfirmed_txs(limit).await?)
+                .map_err(Error::serde)?
+        },
         ClientRequest::Validators {
             height,
             all,
@@ -0,0 +1,469 @@
+//! Discovery of the RPC endpoints of a chain from a chain registry.
+//!
+//! A [`ChainRegistry`] fetches the metadata of chains from a source laid out
+//! like the [Cosmos chain registry]: one directory per chain name, holding a
+//! `chain.json` file which describes the chain, including its ID and the
+//! public RPC endpoints serving it. This allows applications to bootstrap
+//! from the name of a chain rather than from hard-coded URLs.
+//!
+//! The registry is trusted to report the right chain ID, and
+//! [`ChainInfo::connect`] only returns clients connected to nodes on that
+//! chain. The endpoints listed for a chain are operated by third parties,
+//! and the data they serve should still be verified, e.g. with the light
+//! client.
+//!
+//! [Cosmos chain registry]: https://github.com/cosmos/chain-registry
+
+use core::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use tendermint::{
+    chain,
+    crypto::{default::Sha256, Sha256 as _},
+    Hash,
+};
+use tokio::time::Duration;
+
+use crate::{
+    client::transport::http::USER_AGENT, prelude::*, Client, Error, HttpClient, HttpClientUrl,
+};
+
+/// The URL of the [Cosmos chain registry] on GitHub.
+///
+/// [Cosmos chain registry]: https://github.com/cosmos/chain-registry
+pub const COSMOS_CHAIN_REGISTRY_URL: &str =
+    "https://raw.githubusercontent.com/cosmos/chain-registry/master/";
+
+/// The default timeout of the requests to a [`ChainRegistry`].
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The default maximum size of the genesis files downloaded from a
+/// [`ChainRegistry`], which leaves room for the genesis of large chains.
+pub const DEFAULT_MAX_GENESIS_SIZE: usize = 512 * 1024 * 1024;
+
+/// The maximum size of the `chain.json` files fetched from a
+/// [`ChainRegistry`].
+const MAX_CHAIN_JSON_SIZE: usize = 1024 * 1024;
+
+/// The metadata of a chain, as listed in its `chain.json` file.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainInfo {
+    /// The name of the chain in the registry
+    pub chain_name: String,
+    /// The ID of the chain
+    pub chain_id: chain::Id,
+    /// The source code and genesis of the chain
+    #[serde(default)]
+    pub codebase: Codebase,
+    /// The public API endpoints serving the chain
+    #[serde(default)]
+    pub apis: Apis,
+}
+
+/// The part of the metadata of a chain describing its source code and
+/// genesis.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Codebase {
+    /// The genesis of the chain, if listed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub genesis: Option<Genesis>,
+}
+
+/// The genesis of a chain.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Genesis {
+    /// The URL at which the genesis file can be downloaded
+    pub genesis_url: String,
+}
+
+/// The public API endpoints serving a chain.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Apis {
+    /// The Tendermint RPC endpoints
+    #[serde(default)]
+    pub rpc: Vec<Endpoint>,
+}
+
+/// A public API endpoint.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Endpoint {
+    /// The URL of the endpoint
+    pub address: String,
+    /// The operator of the endpoint, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+}
+
+impl ChainInfo {
+    /// Parse the contents of a `chain.json` file.
+    pub fn from_json(json: &[u8]) -> Result<Self, Error> {
+        serde_json::from_slice(json).map_err(Error::serde)
+    }
+
+    /// The URLs of the RPC endpoints of the chain usable with an
+    /// [`HttpClient`], in the order in which they are listed.
+    ///
+    /// The endpoints whose address is not a valid HTTP or HTTPS URL are left
+    /// out.
+    pub fn rpc_urls(&self) -> Vec<HttpClientUrl> {
+        self.apis
+            .rpc
+            .iter()
+            .filter_map(|endpoint| HttpClientUrl::from_str(endpoint.address.trim()).ok())
+            .collect()
+    }
+
+    /// Construct an [`HttpClient`] for each of the RPC endpoints of the
+    /// chain, e.g. to configure the primary and the witnesses of a light
+    /// client.
+    ///
+    /// The clients are not checked to be connected to nodes on the chain;
+    /// see [`ChainInfo::connect`] for that.
+    pub fn http_clients(&self) -> Result<Vec<HttpClient>, Error> {
+        self.rpc_urls()
+            .into_iter()
+            .map(|url| HttpClient::builder(url).build())
+            .collect()
+    }
+
+    /// Connect to the first RPC endpoint of the chain, in the order in which
+    /// they are listed, which responds within the given timeout and reports
+    /// being on the chain.
+    ///
+    /// The endpoints failing to do so are skipped. If none of them succeeds,
+    /// the error of the last one is returned.
+    pub async fn connect(&self, timeout: Duration) -> Result<HttpClient, Error> {
+        let mut last_error = Error::no_rpc_endpoint(self.chain_name.clone());
+        for url in self.rpc_urls() {
+            match self.try_connect(url, timeout).await {
+                Ok(client) => return Ok(client),
+                Err(e) => last_error = e,
+            }
+        }
+        Err(last_error)
+    }
+
+    async fn try_connect(
+        &self,
+        url: HttpClientUrl,
+        timeout: Duration,
+    ) -> Result<HttpClient, Error> {
+        let client = HttpClient::builder(url).connect_timeout(timeout).build()?;
+        let status = tokio::time::timeout(timeout, client.status())
+            .await
+            .map_err(|_| Error::timeout(timeout))??;
+        if status.node_info.network != self.chain_id {
+            return Err(Error::chain_id_mismatch(
+                self.chain_id.clone(),
+                status.node_info.network,
+            ));
+        }
+        Ok(client)
+    }
+}
+
+/// A source of chain metadata laid out like the Cosmos chain registry.
+///
+/// The requests to the registry time out after [`DEFAULT_TIMEOUT`], and the
+/// genesis files downloaded are limited to [`DEFAULT_MAX_GENESIS_SIZE`],
+/// unless configured otherwise. See the [module documentation](self) for
+/// details.
+#[derive(Clone, Debug)]
+pub struct ChainRegistry {
+    base_url: reqwest::Url,
+    inner: reqwest::Client,
+    timeout: Duration,
+    max_genesis_size: usize,
+}
+
+impl ChainRegistry {
+    /// Use the [Cosmos chain registry] on GitHub.
+    ///
+    /// [Cosmos chain registry]: https://github.com/cosmos/chain-registry
+    pub fn new() -> Self {
+        Self::with_base_url(COSMOS_CHAIN_REGISTRY_URL.parse().unwrap())
+    }
+
+    /// Use the registry whose chain directories are found under the given
+    /// URL, e.g. a mirror or a local copy of the Cosmos chain registry.
+    pub fn with_base_url(mut base_url: reqwest::Url) -> Self {
+        // Make the last segment of the path a directory, for joins to
+        // append to it rather than replace it.
+        if !base_url.path().ends_with('/') {
+            let path = format!("{}/", base_url.path());
+            base_url.set_path(&path);
+        }
+        let inner = reqwest::Client::builder()
+            .user_agent(USER_AGENT)
+            .build()
+            .unwrap_or_default();
+        Self {
+            base_url,
+            inner,
+            timeout: DEFAULT_TIMEOUT,
+            max_genesis_size: DEFAULT_MAX_GENESIS_SIZE,
+        }
+    }
+
+    /// Time out the requests to the registry after the given duration.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Refuse the genesis files larger than the given size, in bytes.
+    pub fn with_max_genesis_size(mut self, max_genesis_size: usize) -> Self {
+        self.max_genesis_size = max_genesis_size;
+        self
+    }
+
+    /// The URL under which the chain directories are found.
+    pub fn base_url(&self) -> &reqwest::Url {
+        &self.base_url
+    }
+
+    /// Fetch the metadata of the chain with the given name.
+    pub async fn chain(&self, chain_name: &str) -> Result<ChainInfo, Error> {
+        let url = self.chain_url(chain_name)?;
+        ChainInfo::from_json(&self.get(url, MAX_CHAIN_JSON_SIZE).await?)
+    }
+
+    /// The URL of the `chain.json` file of the chain with the given name,
+    /// which is a single segment of the path, whatever it contains.
+    fn chain_url(&self, chain_name: &str) -> Result<reqwest::Url, Error> {
+        let mut url = self.base_url.clone();
+        url.path_segments_mut()
+            .map_err(|_| Error::parse_url(url::ParseError::RelativeUrlWithCannotBeABaseBase))?
+            .pop_if_empty()
+            .extend([chain_name, "chain.json"]);
+        Ok(url)
+    }
+
+    /// Download the genesis file of the given chain, if listed, and return
+    /// its SHA-256 hash, which can be compared with the hash published by
+    /// the developers of the chain.
+    pub async fn genesis_hash(&self, chain: &ChainInfo) -> Result<Option<Hash>, Error> {
+        let Some(genesis) = &chain.codebase.genesis else {
+            return Ok(None);
+        };
+        let url = reqwest::Url::parse(&genesis.genesis_url).map_err(Error::parse_url)?;
+        let genesis = self.get(url, self.max_genesis_size).await?;
+        Ok(Some(Hash::Sha256(Sha256::digest(&genesis))))
+    }
+
+    async fn get(&self, url: reqwest::Url, limit: usize) -> Result<Vec<u8>, Error> {
+        let too_large = || Error::download_too_large(url.to_string(), limit);
+        let mut response = self
+            .inner
+            .get(url.clone())
+            .timeout(self.timeout)
+            .send()
+            .await
+            .map_err(Error::http)?;
+        if !response.status().is_success() {
+            return Err(Error::http_request_failed(response.status()));
+        }
+        if response
+            .content_length()
+            .is_some_and(|len| len > limit as u64)
+        {
+            return Err(too_large());
+        }
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await.map_err(Error::http)? {
+            if body.len() + chunk.len() > limit {
+                return Err(too_large());
+            }
+            body.extend_from_slice(&chunk);
+        }
+        Ok(body)
+    }
+}
+
+impl Default for ChainRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    use super::*;
+    use crate::{Code, ErrorKind};
+
+    const CHAIN_JSON: &str = r#"{
+        "$schema": "../chain.schema.json",
+        "chain_name": "testhub",
+        "status": "live",
+        "network_type": "mainnet",
+        "pretty_name": "Test Hub",
+        "chain_id": "testhub-4",
+        "bech32_prefix": "test",
+        "codebase": {
+            "git_repo": "https://github.com/example/testhub",
+            "recommended_version": "v15.0.0",
+            "genesis": {
+                "genesis_url": "https://example.com/genesis.json"
+            }
+        },
+        "apis": {
+            "rpc": [
+                { "address": "https://rpc.example.com", "provider": "Example" },
+                { "address": "not a url" },
+                { "address": "http://127.0.0.1:26657" }
+            ],
+            "rest": [
+                { "address": "https://lcd.example.com", "provider": "Example" }
+            ]
+        }
+    }"#;
+
+    /// Serve the given responses, one per connection, from a local server.
+    fn serve(responses: Vec<(&'static str, String)>) -> (String, thread::JoinHandle<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}/", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            for (status, body) in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = [0; 4096];
+                let _ = stream.read(&mut request).unwrap();
+                write!(
+                    stream,
+                    "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                )
+                .unwrap();
+            }
+        });
+        (base_url, server)
+    }
+
+    #[test]
+    fn parses_chain_json() {
+        let chain = ChainInfo::from_json(CHAIN_JSON.as_bytes()).unwrap();
+        assert_eq!(chain.chain_name, "testhub");
+        assert_eq!(chain.chain_id.as_str(), "testhub-4");
+        assert_eq!(
+            chain.codebase.genesis.unwrap().genesis_url,
+            "https://example.com/genesis.json"
+        );
+        assert_eq!(chain.apis.rpc.len(), 3);
+        assert_eq!(chain.apis.rpc[0].provider.as_deref(), Some("Example"));
+
+        let chain = ChainInfo::from_json(CHAIN_JSON.as_bytes()).unwrap();
+        let urls: Vec<_> = chain
+            .rpc_urls()
+            .into_iter()
+            .map(|url| reqwest::Url::from(url).to_string())
+            .collect();
+        assert_eq!(
+            urls,
+            ["https://rpc.example.com/", "http://127.0.0.1:26657/"]
+        );
+        assert_eq!(chain.http_clients().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn escapes_chain_names() {
+        let registry =
+            ChainRegistry::with_base_url("https://example.com/registry".parse().unwrap());
+        let url = |name| registry.chain_url(name).unwrap().to_string();
+        assert_eq!(
+            url("testhub"),
+            "https://example.com/registry/testhub/chain.json"
+        );
+        assert_eq!(
+            url("../other/x?y#z"),
+            "https://example.com/registry/..%2Fother%2Fx%3Fy%23z/chain.json"
+        );
+    }
+
+    #[tokio::test]
+    async fn refuses_large_genesis_files() {
+        let (base_url, server) = serve(vec![("200 OK", "a".repeat(100))]);
+        let registry = ChainRegistry::with_base_url(base_url.parse().unwrap())
+            .with_timeout(Duration::from_secs(5))
+            .with_max_genesis_size(99);
+        let mut chain = ChainInfo::from_json(CHAIN_JSON.as_bytes()).unwrap();
+        chain.codebase.genesis = Some(Genesis {
+            genesis_url: format!("{base_url}genesis.json"),
+        });
+        let err = registry.genesis_hash(&chain).await.unwrap_err();
+        match err.detail() {
+            crate::error::ErrorDetail::DownloadTooLarge(e) => assert_eq!(e.limit, 99),
+            _ => panic!("unexpected error: {err}"),
+        }
+        server.join().unwrap();
+    }
+
+    #[tokio::test]
+    async fn fetches_chain_and_genesis_hash() {
+        let genesis = r#"{"chain_id":"testhub-4"}"#;
+        let (base_url, server) = serve(vec![
+            ("200 OK", CHAIN_JSON.to_owned()),
+            ("404 Not Found", String::new()),
+            ("200 OK", genesis.to_owned()),
+        ]);
+        let registry = ChainRegistry::with_base_url(base_url.parse().unwrap());
+
+        let mut chain = registry.chain("testhub").await.unwrap();
+        assert_eq!(chain.chain_id.as_str(), "testhub-4");
+
+        let err = registry.chain("unknown").await.unwrap_err();
+        assert_eq!(
+            err.kind(),
+            ErrorKind::Server {
+                code: Code::HttpError
+            }
+        );
+
+        chain.codebase.genesis = Some(Genesis {
+            genesis_url: format!("{base_url}genesis.json"),
+        });
+        let hash = registry.genesis_hash(&chain).await.unwrap().unwrap();
+        assert_eq!(hash, Hash::Sha256(Sha256::digest(genesis.as_bytes())));
+        server.join().unwrap();
+
+        chain.codebase.genesis = None;
+        assert_eq!(registry.genesis_hash(&chain).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn connects_to_endpoint_on_chain() {
+        let status = |network: &str| {
+            let body = include_str!("../../tests/kvstore_fixtures/v0_37/incoming/status.json");
+            let mut json: serde_json::Value = serde_json::from_str(body).unwrap();
+            json["result"]["node_info"]["network"] = network.into();
+            ("200 OK", json.to_string())
+        };
+        let (other_chain, other_server) = serve(vec![status("otherhub-1")]);
+        let (on_chain, on_chain_server) = serve(vec![status("testhub-4"), status("testhub-4")]);
+
+        let mut chain = ChainInfo::from_json(CHAIN_JSON.as_bytes()).unwrap();
+        chain.apis.rpc = vec![
+            Endpoint {
+                address: other_chain,
+                provider: None,
+            },
+            Endpoint {
+                address: on_chain,
+                provider: None,
+            },
+        ];
+        let client = chain.connect(Duration::from_secs(5)).await.unwrap();
+        let status = client.status().await.unwrap();
+        assert_eq!(status.node_info.network, chain.chain_id);
+        other_server.join().unwrap();
+        on_chain_server.join().unwrap();
+
+        chain.apis.rpc.truncate(0);
+        let err = chain.connect(Duration::from_secs(5)).await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Request);
+    }
+}
@@ -0,0 +1,251 @@
+//! Decoding of the transactions received from a node.
+//!
+//! Blocks, `/tx` and `/tx_search` responses and subscription events carry
+//! transactions as raw bytes. Their `decode_tx`/`decode_txs` methods decode
+//! them with a [`TxDecoder`] passed to each call. A [`DecodingClient`]
+//! instead holds the decoder of an application, registered once, and
+//! returns these values along with their decoded transactions.
+
+use alloc::sync::Arc;
+use core::pin::Pin;
+
+use futures::{
+    task::{Context, Poll},
+    Stream,
+};
+use pin_project::pin_project;
+use tendermint::{
+    block::Height,
+    tx::{DecodedTx, TxDecoder},
+    Block, Hash,
+};
+
+use crate::{
+    client::{Client, Subscription, SubscriptionClient},
+    endpoint::{tx, tx_search},
+    event::Event,
+    prelude::*,
+    query::Query,
+    Error, Order,
+};
+
+/// A value carrying transactions, along with its decoded transactions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Decoded<T, Tx, E> {
+    /// The value, which still carries the raw bytes of its transactions.
+    pub value: T,
+    /// The results of decoding the transactions of the value, in order.
+    pub txs: Vec<Result<DecodedTx<Tx>, E>>,
+}
+
+/// A value decoded with the decoder `D`.
+pub type DecodedWith<T, D> = Decoded<T, <D as TxDecoder>::Tx, <D as TxDecoder>::Error>;
+
+/// A client decoding the transactions it receives with a registered
+/// [`TxDecoder`].
+///
+/// The requests which do not carry transactions are made with the wrapped
+/// client, accessible with [`DecodingClient::inner`].
+#[derive(Debug)]
+pub struct DecodingClient<C, D> {
+    inner: C,
+    decoder: Arc<D>,
+}
+
+impl<C, D> DecodingClient<C, D>
+where
+    D: TxDecoder,
+{
+    /// Wrap the given client, decoding the transactions it receives with
+    /// the given decoder.
+    pub fn new(inner: C, decoder: D) -> Self {
+        Self {
+            inner,
+            decoder: Arc::new(decoder),
+        }
+    }
+
+    /// The wrapped client.
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    /// Return the wrapped client.
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+
+    /// The registered decoder.
+    pub fn decoder(&self) -> &D {
+        &self.decoder
+    }
+
+    /// `/block`: get the block at the given height, along with its decoded
+    /// transactions.
+    pub async fn block<H>(&self, height: H) -> Result<DecodedWith<Block, D>, Error>
+    where
+        C: Client + Sync,
+        H: Into<Height> + Send,
+    {
+        let block = self.inner.block(height).await?.block;
+        let txs = block.decode_txs(self.decoder()).collect();
+        Ok(Decoded { value: block, txs })
+    }
+
+    /// `/tx`: find the transaction with the given hash, and decode it.
+    pub async fn tx(&self, hash: Hash, prove: bool) -> Result<DecodedWith<tx::Response, D>, Error>
+    where
+        C: Client + Sync,
+    {
+        let response = self.inner.tx(hash, prove).await?;
+        let txs = vec![response.decode_tx(self.decoder())];
+        Ok(Decoded {
+            value: response,
+            txs,
+        })
+    }
+
+    /// `/tx_search`: search for transactions, and decode the transactions
+    /// found.
+    pub async fn tx_search(
+        &self,
+        query: Query,
+        prove: bool,
+        page: u32,
+        per_page: u8,
+        order: Order,
+    ) -> Result<DecodedWith<tx_search::Response, D>, Error>
+    where
+        C: Client + Sync,
+    {
+        let response = self
+            .inner
+            .tx_search(query, prove, page, per_page, order)
+            .await?;
+        let txs = response.decode_txs(self.decoder()).collect();
+        Ok(Decoded {
+            value: response,
+            txs,
+        })
+    }
+
+    /// Subscribe to the events matching the given query, decoding the
+    /// transactions they carry.
+    pub async fn subscribe(&self, query: Query) -> Result<DecodedSubscription<D>, Error>
+    where
+        C: SubscriptionClient + Sync,
+    {
+        let subscription = self.inner.subscribe(query).await?;
+        Ok(DecodedSubscription {
+            subscription,
+            decoder: self.decoder.clone(),
+        })
+    }
+}
+
+/// A [`Subscription`] whose events are decoded with the decoder of the
+/// [`DecodingClient`] which created it.
+#[pin_project]
+#[derive(Debug)]
+pub struct DecodedSubscription<D> {
+    #[pin]
+    subscription: Subscription,
+    decoder: Arc<D>,
+}
+
+impl<D> DecodedSubscription<D> {
+    /// The underlying subscription.
+    pub fn subscription(&self) -> &Subscription {
+        &self.subscription
+    }
+}
+
+impl<D: TxDecoder> Stream for DecodedSubscription<D> {
+    type Item = Result<DecodedWith<Event, D>, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+        this.subscription.poll_next(cx).map(|event| {
+            event.map(|event| {
+                event.map(|event| {
+                    let txs = event.decode_txs(&**this.decoder);
+                    Decoded { value: event, txs }
+                })
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+
+    use super::*;
+    use crate::{
+        client::{MockClient, MockRequestMethodMatcher},
+        event::v0_37::DeEvent,
+        query::EventType,
+        Method, Response,
+    };
+
+    /// Decodes the `key=value` transactions of the kvstore application.
+    fn kvstore_tx(raw: &[u8]) -> Result<(String, String), &'static str> {
+        let tx = core::str::from_utf8(raw).map_err(|_| "not UTF-8")?;
+        tx.split_once('=')
+            .map(|(k, v)| (k.to_owned(), v.to_owned()))
+            .ok_or("missing '='")
+    }
+
+    #[tokio::test]
+    async fn decodes_searched_txs() {
+        let matcher = MockRequestMethodMatcher::default().map(
+            Method::TxSearch,
+            Ok(
+                include_str!("../../tests/kvstore_fixtures/v0_38/incoming/tx_search_no_prove.json")
+                    .to_string(),
+            ),
+        );
+        let (client, driver) = MockClient::new(matcher);
+        let driver_hdl = tokio::spawn(driver.run());
+        let client = DecodingClient::new(client, kvstore_tx);
+
+        let query = Query::from(EventType::Tx);
+        let found = client
+            .tx_search(query, false, 1, 10, Order::Ascending)
+            .await
+            .unwrap();
+        assert_eq!(found.txs.len(), found.value.txs.len());
+        let decoded = found.txs[0].as_ref().unwrap();
+        assert_eq!(decoded.tx, ("async-key".to_owned(), "value".to_owned()));
+        assert_eq!(decoded.raw, found.value.txs[0].tx);
+
+        client.into_inner().close();
+        driver_hdl.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn decodes_subscribed_events() {
+        let (client, driver) = MockClient::new(MockRequestMethodMatcher::default());
+        let driver_hdl = tokio::spawn(driver.run());
+        let client = DecodingClient::new(client, kvstore_tx);
+
+        let event: Event = DeEvent::from_string(include_str!(
+            "../../tests/kvstore_fixtures/v0_37/incoming/subscribe_txs_0.json"
+        ))
+        .unwrap()
+        .into();
+        let mut subscription = client.subscribe(EventType::Tx.into()).await.unwrap();
+        client.inner().publish(&event);
+
+        let decoded = subscription.next().await.unwrap().unwrap();
+        assert_eq!(decoded.value, event);
+        assert_eq!(decoded.txs.len(), 1);
+        let (key, value) = &decoded.txs[0].as_ref().unwrap().tx;
+        assert!(key.starts_with("tx"), "unexpected key {key}");
+        assert_eq!(value, "value");
+
+        drop(subscription);
+        client.into_inner().close();
+        driver_hdl.await.unwrap().unwrap();
+    }
+}
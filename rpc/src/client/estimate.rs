@@ -0,0 +1,463 @@
+//! Estimation of the time of future blocks.
+//!
+//! A [`BlockTimeEstimator`] is fed with the heights and times of recent
+//! blocks, e.g. fetched with [`BlockTimeEstimator::fetch_recent`] or taken
+//! from the headers verified by a light client, and estimates the time at
+//! which a future height will be reached, or the height reached at a future
+//! time, such as the height of a planned upgrade.
+//!
+//! The duration of a block is estimated from the intervals between the
+//! recorded blocks by a pluggable [`Smoothing`], and the estimates come
+//! with bounds derived from the variability of the intervals: the duration
+//! of `n` blocks is assumed to deviate from its expected value by at most
+//! `z` times the standard deviation of the duration of a block, times `√n`.
+
+use alloc::collections::BTreeMap;
+use core::time::Duration;
+
+use tendermint::{block::Header, block::Height, Time};
+
+use crate::{client::Client, prelude::*, Error};
+
+/// The maximum number of blocks returned by the `/blockchain` endpoint.
+const BLOCKCHAIN_PAGE_SIZE: u64 = 20;
+
+/// The interval between two consecutive recorded blocks, which may be
+/// separated by blocks which were not recorded.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Interval {
+    /// The number of blocks between the two recorded blocks.
+    pub blocks: u64,
+    /// The time elapsed between the two recorded blocks.
+    pub elapsed: Duration,
+}
+
+impl Interval {
+    /// The average duration of a block over this interval, in seconds.
+    pub fn block_time(&self) -> f64 {
+        self.elapsed.as_secs_f64() / self.blocks as f64
+    }
+}
+
+/// Estimates the duration of a block from the intervals between the
+/// recorded blocks.
+pub trait Smoothing {
+    /// Estimate the duration of a block in seconds, from the given
+    /// intervals, oldest first. There is at least one interval.
+    fn block_time(&self, intervals: &[Interval]) -> f64;
+}
+
+/// The average duration of a block over all the recorded blocks.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Mean;
+
+impl Smoothing for Mean {
+    fn block_time(&self, intervals: &[Interval]) -> f64 {
+        let blocks: u64 = intervals.iter().map(|interval| interval.blocks).sum();
+        let elapsed: f64 = intervals.iter().map(|i| i.elapsed.as_secs_f64()).sum();
+        elapsed / blocks as f64
+    }
+}
+
+/// The median of the durations of the intervals, which is not skewed by a
+/// few outliers, e.g. after a chain halt.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Median;
+
+impl Smoothing for Median {
+    fn block_time(&self, intervals: &[Interval]) -> f64 {
+        let mut block_times: Vec<f64> = intervals.iter().map(Interval::block_time).collect();
+        block_times.sort_by(f64::total_cmp);
+        // Both are the middle element when there is an odd number of them.
+        let lower = block_times[(block_times.len() - 1) / 2];
+        let upper = block_times[block_times.len() / 2];
+        (lower + upper) / 2.0
+    }
+}
+
+/// An exponential moving average of the durations of the intervals, which
+/// follows changes of the block time more closely than the mean.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ExponentialMovingAverage {
+    /// The weight of the latest interval, between 0 and 1.
+    pub alpha: f64,
+}
+
+impl Default for ExponentialMovingAverage {
+    fn default() -> Self {
+        Self { alpha: 0.1 }
+    }
+}
+
+impl Smoothing for ExponentialMovingAverage {
+    fn block_time(&self, intervals: &[Interval]) -> f64 {
+        let (first, rest) = intervals.split_first().expect("at least one interval");
+        rest.iter().fold(first.block_time(), |average, interval| {
+            self.alpha * interval.block_time() + (1.0 - self.alpha) * average
+        })
+    }
+}
+
+/// Configuration of a [`BlockTimeEstimator`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct EstimatorConfig {
+    /// The number of most recent blocks kept to estimate the block time.
+    pub max_samples: usize,
+    /// The number of standard deviations covered by the bounds of the
+    /// estimates; 2 covers about 95% of normally distributed block times.
+    pub z_score: f64,
+}
+
+impl Default for EstimatorConfig {
+    fn default() -> Self {
+        Self {
+            max_samples: 1000,
+            z_score: 2.0,
+        }
+    }
+}
+
+/// The estimated duration of a block.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BlockTime {
+    /// The expected duration of a block, as estimated by the smoothing.
+    pub expected: Duration,
+    /// The standard deviation of the duration of a block.
+    pub std_dev: Duration,
+}
+
+/// The estimated time of a future block.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TimeEstimate {
+    /// The expected time of the block.
+    pub expected: Time,
+    /// The earliest time of the block, within the confidence bounds.
+    pub earliest: Time,
+    /// The latest time of the block, within the confidence bounds.
+    pub latest: Time,
+}
+
+/// The estimated height of the chain at a future time.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct HeightEstimate {
+    /// The expected height.
+    pub expected: Height,
+    /// The lowest height, within the confidence bounds.
+    pub lowest: Height,
+    /// The highest height, within the confidence bounds.
+    pub highest: Height,
+}
+
+/// Estimates the time of future blocks from the times of recent ones.
+///
+/// ## Examples
+///
+/// ```rust,ignore
+/// use tendermint_rpc::client::estimate::{BlockTimeEstimator, EstimatorConfig};
+///
+/// let mut estimator = BlockTimeEstimator::new(EstimatorConfig::default());
+/// estimator.fetch_recent(&client, 200).await?;
+/// if let Some(estimate) = estimator.estimate_time(upgrade_height) {
+///     println!("upgrade expected at {}, between {} and {}",
+///         estimate.expected, estimate.earliest, estimate.latest);
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct BlockTimeEstimator<S = Mean> {
+    config: EstimatorConfig,
+    smoothing: S,
+    samples: BTreeMap<Height, Time>,
+}
+
+impl BlockTimeEstimator {
+    /// Create an estimator averaging the durations of the recorded blocks.
+    pub fn new(config: EstimatorConfig) -> Self {
+        Self::with_smoothing(config, Mean)
+    }
+}
+
+impl<S: Smoothing> BlockTimeEstimator<S> {
+    /// Create an estimator estimating the duration of a block with the
+    /// given smoothing.
+    pub fn with_smoothing(config: EstimatorConfig, smoothing: S) -> Self {
+        Self {
+            config,
+            smoothing,
+            samples: BTreeMap::new(),
+        }
+    }
+
+    /// Record the time of the block at the given height.
+    ///
+    /// Only the configured number of highest blocks are kept.
+    pub fn record(&mut self, height: Height, time: Time) {
+        self.samples.insert(height, time);
+        while self.samples.len() > self.config.max_samples {
+            self.samples.pop_first();
+        }
+    }
+
+    /// Record the height and time of the given header.
+    pub fn record_header(&mut self, header: &Header) {
+        self.record(header.height, header.time);
+    }
+
+    /// Fetch and record the headers of the given number of latest blocks.
+    pub async fn fetch_recent<C>(&mut self, client: &C, count: u64) -> Result<(), Error>
+    where
+        C: Client + Sync,
+    {
+        let latest = client.status().await?.sync_info.latest_block_height.value();
+        let lowest = latest.saturating_sub(count.saturating_sub(1)).max(1);
+        let mut max = latest;
+        while max >= lowest {
+            let min = max.saturating_sub(BLOCKCHAIN_PAGE_SIZE - 1).max(lowest);
+            let min_height = Height::try_from(min).map_err(Error::tendermint)?;
+            let max_height = Height::try_from(max).map_err(Error::tendermint)?;
+            let response = client.blockchain(min_height, max_height).await?;
+            for meta in &response.block_metas {
+                self.record_header(&meta.header);
+            }
+            max = min - 1;
+        }
+        Ok(())
+    }
+
+    /// The height and time of the highest recorded block.
+    pub fn latest(&self) -> Option<(Height, Time)> {
+        self.samples
+            .last_key_value()
+            .map(|(height, time)| (*height, *time))
+    }
+
+    /// The estimated duration of a block, if at least two blocks were
+    /// recorded, with increasing times.
+    pub fn block_time(&self) -> Option<BlockTime> {
+        let (expected, std_dev) = self.block_time_secs()?;
+        Some(BlockTime {
+            expected: Duration::from_secs_f64(expected),
+            std_dev: Duration::from_secs_f64(std_dev),
+        })
+    }
+
+    /// Estimate the time of the block at the given height, which must be
+    /// higher than the highest recorded block.
+    pub fn estimate_time(&self, height: Height) -> Option<TimeEstimate> {
+        let (latest_height, latest_time) = self.latest()?;
+        let blocks = height.value().checked_sub(latest_height.value())?;
+        if blocks == 0 {
+            return None;
+        }
+        let (block_time, std_dev) = self.block_time_secs()?;
+        let expected = blocks as f64 * block_time;
+        let spread = self.config.z_score * std_dev * (blocks as f64).sqrt();
+        let at = |secs: f64| latest_time.checked_add(Duration::try_from_secs_f64(secs).ok()?);
+        Some(TimeEstimate {
+            expected: at(expected)?,
+            earliest: at((expected - spread).max(0.0))?,
+            latest: at(expected + spread)?,
+        })
+    }
+
+    /// Estimate the height of the chain at the given time, which must be
+    /// after the time of the highest recorded block.
+    pub fn estimate_height(&self, time: Time) -> Option<HeightEstimate> {
+        let (latest_height, latest_time) = self.latest()?;
+        if !time.after(latest_time) {
+            return None;
+        }
+        let elapsed = time.duration_since(latest_time).ok()?.as_secs_f64();
+        let (block_time, std_dev) = self.block_time_secs()?;
+        // The number of blocks `n` whose duration `n * block_time ± spread`,
+        // with `spread = z * std_dev * √n`, equals the elapsed time, solved
+        // for `√n`.
+        let deviation = self.config.z_score * std_dev;
+        let root = (deviation * deviation + 4.0 * block_time * elapsed).sqrt();
+        let fastest = ((deviation + root) / (2.0 * block_time)).powi(2);
+        let slowest = ((root - deviation) / (2.0 * block_time)).powi(2);
+        let at = |blocks: f64| {
+            let height = latest_height.value().checked_add(blocks as u64)?;
+            Height::try_from(height).ok()
+        };
+        // Tolerate rounding errors, which would otherwise widen the bounds
+        // by a block when they are tight.
+        const EPSILON: f64 = 1e-6;
+        Some(HeightEstimate {
+            expected: at((elapsed / block_time).round())?,
+            lowest: at((slowest + EPSILON).floor())?,
+            highest: at((fastest - EPSILON).ceil())?,
+        })
+    }
+
+    /// The expected duration of a block and its standard deviation, in
+    /// seconds.
+    fn block_time_secs(&self) -> Option<(f64, f64)> {
+        let intervals = self.intervals();
+        if intervals.is_empty() {
+            return None;
+        }
+        let block_time = self.smoothing.block_time(&intervals);
+        if !(block_time.is_finite() && block_time > 0.0) {
+            return None;
+        }
+        // The deviations of the intervals are weighted by their number of
+        // blocks.
+        let blocks: u64 = intervals.iter().map(|interval| interval.blocks).sum();
+        let variance = intervals
+            .iter()
+            .map(|interval| interval.blocks as f64 * (interval.block_time() - block_time).powi(2))
+            .sum::<f64>()
+            / blocks as f64;
+        Some((block_time, variance.sqrt()))
+    }
+
+    fn intervals(&self) -> Vec<Interval> {
+        self.samples
+            .iter()
+            .zip(self.samples.iter().skip(1))
+            .filter_map(|((h1, t1), (h2, t2))| {
+                Some(Interval {
+                    blocks: h2.value() - h1.value(),
+                    // Block times are not decreasing, unless a block is
+                    // recorded with a wrong time, which is then ignored.
+                    elapsed: t2.duration_since(*t1).ok()?,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Method, MockClient, MockRequestMethodMatcher};
+
+    fn time(secs: f64) -> Time {
+        (Time::unix_epoch() + Duration::from_secs_f64(secs)).unwrap()
+    }
+
+    fn estimator<S: Smoothing>(smoothing: S, times: &[(u32, f64)]) -> BlockTimeEstimator<S> {
+        let mut estimator =
+            BlockTimeEstimator::with_smoothing(EstimatorConfig::default(), smoothing);
+        for (height, secs) in times {
+            estimator.record(Height::from(*height), time(*secs));
+        }
+        estimator
+    }
+
+    #[test]
+    fn estimates_regular_blocks_exactly() {
+        let estimator = estimator(Mean, &[(1, 0.0), (2, 5.0), (3, 10.0), (5, 20.0)]);
+        let block_time = estimator.block_time().unwrap();
+        assert_eq!(block_time.expected, Duration::from_secs(5));
+        assert_eq!(block_time.std_dev, Duration::ZERO);
+
+        let estimate = estimator.estimate_time(Height::from(15_u32)).unwrap();
+        assert_eq!(estimate.expected, time(70.0));
+        assert_eq!(estimate.earliest, estimate.expected);
+        assert_eq!(estimate.latest, estimate.expected);
+
+        let estimate = estimator.estimate_height(time(70.0)).unwrap();
+        assert_eq!(estimate.expected, Height::from(15_u32));
+        assert_eq!(estimate.lowest, Height::from(15_u32));
+        assert_eq!(estimate.highest, Height::from(15_u32));
+    }
+
+    #[test]
+    fn bounds_irregular_blocks() {
+        let estimator = estimator(Mean, &[(1, 0.0), (2, 4.0), (3, 10.0), (4, 14.0), (5, 20.0)]);
+        let estimate = estimator.estimate_time(Height::from(105_u32)).unwrap();
+        assert_eq!(estimate.expected, time(520.0));
+        assert!(estimate.earliest.before(estimate.expected));
+        assert!(estimate.latest.after(estimate.expected));
+
+        let estimate = estimator.estimate_height(time(520.0)).unwrap();
+        assert_eq!(estimate.expected, Height::from(105_u32));
+        assert!(estimate.lowest < estimate.expected);
+        assert!(estimate.highest > estimate.expected);
+    }
+
+    #[test]
+    fn rejects_insufficient_or_past_samples() {
+        let estimator = estimator(Mean, &[(1, 0.0)]);
+        assert_eq!(estimator.block_time(), None);
+        assert_eq!(estimator.estimate_time(Height::from(2_u32)), None);
+
+        let estimator = self::estimator(Mean, &[(1, 0.0), (2, 5.0)]);
+        assert_eq!(estimator.estimate_time(Height::from(2_u32)), None);
+        assert_eq!(estimator.estimate_height(time(5.0)), None);
+
+        // Blocks with the same time give no block time.
+        let estimator = self::estimator(Mean, &[(1, 5.0), (2, 5.0)]);
+        assert_eq!(estimator.block_time(), None);
+    }
+
+    #[test]
+    fn smoothings() {
+        let times = [
+            (1, 0.0),
+            (2, 5.0),
+            (3, 10.0),
+            (4, 15.0),
+            (5, 65.0),
+            (6, 67.0),
+        ];
+        assert_eq!(
+            estimator(Mean, &times).block_time().unwrap().expected,
+            Duration::from_secs_f64(13.4)
+        );
+        assert_eq!(
+            estimator(Median, &times).block_time().unwrap().expected,
+            Duration::from_secs(5)
+        );
+        let ema = estimator(ExponentialMovingAverage { alpha: 0.5 }, &times);
+        // 5, 5, 5, then (5 + 50) / 2 and (27.5 + 2) / 2.
+        assert_eq!(
+            ema.block_time().unwrap().expected,
+            Duration::from_secs_f64(14.75)
+        );
+    }
+
+    #[test]
+    fn keeps_latest_samples() {
+        let mut estimator = BlockTimeEstimator::new(EstimatorConfig {
+            max_samples: 2,
+            ..Default::default()
+        });
+        for height in 1..=4_u32 {
+            estimator.record(Height::from(height), time(height as f64 * height as f64));
+        }
+        assert_eq!(estimator.latest(), Some((Height::from(4_u32), time(16.0))));
+        assert_eq!(
+            estimator.block_time().unwrap().expected,
+            Duration::from_secs(7)
+        );
+    }
+
+    #[tokio::test]
+    async fn fetches_recent_headers() {
+        let matcher = MockRequestMethodMatcher::default()
+            .map(
+                Method::Status,
+                Ok(
+                    include_str!("../../tests/kvstore_fixtures/v0_38/incoming/status.json")
+                        .to_owned(),
+                ),
+            )
+            .map(
+                Method::Blockchain,
+                Ok(include_str!(
+                    "../../tests/kvstore_fixtures/v0_38/incoming/blockchain_from_1_to_10.json"
+                )
+                .to_owned()),
+            );
+        let (client, _driver) = MockClient::new(matcher);
+        let mut estimator = BlockTimeEstimator::new(EstimatorConfig::default());
+        estimator.fetch_recent(&client, 50).await.unwrap();
+
+        let (height, _) = estimator.latest().unwrap();
+        assert_eq!(height, Height::from(10_u32));
+        let block_time = estimator.block_time().unwrap().expected;
+        assert!(block_time > Duration::from_millis(400) && block_time < Duration::from_millis(700));
+    }
+}
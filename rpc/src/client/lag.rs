@@ -0,0 +1,275 @@
+//! Detection of subscriptions falling behind the chain.
+//!
+//! The events of a subscription are pushed by the node, which may drop or
+//! delay them, e.g. when the subscriber does not keep up or the node is
+//! overloaded. A [`LagDetector`] compares the height of the latest `NewBlock`
+//! event received on a subscription with the latest block height reported
+//! by the `/status` endpoint of the node, and notifies the consumer of the
+//! subscription when it falls behind, so that it can catch up by polling.
+
+use core::{
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures::Stream;
+use pin_project::pin_project;
+use tendermint::block::Height;
+use tokio::sync::mpsc;
+
+use crate::{
+    client::{
+        poller::{spawn_poller, AbortOnDrop},
+        Client, Subscription,
+    },
+    event::{Event, EventData},
+    prelude::*,
+    Error,
+};
+
+/// Configuration of a [`LagDetector`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct LagDetectorConfig {
+    /// The interval at which the `/status` endpoint is polled.
+    pub poll_interval: Duration,
+    /// The number of blocks the subscription may be behind the node before
+    /// it is considered lagging, accounting for the events in flight when
+    /// the node is polled.
+    pub max_lag: u64,
+}
+
+impl Default for LagDetectorConfig {
+    fn default() -> Self {
+        Self {
+            poll_interval: Duration::from_secs(5),
+            max_lag: 2,
+        }
+    }
+}
+
+/// An item produced by a [`LagDetector`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[allow(clippy::large_enum_variant)]
+pub enum SubscriptionItem {
+    /// An event received on the subscription.
+    Event(Event),
+    /// The latest block received on the subscription is the given number of
+    /// blocks behind the latest block of the node.
+    ///
+    /// Produced after each poll of the node finding the subscription lagging.
+    Lagging { behind_by: u64 },
+}
+
+/// Wraps a subscription to `NewBlock` events, and interleaves
+/// [`SubscriptionItem::Lagging`] notifications with its events when they are
+/// not delivered as fast as the node commits blocks.
+///
+/// The `/status` endpoint of the node is polled in a background task, which
+/// is stopped when the detector is dropped. Failed polls are ignored; see
+/// [`HealthMonitor`] to track the health of the node itself.
+///
+/// [`HealthMonitor`]: crate::client::monitor::HealthMonitor
+///
+/// ## Examples
+///
+/// ```rust,ignore
+/// use futures::StreamExt;
+/// use tendermint_rpc::{
+///     client::lag::{LagDetector, LagDetectorConfig, SubscriptionItem},
+///     query::EventType,
+///     SubscriptionClient, WebSocketClient,
+/// };
+///
+/// let subscription = client.subscribe(EventType::NewBlock.into()).await?;
+/// let mut blocks = LagDetector::new(subscription, http_client, LagDetectorConfig::default());
+/// while let Some(item) = blocks.next().await {
+///     match item? {
+///         SubscriptionItem::Event(event) => { /* process the block */ },
+///         SubscriptionItem::Lagging { behind_by } => { /* fetch the missing blocks */ },
+///     }
+/// }
+/// ```
+#[pin_project]
+#[derive(Debug)]
+pub struct LagDetector {
+    #[pin]
+    subscription: Subscription,
+    heights: mpsc::Receiver<Height>,
+    tracker: LagTracker,
+    _poller: AbortOnDrop,
+}
+
+impl LagDetector {
+    /// Start detecting the lag of the given subscription, polling the node
+    /// with the given client.
+    ///
+    /// Must be called from within a Tokio runtime.
+    pub fn new<C>(subscription: Subscription, client: C, config: LagDetectorConfig) -> Self
+    where
+        C: Client + Send + Sync + 'static,
+    {
+        let (heights, poller) = spawn_poller(client, config.poll_interval, |client| {
+            Box::pin(async move { Ok(client.status().await?.sync_info.latest_block_height) })
+        });
+
+        Self {
+            subscription,
+            heights,
+            tracker: LagTracker::new(config.max_lag),
+            _poller: poller,
+        }
+    }
+
+    /// The subscription whose lag is detected.
+    pub fn subscription(&self) -> &Subscription {
+        &self.subscription
+    }
+}
+
+impl Stream for LagDetector {
+    type Item = Result<SubscriptionItem, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        loop {
+            match this.subscription.as_mut().poll_next(cx) {
+                Poll::Ready(Some(Ok(event))) => {
+                    if let Some(height) = new_block_height(&event) {
+                        this.tracker.on_block(height);
+                    }
+                    return Poll::Ready(Some(Ok(SubscriptionItem::Event(event))));
+                },
+                Poll::Ready(Some(Err(e))) => return Poll::Ready(Some(Err(e))),
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => {},
+            }
+            match this.heights.poll_recv(cx) {
+                Poll::Ready(Some(height)) => {
+                    if let Some(behind_by) = this.tracker.on_status(height) {
+                        return Poll::Ready(Some(Ok(SubscriptionItem::Lagging { behind_by })));
+                    }
+                },
+                // The polling task only stops once the detector is dropped.
+                Poll::Ready(None) | Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+/// The height of the block of a `NewBlock` event.
+fn new_block_height(event: &Event) -> Option<Height> {
+    match &event.data {
+        EventData::NewBlock { block, .. } | EventData::LegacyNewBlock { block, .. } => {
+            block.as_ref().map(|block| block.header.height)
+        },
+        _ => None,
+    }
+}
+
+/// Compares the heights of the blocks received on a subscription with the
+/// heights reported by the node.
+#[derive(Debug)]
+struct LagTracker {
+    max_lag: u64,
+    /// The height of the latest block received on the subscription, or the
+    /// height of the node when first polled if no block was received yet.
+    latest: Option<Height>,
+}
+
+impl LagTracker {
+    fn new(max_lag: u64) -> Self {
+        Self {
+            max_lag,
+            latest: None,
+        }
+    }
+
+    fn on_block(&mut self, height: Height) {
+        self.latest = Some(self.latest.map_or(height, |latest| latest.max(height)));
+    }
+
+    /// Returns the number of blocks the subscription is behind the node, if
+    /// it is lagging.
+    fn on_status(&mut self, node_height: Height) -> Option<u64> {
+        let Some(latest) = self.latest else {
+            self.latest = Some(node_height);
+            return None;
+        };
+        let behind_by = node_height.value().saturating_sub(latest.value());
+        (behind_by > self.max_lag).then_some(behind_by)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+    use tokio::time;
+
+    use super::*;
+    use crate::{
+        client::sync::unbounded, event::v0_38::DeEvent, query::EventType, Method, MockClient,
+        MockRequestMethodMatcher, Response,
+    };
+
+    #[test]
+    fn lagging_when_behind_the_node() {
+        let mut tracker = LagTracker::new(2);
+        // The first poll sets the baseline when no block was received yet.
+        assert_eq!(tracker.on_status(Height::from(10_u32)), None);
+        assert_eq!(tracker.on_status(Height::from(12_u32)), None);
+        assert_eq!(tracker.on_status(Height::from(13_u32)), Some(3));
+
+        tracker.on_block(Height::from(12_u32));
+        assert_eq!(tracker.on_status(Height::from(13_u32)), None);
+        // Blocks received out of order do not move the latest height back.
+        tracker.on_block(Height::from(11_u32));
+        assert_eq!(tracker.on_status(Height::from(15_u32)), Some(3));
+        // The node may report a height behind the subscription.
+        tracker.on_block(Height::from(16_u32));
+        assert_eq!(tracker.on_status(Height::from(15_u32)), None);
+    }
+
+    async fn next(detector: &mut LagDetector) -> SubscriptionItem {
+        time::timeout(Duration::from_secs(5), detector.next())
+            .await
+            .expect("timed out waiting for the next item")
+            .unwrap()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn notifies_lagging_subscription() {
+        let status = include_str!("../../tests/kvstore_fixtures/v0_38/incoming/status.json")
+            .replace(
+                r#""latest_block_height": "232""#,
+                r#""latest_block_height": "240""#,
+            );
+        let matcher = MockRequestMethodMatcher::default().map(Method::Status, Ok(status));
+        let (client, _driver) = MockClient::new(matcher);
+
+        let (event_tx, event_rx) = unbounded();
+        let subscription =
+            Subscription::new("sub".to_owned(), EventType::NewBlock.into(), event_rx);
+        let event: Event = DeEvent::from_string(include_str!(
+            "../../tests/kvstore_fixtures/v0_38/incoming/subscribe_newblock_0.json"
+        ))
+        .unwrap()
+        .into();
+        event_tx.send(Ok(event.clone())).unwrap();
+
+        let mut detector = LagDetector::new(
+            subscription,
+            client,
+            LagDetectorConfig {
+                poll_interval: Duration::from_millis(10),
+                max_lag: 2,
+            },
+        );
+        assert_eq!(next(&mut detector).await, SubscriptionItem::Event(event));
+        assert_eq!(
+            next(&mut detector).await,
+            SubscriptionItem::Lagging { behind_by: 5 }
+        );
+    }
+}
@@ -0,0 +1,423 @@
+//! Monitoring of the mempool of a node.
+//!
+//! A [`MempoolWatcher`] polls the `/unconfirmed_txs` endpoint of a node for
+//! the size and the transactions of its mempool, and follows a subscription
+//! to `Tx` events to observe the inclusion of transactions in blocks. The
+//! time the transactions are first seen, from which their time to inclusion
+//! is measured, is the time of the first poll listing them, or of the call
+//! to [`MempoolWatcher::track`] registering them, e.g. when they are
+//! broadcast, if it came first.
+
+use alloc::collections::{BTreeMap, BTreeSet};
+use core::{
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures::Stream;
+use pin_project::pin_project;
+use tendermint::{
+    block::Height,
+    crypto::{default::Sha256, Sha256 as _},
+    hash::Algorithm,
+    Hash,
+};
+use tokio::{sync::mpsc, time::Instant};
+
+use crate::{
+    client::{
+        poller::{spawn_poller, AbortOnDrop},
+        Client, Subscription,
+    },
+    event::{Event, EventData},
+    prelude::*,
+    Error,
+};
+
+/// Configuration of a [`MempoolWatcher`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MempoolWatcherConfig {
+    /// The interval at which the `/unconfirmed_txs` endpoint is polled.
+    pub poll_interval: Duration,
+    /// The maximum number of transactions listed by each poll, which nodes
+    /// cap at 100. The transactions further down the mempool are only seen
+    /// once those ahead of them leave it.
+    pub max_txs: u32,
+    /// How long transactions are tracked for. Those whose inclusion is not
+    /// observed within that time, e.g. because they were rejected or evicted
+    /// from the mempool, are no longer tracked once they are no longer
+    /// listed by the polls.
+    pub tracking_period: Duration,
+}
+
+impl Default for MempoolWatcherConfig {
+    fn default() -> Self {
+        Self {
+            poll_interval: Duration::from_secs(1),
+            max_txs: 100,
+            tracking_period: Duration::from_secs(600),
+        }
+    }
+}
+
+/// The size of the mempool of a node, as polled by a [`MempoolWatcher`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MempoolSize {
+    /// The number of transactions in the mempool.
+    pub txs: u64,
+    /// The total size of the transactions in the mempool, in bytes.
+    pub bytes: u64,
+    /// When the size was received.
+    pub observed_at: Instant,
+}
+
+/// The inclusion of a transaction in a block, as observed by a
+/// [`MempoolWatcher`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TxInclusion {
+    /// The hash of the transaction.
+    pub hash: Hash,
+    /// The height of the block including the transaction.
+    pub height: Height,
+    /// When the transaction was first seen, if it was tracked, i.e. listed
+    /// in the mempool or registered with [`MempoolWatcher::track`].
+    pub first_seen: Option<Instant>,
+    /// When the event of the inclusion of the transaction was received.
+    pub included_at: Instant,
+}
+
+impl TxInclusion {
+    /// The time elapsed between the transaction being first seen and its
+    /// inclusion being observed, if it was tracked.
+    pub fn time_to_inclusion(&self) -> Option<Duration> {
+        self.first_seen
+            .map(|first_seen| self.included_at.saturating_duration_since(first_seen))
+    }
+}
+
+/// An item produced by a [`MempoolWatcher`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MempoolEvent {
+    /// The size of the mempool, produced after each successful poll.
+    Size(MempoolSize),
+    /// A transaction was included in a block.
+    Included(TxInclusion),
+}
+
+/// Produces the size of the mempool of a node, and the inclusion of the
+/// transactions received on a subscription to `Tx` events.
+///
+/// The `/unconfirmed_txs` endpoint of the node is polled in a background
+/// task, which is stopped when the watcher is dropped. Failed polls are
+/// ignored. After each poll, the transactions it lists are tracked, and the
+/// transactions tracked for longer than the tracking period of the
+/// configuration which it no longer lists are dropped.
+///
+/// ## Examples
+///
+/// ```rust,ignore
+/// use futures::StreamExt;
+/// use tendermint_rpc::{
+///     client::mempool::{MempoolEvent, MempoolWatcher, MempoolWatcherConfig},
+///     query::EventType,
+///     Client, SubscriptionClient,
+/// };
+///
+/// let subscription = ws_client.subscribe(EventType::Tx.into()).await?;
+/// let mut watcher = MempoolWatcher::new(subscription, http_client.clone(), MempoolWatcherConfig::default());
+/// let response = http_client.broadcast_tx_sync(tx).await?;
+/// watcher.track(response.hash);
+/// while let Some(event) = watcher.next().await {
+///     match event? {
+///         MempoolEvent::Size(size) => println!("{} txs, {} bytes", size.txs, size.bytes),
+///         MempoolEvent::Included(inclusion) => {
+///             println!("{} included after {:?}", inclusion.hash, inclusion.time_to_inclusion())
+///         },
+///     }
+/// }
+/// ```
+#[pin_project]
+#[derive(Debug)]
+pub struct MempoolWatcher {
+    #[pin]
+    subscription: Subscription,
+    polls: mpsc::Receiver<MempoolPoll>,
+    first_seen: BTreeMap<Hash, Instant>,
+    tracking_period: Duration,
+    _poller: AbortOnDrop,
+}
+
+/// The result of a poll of the mempool.
+#[derive(Debug)]
+struct MempoolPoll {
+    size: MempoolSize,
+    /// The hashes of the transactions listed by the poll.
+    txs: BTreeSet<Hash>,
+}
+
+impl MempoolWatcher {
+    /// Start watching the mempool of the node polled with the given client,
+    /// and the inclusion of the transactions of the given subscription.
+    ///
+    /// Must be called from within a Tokio runtime.
+    pub fn new<C>(subscription: Subscription, client: C, config: MempoolWatcherConfig) -> Self
+    where
+        C: Client + Send + Sync + 'static,
+    {
+        let (polls, poller) = spawn_poller(client, config.poll_interval, move |client| {
+            Box::pin(async move {
+                let response = client.unconfirmed_txs(Some(config.max_txs)).await?;
+                Ok(MempoolPoll {
+                    size: MempoolSize {
+                        txs: response.total,
+                        bytes: response.total_bytes,
+                        observed_at: Instant::now(),
+                    },
+                    txs: response
+                        .txs
+                        .iter()
+                        .map(|tx| Hash::Sha256(Sha256::digest(tx)))
+                        .collect(),
+                })
+            })
+        });
+
+        Self {
+            subscription,
+            polls,
+            first_seen: BTreeMap::new(),
+            tracking_period: config.tracking_period,
+            _poller: poller,
+        }
+    }
+
+    /// Record the current time as the time the transaction with the given
+    /// hash was first seen, unless it was already tracked.
+    ///
+    /// The transaction is no longer tracked once its inclusion is observed,
+    /// or after the tracking period of the configuration if it is no longer
+    /// listed in the mempool.
+    pub fn track(&mut self, hash: Hash) {
+        self.first_seen.entry(hash).or_insert_with(Instant::now);
+    }
+
+    /// Stop tracking the transaction with the given hash, e.g. once it is
+    /// known to be rejected, returning when it was first seen.
+    pub fn untrack(&mut self, hash: &Hash) -> Option<Instant> {
+        self.first_seen.remove(hash)
+    }
+
+    /// When the tracked transaction with the given hash was first seen, if
+    /// its inclusion was not observed yet.
+    pub fn first_seen(&self, hash: &Hash) -> Option<Instant> {
+        self.first_seen.get(hash).copied()
+    }
+
+    /// The number of tracked transactions whose inclusion was not observed
+    /// yet.
+    pub fn pending(&self) -> usize {
+        self.first_seen.len()
+    }
+}
+
+impl Stream for MempoolWatcher {
+    type Item = Result<MempoolEvent, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        loop {
+            match this.subscription.as_mut().poll_next(cx) {
+                Poll::Ready(Some(Ok(event))) => {
+                    // Events of other types can be produced by the subscription.
+                    let Some((hash, height)) = tx_inclusion(&event) else {
+                        continue;
+                    };
+                    return Poll::Ready(Some(Ok(MempoolEvent::Included(TxInclusion {
+                        first_seen: this.first_seen.remove(&hash),
+                        hash,
+                        height,
+                        included_at: Instant::now(),
+                    }))));
+                },
+                Poll::Ready(Some(Err(e))) => return Poll::Ready(Some(Err(e))),
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => {},
+            }
+            return match this.polls.poll_recv(cx) {
+                Poll::Ready(Some(MempoolPoll { size, txs })) => {
+                    let tracking_period = *this.tracking_period;
+                    this.first_seen.retain(|hash, first_seen| {
+                        txs.contains(hash)
+                            || size.observed_at.saturating_duration_since(*first_seen)
+                                < tracking_period
+                    });
+                    for hash in txs {
+                        this.first_seen.entry(hash).or_insert(size.observed_at);
+                    }
+                    Poll::Ready(Some(Ok(MempoolEvent::Size(size))))
+                },
+                // The polling task only stops once the watcher is dropped.
+                Poll::Ready(None) | Poll::Pending => Poll::Pending,
+            };
+        }
+    }
+}
+
+/// The hash and the height of the block of the transaction of a `Tx` event.
+fn tx_inclusion(event: &Event) -> Option<(Hash, Height)> {
+    let EventData::Tx { tx_result } = &event.data else {
+        return None;
+    };
+    let hash = match &event.events {
+        Some(events) => events.get("tx.hash")?.first()?.clone(),
+        None => event.data.attributes().remove("tx.hash")?.pop()?,
+    };
+    let hash = Hash::from_hex_upper(Algorithm::Sha256, &hash).ok()?;
+    let height = Height::try_from(tx_result.height).ok()?;
+    Some((hash, height))
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+    use tokio::time;
+
+    use super::*;
+    use crate::{
+        client::sync::unbounded,
+        event::{TxInfo, TxResult},
+        query::EventType,
+        Method, MockClient, MockRequestMethodMatcher,
+    };
+
+    fn tx_event(hash: &Hash, height: i64) -> Event {
+        let mut events = BTreeMap::new();
+        events.insert("tx.hash".to_owned(), vec![hash.to_string()]);
+        Event {
+            query: "tm.event = 'Tx'".to_owned(),
+            data: EventData::Tx {
+                tx_result: TxInfo {
+                    height,
+                    index: Some(0),
+                    tx: vec![],
+                    result: TxResult {
+                        log: None,
+                        gas_wanted: None,
+                        gas_used: None,
+                        events: vec![],
+                    },
+                },
+            },
+            events: Some(events),
+        }
+    }
+
+    /// A client listing the transaction `abc` in the mempool, out of the
+    /// given total.
+    fn client(total: u64) -> MockClient<MockRequestMethodMatcher> {
+        let response = format!(
+            r#"{{"jsonrpc":"2.0","id":"","result":{{"n_txs":"1","total":"{total}","total_bytes":"120","txs":["YWJj"]}}}}"#
+        );
+        let matcher = MockRequestMethodMatcher::default().map(Method::UnconfirmedTxs, Ok(response));
+        MockClient::new(matcher).0
+    }
+
+    fn listed() -> Hash {
+        Hash::Sha256(Sha256::digest(b"abc"))
+    }
+
+    async fn next(watcher: &mut MempoolWatcher) -> MempoolEvent {
+        time::timeout(Duration::from_secs(5), watcher.next())
+            .await
+            .expect("timed out waiting for the next item")
+            .unwrap()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn reports_size_and_inclusions() {
+        let (event_tx, event_rx) = unbounded();
+        let subscription = Subscription::new("sub".to_owned(), EventType::Tx.into(), event_rx);
+        let mut watcher = MempoolWatcher::new(
+            subscription,
+            client(3),
+            MempoolWatcherConfig {
+                poll_interval: Duration::from_millis(10),
+                ..Default::default()
+            },
+        );
+
+        match next(&mut watcher).await {
+            MempoolEvent::Size(size) => {
+                assert_eq!(size.txs, 3);
+                assert_eq!(size.bytes, 120);
+            },
+            other => panic!("unexpected event: {other:?}"),
+        }
+        // The transactions listed in the mempool are tracked.
+        let listed_at = watcher.first_seen(&listed()).unwrap();
+        next(&mut watcher).await;
+        assert_eq!(watcher.first_seen(&listed()), Some(listed_at));
+
+        let tracked = Hash::Sha256([1; 32]);
+        let untracked = Hash::Sha256([2; 32]);
+        watcher.track(tracked);
+        let first_seen = watcher.first_seen(&tracked).unwrap();
+        // Tracking a transaction again keeps the time it was first seen.
+        watcher.track(tracked);
+        assert_eq!(watcher.first_seen(&tracked), Some(first_seen));
+        assert_eq!(watcher.pending(), 2);
+
+        event_tx.send(Ok(tx_event(&untracked, 7))).unwrap();
+        event_tx.send(Ok(tx_event(&tracked, 8))).unwrap();
+        event_tx.send(Ok(tx_event(&listed(), 8))).unwrap();
+        let mut inclusions = vec![];
+        while inclusions.len() < 3 {
+            if let MempoolEvent::Included(inclusion) = next(&mut watcher).await {
+                inclusions.push(inclusion);
+            }
+        }
+        assert_eq!(inclusions[0].hash, untracked);
+        assert_eq!(inclusions[0].time_to_inclusion(), None);
+        assert_eq!(inclusions[1].hash, tracked);
+        assert_eq!(inclusions[1].height, Height::from(8_u32));
+        assert_eq!(inclusions[1].first_seen, Some(first_seen));
+        assert!(inclusions[1].time_to_inclusion().is_some());
+        assert_eq!(inclusions[2].first_seen, Some(listed_at));
+        // The mock node keeps listing the included transaction, which the
+        // next polls track again.
+        assert_eq!(watcher.first_seen(&tracked), None);
+    }
+
+    #[tokio::test]
+    async fn stops_tracking_transactions_never_included() {
+        let (_event_tx, event_rx) = unbounded();
+        let subscription = Subscription::new("sub".to_owned(), EventType::Tx.into(), event_rx);
+        let mut watcher = MempoolWatcher::new(
+            subscription,
+            client(1),
+            MempoolWatcherConfig {
+                poll_interval: Duration::from_millis(10),
+                tracking_period: Duration::from_millis(50),
+                ..Default::default()
+            },
+        );
+
+        let rejected = Hash::Sha256([1; 32]);
+        let evicted = Hash::Sha256([2; 32]);
+        watcher.track(rejected);
+        watcher.track(evicted);
+        assert!(watcher.untrack(&rejected).is_some());
+        assert_eq!(watcher.untrack(&rejected), None);
+        assert_eq!(watcher.pending(), 1);
+
+        while watcher.first_seen(&evicted).is_some() {
+            next(&mut watcher).await;
+        }
+        // The transactions still listed in the mempool remain tracked.
+        let listed_at = watcher.first_seen(&listed()).unwrap();
+        assert!(listed_at.elapsed() >= Duration::from_millis(50));
+        assert_eq!(watcher.pending(), 1);
+    }
+}
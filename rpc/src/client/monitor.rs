@@ -0,0 +1,351 @@
+//! Background monitoring of the health of the node an RPC client is connected to.
+
+use core::time::Duration;
+
+use tendermint::{block::Height, Time};
+use tokio::{
+    sync::watch,
+    task::JoinHandle,
+    time::{self, Instant, MissedTickBehavior},
+};
+
+use crate::{client::Client, endpoint::status::SyncInfo, prelude::*};
+
+/// The health of the connection to a node, as observed by a [`HealthMonitor`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ConnectionHealth {
+    /// The node responds, is not catching up, and its latest block height advances.
+    Healthy,
+    /// The node responds, but it is either catching up, or its latest block height
+    /// did not advance within the configured stall timeout.
+    Lagging,
+    /// The node did not respond successfully to the configured number of consecutive
+    /// status requests, or has not responded successfully yet.
+    Down,
+}
+
+/// The latest status of a node, as observed by a [`HealthMonitor`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HealthStatus {
+    /// The health of the connection to the node.
+    pub health: ConnectionHealth,
+    /// The latest block height reported by the node, if it ever responded.
+    pub latest_block_height: Option<Height>,
+    /// The time of the latest block reported by the node, if it ever responded.
+    pub latest_block_time: Option<Time>,
+    /// Whether the node reported it is catching up in its latest response.
+    pub catching_up: bool,
+    /// The number of consecutive status requests that failed.
+    pub consecutive_failures: usize,
+}
+
+impl Default for HealthStatus {
+    fn default() -> Self {
+        Self {
+            health: ConnectionHealth::Down,
+            latest_block_height: None,
+            latest_block_time: None,
+            catching_up: false,
+            consecutive_failures: 0,
+        }
+    }
+}
+
+/// Configuration of a [`HealthMonitor`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct HealthMonitorConfig {
+    /// The interval at which the `/status` endpoint is polled.
+    ///
+    /// Polls taking longer than this interval are considered failed.
+    pub poll_interval: Duration,
+    /// The time after which the node is considered lagging if its latest block
+    /// height does not advance.
+    pub stall_timeout: Duration,
+    /// The number of consecutive failed polls after which the node is considered down.
+    pub max_failures: usize,
+}
+
+impl Default for HealthMonitorConfig {
+    fn default() -> Self {
+        Self {
+            poll_interval: Duration::from_secs(5),
+            stall_timeout: Duration::from_secs(30),
+            max_failures: 3,
+        }
+    }
+}
+
+/// Periodically polls the `/status` endpoint of a node in a background task,
+/// and tracks the health of the connection to it.
+///
+/// The background task is stopped when the monitor is dropped.
+///
+/// ## Examples
+///
+/// ```rust,ignore
+/// use tendermint_rpc::{client::monitor::{ConnectionHealth, HealthMonitorConfig}, HttpClient};
+///
+/// let client = HttpClient::new("http://127.0.0.1:26657").unwrap();
+/// let monitor = client.health_monitor(HealthMonitorConfig::default());
+///
+/// let mut health = monitor.subscribe();
+/// while health.changed().await.is_ok() {
+///     if *health.borrow() == ConnectionHealth::Down {
+///         // switch to another node
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct HealthMonitor {
+    health: watch::Receiver<ConnectionHealth>,
+    status: watch::Receiver<HealthStatus>,
+    handle: JoinHandle<()>,
+}
+
+impl HealthMonitor {
+    /// Spawn a background task monitoring the node the given client is connected to.
+    ///
+    /// Must be called from within a Tokio runtime.
+    pub fn spawn<C>(client: C, config: HealthMonitorConfig) -> Self
+    where
+        C: Client + Send + Sync + 'static,
+    {
+        let (health_tx, health) = watch::channel(ConnectionHealth::Down);
+        let (status_tx, status) = watch::channel(HealthStatus::default());
+
+        let handle = tokio::spawn(async move {
+            let mut tracker = HealthTracker::new(config);
+            let mut interval = time::interval(config.poll_interval);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                match time::timeout(config.poll_interval, client.status()).await {
+                    Ok(Ok(response)) => tracker.on_status(&response.sync_info, Instant::now()),
+                    _ => tracker.on_failure(),
+                }
+
+                let new_health = tracker.status.health;
+                health_tx.send_if_modified(|health| {
+                    let changed = *health != new_health;
+                    *health = new_health;
+                    changed
+                });
+                status_tx.send_replace(tracker.status.clone());
+            }
+        });
+
+        Self {
+            health,
+            status,
+            handle,
+        }
+    }
+
+    /// The current health of the connection to the node.
+    pub fn health(&self) -> ConnectionHealth {
+        *self.health.borrow()
+    }
+
+    /// The latest status of the node.
+    pub fn status(&self) -> HealthStatus {
+        self.status.borrow().clone()
+    }
+
+    /// Subscribe to the transitions of the health of the connection to the node.
+    ///
+    /// The receiver is only notified when the health changes.
+    pub fn subscribe(&self) -> watch::Receiver<ConnectionHealth> {
+        self.health.clone()
+    }
+}
+
+impl Drop for HealthMonitor {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+/// Derives the health of the connection from the outcome of successive polls.
+#[derive(Debug)]
+struct HealthTracker {
+    config: HealthMonitorConfig,
+    status: HealthStatus,
+    last_advanced: Option<Instant>,
+}
+
+impl HealthTracker {
+    fn new(config: HealthMonitorConfig) -> Self {
+        Self {
+            config,
+            status: HealthStatus::default(),
+            last_advanced: None,
+        }
+    }
+
+    fn on_status(&mut self, sync_info: &SyncInfo, now: Instant) {
+        let height = sync_info.latest_block_height;
+        let advanced = self
+            .status
+            .latest_block_height
+            .is_some_and(|latest| height > latest);
+        if advanced || self.last_advanced.is_none() {
+            self.last_advanced = Some(now);
+        }
+        let stalled = self
+            .last_advanced
+            .is_some_and(|at| now.duration_since(at) >= self.config.stall_timeout);
+
+        self.status = HealthStatus {
+            health: if sync_info.catching_up || stalled {
+                ConnectionHealth::Lagging
+            } else {
+                ConnectionHealth::Healthy
+            },
+            latest_block_height: Some(height),
+            latest_block_time: Some(sync_info.latest_block_time),
+            catching_up: sync_info.catching_up,
+            consecutive_failures: 0,
+        };
+    }
+
+    fn on_failure(&mut self) {
+        self.status.consecutive_failures += 1;
+        if self.status.consecutive_failures >= self.config.max_failures {
+            self.status.health = ConnectionHealth::Down;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Error, Method, MockClient, MockRequestMethodMatcher};
+
+    fn sync_info(height: u32, catching_up: bool) -> SyncInfo {
+        SyncInfo {
+            earliest_block_hash: Default::default(),
+            earliest_app_hash: Default::default(),
+            earliest_block_height: Height::from(1_u32),
+            earliest_block_time: Time::unix_epoch(),
+            latest_block_hash: Default::default(),
+            latest_app_hash: Default::default(),
+            latest_block_height: Height::from(height),
+            latest_block_time: Time::unix_epoch(),
+            catching_up,
+        }
+    }
+
+    fn tracker() -> HealthTracker {
+        HealthTracker::new(HealthMonitorConfig {
+            poll_interval: Duration::from_secs(1),
+            stall_timeout: Duration::from_secs(10),
+            max_failures: 2,
+        })
+    }
+
+    #[test]
+    fn down_until_first_response() {
+        let mut tracker = tracker();
+        assert_eq!(tracker.status.health, ConnectionHealth::Down);
+        tracker.on_failure();
+        assert_eq!(tracker.status.health, ConnectionHealth::Down);
+        tracker.on_status(&sync_info(5, false), Instant::now());
+        assert_eq!(tracker.status.health, ConnectionHealth::Healthy);
+        assert_eq!(
+            tracker.status.latest_block_height,
+            Some(Height::from(5_u32))
+        );
+        assert_eq!(tracker.status.consecutive_failures, 0);
+    }
+
+    #[test]
+    fn lagging_when_catching_up_or_stalled() {
+        let mut tracker = tracker();
+        let start = Instant::now();
+
+        tracker.on_status(&sync_info(5, true), start);
+        assert_eq!(tracker.status.health, ConnectionHealth::Lagging);
+        assert!(tracker.status.catching_up);
+
+        tracker.on_status(&sync_info(6, false), start + Duration::from_secs(1));
+        assert_eq!(tracker.status.health, ConnectionHealth::Healthy);
+
+        tracker.on_status(&sync_info(6, false), start + Duration::from_secs(5));
+        assert_eq!(tracker.status.health, ConnectionHealth::Healthy);
+        tracker.on_status(&sync_info(6, false), start + Duration::from_secs(11));
+        assert_eq!(tracker.status.health, ConnectionHealth::Lagging);
+
+        tracker.on_status(&sync_info(7, false), start + Duration::from_secs(12));
+        assert_eq!(tracker.status.health, ConnectionHealth::Healthy);
+    }
+
+    #[test]
+    fn down_after_consecutive_failures() {
+        let mut tracker = tracker();
+        tracker.on_status(&sync_info(5, false), Instant::now());
+
+        tracker.on_failure();
+        assert_eq!(tracker.status.health, ConnectionHealth::Healthy);
+        tracker.on_status(&sync_info(6, false), Instant::now());
+        tracker.on_failure();
+        assert_eq!(tracker.status.health, ConnectionHealth::Healthy);
+        tracker.on_failure();
+        assert_eq!(tracker.status.health, ConnectionHealth::Down);
+        assert_eq!(tracker.status.consecutive_failures, 2);
+        // the last known status of the node is retained
+        assert_eq!(
+            tracker.status.latest_block_height,
+            Some(Height::from(6_u32))
+        );
+    }
+
+    fn monitor_config() -> HealthMonitorConfig {
+        HealthMonitorConfig {
+            poll_interval: Duration::from_millis(10),
+            stall_timeout: Duration::from_secs(60),
+            max_failures: 1,
+        }
+    }
+
+    async fn wait_for(monitor: &HealthMonitor, expected: ConnectionHealth) {
+        let mut health = monitor.subscribe();
+        time::timeout(
+            Duration::from_secs(5),
+            health.wait_for(|health| *health == expected),
+        )
+        .await
+        .expect("timed out waiting for health transition")
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn monitor_reports_healthy_node() {
+        let status = include_str!("../../tests/kvstore_fixtures/v0_37/incoming/status.json");
+        let matcher =
+            MockRequestMethodMatcher::default().map(Method::Status, Ok(status.to_owned()));
+        let (client, _driver) = MockClient::new(matcher);
+
+        let monitor = HealthMonitor::spawn(client, monitor_config());
+        assert_eq!(monitor.health(), ConnectionHealth::Down);
+        wait_for(&monitor, ConnectionHealth::Healthy).await;
+
+        let status = monitor.status();
+        assert!(status.latest_block_height.is_some());
+        assert!(!status.catching_up);
+    }
+
+    #[tokio::test]
+    async fn monitor_reports_unreachable_node() {
+        let matcher = MockRequestMethodMatcher::default().map(
+            Method::Status,
+            Err(Error::client_internal("unreachable".into())),
+        );
+        let (client, _driver) = MockClient::new(matcher);
+
+        let monitor = HealthMonitor::spawn(client, monitor_config());
+        time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(monitor.health(), ConnectionHealth::Down);
+        assert!(monitor.status().consecutive_failures > 0);
+        assert!(monitor.status().latest_block_height.is_none());
+    }
+}
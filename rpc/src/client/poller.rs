@@ -0,0 +1,59 @@
+//! Polling of a node in a background task.
+
+use core::{future::Future, pin::Pin, time::Duration};
+
+use tokio::{
+    sync::mpsc,
+    task::JoinHandle,
+    time::{self, MissedTickBehavior},
+};
+
+use crate::{prelude::*, Error};
+
+/// A poll of a node, borrowing the client it is made with.
+pub(crate) type Poll<'a, T> = Pin<Box<dyn Future<Output = Result<T, Error>> + Send + 'a>>;
+
+/// Aborts a background task when dropped.
+#[derive(Debug)]
+pub(crate) struct AbortOnDrop(JoinHandle<()>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Spawn a task polling a node with the given client at the given interval,
+/// and sending the results of the successful polls.
+///
+/// Polls taking longer than the interval are considered failed, and failed
+/// polls are ignored. The task stops when the receiver or the returned guard
+/// is dropped.
+///
+/// Must be called from within a Tokio runtime.
+pub(crate) fn spawn_poller<C, T, F>(
+    client: C,
+    interval: Duration,
+    poll: F,
+) -> (mpsc::Receiver<T>, AbortOnDrop)
+where
+    C: Send + Sync + 'static,
+    T: Send + 'static,
+    F: for<'a> Fn(&'a C) -> Poll<'a, T> + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel(1);
+    let handle = tokio::spawn(async move {
+        let mut ticks = time::interval(interval);
+        ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            ticks.tick().await;
+            let Ok(Ok(value)) = time::timeout(interval, poll(&client)).await else {
+                continue;
+            };
+            if tx.send(value).await.is_err() {
+                return;
+            }
+        }
+    });
+    (rx, AbortOnDrop(handle))
+}
@@ -0,0 +1,690 @@
+//! Delivery of the events of a subscription to pluggable sinks.
+//!
+//! A [`Pipeline`] pipes a stream of events, e.g. a [`Subscription`], into a
+//! [`Sink`], such as:
+//!
+//! - a [`JsonLinesSink`], writing each event as a line of JSON,
+//! - a [`RowSink`], writing the attributes of each event as rows, e.g. to a
+//!   CSV file with a [`CsvWriter`], or to a relational database with its
+//!   own [`RowWriter`],
+//! - a [`ProducerSink`], publishing each event as a message, e.g. to a
+//!   Kafka topic with its own [`MessageProducer`].
+//!
+//! Delivery is at least once: the pipeline records the height of the
+//! events it delivered in a [`CheckpointStore`] only once the sink flushed
+//! all the events of that height, and skips the events at or below the
+//! checkpoint when resumed. The events above the checkpoint may be
+//! delivered again after a restart, so sinks should handle duplicates,
+//! e.g. by keying their records by height.
+//!
+//! The sinks writing to files buffer the events in memory, and write them
+//! on a blocking thread when flushed, synchronizing the files to disk with
+//! [`DurableWrite`] before the pipeline records its checkpoint.
+//!
+//! [`Subscription`]: crate::Subscription
+
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+};
+
+use async_trait::async_trait;
+use futures::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use tendermint::block::Height;
+
+use crate::{
+    event::{v0_38::SerEvent, Event},
+    prelude::*,
+    Error,
+};
+
+/// A destination of events.
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait Sink {
+    /// Write the given event. It may be buffered until the next flush.
+    async fn write(&mut self, event: &Event) -> Result<(), Error>;
+
+    /// Make sure all the events written so far are durably delivered.
+    async fn flush(&mut self) -> Result<(), Error>;
+}
+
+/// A writer which can make the data written to it durable, as needed by
+/// the sinks writing to it to deliver events at least once.
+pub trait DurableWrite: Write {
+    /// Flush the data written so far and make it durable, e.g. by
+    /// synchronizing a file to disk.
+    fn sync(&mut self) -> io::Result<()>;
+}
+
+impl DurableWrite for File {
+    fn sync(&mut self) -> io::Result<()> {
+        self.flush()?;
+        self.sync_data()
+    }
+}
+
+impl<W: DurableWrite> DurableWrite for BufWriter<W> {
+    fn sync(&mut self) -> io::Result<()> {
+        self.flush()?;
+        self.get_mut().sync()
+    }
+}
+
+/// Writes to memory, e.g. in tests, are durable as soon as they are made.
+impl DurableWrite for Vec<u8> {
+    fn sync(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Buffers the output of a sink in memory, and writes it to a
+/// [`DurableWrite`] on a blocking thread when flushed.
+#[derive(Debug)]
+struct BufferedWriter<W> {
+    // Only missing if a flush panicked.
+    writer: Option<W>,
+    buffer: Vec<u8>,
+}
+
+impl<W: DurableWrite + Send + 'static> BufferedWriter<W> {
+    fn new(writer: W) -> Self {
+        Self {
+            writer: Some(writer),
+            buffer: Vec::new(),
+        }
+    }
+
+    async fn flush(&mut self) -> Result<(), Error> {
+        let mut writer = self
+            .writer
+            .take()
+            .ok_or_else(|| Error::sink("the writer was lost by a previous flush".to_string()))?;
+        let buffer = core::mem::take(&mut self.buffer);
+        let (writer, result) = tokio::task::spawn_blocking(move || {
+            let result = writer.write_all(&buffer).and_then(|()| writer.sync());
+            (writer, result)
+        })
+        .await
+        .map_err(|e| Error::sink(e.to_string()))?;
+        self.writer = Some(writer);
+        result.map_err(Error::io)
+    }
+
+    async fn into_inner(mut self) -> Result<W, Error> {
+        self.flush().await?;
+        Ok(self
+            .writer
+            .take()
+            .expect("the writer is put back by a successful flush"))
+    }
+}
+
+/// Serialize an event to JSON, in the format of the CometBFT 0.38 events.
+fn event_json(event: &Event) -> Result<Vec<u8>, Error> {
+    serde_json::to_vec(&SerEvent::from(event.clone())).map_err(|e| Error::sink(e.to_string()))
+}
+
+/// Writes each event as a line of JSON.
+#[derive(Debug)]
+pub struct JsonLinesSink<W> {
+    writer: BufferedWriter<W>,
+}
+
+impl<W: DurableWrite + Send + 'static> JsonLinesSink<W> {
+    /// Write the events to the given writer.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: BufferedWriter::new(writer),
+        }
+    }
+
+    /// Flush the events written so far, and get back the underlying writer.
+    pub async fn into_inner(self) -> Result<W, Error> {
+        self.writer.into_inner().await
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl<W: DurableWrite + Send + 'static> Sink for JsonLinesSink<W> {
+    async fn write(&mut self, event: &Event) -> Result<(), Error> {
+        let line = event_json(event)?;
+        self.writer.buffer.extend(line);
+        self.writer.buffer.push(b'\n');
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> Result<(), Error> {
+        self.writer.flush().await
+    }
+}
+
+/// An attribute of an event, as a row of a table.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AttributeRow {
+    /// The height of the event, if known.
+    pub height: Option<Height>,
+    /// The composite `<event type>.<attribute key>` name of the attribute,
+    /// or `tm.event` for the type of the event.
+    pub name: String,
+    /// The value of the attribute.
+    pub value: String,
+}
+
+impl AttributeRow {
+    /// The rows of the attributes of the given event, taken from its
+    /// `events` map when it is present, and otherwise collected from the
+    /// ABCI events in its data.
+    pub fn from_event(event: &Event) -> Vec<Self> {
+        let height = event.height();
+        let attributes = match &event.events {
+            Some(events) => events.clone(),
+            None => event.data.attributes(),
+        };
+        attributes
+            .into_iter()
+            .flat_map(|(name, values)| {
+                values.into_iter().map(move |value| AttributeRow {
+                    height,
+                    name: name.clone(),
+                    value,
+                })
+            })
+            .collect()
+    }
+}
+
+/// A destination of rows of attributes, e.g. a table of a relational
+/// database.
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait RowWriter {
+    /// Insert the rows of the attributes of an event, e.g. as part of a
+    /// transaction which is committed at the next flush.
+    async fn insert(&mut self, rows: Vec<AttributeRow>) -> Result<(), Error>;
+
+    /// Make sure all the rows inserted so far are durably stored.
+    async fn flush(&mut self) -> Result<(), Error>;
+}
+
+/// Writes the attributes of each event as rows, with a [`RowWriter`].
+#[derive(Debug)]
+pub struct RowSink<R> {
+    writer: R,
+}
+
+impl<R: RowWriter> RowSink<R> {
+    /// Write the rows of the events with the given writer.
+    pub fn new(writer: R) -> Self {
+        Self { writer }
+    }
+
+    /// Get back the underlying writer.
+    pub fn into_inner(self) -> R {
+        self.writer
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl<R: RowWriter + Send> Sink for RowSink<R> {
+    async fn write(&mut self, event: &Event) -> Result<(), Error> {
+        let rows = AttributeRow::from_event(event);
+        if rows.is_empty() {
+            return Ok(());
+        }
+        self.writer.insert(rows).await
+    }
+
+    async fn flush(&mut self) -> Result<(), Error> {
+        self.writer.flush().await
+    }
+}
+
+/// Writes rows of attributes as CSV records, with the `height`, `name` and
+/// `value` columns.
+#[derive(Debug)]
+pub struct CsvWriter<W> {
+    writer: BufferedWriter<W>,
+    header: bool,
+}
+
+impl<W: DurableWrite + Send + 'static> CsvWriter<W> {
+    /// Write the rows to the given writer, starting with a header record.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: BufferedWriter::new(writer),
+            header: true,
+        }
+    }
+
+    /// Write the rows to the given writer without a header record, e.g. to
+    /// append to an existing file.
+    pub fn without_header(writer: W) -> Self {
+        Self {
+            writer: BufferedWriter::new(writer),
+            header: false,
+        }
+    }
+
+    /// Flush the rows inserted so far, and get back the underlying writer.
+    pub async fn into_inner(self) -> Result<W, Error> {
+        self.writer.into_inner().await
+    }
+
+    fn write_record(&mut self, fields: [&str; 3]) {
+        let record = fields.map(csv_field).join(",");
+        self.writer.buffer.extend(record.into_bytes());
+        self.writer.buffer.push(b'\n');
+    }
+}
+
+/// Quote a CSV field if it contains a separator, a quote or a line break.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl<W: DurableWrite + Send + 'static> RowWriter for CsvWriter<W> {
+    async fn insert(&mut self, rows: Vec<AttributeRow>) -> Result<(), Error> {
+        if core::mem::take(&mut self.header) {
+            self.write_record(["height", "name", "value"]);
+        }
+        for row in rows {
+            let height = row.height.map(|h| h.to_string()).unwrap_or_default();
+            self.write_record([&height, &row.name, &row.value]);
+        }
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> Result<(), Error> {
+        self.writer.flush().await
+    }
+}
+
+/// A producer of messages, e.g. to a Kafka topic.
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait MessageProducer {
+    /// Send a message with the given key and payload. It may be buffered
+    /// until the next flush.
+    async fn send(&mut self, key: String, payload: Vec<u8>) -> Result<(), Error>;
+
+    /// Wait until all the messages sent so far are acknowledged.
+    async fn flush(&mut self) -> Result<(), Error>;
+}
+
+/// Publishes each event as a message with a [`MessageProducer`].
+///
+/// The payload of the message is the event serialized to JSON, and its key
+/// is the height of the event, so that the events of a block are kept in
+/// order when the messages are partitioned by key.
+#[derive(Debug)]
+pub struct ProducerSink<P> {
+    producer: P,
+}
+
+impl<P: MessageProducer> ProducerSink<P> {
+    /// Publish the events with the given producer.
+    pub fn new(producer: P) -> Self {
+        Self { producer }
+    }
+
+    /// Get back the underlying producer.
+    pub fn into_inner(self) -> P {
+        self.producer
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl<P: MessageProducer + Send> Sink for ProducerSink<P> {
+    async fn write(&mut self, event: &Event) -> Result<(), Error> {
+        let key = event.height().map(|h| h.to_string()).unwrap_or_default();
+        self.producer.send(key, event_json(event)?).await
+    }
+
+    async fn flush(&mut self) -> Result<(), Error> {
+        self.producer.flush().await
+    }
+}
+
+/// The progress of the delivery of events to a sink.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// The height up to which all the events were delivered.
+    pub height: Height,
+}
+
+/// A store of the checkpoint of a [`Pipeline`], from which it resumes.
+pub trait CheckpointStore {
+    /// Load the latest checkpoint, if any.
+    fn load(&self) -> Result<Option<Checkpoint>, Error>;
+
+    /// Durably replace the latest checkpoint with the given one.
+    fn save(&mut self, checkpoint: Checkpoint) -> Result<(), Error>;
+}
+
+/// Keeps the checkpoint in memory, e.g. for tests.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryCheckpointStore {
+    checkpoint: Option<Checkpoint>,
+}
+
+impl MemoryCheckpointStore {
+    /// A store holding the given checkpoint.
+    pub fn new(checkpoint: Option<Checkpoint>) -> Self {
+        Self { checkpoint }
+    }
+}
+
+impl CheckpointStore for MemoryCheckpointStore {
+    fn load(&self) -> Result<Option<Checkpoint>, Error> {
+        Ok(self.checkpoint)
+    }
+
+    fn save(&mut self, checkpoint: Checkpoint) -> Result<(), Error> {
+        self.checkpoint = Some(checkpoint);
+        Ok(())
+    }
+}
+
+/// Pipes events into a [`Sink`], recording its progress in a
+/// [`CheckpointStore`].
+///
+/// Events are expected in increasing order of height. When an event of a
+/// greater height than the previous ones is received, the sink is flushed
+/// and the previous height is saved as the checkpoint. The events whose
+/// height is at or below the checkpoint are skipped.
+///
+/// ## Examples
+///
+/// ```rust,ignore
+/// use tendermint_rpc::{
+///     client::sink::{JsonLinesSink, MemoryCheckpointStore, Pipeline},
+///     query::EventType,
+///     SubscriptionClient,
+/// };
+///
+/// let sink = JsonLinesSink::new(std::fs::File::create("events.jsonl")?);
+/// let mut pipeline = Pipeline::new(sink, MemoryCheckpointStore::default())?;
+/// let subscription = client.subscribe(EventType::NewBlock.into()).await?;
+/// pipeline.run(subscription).await?;
+/// ```
+#[derive(Debug)]
+pub struct Pipeline<K, C> {
+    sink: K,
+    checkpoints: C,
+    checkpoint: Option<Checkpoint>,
+    /// The greatest height of the events written since the checkpoint.
+    pending: Option<Height>,
+}
+
+impl<K: Sink, C: CheckpointStore> Pipeline<K, C> {
+    /// Pipe events into the given sink, resuming from the checkpoint in
+    /// the given store, if any.
+    pub fn new(sink: K, checkpoints: C) -> Result<Self, Error> {
+        let checkpoint = checkpoints.load()?;
+        Ok(Self {
+            sink,
+            checkpoints,
+            checkpoint,
+            pending: None,
+        })
+    }
+
+    /// The latest checkpoint.
+    pub fn checkpoint(&self) -> Option<Checkpoint> {
+        self.checkpoint
+    }
+
+    /// The lowest height of the events which were not delivered yet, e.g.
+    /// to backfill them from the `/block_results` endpoint before
+    /// subscribing.
+    pub fn resume_height(&self) -> Height {
+        self.checkpoint
+            .map(|checkpoint| checkpoint.height.increment())
+            .unwrap_or_default()
+    }
+
+    /// Deliver the given event to the sink, unless it is at or below the
+    /// checkpoint. Returns whether the event was delivered.
+    ///
+    /// Events without a height, e.g. generic JSON events, are always
+    /// delivered, and do not advance the checkpoint.
+    pub async fn process(&mut self, event: &Event) -> Result<bool, Error> {
+        let height = event.height();
+        if let (Some(height), Some(checkpoint)) = (height, self.checkpoint) {
+            if height <= checkpoint.height {
+                return Ok(false);
+            }
+        }
+        if let (Some(height), Some(pending)) = (height, self.pending) {
+            if height > pending {
+                self.commit(pending).await?;
+            }
+        }
+        self.sink.write(event).await?;
+        self.pending = self.pending.max(height);
+        Ok(true)
+    }
+
+    /// Deliver the events of the given stream to the sink, until the end
+    /// of the stream or the first error.
+    ///
+    /// The sink is flushed at the end of the stream, but the height of the
+    /// last events is not checkpointed, as further events of that height
+    /// may follow when the stream is resumed. Use [`Pipeline::finish`] to
+    /// checkpoint it once it is known to be complete.
+    pub async fn run<S>(&mut self, events: S) -> Result<(), Error>
+    where
+        S: Stream<Item = Result<Event, Error>>,
+    {
+        let mut events = Box::pin(events);
+        while let Some(event) = events.next().await {
+            self.process(&event?).await?;
+        }
+        self.sink.flush().await
+    }
+
+    /// Flush the sink and checkpoint the height of the last events, once
+    /// all the events of that height were delivered.
+    pub async fn finish(&mut self) -> Result<Option<Checkpoint>, Error> {
+        match self.pending {
+            Some(pending) => self.commit(pending).await?,
+            None => self.sink.flush().await?,
+        }
+        Ok(self.checkpoint)
+    }
+
+    /// Get back the sink and the checkpoint store.
+    pub fn into_parts(self) -> (K, C) {
+        (self.sink, self.checkpoints)
+    }
+
+    async fn commit(&mut self, height: Height) -> Result<(), Error> {
+        self.sink.flush().await?;
+        let checkpoint = Checkpoint { height };
+        self.checkpoints.save(checkpoint)?;
+        self.checkpoint = Some(checkpoint);
+        self.pending = None;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::stream;
+    use tendermint::abci::{self, EventAttributeIndexExt};
+
+    use super::*;
+    use crate::event::{EventData, TxInfo, TxResult};
+
+    fn tx_event(height: i64, value: &str) -> Event {
+        Event {
+            query: "tm.event = 'Tx'".to_string(),
+            data: EventData::Tx {
+                tx_result: TxInfo {
+                    height,
+                    index: Some(0),
+                    tx: vec![1, 2, 3],
+                    result: TxResult {
+                        log: None,
+                        gas_wanted: None,
+                        gas_used: None,
+                        events: vec![abci::Event::new("transfer", [("amount", value).index()])],
+                    },
+                },
+            },
+            events: None,
+        }
+    }
+
+    #[derive(Debug, Default)]
+    struct Recorder {
+        written: Vec<i64>,
+        flushed: usize,
+    }
+
+    #[async_trait]
+    impl Sink for Recorder {
+        async fn write(&mut self, event: &Event) -> Result<(), Error> {
+            self.written.push(event.height().unwrap().value() as i64);
+            Ok(())
+        }
+
+        async fn flush(&mut self) -> Result<(), Error> {
+            self.flushed = self.written.len();
+            Ok(())
+        }
+    }
+
+    fn events(heights: &[i64]) -> impl Stream<Item = Result<Event, Error>> {
+        stream::iter(
+            heights
+                .iter()
+                .map(|&height| Ok(tx_event(height, "1")))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    #[tokio::test]
+    async fn checkpoints_flushed_heights() {
+        let mut pipeline =
+            Pipeline::new(Recorder::default(), MemoryCheckpointStore::default()).unwrap();
+        assert_eq!(pipeline.resume_height(), Height::from(1_u32));
+
+        pipeline.run(events(&[1, 1, 2, 3, 3])).await.unwrap();
+        assert_eq!(pipeline.checkpoint().unwrap().height, Height::from(2_u32));
+        assert_eq!(pipeline.resume_height(), Height::from(3_u32));
+
+        let (sink, checkpoints) = pipeline.into_parts();
+        assert_eq!(sink.written, [1, 1, 2, 3, 3]);
+        assert_eq!(sink.flushed, 5);
+
+        // Resuming skips the checkpointed heights, and delivers the events
+        // of height 3 again.
+        let mut pipeline = Pipeline::new(Recorder::default(), checkpoints).unwrap();
+        pipeline.run(events(&[2, 3, 3, 4])).await.unwrap();
+        assert_eq!(
+            pipeline.finish().await.unwrap().unwrap().height,
+            Height::from(4_u32)
+        );
+        assert_eq!(pipeline.into_parts().0.written, [3, 3, 4]);
+    }
+
+    #[tokio::test]
+    async fn stops_at_errors() {
+        let mut pipeline =
+            Pipeline::new(Recorder::default(), MemoryCheckpointStore::default()).unwrap();
+        let events = stream::iter(vec![
+            Ok(tx_event(1, "1")),
+            Ok(tx_event(2, "1")),
+            Err(Error::client_internal("disconnected".to_string())),
+            Ok(tx_event(3, "1")),
+        ]);
+        assert!(pipeline.run(events).await.is_err());
+        assert_eq!(pipeline.checkpoint().unwrap().height, Height::from(1_u32));
+        assert_eq!(pipeline.into_parts().0.written, [1, 2]);
+    }
+
+    #[tokio::test]
+    async fn writes_to_files_before_checkpointing() {
+        let path = std::env::temp_dir().join(format!("sink-{}.jsonl", std::process::id()));
+        let sink = JsonLinesSink::new(File::create(&path).unwrap());
+        let mut pipeline = Pipeline::new(sink, MemoryCheckpointStore::default()).unwrap();
+
+        pipeline.run(events(&[1, 2])).await.unwrap();
+        assert_eq!(pipeline.checkpoint().unwrap().height, Height::from(1_u32));
+        // The events are written by the time the checkpoint is recorded.
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 2);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn writes_json_lines() {
+        let mut sink = JsonLinesSink::new(Vec::new());
+        sink.write(&tx_event(5, "1")).await.unwrap();
+        sink.write(&tx_event(6, "2")).await.unwrap();
+        let output = String::from_utf8(sink.into_inner().await.unwrap()).unwrap();
+        let lines: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1]["data"]["value"]["TxResult"]["height"], "6");
+    }
+
+    #[tokio::test]
+    async fn writes_csv_rows() {
+        let mut sink = RowSink::new(CsvWriter::new(Vec::new()));
+        sink.write(&tx_event(5, "1,5\"atom\"")).await.unwrap();
+        let output = String::from_utf8(sink.into_inner().into_inner().await.unwrap()).unwrap();
+        let mut expected = "height,name,value\n\
+                            5,tm.event,Tx\n\
+                            5,transfer.amount,\"1,5\"\"atom\"\"\"\n"
+            .to_string();
+        #[cfg(feature = "rust-crypto")]
+        expected.push_str(
+            "5,tx.hash,039058C6F2C0CB492C533B0A4D14EF77CC0F78ABCCCED5287D84A1A2011CFB81\n",
+        );
+        expected.push_str("5,tx.height,5\n");
+        assert_eq!(output, expected);
+    }
+
+    #[derive(Debug, Default)]
+    struct Producer {
+        messages: Vec<(String, Vec<u8>)>,
+    }
+
+    #[async_trait]
+    impl MessageProducer for Producer {
+        async fn send(&mut self, key: String, payload: Vec<u8>) -> Result<(), Error> {
+            self.messages.push((key, payload));
+            Ok(())
+        }
+
+        async fn flush(&mut self) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn produces_messages_keyed_by_height() {
+        let mut sink = ProducerSink::new(Producer::default());
+        sink.write(&tx_event(7, "1")).await.unwrap();
+        let messages = sink.into_inner().messages;
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].0, "7");
+        let event: serde_json::Value = serde_json::from_slice(&messages[0].1).unwrap();
+        assert_eq!(event["query"], "tm.event = 'Tx'");
+    }
+}
@@ -7,10 +7,10 @@ use futures::{
     task::{Context, Poll},
     Stream,
 };
-use pin_project::pin_project;
+use pin_project::{pin_project, pinned_drop};
 
 use crate::{
-    client::sync::{ChannelRx, ChannelTx},
+    client::sync::{unbounded, ChannelRx, ChannelTx},
     event::Event,
     prelude::*,
     query::Query,
@@ -27,6 +27,9 @@ pub trait SubscriptionClient {
 
     /// `/unsubscribe`: unsubscribe from events relating to the given query.
     ///
+    /// This terminates all the [`Subscription`]s to the query. To only
+    /// terminate one of them, use [`Subscription::unsubscribe`].
+    ///
     /// This method is particularly useful when you want to terminate multiple
     /// [`Subscription`]s to the same [`Query`] simultaneously, or if you've
     /// joined multiple `Subscription`s together using [`select_all`] and you
@@ -43,6 +46,19 @@ pub trait SubscriptionClient {
 
 pub(crate) type SubscriptionTx = ChannelTx<Result<Event, Error>>;
 pub(crate) type SubscriptionRx = ChannelRx<Result<Event, Error>>;
+pub(crate) type ReleaseTx = ChannelTx<Release>;
+
+/// The release of its share of a remote subscription by a [`Subscription`].
+// Only the WebSocket client keeps track of the subscriptions to a query.
+#[cfg_attr(not(feature = "websocket-client"), allow(dead_code))]
+#[derive(Debug, Clone)]
+pub(crate) struct Release {
+    // The ID of the subscription.
+    pub id: String,
+    // Where to send the result of the release, if the subscription was
+    // explicitly unsubscribed rather than dropped.
+    pub response_tx: Option<ChannelTx<Result<(), Error>>>,
+}
 
 /// An interface that can be used to asynchronously receive [`Event`]s for a
 /// particular subscription.
@@ -69,7 +85,16 @@ pub(crate) type SubscriptionRx = ChannelRx<Result<Event, Error>>;
 ///     }
 /// }
 /// ```
-#[pin_project]
+///
+/// Subscriptions to the same query can share a single subscription on the
+/// remote endpoint, depending on the client (see [`WebSocketClient`]). In this
+/// case, dropping a `Subscription` releases its share of the remote
+/// subscription, as does [`Subscription::unsubscribe`], and the remote
+/// subscription is terminated once all the `Subscription`s to the query have
+/// been released.
+///
+/// [`WebSocketClient`]: crate::WebSocketClient
+#[pin_project(PinnedDrop)]
 #[derive(Debug)]
 pub struct Subscription {
     // A unique identifier for this subscription.
@@ -79,6 +104,23 @@ pub struct Subscription {
     // Our internal result event receiver for this subscription.
     #[pin]
     rx: SubscriptionRx,
+    // Where to send the ID of this subscription once it is dropped, if the
+    // client keeps track of the subscriptions sharing a remote subscription.
+    release_tx: Option<ReleaseTx>,
+}
+
+#[pinned_drop]
+impl PinnedDrop for Subscription {
+    fn drop(self: Pin<&mut Self>) {
+        let this = self.project();
+        if let Some(release_tx) = this.release_tx.take() {
+            // The client may already have terminated.
+            let _ = release_tx.send(Release {
+                id: this.id.clone(),
+                response_tx: None,
+            });
+        }
+    }
 }
 
 impl Stream for Subscription {
@@ -91,7 +133,20 @@ impl Stream for Subscription {
 
 impl Subscription {
     pub(crate) fn new(id: String, query: Query, rx: SubscriptionRx) -> Self {
-        Self { id, query, rx }
+        Self {
+            id,
+            query,
+            rx,
+            release_tx: None,
+        }
+    }
+
+    /// Notify the given channel with the ID of this subscription once it is
+    /// dropped.
+    #[cfg_attr(not(feature = "websocket-client"), allow(dead_code))]
+    pub(crate) fn with_release(mut self, release_tx: ReleaseTx) -> Self {
+        self.release_tx = Some(release_tx);
+        self
     }
 
     /// Return this subscription's ID for informational purposes.
@@ -102,4 +157,27 @@ impl Subscription {
     pub fn query(&self) -> &Query {
         &self.query
     }
+
+    /// Terminate this subscription.
+    ///
+    /// If the client shares a remote subscription between the subscriptions
+    /// to the same query, only this subscription releases its share, and the
+    /// other subscriptions to the query keep receiving events. The remote
+    /// subscription is terminated by the last subscription to be released,
+    /// in which case this waits for the remote endpoint to confirm it.
+    /// Otherwise, this is equivalent to dropping the subscription.
+    pub async fn unsubscribe(mut self) -> Result<(), Error> {
+        let release_tx = match self.release_tx.take() {
+            Some(release_tx) => release_tx,
+            None => return Ok(()),
+        };
+        let (response_tx, mut response_rx) = unbounded();
+        release_tx.send(Release {
+            id: self.id.clone(),
+            response_tx: Some(response_tx),
+        })?;
+        response_rx.recv().await.ok_or_else(|| {
+            Error::client_internal("failed to hear back from the client".to_string())
+        })?
+    }
 }
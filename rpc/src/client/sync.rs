@@ -32,6 +32,11 @@ impl<T> ChannelTx<T> {
     pub fn send(&self, value: T) -> Result<(), Error> {
         self.0.send(value).map_err(Error::send)
     }
+
+    /// Wait until the receiving half of the channel is dropped.
+    pub async fn closed(&self) {
+        self.0.closed().await
+    }
 }
 
 /// Receiver interface for a channel.
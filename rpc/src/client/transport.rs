@@ -1,7 +1,11 @@
 //! Tendermint RPC client implementations for different transports.
 
 mod auth;
+pub mod dns;
+pub mod faulty;
 pub mod mock;
+pub mod polling;
+pub mod retry;
 mod router;
 
 macro_rules! perform_with_compat {
@@ -24,5 +28,7 @@ macro_rules! perform_with_compat {
 
 #[cfg(feature = "http-client")]
 pub mod http;
+#[cfg(feature = "http-client")]
+pub mod sse;
 #[cfg(feature = "websocket-client")]
 pub mod websocket;
@@ -0,0 +1,253 @@
+//! Pluggable resolution of host names, and dual-stack connection
+//! establishment for the HTTP and WebSocket transports.
+//!
+//! Nodes are often published behind host names resolving to several IPv4
+//! and IPv6 addresses, some of which may be unreachable from the client.
+//! Connecting to the resolved addresses one after the other then stalls for
+//! as long as the operating system takes to give up on each unreachable
+//! address. [`HappyEyeballsConfig::connect`] instead implements the
+//! connection racing of [RFC 8305]: the addresses of both families are
+//! interleaved, and a new connection attempt is started whenever the
+//! previous one fails or does not complete within a short delay, the first
+//! established connection winning.
+//!
+//! [RFC 8305]: https://www.rfc-editor.org/rfc/rfc8305
+
+use std::{
+    io,
+    net::{IpAddr, SocketAddr},
+};
+
+use async_trait::async_trait;
+use tokio::{net::TcpStream, task::JoinSet, time::Duration};
+
+use crate::prelude::*;
+
+/// Resolves the host names of RPC endpoints to IP addresses.
+///
+/// The default resolver, [`SystemResolver`], uses the resolver of the
+/// operating system. Custom resolvers allow e.g. querying specific name
+/// servers, or pinning the addresses of known nodes.
+#[async_trait]
+pub trait Resolver: Send + Sync {
+    /// Resolve the given host name to the IP addresses it designates, in
+    /// order of preference.
+    async fn resolve(&self, host: &str) -> io::Result<Vec<IpAddr>>;
+}
+
+/// A [`Resolver`] using the resolver of the operating system, through
+/// `getaddrinfo(3)` or its equivalent.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SystemResolver;
+
+#[async_trait]
+impl Resolver for SystemResolver {
+    async fn resolve(&self, host: &str) -> io::Result<Vec<IpAddr>> {
+        let addrs = tokio::net::lookup_host((host, 0)).await?;
+        Ok(addrs.map(|addr| addr.ip()).collect())
+    }
+}
+
+/// Configuration of the dual-stack connection establishment performed by
+/// [`HappyEyeballsConfig::connect`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct HappyEyeballsConfig {
+    /// The delay after which a new connection attempt is started, to the
+    /// next resolved address, while the previous attempts are still pending.
+    pub attempt_delay: Duration,
+    /// The time after which a single connection attempt is abandoned, if
+    /// any. Without a timeout, pending attempts are only abandoned by the
+    /// operating system.
+    pub attempt_timeout: Option<Duration>,
+}
+
+impl Default for HappyEyeballsConfig {
+    fn default() -> Self {
+        Self {
+            // The "Connection Attempt Delay" recommended by RFC 8305.
+            attempt_delay: Duration::from_millis(250),
+            attempt_timeout: Some(Duration::from_secs(10)),
+        }
+    }
+}
+
+impl HappyEyeballsConfig {
+    /// Open a TCP connection to the given host and port, resolving the host
+    /// with the given resolver unless it is an IP address.
+    pub async fn connect(
+        &self,
+        resolver: &dyn Resolver,
+        host: &str,
+        port: u16,
+    ) -> io::Result<TcpStream> {
+        // IPv6 addresses may be bracketed, as in URLs.
+        let literal = host.trim_start_matches('[').trim_end_matches(']');
+        let ips = match literal.parse::<IpAddr>() {
+            Ok(ip) => vec![ip],
+            Err(_) => resolver.resolve(host).await?,
+        };
+        if ips.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no address found for host {host}"),
+            ));
+        }
+
+        let mut pending = interleave(ips)
+            .into_iter()
+            .map(|ip| SocketAddr::new(ip, port));
+        let mut attempts = JoinSet::new();
+        let mut last_error = None;
+        loop {
+            if let Some(addr) = pending.next() {
+                attempts.spawn(attempt(addr, self.attempt_timeout));
+            }
+            if attempts.is_empty() {
+                return Err(last_error.expect("at least one connection attempt failed"));
+            }
+
+            let has_pending = pending.len() > 0;
+            tokio::select! {
+                Some(result) = attempts.join_next() => {
+                    match result? {
+                        // The remaining attempts are aborted when dropped.
+                        Ok(stream) => return Ok(stream),
+                        Err(e) => last_error = Some(e),
+                    }
+                },
+                _ = tokio::time::sleep(self.attempt_delay), if has_pending => {},
+            }
+        }
+    }
+}
+
+async fn attempt(addr: SocketAddr, timeout: Option<Duration>) -> io::Result<TcpStream> {
+    let Some(timeout) = timeout else {
+        return TcpStream::connect(addr).await;
+    };
+    tokio::time::timeout(timeout, TcpStream::connect(addr))
+        .await
+        .unwrap_or_else(|_| {
+            Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("connection to {addr} timed out"),
+            ))
+        })
+}
+
+/// Order the given addresses by alternating between the address families,
+/// starting with the family of the first address, and otherwise preserving
+/// the order of the addresses of each family.
+fn interleave(ips: Vec<IpAddr>) -> Vec<IpAddr> {
+    let Some(first) = ips.first() else {
+        return ips;
+    };
+    let first_is_v6 = first.is_ipv6();
+    let (mut preferred, mut other): (Vec<_>, Vec<_>) =
+        ips.into_iter().partition(|ip| ip.is_ipv6() == first_is_v6);
+    other.reverse();
+    preferred.reverse();
+
+    let mut interleaved = Vec::with_capacity(preferred.len() + other.len());
+    loop {
+        match (preferred.pop(), other.pop()) {
+            (None, None) => return interleaved,
+            (a, b) => interleaved.extend(a.into_iter().chain(b)),
+        }
+    }
+}
+
+/// Adapts a [`Resolver`] to the resolution interface of the HTTP client.
+#[cfg(feature = "http-client")]
+pub(crate) struct ReqwestResolver(pub(crate) alloc::sync::Arc<dyn Resolver>);
+
+#[cfg(feature = "http-client")]
+impl reqwest::dns::Resolve for ReqwestResolver {
+    fn resolve(&self, name: hyper::client::connect::dns::Name) -> reqwest::dns::Resolving {
+        let resolver = self.0.clone();
+        Box::pin(async move {
+            let ips = resolver.resolve(name.as_str()).await?;
+            // The port is set by the HTTP client.
+            let addrs: reqwest::dns::Addrs =
+                Box::new(interleave(ips).into_iter().map(|ip| SocketAddr::new(ip, 0)));
+            Ok(addrs)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use tokio::{net::TcpListener, time::Instant};
+
+    use super::*;
+
+    struct StaticResolver(Vec<IpAddr>);
+
+    #[async_trait]
+    impl Resolver for StaticResolver {
+        async fn resolve(&self, _host: &str) -> io::Result<Vec<IpAddr>> {
+            Ok(self.0.clone())
+        }
+    }
+
+    #[test]
+    fn interleaves_address_families() {
+        let v4 = |n| IpAddr::V4(Ipv4Addr::new(10, 0, 0, n));
+        let v6 = |n| IpAddr::V6(Ipv6Addr::new(0xfd00, 0, 0, 0, 0, 0, 0, n));
+
+        assert_eq!(
+            interleave(vec![v6(1), v6(2), v6(3), v4(1), v4(2)]),
+            vec![v6(1), v4(1), v6(2), v4(2), v6(3)]
+        );
+        assert_eq!(
+            interleave(vec![v4(1), v4(2), v6(1)]),
+            vec![v4(1), v6(1), v4(2)]
+        );
+        assert!(interleave(vec![]).is_empty());
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_reachable_address() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        // Nothing listens on the first address, which refuses the connection.
+        let resolver = StaticResolver(vec![
+            IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2)),
+            IpAddr::V4(Ipv4Addr::LOCALHOST),
+        ]);
+        let config = HappyEyeballsConfig {
+            attempt_delay: Duration::from_secs(60),
+            attempt_timeout: None,
+        };
+        let started = Instant::now();
+        let stream = config
+            .connect(&resolver, "node.example", port)
+            .await
+            .unwrap();
+        assert!(started.elapsed() < Duration::from_secs(60));
+        assert_eq!(stream.peer_addr().unwrap().port(), port);
+    }
+
+    #[tokio::test]
+    async fn reports_failure_of_all_attempts() {
+        let refusing = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = refusing.local_addr().unwrap().port();
+        drop(refusing);
+
+        let resolver = StaticResolver(vec![IpAddr::V4(Ipv4Addr::LOCALHOST)]);
+        let err = HappyEyeballsConfig::default()
+            .connect(&resolver, "node.example", port)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::ConnectionRefused);
+
+        let err = HappyEyeballsConfig::default()
+            .connect(&StaticResolver(vec![]), "node.example", port)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+}
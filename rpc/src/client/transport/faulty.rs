@@ -0,0 +1,715 @@
+//! Injection of faults into the requests and subscriptions of a client, to
+//! soak test the systems built on top of it.
+
+use core::{future::Future, time::Duration};
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use tendermint::{block::Height, evidence::Evidence, Hash};
+use tokio::time;
+
+use crate::{
+    client::{
+        subscription::SubscriptionTx,
+        sync::{unbounded, ChannelTx},
+        Client,
+    },
+    endpoint::{block_results, broadcast, evidence, header, header_by_hash, tx, tx_search},
+    event::{self, Event},
+    prelude::*,
+    query::Query,
+    request::{Request, RequestMessage, SimpleRequest},
+    response::Response,
+    Error, Method, Order, Subscription, SubscriptionClient,
+};
+
+/// Configuration of the faults injected by a [`FaultyClient`].
+///
+/// The probabilities are between 0 and 1. The default configuration injects
+/// no faults.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FaultConfig {
+    /// The seed of the schedule of the faults. The same seed injects the
+    /// same faults into the same sequence of requests and events.
+    pub seed: u64,
+    /// The minimum latency added to each request and event.
+    pub min_latency: Duration,
+    /// The maximum latency added to each request and event.
+    pub max_latency: Duration,
+    /// The probability that the response to a request is lost, or that an
+    /// event is not delivered.
+    ///
+    /// Lost responses fail with a timeout after the drop timeout, although
+    /// the request was performed by the node.
+    pub drop_probability: f64,
+    /// The time after which a request whose response is lost times out.
+    pub drop_timeout: Duration,
+    /// The probability that an event is delivered twice.
+    pub duplicate_probability: f64,
+    /// The probability that a request is held back by the reorder delay,
+    /// or that an event is delivered after the next one.
+    ///
+    /// Requests are only delayed: a request held back is performed after
+    /// the delay, so that the requests made concurrently may complete
+    /// before it, but the responses of the node are not reordered.
+    pub reorder_probability: f64,
+    /// The delay of the requests which are held back.
+    pub reorder_delay: Duration,
+    /// The probability that the payload of a response or event is
+    /// corrupted.
+    ///
+    /// Corrupted responses and events have one of the characters of their
+    /// JSON encoding replaced, and are decoded again, into a value with
+    /// altered data or a decoding error, unless the character is ignored
+    /// by the decoding. The responses to subscription requests, which have
+    /// no payload, are not corrupted.
+    pub corrupt_probability: f64,
+}
+
+impl Default for FaultConfig {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            min_latency: Duration::ZERO,
+            max_latency: Duration::ZERO,
+            drop_probability: 0.0,
+            drop_timeout: Duration::from_secs(1),
+            duplicate_probability: 0.0,
+            reorder_probability: 0.0,
+            reorder_delay: Duration::from_millis(100),
+            corrupt_probability: 0.0,
+        }
+    }
+}
+
+/// A client wrapping another one, injecting faults into its requests and
+/// subscriptions: latency, lost responses and events, duplicated events,
+/// delayed requests, reordered events, and corrupted payloads.
+///
+/// The faults are drawn from a schedule generated from the seed of the
+/// [`FaultConfig`], so that a failure found by a soak test can be
+/// reproduced. The faults of the requests are drawn in the order the
+/// requests are made, and each subscription draws the faults of its events
+/// from its own schedule, in the order the events are received.
+///
+/// ## Examples
+///
+/// ```rust,ignore
+/// use tendermint_rpc::{
+///     client::{FaultConfig, FaultyClient},
+///     Client, HttpClient,
+/// };
+///
+/// let client = HttpClient::new("http://127.0.0.1:26657").unwrap();
+/// let client = FaultyClient::new(
+///     client,
+///     FaultConfig {
+///         seed: 42,
+///         drop_probability: 0.05,
+///         corrupt_probability: 0.01,
+///         ..Default::default()
+///     },
+/// );
+/// run_relayer(client).await;
+/// ```
+#[derive(Debug)]
+pub struct FaultyClient<C> {
+    inner: C,
+    config: FaultConfig,
+    schedule: Mutex<Schedule>,
+}
+
+impl<C> FaultyClient<C> {
+    /// Inject the faults of the given configuration into the given client.
+    pub fn new(inner: C, config: FaultConfig) -> Self {
+        Self {
+            inner,
+            config,
+            schedule: Mutex::new(Schedule::new(config.seed)),
+        }
+    }
+
+    /// The configuration of the injected faults.
+    pub fn config(&self) -> &FaultConfig {
+        &self.config
+    }
+
+    /// The wrapped client.
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    /// Get back the wrapped client.
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+
+    fn schedule(&self) -> std::sync::MutexGuard<'_, Schedule> {
+        // The schedule is left consistent if a holder of the lock panics.
+        self.schedule
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Perform a request, injecting the faults drawn for it.
+    async fn inject<T, F>(&self, request: F) -> Result<T, Error>
+    where
+        T: Response + Serialize,
+        F: Future<Output = Result<T, Error>> + Send,
+    {
+        let faults = self.delay().await;
+        let response = self.complete(&faults, request).await?;
+        match faults.corrupt {
+            Some(mut schedule) => {
+                let mut json = response_json(&response)?;
+                corrupt_json(&mut json, &mut schedule);
+                T::from_string(json)
+            },
+            None => Ok(response),
+        }
+    }
+
+    /// Perform a request whose response has no payload, injecting the
+    /// faults drawn for it, except for the corruption of the payload.
+    async fn inject_without_payload<T, F>(&self, request: F) -> Result<T, Error>
+    where
+        F: Future<Output = Result<T, Error>> + Send,
+    {
+        let faults = self.delay().await;
+        self.complete(&faults, request).await
+    }
+
+    /// Draw the faults of a request, and wait for its latency and for it to
+    /// be held back.
+    async fn delay(&self) -> RequestFaults {
+        let faults = RequestFaults::draw(&mut self.schedule(), &self.config);
+        time::sleep(faults.latency).await;
+        // The responses are not held across delays, as they may not be
+        // `Send`, so requests are held back before they are performed.
+        if faults.reorder {
+            time::sleep(self.config.reorder_delay).await;
+        }
+        faults
+    }
+
+    /// Complete a request, losing its response if drawn.
+    async fn complete<T, F>(&self, faults: &RequestFaults, request: F) -> Result<T, Error>
+    where
+        F: Future<Output = Result<T, Error>> + Send,
+    {
+        if faults.drop {
+            let _ = request.await;
+            time::sleep(self.config.drop_timeout).await;
+            return Err(Error::timeout(self.config.drop_timeout));
+        }
+        request.await
+    }
+}
+
+#[async_trait]
+impl<C> Client for FaultyClient<C>
+where
+    C: Client + Send + Sync,
+{
+    async fn perform<R>(&self, request: R) -> Result<R::Output, Error>
+    where
+        R: SimpleRequest,
+    {
+        let mut faults = self.delay().await;
+        let Some(mut schedule) = faults.corrupt.take() else {
+            return self.complete(&faults, self.inner.perform(request)).await;
+        };
+        // The responses of the requests are not required to be serializable,
+        // so the result of a corrupted request is received as raw JSON, and
+        // decoded once it is corrupted.
+        let request = RawRequest::new(request)?;
+        let result = self.complete(&faults, self.inner.perform(request)).await?;
+        let mut json = response_json(&result.0)?;
+        corrupt_json(&mut json, &mut schedule);
+        R::Response::from_string(json).map(Into::into)
+    }
+
+    // The methods whose request depends on the compatibility mode of the
+    // wrapped client are forwarded to it.
+
+    async fn block_results<H>(&self, height: H) -> Result<block_results::Response, Error>
+    where
+        H: Into<Height> + Send,
+    {
+        self.inject(self.inner.block_results(height)).await
+    }
+
+    async fn latest_block_results(&self) -> Result<block_results::Response, Error> {
+        self.inject(self.inner.latest_block_results()).await
+    }
+
+    async fn header<H>(&self, height: H) -> Result<header::Response, Error>
+    where
+        H: Into<Height> + Send,
+    {
+        self.inject(self.inner.header(height)).await
+    }
+
+    async fn header_by_hash(&self, hash: Hash) -> Result<header_by_hash::Response, Error> {
+        self.inject(self.inner.header_by_hash(hash)).await
+    }
+
+    async fn broadcast_evidence(&self, e: Evidence) -> Result<evidence::Response, Error> {
+        self.inject(self.inner.broadcast_evidence(e)).await
+    }
+
+    async fn tx(&self, hash: Hash, prove: bool) -> Result<tx::Response, Error> {
+        self.inject(self.inner.tx(hash, prove)).await
+    }
+
+    async fn tx_search(
+        &self,
+        query: Query,
+        prove: bool,
+        page: u32,
+        per_page: u8,
+        order: Order,
+    ) -> Result<tx_search::Response, Error> {
+        self.inject(self.inner.tx_search(query, prove, page, per_page, order))
+            .await
+    }
+
+    async fn broadcast_tx_commit<T>(&self, tx: T) -> Result<broadcast::tx_commit::Response, Error>
+    where
+        T: Into<Vec<u8>> + Send,
+    {
+        self.inject(self.inner.broadcast_tx_commit(tx)).await
+    }
+}
+
+#[async_trait]
+impl<C> SubscriptionClient for FaultyClient<C>
+where
+    C: SubscriptionClient + Send + Sync,
+{
+    async fn subscribe(&self, query: Query) -> Result<Subscription, Error> {
+        let subscription = self
+            .inject_without_payload(self.inner.subscribe(query))
+            .await?;
+        let id = subscription.id().to_string();
+        let query = subscription.query().clone();
+        let schedule = Schedule::new(self.schedule().next_u64());
+        let (tx, rx) = unbounded();
+        tokio::spawn(inject_event_faults(subscription, tx, schedule, self.config));
+        Ok(Subscription::new(id, query, rx))
+    }
+
+    async fn unsubscribe(&self, query: Query) -> Result<(), Error> {
+        self.inject_without_payload(self.inner.unsubscribe(query))
+            .await
+    }
+
+    fn close(self) -> Result<(), Error> {
+        self.inner.close()
+    }
+}
+
+/// Forward the events of a subscription, injecting the faults drawn for
+/// them, until the subscription ends or the receiving end is dropped.
+async fn inject_event_faults(
+    mut subscription: Subscription,
+    tx: SubscriptionTx,
+    mut schedule: Schedule,
+    config: FaultConfig,
+) {
+    // An event held back to be delivered after the next one, and the number
+    // of copies to deliver.
+    let mut held: Option<(Result<Event, Error>, usize)> = None;
+    while let Some(result) = subscription.next().await {
+        let faults = EventFaults::draw(&mut schedule, &config);
+        time::sleep(faults.latency).await;
+        if faults.drop {
+            continue;
+        }
+        let result = match result {
+            Ok(event) if faults.corrupt => corrupt_event(&event, &mut schedule),
+            result => result,
+        };
+        let copies = if faults.duplicate { 2 } else { 1 };
+        if faults.reorder && held.is_none() {
+            held = Some((result, copies));
+            continue;
+        }
+        if deliver(&tx, result, copies).is_err() {
+            return;
+        }
+        if let Some((result, copies)) = held.take() {
+            if deliver(&tx, result, copies).is_err() {
+                return;
+            }
+        }
+    }
+    if let Some((result, copies)) = held {
+        let _ = deliver(&tx, result, copies);
+    }
+}
+
+fn deliver(
+    tx: &ChannelTx<Result<Event, Error>>,
+    result: Result<Event, Error>,
+    copies: usize,
+) -> Result<(), Error> {
+    for _ in 1..copies {
+        tx.send(result.clone())?;
+    }
+    tx.send(result)
+}
+
+/// Replace one of the alphanumeric characters of the JSON encoding of the
+/// event, and decode it again.
+fn corrupt_event(event: &Event, schedule: &mut Schedule) -> Result<Event, Error> {
+    let mut json = response_json(event::v0_38::SerEvent::from(event.clone()))?;
+    corrupt_json(&mut json, schedule);
+    event::v0_38::DeEvent::from_string(json).map(Into::into)
+}
+
+/// The JSON encoding of a JSON-RPC response with the given result.
+fn response_json(result: impl Serialize) -> Result<Vec<u8>, Error> {
+    let wrapper = serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": "",
+        "result": result,
+    });
+    serde_json::to_vec(&wrapper).map_err(Error::serde)
+}
+
+/// Replace one of the alphanumeric characters of the given JSON, if any.
+fn corrupt_json(json: &mut [u8], schedule: &mut Schedule) {
+    let positions: Vec<usize> = (0..json.len())
+        .filter(|&i| json[i].is_ascii_alphanumeric())
+        .collect();
+    if !positions.is_empty() {
+        let i = positions[schedule.below(positions.len())];
+        json[i] = match json[i] {
+            b'0'..=b'9' => b'0' + (json[i] - b'0' + 1 + schedule.below(9) as u8) % 10,
+            b'a'..=b'z' => b'a' + (json[i] - b'a' + 1 + schedule.below(25) as u8) % 26,
+            _ => b'A' + (json[i] - b'A' + 1 + schedule.below(25) as u8) % 26,
+        };
+    }
+}
+
+/// A request made with the method and parameters of another request, whose
+/// result is received as raw JSON.
+struct RawRequest {
+    method: Method,
+    params: serde_json::Value,
+}
+
+impl RawRequest {
+    fn new(request: impl RequestMessage) -> Result<Self, Error> {
+        Ok(Self {
+            method: request.method(),
+            params: serde_json::to_value(request).map_err(Error::serde)?,
+        })
+    }
+}
+
+impl Serialize for RawRequest {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.params.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for RawRequest {
+    fn deserialize<D: Deserializer<'de>>(_deserializer: D) -> Result<Self, D::Error> {
+        // The method of a request is not part of its parameters.
+        Err(serde::de::Error::custom("raw requests cannot be decoded"))
+    }
+}
+
+impl RequestMessage for RawRequest {
+    fn method(&self) -> Method {
+        self.method
+    }
+}
+
+impl Request for RawRequest {
+    type Response = RawResult;
+}
+
+impl SimpleRequest for RawRequest {
+    type Output = RawResult;
+}
+
+/// The raw JSON result of a [`RawRequest`].
+#[derive(Deserialize)]
+struct RawResult(serde_json::Value);
+
+impl Response for RawResult {}
+
+/// The faults injected into a request.
+struct RequestFaults {
+    latency: Duration,
+    drop: bool,
+    reorder: bool,
+    // The schedule of the corruption of the response, if it is corrupted.
+    corrupt: Option<Schedule>,
+}
+
+impl RequestFaults {
+    fn draw(schedule: &mut Schedule, config: &FaultConfig) -> Self {
+        // All the faults are always drawn, so that the faults of a request
+        // do not depend on the faults of the previous ones.
+        Self {
+            latency: schedule.duration(config.min_latency, config.max_latency),
+            drop: schedule.chance(config.drop_probability),
+            reorder: schedule.chance(config.reorder_probability),
+            corrupt: {
+                let corrupt = schedule.chance(config.corrupt_probability);
+                let seed = schedule.next_u64();
+                corrupt.then(|| Schedule::new(seed))
+            },
+        }
+    }
+}
+
+/// The faults injected into an event.
+struct EventFaults {
+    latency: Duration,
+    drop: bool,
+    duplicate: bool,
+    reorder: bool,
+    corrupt: bool,
+}
+
+impl EventFaults {
+    fn draw(schedule: &mut Schedule, config: &FaultConfig) -> Self {
+        Self {
+            latency: schedule.duration(config.min_latency, config.max_latency),
+            drop: schedule.chance(config.drop_probability),
+            duplicate: schedule.chance(config.duplicate_probability),
+            reorder: schedule.chance(config.reorder_probability),
+            corrupt: schedule.chance(config.corrupt_probability),
+        }
+    }
+}
+
+/// A deterministic schedule of faults, drawn from a splitmix64 generator.
+#[derive(Clone, Debug)]
+struct Schedule {
+    state: u64,
+}
+
+impl Schedule {
+    fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number uniformly distributed in `[0, 1)`.
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1_u64 << 53) as f64
+    }
+
+    fn chance(&mut self, probability: f64) -> bool {
+        self.next_f64() < probability
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    fn duration(&mut self, min: Duration, max: Duration) -> Duration {
+        let fraction = self.next_f64();
+        if max <= min {
+            min
+        } else {
+            min + (max - min).mul_f64(fraction)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tendermint::abci::{self, EventAttributeIndexExt};
+
+    use super::*;
+    use crate::{
+        client::{MockClient, MockRequestMethodMatcher},
+        error::ErrorKind,
+        event::{EventData, TxInfo, TxResult},
+        query::EventType,
+        Method,
+    };
+
+    fn tx_event(height: i64) -> Event {
+        Event {
+            query: Query::from(EventType::Tx).to_string(),
+            data: EventData::Tx {
+                tx_result: TxInfo {
+                    height,
+                    index: Some(0),
+                    tx: vec![1, 2, 3],
+                    result: TxResult {
+                        log: None,
+                        gas_wanted: None,
+                        gas_used: None,
+                        events: vec![abci::Event::new("transfer", [("amount", "10").index()])],
+                    },
+                },
+            },
+            events: None,
+        }
+    }
+
+    fn mock_client() -> MockClient<MockRequestMethodMatcher> {
+        let matcher = MockRequestMethodMatcher::default().map(
+            Method::AbciInfo,
+            Ok(
+                include_str!("../../../tests/kvstore_fixtures/v0_38/incoming/abci_info.json")
+                    .to_string(),
+            ),
+        );
+        let (client, driver) = MockClient::new(matcher);
+        tokio::spawn(driver.run());
+        client
+    }
+
+    /// The heights of the events received on a subscription to the given
+    /// number of events, or `None` for errors.
+    async fn received(config: FaultConfig, count: i64) -> Vec<Option<i64>> {
+        let client = FaultyClient::new(mock_client(), config);
+        let subscription = subscribe(&client).await;
+        for height in 1..=count {
+            client.inner().publish(&tx_event(height));
+        }
+        client.into_inner().close();
+        subscription
+            .map(|result| {
+                result
+                    .ok()
+                    .map(|event| event.height().unwrap().value() as i64)
+            })
+            .collect()
+            .await
+    }
+
+    async fn subscribe(
+        client: &FaultyClient<MockClient<MockRequestMethodMatcher>>,
+    ) -> Subscription {
+        loop {
+            // Subscription requests may be dropped too.
+            if let Ok(subscription) = client.subscribe(EventType::Tx.into()).await {
+                return subscription;
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn forwards_without_faults() {
+        let client = FaultyClient::new(mock_client(), FaultConfig::default());
+        assert!(client.abci_info().await.is_ok());
+        client.into_inner().close();
+        assert_eq!(
+            received(FaultConfig::default(), 5).await,
+            [1, 2, 3, 4, 5].map(Some)
+        );
+    }
+
+    #[tokio::test]
+    async fn injects_request_faults() {
+        let dropping = FaultyClient::new(
+            mock_client(),
+            FaultConfig {
+                drop_probability: 1.0,
+                drop_timeout: Duration::from_millis(1),
+                ..Default::default()
+            },
+        );
+        let err = dropping.abci_info().await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Timeout);
+        dropping.into_inner().close();
+    }
+
+    #[tokio::test]
+    async fn corrupts_responses() {
+        let client = mock_client();
+        let info = client.abci_info().await.unwrap();
+        let corrupting = FaultyClient::new(
+            client,
+            FaultConfig {
+                corrupt_probability: 1.0,
+                ..Default::default()
+            },
+        );
+        let mut altered = 0;
+        let mut failed = 0;
+        for _ in 0..20 {
+            match corrupting.abci_info().await {
+                Ok(corrupted) if corrupted == info => {},
+                Ok(_) => altered += 1,
+                Err(e) => {
+                    assert_eq!(e.kind(), ErrorKind::Deserialization);
+                    failed += 1;
+                },
+            }
+        }
+        assert!(altered > 0, "no response was altered");
+        assert!(failed > 0, "no response failed to decode");
+        corrupting.into_inner().close();
+    }
+
+    #[tokio::test]
+    async fn injects_event_faults_deterministically() {
+        let config = FaultConfig {
+            seed: 7,
+            drop_probability: 0.2,
+            duplicate_probability: 0.2,
+            reorder_probability: 0.2,
+            drop_timeout: Duration::from_millis(1),
+            reorder_delay: Duration::from_millis(1),
+            ..Default::default()
+        };
+        let events = received(config, 50).await;
+        assert_eq!(events, received(config, 50).await);
+        assert_ne!(
+            events,
+            received(FaultConfig { seed: 8, ..config }, 50).await
+        );
+
+        let mut heights: Vec<_> = events.iter().map(|height| height.unwrap()).collect();
+        assert_ne!(heights, (1..=50).collect::<Vec<_>>());
+        let total = heights.len();
+        heights.sort_unstable();
+        heights.dedup();
+        assert!(heights.len() < 50, "events were dropped");
+        assert!(heights.len() < total, "events were duplicated");
+    }
+
+    #[tokio::test]
+    async fn corrupts_events() {
+        let config = FaultConfig {
+            corrupt_probability: 1.0,
+            ..Default::default()
+        };
+        let client = FaultyClient::new(mock_client(), config);
+        let subscription = subscribe(&client).await;
+        for height in 1..=20 {
+            client.inner().publish(&tx_event(height));
+        }
+        client.into_inner().close();
+        let results: Vec<_> = subscription.collect().await;
+        assert_eq!(results.len(), 20);
+        // Replaced characters may be ignored by the decoding, e.g. in the
+        // name of a field whose value is the default one.
+        let unchanged = (1..)
+            .zip(results)
+            .filter(|(height, result)| result.as_ref().ok() == Some(&tx_event(*height)))
+            .count();
+        assert!(unchanged < 20);
+    }
+}
@@ -1,29 +1,45 @@
 //! HTTP-based transport for Tendermint RPC Client.
 
+use alloc::{collections::BTreeMap, sync::Arc};
 use core::{
     convert::{TryFrom, TryInto},
     str::FromStr,
+    time::Duration,
 };
 
 use async_trait::async_trait;
+use bytes::Bytes;
 use reqwest::{header, Proxy};
+use std::io::{self, Read};
+use tokio::runtime::RuntimeFlavor;
 
 use tendermint::{block::Height, evidence::Evidence, Hash};
 use tendermint_config::net;
 
-use super::auth;
+use super::{
+    auth,
+    dns::{ReqwestResolver, Resolver},
+};
 use crate::prelude::*;
 use crate::{
-    client::{Client, CompatMode},
+    client::{
+        monitor::{HealthMonitor, HealthMonitorConfig},
+        Client, CompatMode,
+    },
     dialect::{v0_34, Dialect, LatestDialect},
     endpoint,
     query::Query,
     request::RequestMessage,
     response::Response,
-    Error, Order, Scheme, SimpleRequest, Url,
+    Error, Method, Order, Scheme, SimpleRequest, Url,
 };
 
-const USER_AGENT: &str = concat!("tendermint.rs/", env!("CARGO_PKG_VERSION"));
+pub(crate) const USER_AGENT: &str = concat!("tendermint.rs/", env!("CARGO_PKG_VERSION"));
+
+/// The default limit on the size of the response bodies, in bytes.
+///
+/// See [`Builder::max_response_size`].
+pub const DEFAULT_MAX_RESPONSE_SIZE: usize = 64 * 1024 * 1024;
 
 /// A JSON-RPC/HTTP Tendermint RPC client (implements [`crate::Client`]).
 ///
@@ -56,6 +72,8 @@ pub struct HttpClient {
     inner: reqwest::Client,
     url: reqwest::Url,
     compat: CompatMode,
+    strict: bool,
+    response_limits: ResponseLimits,
 }
 
 /// The builder pattern constructor for [`HttpClient`].
@@ -63,6 +81,10 @@ pub struct Builder {
     url: HttpClientUrl,
     compat: CompatMode,
     proxy_url: Option<HttpClientUrl>,
+    strict: bool,
+    resolver: Option<Arc<dyn Resolver>>,
+    connect_timeout: Option<Duration>,
+    response_limits: ResponseLimits,
 }
 
 impl Builder {
@@ -85,9 +107,77 @@ impl Builder {
         self
     }
 
+    /// Validate the format of the responses strictly, failing requests whose
+    /// responses contain fields unknown to this crate, and logging a warning
+    /// for the fields which were missing and left to their default value.
+    ///
+    /// This helps diagnose mismatches between the protocol version of the
+    /// node and the compatibility mode of the client. It is disabled by
+    /// default, to stay compatible with newer versions of the node.
+    pub fn strict_validation(mut self, enabled: bool) -> Self {
+        self.strict = enabled;
+        self
+    }
+
+    /// Resolve the host name of the RPC endpoint with the given resolver.
+    ///
+    /// The default is the [`SystemResolver`](super::dns::SystemResolver).
+    /// When the host resolves to addresses of both families, connections to
+    /// addresses of the other family are attempted if those to the addresses
+    /// of the family of the first resolved address do not complete quickly.
+    pub fn resolver(mut self, resolver: impl Resolver + 'static) -> Self {
+        self.resolver = Some(Arc::new(resolver));
+        self
+    }
+
+    /// Abandon the establishment of connections after the given time.
+    ///
+    /// When the host resolves to several addresses, the timeout is split
+    /// between the connection attempts to each of them, so that an
+    /// unreachable address does not use it up entirely. There is no timeout
+    /// by default.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Fail the requests whose response body is larger than the given number
+    /// of bytes, unless another limit is set for the method of the request
+    /// with [`Builder::method_max_response_size`].
+    ///
+    /// The default is [`DEFAULT_MAX_RESPONSE_SIZE`], which protects the
+    /// client from running out of memory on the responses of a misbehaving
+    /// node, but may be too small for the `/genesis` of some chains.
+    pub fn max_response_size(mut self, limit: usize) -> Self {
+        self.response_limits.default = limit;
+        self
+    }
+
+    /// Fail the requests of the given method whose response body is larger
+    /// than the given number of bytes.
+    ///
+    /// On a multi-threaded runtime, the responses of `/genesis` and
+    /// `/block_results` are deserialized as they are received, so that a
+    /// large limit for these methods does not require a copy of the whole
+    /// body. The deserialization runs on the worker thread of the calling
+    /// task with [`tokio::task::block_in_place`]: the task, and any other
+    /// future it is joined or selected with, is stalled until the whole
+    /// body is received, while the other tasks of the worker are moved to
+    /// another thread.
+    pub fn method_max_response_size(mut self, method: Method, limit: usize) -> Self {
+        self.response_limits.per_method.insert(method, limit);
+        self
+    }
+
     /// Try to create a client with the options specified for this builder.
     pub fn build(self) -> Result<HttpClient, Error> {
-        let builder = reqwest::ClientBuilder::new().user_agent(USER_AGENT);
+        let mut builder = reqwest::ClientBuilder::new().user_agent(USER_AGENT);
+        if let Some(resolver) = self.resolver {
+            builder = builder.dns_resolver(Arc::new(ReqwestResolver(resolver)));
+        }
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
         let inner = match self.proxy_url {
             None => builder.build().map_err(Error::http)?,
             Some(proxy_url) => {
@@ -103,6 +193,8 @@ impl Builder {
             inner,
             url: self.url.into(),
             compat: self.compat,
+            strict: self.strict,
+            response_limits: self.response_limits,
         })
     }
 }
@@ -142,6 +234,10 @@ impl HttpClient {
             url,
             compat: Default::default(),
             proxy_url: None,
+            strict: false,
+            resolver: None,
+            connect_timeout: None,
+            response_limits: ResponseLimits::default(),
         }
     }
 
@@ -154,6 +250,12 @@ impl HttpClient {
         self.compat = compat;
     }
 
+    /// Start monitoring the health of the node this client connects to
+    /// in a background task. See [`HealthMonitor`] for details.
+    pub fn health_monitor(&self, config: HealthMonitorConfig) -> HealthMonitor {
+        HealthMonitor::spawn(self.clone(), config)
+    }
+
     fn build_request<R>(&self, request: R) -> Result<reqwest::Request, Error>
     where
         R: RequestMessage,
@@ -180,11 +282,27 @@ impl HttpClient {
         R: SimpleRequest<S>,
         S: Dialect,
     {
+        let method = request.method();
         let request = self.build_request(request)?;
         let response = self.inner.execute(request).await.map_err(Error::http)?;
         let response_status = response.status();
-        let response_body = response.bytes().await.map_err(Error::http)?;
 
+        let limit = self.response_limits.get(method);
+        // The potentially large responses of these methods are deserialized
+        // as they are received, rather than from a copy of the whole body.
+        if !self.strict
+            && matches!(method, Method::Genesis | Method::BlockResults)
+            && tokio::runtime::Handle::current().runtime_flavor() == RuntimeFlavor::MultiThread
+        {
+            tracing::debug!(status = %response_status, "incoming streamed response");
+            if response_status != reqwest::StatusCode::OK {
+                return Err(Error::http_request_failed(response_status));
+            }
+            return receive_streamed::<R::Response>(response, method, limit).map(Into::into);
+        }
+
+        let response_body = Body::receive(response, method, limit).await?;
+        let response_body = response_body.into_vec();
         tracing::debug!(
             status = %response_status,
             body = %String::from_utf8_lossy(&response_body),
@@ -199,7 +317,20 @@ impl HttpClient {
             return Err(Error::http_request_failed(response_status));
         }
 
-        R::Response::from_string(&response_body).map(Into::into)
+        if !self.strict {
+            return R::Response::from_string(&response_body).map(Into::into);
+        }
+        let (response, report) = R::Response::from_string_strict(&response_body)?;
+        if !report.unknown.is_empty() {
+            return Err(Error::strict_validation(report));
+        }
+        if !report.defaulted.is_empty() {
+            tracing::warn!(
+                defaulted = ?report.defaulted,
+                "response fields missing and left to their default value"
+            );
+        }
+        Ok(response.into())
     }
 }
 
@@ -304,6 +435,144 @@ impl Client for HttpClient {
     }
 }
 
+/// The limits on the size of the response bodies.
+#[derive(Debug, Clone)]
+struct ResponseLimits {
+    default: usize,
+    per_method: BTreeMap<Method, usize>,
+}
+
+impl Default for ResponseLimits {
+    fn default() -> Self {
+        Self {
+            default: DEFAULT_MAX_RESPONSE_SIZE,
+            per_method: BTreeMap::new(),
+        }
+    }
+}
+
+impl ResponseLimits {
+    fn get(&self, method: Method) -> usize {
+        self.per_method
+            .get(&method)
+            .copied()
+            .unwrap_or(self.default)
+    }
+}
+
+/// A response body, as the chunks it was received in.
+struct Body {
+    chunks: Vec<Bytes>,
+}
+
+impl Body {
+    /// Receive the body of the given response, failing as soon as it
+    /// exceeds the given size.
+    async fn receive(
+        mut response: reqwest::Response,
+        method: Method,
+        limit: usize,
+    ) -> Result<Self, Error> {
+        if let Some(len) = response.content_length() {
+            if len > limit as u64 {
+                return Err(Error::response_too_large(method, limit));
+            }
+        }
+        let mut chunks = Vec::new();
+        let mut size = 0;
+        while let Some(chunk) = response.chunk().await.map_err(Error::http)? {
+            size += chunk.len();
+            if size > limit {
+                return Err(Error::response_too_large(method, limit));
+            }
+            chunks.push(chunk);
+        }
+        Ok(Self { chunks })
+    }
+
+    fn into_vec(mut self) -> Vec<u8> {
+        match self.chunks.len() {
+            1 => self.chunks.pop().unwrap().into(),
+            _ => self.chunks.concat(),
+        }
+    }
+}
+
+/// The number of chunks of a streamed response body received ahead of its
+/// deserialization.
+const STREAMED_CHUNKS: usize = 16;
+
+/// Deserialize the body of the given response as it is received, failing
+/// as soon as it exceeds the given size.
+///
+/// The chunks of the body are received by a separate task, while they are
+/// deserialized on the current thread with [`tokio::task::block_in_place`],
+/// which requires a multi-threaded runtime and stalls the calling task (see
+/// [`Builder::method_max_response_size`]). The deserialization cannot be
+/// moved to [`tokio::task::spawn_blocking`], as the response types are not
+/// required to be `Send + 'static`.
+fn receive_streamed<T: Response>(
+    mut response: reqwest::Response,
+    method: Method,
+    limit: usize,
+) -> Result<T, Error> {
+    if let Some(len) = response.content_length() {
+        if len > limit as u64 {
+            return Err(Error::response_too_large(method, limit));
+        }
+    }
+    let (chunk_tx, chunk_rx) = tokio::sync::mpsc::channel(STREAMED_CHUNKS);
+    let receiver = tokio::spawn(async move {
+        let mut size = 0;
+        while let Some(chunk) = response.chunk().await.map_err(Error::http)? {
+            size += chunk.len();
+            if size > limit {
+                return Err(Error::response_too_large(method, limit));
+            }
+            if chunk_tx.send(chunk).await.is_err() {
+                // The deserialization stopped early.
+                break;
+            }
+        }
+        Ok(size)
+    });
+    tokio::task::block_in_place(|| {
+        let reader = ChunkReader {
+            chunks: chunk_rx,
+            chunk: Bytes::new(),
+        };
+        let result = T::from_reader(io::BufReader::new(reader));
+        let size = tokio::runtime::Handle::current()
+            .block_on(receiver)
+            .map_err(Error::join)?;
+        // A failure to receive the body takes precedence over the failure
+        // to deserialize the part which was received.
+        tracing::debug!(size = size.as_ref().ok(), "received streamed response");
+        size?;
+        result
+    })
+}
+
+/// Reads the chunks of a streamed response body as they are received.
+struct ChunkReader {
+    chunks: tokio::sync::mpsc::Receiver<Bytes>,
+    chunk: Bytes,
+}
+
+impl Read for ChunkReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.chunk.is_empty() {
+            match self.chunks.blocking_recv() {
+                Some(chunk) => self.chunk = chunk,
+                None => return Ok(0),
+            }
+        }
+        let n = self.chunk.len().min(buf.len());
+        buf[..n].copy_from_slice(&self.chunk.split_to(n));
+        Ok(n)
+    }
+}
+
 /// A URL limited to use with HTTP clients.
 ///
 /// Facilitates useful type conversions and inferences.
@@ -369,11 +638,22 @@ impl From<HttpClientUrl> for url::Url {
 mod tests {
     use core::str::FromStr;
 
+    use std::{
+        fs,
+        io::{self, Read, Write},
+        net::{IpAddr, Ipv4Addr, TcpListener},
+        thread,
+    };
+
+    use async_trait::async_trait;
     use reqwest::{header::AUTHORIZATION, Request};
 
     use super::HttpClient;
-    use crate::endpoint::abci_info;
-    use crate::Url;
+    use crate::client::{dns::Resolver, Client};
+    use crate::endpoint::{abci_info, block_results};
+    use crate::error::ErrorDetail;
+    use crate::prelude::*;
+    use crate::{Method, Response, Url};
 
     fn authorization(req: &Request) -> Option<&str> {
         req.headers()
@@ -398,4 +678,150 @@ mod tests {
 
         assert_eq!(authorization(&req), Some("Basic dG90bzp0YXRh"));
     }
+
+    struct LocalhostResolver;
+
+    #[async_trait]
+    impl Resolver for LocalhostResolver {
+        async fn resolve(&self, host: &str) -> io::Result<Vec<IpAddr>> {
+            assert_eq!(host, "node.example");
+            Ok(vec![IpAddr::V4(Ipv4Addr::LOCALHOST)])
+        }
+    }
+
+    #[tokio::test]
+    async fn with_custom_resolver() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0; 4096];
+            let _ = stream.read(&mut request).unwrap();
+            let body = r#"{"jsonrpc":"2.0","id":"","result":{}}"#;
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            )
+            .unwrap();
+        });
+
+        let url = Url::from_str(&format!("http://node.example:{port}")).unwrap();
+        let client = HttpClient::builder(url.try_into().unwrap())
+            .resolver(LocalhostResolver)
+            .connect_timeout(core::time::Duration::from_secs(5))
+            .build()
+            .unwrap();
+        client.health().await.unwrap();
+        server.join().unwrap();
+    }
+
+    /// Serve a single request with the given response body, written in
+    /// small chunks and without a `Content-Length` unless `sized` is set.
+    fn serve_once(body: String, sized: bool) -> (u16, thread::JoinHandle<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0; 4096];
+            let _ = stream.read(&mut request).unwrap();
+            let length = if sized {
+                format!("Content-Length: {}\r\n", body.len())
+            } else {
+                String::new()
+            };
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n{length}Connection: close\r\n\r\n"
+            )
+            .unwrap();
+            // The client may give up before the whole body is written.
+            for chunk in body.as_bytes().chunks(256) {
+                if stream
+                    .write_all(chunk)
+                    .and_then(|_| stream.flush())
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+        (port, server)
+    }
+
+    #[tokio::test]
+    async fn rejects_responses_too_large() {
+        let body = format!(
+            r#"{{"jsonrpc":"2.0","id":"","result":{{"response":{{"data":"{}"}}}}}}"#,
+            "a".repeat(4096)
+        );
+        for sized in [true, false] {
+            let (port, server) = serve_once(body.clone(), sized);
+            let url = Url::from_str(&format!("http://127.0.0.1:{port}")).unwrap();
+            let client = HttpClient::builder(url.try_into().unwrap())
+                .max_response_size(1024)
+                .build()
+                .unwrap();
+            let err = client.abci_info().await.unwrap_err();
+            match err.detail() {
+                ErrorDetail::ResponseTooLarge(e) => {
+                    assert_eq!(e.method, Method::AbciInfo);
+                    assert_eq!(e.limit, 1024);
+                },
+                _ => panic!("unexpected error: {err}"),
+            }
+            server.join().unwrap();
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn streams_block_results() {
+        let body = fs::read_to_string(
+            "tests/kvstore_fixtures/v0_38/incoming/block_results_at_height_10.json",
+        )
+        .unwrap();
+        let (port, server) = serve_once(body.clone(), false);
+        let url = Url::from_str(&format!("http://127.0.0.1:{port}")).unwrap();
+        // The limit set for the method overrides the default one.
+        let client = HttpClient::builder(url.try_into().unwrap())
+            .max_response_size(16)
+            .method_max_response_size(Method::BlockResults, body.len())
+            .build()
+            .unwrap();
+        let response = client.block_results(10_u32).await.unwrap();
+        server.join().unwrap();
+        let expected = block_results::Response::from_string(&body).unwrap();
+        assert_eq!(response.height, expected.height);
+        assert_eq!(response.txs_results, expected.txs_results);
+        assert_eq!(
+            response.finalize_block_events,
+            expected.finalize_block_events
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn rejects_streamed_responses_too_large() {
+        let body = fs::read_to_string(
+            "tests/kvstore_fixtures/v0_38/incoming/block_results_at_height_10.json",
+        )
+        .unwrap();
+        let limit = body.len() / 2;
+        for sized in [true, false] {
+            let (port, server) = serve_once(body.clone(), sized);
+            let url = Url::from_str(&format!("http://127.0.0.1:{port}")).unwrap();
+            let client = HttpClient::builder(url.try_into().unwrap())
+                .method_max_response_size(Method::BlockResults, limit)
+                .build()
+                .unwrap();
+            let err = client.block_results(10_u32).await.unwrap_err();
+            match err.detail() {
+                ErrorDetail::ResponseTooLarge(e) => {
+                    assert_eq!(e.method, Method::BlockResults);
+                    assert_eq!(e.limit, limit);
+                },
+                _ => panic!("unexpected error: {err}"),
+            }
+            server.join().unwrap();
+        }
+    }
 }
@@ -396,5 +396,64 @@ mod test {
             client.close();
             driver_hdl.await.unwrap().unwrap();
         }
+
+        #[cfg(feature = "rust-crypto")]
+        #[tokio::test]
+        async fn validator_set_is_cached() {
+            const VALIDATORS: &str = r#"{
+                "jsonrpc": "2.0",
+                "id": "",
+                "result": {
+                    "block_height": "10",
+                    "validators": [
+                        {
+                            "address": "01F527D77D3FFCC4FCFF2DDC2952EEA5414F2A33",
+                            "pub_key": {
+                                "type": "tendermint/PubKeyEd25519",
+                                "value": "OAaNq3DX/15fGJP2MI6bujt1GRpvjwrqIevChirJsbc="
+                            },
+                            "voting_power": "10",
+                            "proposer_priority": "0"
+                        }
+                    ],
+                    "count": "1",
+                    "total": "1"
+                }
+            }"#;
+
+            let cache = crate::ValidatorSetCache::default();
+            let chain_id: Id = "dockerchain".parse().unwrap();
+            let height = Height::from(10_u32);
+
+            let matcher = MockRequestMethodMatcher::default()
+                .map(Method::Validators, Ok(VALIDATORS.to_string()));
+            let (client, driver) = MockClient::new(matcher);
+            let driver_hdl = tokio::spawn(async move { driver.run().await });
+            let validators = client
+                .validator_set(&chain_id, height, tendermint::Hash::None, &cache)
+                .await
+                .unwrap();
+            assert_eq!(validators.validators().len(), 1);
+            client.close();
+            driver_hdl.await.unwrap().unwrap();
+
+            // Served from the cache at any height, without any request to the
+            // node
+            let (client, driver) = MockClient::new(MockRequestMethodMatcher::default());
+            let driver_hdl = tokio::spawn(async move { driver.run().await });
+            for height in [height, height.increment()] {
+                let cached = client
+                    .validator_set(&chain_id, height, validators.hash(), &cache)
+                    .await
+                    .unwrap();
+                assert_eq!(cached, validators);
+            }
+            assert!(client
+                .validator_set(&chain_id, height, tendermint::Hash::None, &cache)
+                .await
+                .is_err());
+            client.close();
+            driver_hdl.await.unwrap().unwrap();
+        }
     }
 }
@@ -0,0 +1,584 @@
+//! Emulation of event subscriptions over plain request/response transports,
+//! for nodes which only expose their RPC endpoints over HTTP.
+
+use alloc::{collections::BTreeMap as HashMap, sync::Arc};
+use core::time::Duration;
+
+use async_trait::async_trait;
+use tendermint::{abci, block::Height};
+use tokio::time::{self, MissedTickBehavior};
+use tracing::{debug, warn};
+
+use crate::{
+    client::{
+        subscription::SubscriptionTx,
+        sync::{unbounded, ChannelRx, ChannelTx},
+        transport::router::{PublishResult, SubscriptionRouter},
+        Client,
+    },
+    endpoint::{block, block_results, tx},
+    event::{Event, EventData, TxInfo, TxResult},
+    prelude::*,
+    query::{EventType, Query},
+    utils::uuid_str,
+    Error, Order, Subscription, SubscriptionClient,
+};
+
+/// The number of transactions requested per page from the `/tx_search` endpoint.
+const TX_SEARCH_PER_PAGE: u8 = 100;
+
+/// A [`SubscriptionClient`] emulating event subscriptions by polling the
+/// `/block`, `/block_results` and `/tx_search` endpoints of a node through
+/// the wrapped client.
+///
+/// This allows code written against [`Subscription`] streams to keep working
+/// when the node does not expose its websocket endpoint. Newly committed blocks
+/// are fetched at every polling interval, and the events they produced are
+/// matched locally against the subscription queries.
+///
+/// Like the [`WebSocketClient`], it is driven by a [`PollingClientDriver`],
+/// which must be run in a separate task.
+///
+/// ## Examples
+///
+/// ```rust,ignore
+/// use futures::StreamExt;
+/// use tendermint_rpc::{
+///     client::PollingClient, query::EventType, HttpClient, SubscriptionClient,
+/// };
+///
+/// let client = HttpClient::new("http://127.0.0.1:26657").unwrap();
+/// let (client, driver) = PollingClient::new(client, std::time::Duration::from_secs(1));
+/// let driver_handle = tokio::spawn(async move { driver.run().await });
+///
+/// let mut subs = client.subscribe(EventType::NewBlock.into()).await.unwrap();
+/// while let Some(event) = subs.next().await {
+///     println!("Got event: {:?}", event);
+/// }
+/// ```
+///
+/// [`WebSocketClient`]: crate::WebSocketClient
+#[derive(Debug)]
+pub struct PollingClient<C> {
+    client: Arc<C>,
+    driver_tx: ChannelTx<DriverCommand>,
+}
+
+impl<C> PollingClient<C>
+where
+    C: Client + Send + Sync,
+{
+    /// Wrap the given client, polling its node for new blocks at the given interval.
+    pub fn new(client: C, poll_interval: Duration) -> (Self, PollingClientDriver<C>) {
+        let client = Arc::new(client);
+        let (driver_tx, driver_rx) = unbounded();
+        let driver = PollingClientDriver::new(client.clone(), poll_interval, driver_rx);
+        (Self { client, driver_tx }, driver)
+    }
+
+    /// The wrapped client, which can be used to perform other requests.
+    pub fn inner(&self) -> &C {
+        &self.client
+    }
+}
+
+#[async_trait]
+impl<C> SubscriptionClient for PollingClient<C>
+where
+    C: Client + Send + Sync,
+{
+    async fn subscribe(&self, query: Query) -> Result<Subscription, Error> {
+        let id = uuid_str();
+        let (subscription_tx, subscription_rx) = unbounded();
+        let (result_tx, mut result_rx) = unbounded();
+        self.driver_tx.send(DriverCommand::Subscribe {
+            id: id.clone(),
+            query: query.clone(),
+            subscription_tx,
+            result_tx,
+        })?;
+        result_rx.recv().await.ok_or_else(|| {
+            Error::client_internal(
+                "failed to hear back from polling driver for subscribe request".to_string(),
+            )
+        })??;
+        Ok(Subscription::new(id, query, subscription_rx))
+    }
+
+    async fn unsubscribe(&self, query: Query) -> Result<(), Error> {
+        let (result_tx, mut result_rx) = unbounded();
+        self.driver_tx
+            .send(DriverCommand::Unsubscribe { query, result_tx })?;
+        result_rx.recv().await.ok_or_else(|| {
+            Error::client_internal(
+                "failed to hear back from polling driver for unsubscribe request".to_string(),
+            )
+        })?
+    }
+
+    fn close(self) -> Result<(), Error> {
+        self.driver_tx.send(DriverCommand::Terminate)
+    }
+}
+
+#[derive(Debug)]
+enum DriverCommand {
+    Subscribe {
+        id: String,
+        query: Query,
+        subscription_tx: SubscriptionTx,
+        result_tx: ChannelTx<Result<(), Error>>,
+    },
+    Unsubscribe {
+        query: Query,
+        result_tx: ChannelTx<Result<(), Error>>,
+    },
+    Terminate,
+}
+
+/// Drives the polling of the node on behalf of a [`PollingClient`].
+#[derive(Debug)]
+pub struct PollingClientDriver<C> {
+    client: Arc<C>,
+    poll_interval: Duration,
+    rx: ChannelRx<DriverCommand>,
+    router: SubscriptionRouter,
+    // The subscribed queries, keyed by their string representation.
+    queries: HashMap<String, Query>,
+    // The height of the next block to produce events for.
+    next_height: Option<Height>,
+    // Whether the block events of the next height were already produced, so
+    // that only its transaction events are retried after a failed poll.
+    block_events_published: bool,
+}
+
+impl<C> PollingClientDriver<C>
+where
+    C: Client + Send + Sync,
+{
+    fn new(client: Arc<C>, poll_interval: Duration, rx: ChannelRx<DriverCommand>) -> Self {
+        Self {
+            client,
+            poll_interval,
+            rx,
+            router: SubscriptionRouter::default(),
+            queries: HashMap::new(),
+            next_height: None,
+            block_events_published: false,
+        }
+    }
+
+    /// Executes the polling loop until the client is closed, or all of its
+    /// handles have been dropped.
+    pub async fn run(mut self) -> Result<(), Error> {
+        let mut interval = time::interval(self.poll_interval);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                cmd = self.rx.recv() => match cmd {
+                    Some(DriverCommand::Subscribe { id, query, subscription_tx, result_tx }) => {
+                        let result = self.subscribe(id, query, subscription_tx).await;
+                        let _ = result_tx.send(result);
+                    },
+                    Some(DriverCommand::Unsubscribe { query, result_tx }) => {
+                        self.unsubscribe(&query);
+                        let _ = result_tx.send(Ok(()));
+                    },
+                    Some(DriverCommand::Terminate) | None => return Ok(()),
+                },
+                _ = interval.tick() => {
+                    if !self.queries.is_empty() {
+                        if let Err(e) = self.poll().await {
+                            warn!("Failed to poll for new blocks, retrying at next interval: {}", e);
+                        }
+                    }
+                },
+            }
+        }
+    }
+
+    async fn subscribe(
+        &mut self,
+        id: String,
+        query: Query,
+        subscription_tx: SubscriptionTx,
+    ) -> Result<(), Error> {
+        // Only produce events for blocks committed after the first subscription.
+        if self.next_height.is_none() {
+            let latest = self.client.latest_block().await?.block.header.height;
+            self.next_height = Some(latest.increment());
+        }
+        let key = query.to_string();
+        self.router.add(id, &key, subscription_tx);
+        self.queries.insert(key, query);
+        Ok(())
+    }
+
+    fn unsubscribe(&mut self, query: &Query) {
+        let key = query.to_string();
+        self.router.remove_by_query(&key);
+        self.queries.remove(&key);
+        if self.queries.is_empty() {
+            self.next_height = None;
+            self.block_events_published = false;
+        }
+    }
+
+    /// Produces the events of all the blocks committed since the last poll.
+    async fn poll(&mut self) -> Result<(), Error> {
+        let latest = self.client.latest_block().await?;
+        let latest_height = latest.block.header.height;
+        let mut height = match self.next_height {
+            Some(height) => height,
+            None => return Ok(()),
+        };
+        while height <= latest_height && !self.queries.is_empty() {
+            if !self.block_events_published {
+                let block = if height == latest_height {
+                    latest.clone()
+                } else {
+                    self.client.block(height).await?
+                };
+                self.publish_block_events(block).await?;
+                self.block_events_published = true;
+            }
+            self.publish_tx_events(height).await?;
+
+            height = height.increment();
+            self.next_height = Some(height);
+            self.block_events_published = false;
+        }
+        Ok(())
+    }
+
+    async fn publish_block_events(&mut self, block: block::Response) -> Result<(), Error> {
+        let queries = self.queries_for(EventType::NewBlock);
+        if queries.is_empty() {
+            return Ok(());
+        }
+        let results = self.client.block_results(block.block.header.height).await?;
+        let data = new_block_event_data(block, results);
+        let attributes = data.attributes();
+        for query in queries {
+            if query.matches(&attributes) {
+                self.publish(Event {
+                    query: query.to_string(),
+                    data: data.clone(),
+                    events: Some(attributes.clone()),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    async fn publish_tx_events(&mut self, height: Height) -> Result<(), Error> {
+        // The searches of all the queries must succeed before any event is
+        // published, as the events are searched for again at the next poll.
+        let mut events = Vec::new();
+        for query in self.queries_for(EventType::Tx) {
+            let search_query = Query {
+                event_type: None,
+                conditions: query.conditions.clone(),
+            }
+            .and_eq("tx.height", height.value());
+
+            let mut page = 1;
+            let mut txs = Vec::new();
+            loop {
+                let response = self
+                    .client
+                    .tx_search(
+                        search_query.clone(),
+                        false,
+                        page,
+                        TX_SEARCH_PER_PAGE,
+                        Order::Ascending,
+                    )
+                    .await?;
+                let received = response.txs.len();
+                txs.extend(response.txs);
+                if received == 0 || txs.len() >= response.total_count as usize {
+                    break;
+                }
+                page += 1;
+            }
+
+            for tx in txs {
+                let (data, attributes) = tx_event_data(tx);
+                if query.matches(&attributes) {
+                    events.push(Event {
+                        query: query.to_string(),
+                        data,
+                        events: Some(attributes),
+                    });
+                }
+            }
+        }
+        for event in events {
+            self.publish(event);
+        }
+        Ok(())
+    }
+
+    /// The subscribed queries which can match events of the given type.
+    fn queries_for(&self, event_type: EventType) -> Vec<Query> {
+        let event_type = Some(event_type);
+        self.queries
+            .values()
+            .filter(|query| query.event_type.is_none() || query.event_type == event_type)
+            .cloned()
+            .collect()
+    }
+
+    fn publish(&mut self, event: Event) {
+        if let PublishResult::AllDisconnected(query) = self.router.publish_event(event) {
+            debug!("All subscribers for query \"{}\" have disconnected", query);
+            self.queries.remove(&query);
+            if self.queries.is_empty() {
+                self.next_height = None;
+                self.block_events_published = false;
+            }
+        }
+    }
+}
+
+/// Assembles the data of a new block event, in the format of the node's
+/// version of the protocol.
+fn new_block_event_data(block: block::Response, results: block_results::Response) -> EventData {
+    let block_results::Response {
+        txs_results,
+        finalize_block_events,
+        begin_block_events,
+        end_block_events,
+        validator_updates,
+        consensus_param_updates,
+        app_hash,
+        ..
+    } = results;
+
+    if begin_block_events.is_some() || end_block_events.is_some() {
+        EventData::LegacyNewBlock {
+            block: Some(Box::new(block.block)),
+            result_begin_block: Some(abci::response::BeginBlock {
+                events: begin_block_events.unwrap_or_default(),
+            }),
+            result_end_block: Some(abci::response::EndBlock {
+                validator_updates,
+                consensus_param_updates,
+                events: end_block_events.unwrap_or_default(),
+            }),
+        }
+    } else {
+        EventData::NewBlock {
+            block: Some(Box::new(block.block)),
+            block_id: block.block_id,
+            result_finalize_block: Some(abci::response::FinalizeBlock {
+                events: finalize_block_events,
+                tx_results: txs_results.unwrap_or_default(),
+                validator_updates,
+                consensus_param_updates,
+                app_hash,
+            }),
+        }
+    }
+}
+
+/// Assembles the data and the attributes of a transaction event.
+fn tx_event_data(tx: tx::Response) -> (EventData, HashMap<String, Vec<String>>) {
+    let result = tx.tx_result;
+    let data = EventData::Tx {
+        tx_result: TxInfo {
+            height: tx.height.value() as i64,
+            index: Some(tx.index as i64),
+            tx: tx.tx,
+            result: TxResult {
+                log: Some(result.log),
+                gas_wanted: Some(result.gas_wanted.to_string()),
+                gas_used: Some(result.gas_used.to_string()),
+                events: result.events,
+            },
+        },
+    };
+    let mut attributes = data.attributes();
+    attributes.insert("tx.hash".to_owned(), vec![tx.hash.to_string()]);
+    (data, attributes)
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use futures::StreamExt;
+    use tokio::fs;
+
+    use super::*;
+    use crate::{
+        client::{MockClient, MockRequestMatcher, MockRequestMethodMatcher},
+        dialect::Dialect,
+        request::Request,
+        Method,
+    };
+
+    async fn read_json_fixture(name: &str) -> String {
+        fs::read_to_string(
+            PathBuf::from("./tests/kvstore_fixtures/v0_37/incoming")
+                .join(name.to_owned() + ".json"),
+        )
+        .await
+        .unwrap()
+    }
+
+    async fn polling_driver() -> PollingClientDriver<MockClient<MockRequestMethodMatcher>> {
+        polling_driver_with_tx_search(Ok(read_json_fixture("tx_search_no_prove").await)).await
+    }
+
+    async fn polling_driver_with_tx_search(
+        tx_search: Result<String, Error>,
+    ) -> PollingClientDriver<MockClient<MockRequestMethodMatcher>> {
+        let matcher = MockRequestMethodMatcher::default()
+            .map(
+                Method::Block,
+                Ok(read_json_fixture("block_at_height_10").await),
+            )
+            .map(
+                Method::BlockResults,
+                Ok(read_json_fixture("block_results_at_height_10").await),
+            )
+            .map(Method::TxSearch, tx_search);
+        let (client, _) = MockClient::new(matcher);
+        let (_, driver) = PollingClient::new(client, Duration::from_secs(1));
+        driver
+    }
+
+    #[tokio::test]
+    async fn publishes_events_of_new_blocks() {
+        let mut driver = polling_driver().await;
+        let (block_tx, block_rx) = unbounded();
+        let (tx_tx, tx_rx) = unbounded();
+        driver
+            .subscribe("block".to_owned(), EventType::NewBlock.into(), block_tx)
+            .await
+            .unwrap();
+        driver
+            .subscribe(
+                "tx".to_owned(),
+                Query::from(EventType::Tx).and_exists("app.key"),
+                tx_tx,
+            )
+            .await
+            .unwrap();
+        // No new blocks were committed since the subscriptions were made.
+        assert_eq!(driver.next_height, Some(Height::from(11_u32)));
+        driver.poll().await.unwrap();
+
+        driver.next_height = Some(Height::from(10_u32));
+        driver.poll().await.unwrap();
+        assert_eq!(driver.next_height, Some(Height::from(11_u32)));
+        driver.unsubscribe(&EventType::NewBlock.into());
+        driver.unsubscribe(&Query::from(EventType::Tx).and_exists("app.key"));
+        assert_eq!(driver.next_height, None);
+
+        let mut blocks =
+            Subscription::new("block".to_owned(), EventType::NewBlock.into(), block_rx);
+        let event = blocks.next().await.unwrap().unwrap();
+        assert_eq!(event.query, "tm.event = 'NewBlock'");
+        match event.data {
+            EventData::NewBlock { block, .. } => {
+                assert_eq!(block.unwrap().header.height.value(), 10);
+            },
+            data => panic!("unexpected event data: {data:?}"),
+        }
+        assert!(blocks.next().await.is_none());
+
+        let txs = Subscription::new("tx".to_owned(), EventType::Tx.into(), tx_rx);
+        let events: Vec<_> = txs.collect().await;
+        assert_eq!(events.len(), 9);
+        for event in events {
+            let event = event.unwrap();
+            assert!(event.events.unwrap().contains_key("tx.hash"));
+            assert!(matches!(event.data, EventData::Tx { .. }));
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_only_tx_events_after_a_failed_poll() {
+        let mut driver =
+            polling_driver_with_tx_search(Err(Error::client_internal("unavailable".to_owned())))
+                .await;
+        let (block_tx, block_rx) = unbounded();
+        let (tx_tx, _tx_rx) = unbounded();
+        driver
+            .subscribe("block".to_owned(), EventType::NewBlock.into(), block_tx)
+            .await
+            .unwrap();
+        driver
+            .subscribe("tx".to_owned(), EventType::Tx.into(), tx_tx)
+            .await
+            .unwrap();
+
+        driver.next_height = Some(Height::from(10_u32));
+        assert!(driver.poll().await.is_err());
+        assert!(driver.poll().await.is_err());
+        assert_eq!(driver.next_height, Some(Height::from(10_u32)));
+        driver.unsubscribe(&EventType::NewBlock.into());
+        driver.unsubscribe(&EventType::Tx.into());
+
+        let blocks = Subscription::new("block".to_owned(), EventType::NewBlock.into(), block_rx);
+        assert_eq!(blocks.collect::<Vec<_>>().await.len(), 1);
+    }
+
+    /// Fails the transaction searches whose query mentions `unavailable`.
+    struct PartiallyFailingMatcher(MockRequestMethodMatcher);
+
+    impl MockRequestMatcher for PartiallyFailingMatcher {
+        fn response_for<R, S>(&self, request: R) -> Option<Result<R::Response, Error>>
+        where
+            R: Request<S>,
+            S: Dialect,
+        {
+            let json = serde_json::to_string(&request).unwrap();
+            if request.method() == Method::TxSearch && json.contains("unavailable") {
+                return Some(Err(Error::client_internal("unavailable".to_owned())));
+            }
+            self.0.response_for(request)
+        }
+    }
+
+    #[tokio::test]
+    async fn publishes_no_tx_events_before_all_searches_succeed() {
+        let matcher = MockRequestMethodMatcher::default()
+            .map(
+                Method::Block,
+                Ok(read_json_fixture("block_at_height_10").await),
+            )
+            .map(
+                Method::TxSearch,
+                Ok(read_json_fixture("tx_search_no_prove").await),
+            );
+        let (client, _) = MockClient::new(PartiallyFailingMatcher(matcher));
+        let (_, mut driver) = PollingClient::new(client, Duration::from_secs(1));
+        let (tx_tx, tx_rx) = unbounded();
+        let (failing_tx, _failing_rx) = unbounded();
+        let query = Query::from(EventType::Tx).and_exists("app.key");
+        let failing = Query::from(EventType::Tx).and_exists("unavailable");
+        driver
+            .subscribe("tx".to_owned(), query.clone(), tx_tx)
+            .await
+            .unwrap();
+        driver
+            .subscribe("failing".to_owned(), failing.clone(), failing_tx)
+            .await
+            .unwrap();
+
+        // The search of the first query succeeds at each poll, but its
+        // events are not published as the search of the second one fails.
+        driver.next_height = Some(Height::from(10_u32));
+        assert!(driver.poll().await.is_err());
+        assert!(driver.poll().await.is_err());
+        driver.unsubscribe(&query);
+        driver.unsubscribe(&failing);
+
+        let txs = Subscription::new("tx".to_owned(), query, tx_rx);
+        assert_eq!(txs.collect::<Vec<_>>().await.len(), 0);
+    }
+}
@@ -0,0 +1,482 @@
+//! Retries of the requests failing with transient errors, and failover
+//! between the endpoints of several nodes.
+
+use core::{
+    future::Future,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use tendermint::{block::Height, evidence::Evidence, Hash};
+use tokio::time;
+
+use crate::{
+    client::Client,
+    endpoint::{block_results, broadcast, evidence, header, header_by_hash, tx, tx_search},
+    prelude::*,
+    query::Query,
+    request::{RequestMessage, SimpleRequest},
+    Error, Order, Subscription, SubscriptionClient,
+};
+
+/// Configuration of the retries of a [`RetryingClient`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct RetryConfig {
+    /// The maximum number of times a request is retried, after its first
+    /// attempt.
+    pub max_retries: u32,
+    /// The delay before the first retry of a request.
+    pub initial_backoff: Duration,
+    /// The maximum delay between two attempts. The delay doubles after each
+    /// retry, up to this maximum.
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+        }
+    }
+}
+
+/// A client wrapping another one, retrying the requests which fail with a
+/// [retryable](Error::is_retryable) error, with an exponential backoff.
+///
+/// Note that retried requests may have been performed by the node already,
+/// such as a transaction whose response was lost.
+///
+/// ## Examples
+///
+/// ```rust,ignore
+/// use tendermint_rpc::{
+///     client::{RetryConfig, RetryingClient},
+///     Client, HttpClient,
+/// };
+///
+/// let client = HttpClient::new("http://127.0.0.1:26657").unwrap();
+/// let client = RetryingClient::new(client, RetryConfig::default());
+/// let status = client.status().await.unwrap();
+/// ```
+#[derive(Debug)]
+pub struct RetryingClient<C> {
+    inner: C,
+    config: RetryConfig,
+}
+
+impl<C> RetryingClient<C> {
+    /// Retry the requests of the given client, as configured.
+    pub fn new(inner: C, config: RetryConfig) -> Self {
+        Self { inner, config }
+    }
+
+    /// The configuration of the retries.
+    pub fn config(&self) -> &RetryConfig {
+        &self.config
+    }
+
+    /// The wrapped client.
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    /// Get back the wrapped client.
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+
+    /// Make the attempts of a request, until one succeeds, fails with an
+    /// error which is not retryable, or the retries are exhausted.
+    async fn retry<T, F, Fut>(&self, mut attempt: F) -> Result<T, Error>
+    where
+        F: FnMut() -> Fut + Send,
+        Fut: Future<Output = Result<T, Error>> + Send,
+    {
+        let mut backoff = self.config.initial_backoff;
+        let mut retries = 0;
+        loop {
+            let error = match attempt().await {
+                Err(e) if e.is_retryable() && retries < self.config.max_retries => e,
+                result => return result,
+            };
+            retries += 1;
+            tracing::debug!("retrying request after {:?}: {}", backoff, error);
+            time::sleep(backoff).await;
+            backoff = (backoff * 2).min(self.config.max_backoff);
+        }
+    }
+}
+
+#[async_trait]
+impl<C> Client for RetryingClient<C>
+where
+    C: Client + Send + Sync,
+{
+    async fn perform<R>(&self, request: R) -> Result<R::Output, Error>
+    where
+        R: SimpleRequest,
+    {
+        let request = &to_json(&request)?;
+        self.retry(|| async { self.inner.perform(from_json::<R>(request)?).await })
+            .await
+    }
+
+    // The methods whose request depends on the compatibility mode of the
+    // wrapped client are forwarded to it.
+
+    async fn block_results<H>(&self, height: H) -> Result<block_results::Response, Error>
+    where
+        H: Into<Height> + Send,
+    {
+        let height = height.into();
+        self.retry(|| self.inner.block_results(height)).await
+    }
+
+    async fn latest_block_results(&self) -> Result<block_results::Response, Error> {
+        self.retry(|| self.inner.latest_block_results()).await
+    }
+
+    async fn header<H>(&self, height: H) -> Result<header::Response, Error>
+    where
+        H: Into<Height> + Send,
+    {
+        let height = height.into();
+        self.retry(|| self.inner.header(height)).await
+    }
+
+    async fn header_by_hash(&self, hash: Hash) -> Result<header_by_hash::Response, Error> {
+        self.retry(|| self.inner.header_by_hash(hash)).await
+    }
+
+    async fn broadcast_evidence(&self, e: Evidence) -> Result<evidence::Response, Error> {
+        self.retry(|| self.inner.broadcast_evidence(e.clone()))
+            .await
+    }
+
+    async fn tx(&self, hash: Hash, prove: bool) -> Result<tx::Response, Error> {
+        self.retry(|| self.inner.tx(hash, prove)).await
+    }
+
+    async fn tx_search(
+        &self,
+        query: Query,
+        prove: bool,
+        page: u32,
+        per_page: u8,
+        order: Order,
+    ) -> Result<tx_search::Response, Error> {
+        self.retry(|| {
+            self.inner
+                .tx_search(query.clone(), prove, page, per_page, order.clone())
+        })
+        .await
+    }
+
+    async fn broadcast_tx_commit<T>(&self, tx: T) -> Result<broadcast::tx_commit::Response, Error>
+    where
+        T: Into<Vec<u8>> + Send,
+    {
+        let tx = tx.into();
+        self.retry(|| self.inner.broadcast_tx_commit(tx.clone()))
+            .await
+    }
+}
+
+#[async_trait]
+impl<C> SubscriptionClient for RetryingClient<C>
+where
+    C: SubscriptionClient + Send + Sync,
+{
+    async fn subscribe(&self, query: Query) -> Result<Subscription, Error> {
+        self.retry(|| self.inner.subscribe(query.clone())).await
+    }
+
+    async fn unsubscribe(&self, query: Query) -> Result<(), Error> {
+        self.retry(|| self.inner.unsubscribe(query.clone())).await
+    }
+
+    fn close(self) -> Result<(), Error> {
+        self.inner.close()
+    }
+}
+
+/// A client sending its requests to the first of several clients, usually
+/// connected to different nodes, and failing over to the next one when a
+/// request fails with a [retryable](Error::is_retryable) error.
+///
+/// The client which last succeeded keeps serving the later requests. Each
+/// request is tried at most once with each client: to retry requests after
+/// a delay, wrap the failover client into a [`RetryingClient`].
+///
+/// ## Examples
+///
+/// ```rust,ignore
+/// use tendermint_rpc::{
+///     client::{FailoverClient, RetryConfig, RetryingClient},
+///     Client, HttpClient,
+/// };
+///
+/// let client = FailoverClient::new(vec![
+///     HttpClient::new("http://node-1:26657").unwrap(),
+///     HttpClient::new("http://node-2:26657").unwrap(),
+/// ]);
+/// let client = RetryingClient::new(client, RetryConfig::default());
+/// let status = client.status().await.unwrap();
+/// ```
+#[derive(Debug)]
+pub struct FailoverClient<C> {
+    clients: Vec<C>,
+    current: AtomicUsize,
+}
+
+impl<C> FailoverClient<C> {
+    /// Fail over between the given clients, in order.
+    ///
+    /// ## Panics
+    ///
+    /// If no client is given.
+    pub fn new(clients: Vec<C>) -> Self {
+        assert!(!clients.is_empty(), "no client to fail over to");
+        Self {
+            clients,
+            current: AtomicUsize::new(0),
+        }
+    }
+
+    /// The clients to fail over between.
+    pub fn clients(&self) -> &[C] {
+        &self.clients
+    }
+
+    /// The index of the client the next request is sent to.
+    pub fn current(&self) -> usize {
+        self.current.load(Ordering::Relaxed)
+    }
+
+    /// Get back the wrapped clients.
+    pub fn into_inner(self) -> Vec<C> {
+        self.clients
+    }
+
+    /// Try a request with each client in turn, starting with the current
+    /// one, until one succeeds or fails with an error which is not
+    /// retryable.
+    async fn failover<'a, T, F, Fut>(&'a self, mut attempt: F) -> Result<T, Error>
+    where
+        F: FnMut(&'a C) -> Fut + Send,
+        Fut: Future<Output = Result<T, Error>> + Send,
+    {
+        let first = self.current();
+        let mut tried = 1;
+        loop {
+            let index = (first + tried - 1) % self.clients.len();
+            match attempt(&self.clients[index]).await {
+                Err(e) if e.is_retryable() && tried < self.clients.len() => {
+                    tracing::debug!("failing over from RPC client {}: {}", index, e);
+                    tried += 1;
+                },
+                result => {
+                    if result.is_ok() {
+                        self.current.store(index, Ordering::Relaxed);
+                    }
+                    return result;
+                },
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<C> Client for FailoverClient<C>
+where
+    C: Client + Send + Sync,
+{
+    async fn perform<R>(&self, request: R) -> Result<R::Output, Error>
+    where
+        R: SimpleRequest,
+    {
+        let request = &to_json(&request)?;
+        self.failover(|client| async move { client.perform(from_json::<R>(request)?).await })
+            .await
+    }
+
+    // The methods whose request depends on the compatibility mode of the
+    // wrapped clients are forwarded to them.
+
+    async fn block_results<H>(&self, height: H) -> Result<block_results::Response, Error>
+    where
+        H: Into<Height> + Send,
+    {
+        let height = height.into();
+        self.failover(|client| client.block_results(height)).await
+    }
+
+    async fn latest_block_results(&self) -> Result<block_results::Response, Error> {
+        self.failover(|client| client.latest_block_results()).await
+    }
+
+    async fn header<H>(&self, height: H) -> Result<header::Response, Error>
+    where
+        H: Into<Height> + Send,
+    {
+        let height = height.into();
+        self.failover(|client| client.header(height)).await
+    }
+
+    async fn header_by_hash(&self, hash: Hash) -> Result<header_by_hash::Response, Error> {
+        self.failover(|client| client.header_by_hash(hash)).await
+    }
+
+    async fn broadcast_evidence(&self, e: Evidence) -> Result<evidence::Response, Error> {
+        self.failover(|client| client.broadcast_evidence(e.clone()))
+            .await
+    }
+
+    async fn tx(&self, hash: Hash, prove: bool) -> Result<tx::Response, Error> {
+        self.failover(|client| client.tx(hash, prove)).await
+    }
+
+    async fn tx_search(
+        &self,
+        query: Query,
+        prove: bool,
+        page: u32,
+        per_page: u8,
+        order: Order,
+    ) -> Result<tx_search::Response, Error> {
+        self.failover(|client| {
+            client.tx_search(query.clone(), prove, page, per_page, order.clone())
+        })
+        .await
+    }
+
+    async fn broadcast_tx_commit<T>(&self, tx: T) -> Result<broadcast::tx_commit::Response, Error>
+    where
+        T: Into<Vec<u8>> + Send,
+    {
+        let tx = tx.into();
+        self.failover(|client| client.broadcast_tx_commit(tx.clone()))
+            .await
+    }
+}
+
+// Requests are neither required to be `Clone` nor `Sync`, so each attempt
+// of a request decodes a copy of it from its JSON encoding.
+
+fn to_json<R: RequestMessage>(request: &R) -> Result<serde_json::Value, Error> {
+    serde_json::to_value(request).map_err(Error::serde)
+}
+
+fn from_json<R: RequestMessage>(request: &serde_json::Value) -> Result<R, Error> {
+    serde_json::from_value(request.clone()).map_err(Error::serde)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::sync::Arc;
+
+    use super::*;
+    use crate::{
+        client::{MockClient, MockRequestMatcher, MockRequestMethodMatcher},
+        dialect::Dialect,
+        request::Request,
+        Method,
+    };
+
+    /// Fails the given number of requests with a transport error, before
+    /// answering the requests for the ABCI info.
+    struct FlakyMatcher {
+        failures: usize,
+        attempts: Arc<AtomicUsize>,
+        inner: MockRequestMethodMatcher,
+    }
+
+    impl MockRequestMatcher for FlakyMatcher {
+        fn response_for<R, S>(&self, request: R) -> Option<Result<R::Response, Error>>
+        where
+            R: Request<S>,
+            S: Dialect,
+        {
+            if self.attempts.fetch_add(1, Ordering::Relaxed) < self.failures {
+                return Some(Err(Error::channel_send()));
+            }
+            self.inner.response_for(request)
+        }
+    }
+
+    /// A client failing the given number of requests, and the counter of
+    /// the requests made to it.
+    fn flaky_client(failures: usize) -> (MockClient<FlakyMatcher>, Arc<AtomicUsize>) {
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let matcher = FlakyMatcher {
+            failures,
+            attempts: attempts.clone(),
+            inner: MockRequestMethodMatcher::default().map(
+                Method::AbciInfo,
+                Ok(
+                    include_str!("../../../tests/kvstore_fixtures/v0_38/incoming/abci_info.json")
+                        .to_string(),
+                ),
+            ),
+        };
+        let (client, driver) = MockClient::new(matcher);
+        tokio::spawn(driver.run());
+        (client, attempts)
+    }
+
+    const NO_BACKOFF: RetryConfig = RetryConfig {
+        max_retries: 2,
+        initial_backoff: Duration::ZERO,
+        max_backoff: Duration::ZERO,
+    };
+
+    #[tokio::test]
+    async fn retries_transient_errors() {
+        let (client, attempts) = flaky_client(2);
+        let client = RetryingClient::new(client, NO_BACKOFF);
+        assert!(client.abci_info().await.is_ok());
+        assert_eq!(attempts.load(Ordering::Relaxed), 3);
+        client.into_inner().close();
+
+        let (client, attempts) = flaky_client(3);
+        let client = RetryingClient::new(client, NO_BACKOFF);
+        assert!(client.abci_info().await.unwrap_err().is_retryable());
+        assert_eq!(attempts.load(Ordering::Relaxed), 3);
+        client.into_inner().close();
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_permanent_errors() {
+        let (client, attempts) = flaky_client(0);
+        let client = RetryingClient::new(client, NO_BACKOFF);
+        // The mock client has no response for the status.
+        assert!(!client.status().await.unwrap_err().is_retryable());
+        assert_eq!(attempts.load(Ordering::Relaxed), 1);
+        client.into_inner().close();
+    }
+
+    #[tokio::test]
+    async fn fails_over_to_the_next_client() {
+        let (failing, failing_attempts) = flaky_client(usize::MAX);
+        let (working, working_attempts) = flaky_client(0);
+        let client = FailoverClient::new(vec![failing, working]);
+        assert!(client.abci_info().await.is_ok());
+        assert_eq!(client.current(), 1);
+        assert!(client.abci_info().await.is_ok());
+        assert_eq!(failing_attempts.load(Ordering::Relaxed), 1);
+        assert_eq!(working_attempts.load(Ordering::Relaxed), 2);
+        client.into_inner().into_iter().for_each(MockClient::close);
+
+        let (first, first_attempts) = flaky_client(usize::MAX);
+        let (second, second_attempts) = flaky_client(usize::MAX);
+        let client = FailoverClient::new(vec![first, second]);
+        assert!(client.abci_info().await.is_err());
+        assert_eq!(client.current(), 0);
+        assert_eq!(first_attempts.load(Ordering::Relaxed), 1);
+        assert_eq!(second_attempts.load(Ordering::Relaxed), 1);
+        client.into_inner().into_iter().for_each(MockClient::close);
+    }
+}
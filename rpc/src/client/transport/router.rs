@@ -122,6 +122,23 @@ impl SubscriptionRouter {
             .map(|subs_for_query| subs_for_query.len())
             .unwrap_or(0)
     }
+
+    /// Removes the subscription with the given ID.
+    ///
+    /// Returns the query of the subscription if it was the last subscription
+    /// relating to this query, in which case the query no longer needs to be
+    /// subscribed to.
+    pub fn remove(&mut self, id: SubscriptionIdRef<'_>) -> Option<SubscriptionQuery> {
+        let query = self.subscription_query(id)?.clone();
+        let subs_for_query = self.subscriptions.get_mut(&query)?;
+        subs_for_query.remove(id);
+        if subs_for_query.is_empty() {
+            self.subscriptions.remove(&query);
+            Some(query)
+        } else {
+            None
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -273,4 +290,22 @@ mod test {
             assert_eq!(ev, subs3_ev);
         }
     }
+
+    #[cfg(feature = "websocket-client")]
+    #[test]
+    fn router_remove_last_subscription() {
+        let mut router = SubscriptionRouter::default();
+
+        let (subs1_id, subs2_id) = (uuid_str(), uuid_str());
+        let (subs1_event_tx, _subs1_event_rx) = unbounded();
+        let (subs2_event_tx, _subs2_event_rx) = unbounded();
+        router.add(&subs1_id, "query1", subs1_event_tx);
+        router.add(&subs2_id, "query1", subs2_event_tx);
+
+        assert_eq!(router.remove(&subs1_id), None);
+        assert_eq!(router.num_subscriptions_for_query("query1"), 1);
+        assert_eq!(router.remove(&subs1_id), None);
+        assert_eq!(router.remove(&subs2_id), Some("query1".to_owned()));
+        assert_eq!(router.num_subscriptions_for_query("query1"), 0);
+    }
 }
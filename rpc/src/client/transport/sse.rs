@@ -0,0 +1,186 @@
+//! Server-Sent Events transport for event subscriptions.
+
+use alloc::{collections::BTreeMap as HashMap, sync::Arc};
+use core::convert::TryInto;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use reqwest::header;
+use tokio::task::JoinHandle;
+
+use super::auth;
+use crate::{
+    client::{subscription::SubscriptionTx, sync::unbounded, CompatMode},
+    prelude::*,
+    query::Query,
+    sse::{self, SseDecoder},
+    utils::uuid_str,
+    Error, HttpClientUrl, Subscription, SubscriptionClient,
+};
+
+/// A [`SubscriptionClient`] receiving events from an endpoint streaming them
+/// as [Server-Sent Events], such as an event proxy built with the encoder of
+/// the [`sse`](crate::sse) module.
+///
+/// Each subscription opens its own event stream, by issuing a `GET` request
+/// to the endpoint URL with the subscription query in its `query` parameter.
+/// Streams are closed when unsubscribing from their query, or once the
+/// corresponding [`Subscription`] has been dropped.
+///
+/// ## Examples
+///
+/// ```rust,ignore
+/// use futures::StreamExt;
+/// use tendermint_rpc::{client::SseClient, query::EventType, SubscriptionClient};
+///
+/// let client = SseClient::new("http://127.0.0.1:8080/events").unwrap();
+/// let mut subs = client.subscribe(EventType::NewBlock.into()).await.unwrap();
+/// while let Some(event) = subs.next().await {
+///     println!("Got event: {:?}", event);
+/// }
+/// ```
+///
+/// [Server-Sent Events]: https://html.spec.whatwg.org/multipage/server-sent-events.html
+#[derive(Debug, Clone)]
+pub struct SseClient {
+    inner: reqwest::Client,
+    url: reqwest::Url,
+    compat: CompatMode,
+    streams: Arc<Mutex<HashMap<String, Vec<JoinHandle<()>>>>>,
+}
+
+impl SseClient {
+    /// Construct a new client receiving events from the SSE endpoint at the
+    /// given URL.
+    pub fn new<U>(url: U) -> Result<Self, Error>
+    where
+        U: TryInto<HttpClientUrl, Error = Error>,
+    {
+        Self::new_with_compat(url, CompatMode::default())
+    }
+
+    /// Construct a new client receiving events from the SSE endpoint at the
+    /// given URL, serialized in the given protocol dialect.
+    pub fn new_with_compat<U>(url: U, compat: CompatMode) -> Result<Self, Error>
+    where
+        U: TryInto<HttpClientUrl, Error = Error>,
+    {
+        let url = url.try_into()?;
+        let inner = reqwest::ClientBuilder::new()
+            .user_agent(concat!("tendermint.rs/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .map_err(Error::http)?;
+        Ok(Self {
+            inner,
+            url: url.into(),
+            compat,
+            streams: Default::default(),
+        })
+    }
+
+    fn build_request(&self, query: &Query) -> Result<reqwest::Request, Error> {
+        let mut url = self.url.clone();
+        url.query_pairs_mut()
+            .append_pair(sse::QUERY_PARAM, &query.to_string());
+
+        let mut builder = self
+            .inner
+            .get(url)
+            .header(header::ACCEPT, sse::CONTENT_TYPE)
+            .header(header::CACHE_CONTROL, "no-cache");
+        if let Some(auth) = auth::authorize(&self.url) {
+            builder = builder.header(header::AUTHORIZATION, auth.to_string());
+        }
+        builder.build().map_err(Error::http)
+    }
+
+    fn streams(&self) -> std::sync::MutexGuard<'_, HashMap<String, Vec<JoinHandle<()>>>> {
+        self.streams.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[async_trait]
+impl SubscriptionClient for SseClient {
+    async fn subscribe(&self, query: Query) -> Result<Subscription, Error> {
+        let request = self.build_request(&query)?;
+        let response = self.inner.execute(request).await.map_err(Error::http)?;
+        if response.status() != reqwest::StatusCode::OK {
+            return Err(Error::http_request_failed(response.status()));
+        }
+
+        let (subscription_tx, subscription_rx) = unbounded();
+        let stream = tokio::spawn(receive_events(response, self.compat, subscription_tx));
+        let mut streams = self.streams();
+        prune_finished(&mut streams);
+        streams.entry(query.to_string()).or_default().push(stream);
+        Ok(Subscription::new(uuid_str(), query, subscription_rx))
+    }
+
+    async fn unsubscribe(&self, query: Query) -> Result<(), Error> {
+        let mut streams = self.streams();
+        if let Some(streams) = streams.remove(&query.to_string()) {
+            streams.iter().for_each(JoinHandle::abort);
+        }
+        prune_finished(&mut streams);
+        Ok(())
+    }
+
+    fn close(self) -> Result<(), Error> {
+        for (_, streams) in core::mem::take(&mut *self.streams()) {
+            streams.iter().for_each(JoinHandle::abort);
+        }
+        Ok(())
+    }
+}
+
+/// Drops the handles of the streams that have already ended, on their own or
+/// because their subscription was dropped.
+fn prune_finished(streams: &mut HashMap<String, Vec<JoinHandle<()>>>) {
+    streams.retain(|_, handles| {
+        handles.retain(|handle| !handle.is_finished());
+        !handles.is_empty()
+    });
+}
+
+/// Forwards the events of the response stream to the subscription, until
+/// either the stream or the subscription is closed.
+async fn receive_events(
+    mut response: reqwest::Response,
+    compat: CompatMode,
+    subscription_tx: SubscriptionTx,
+) {
+    let mut decoder = SseDecoder::new();
+    loop {
+        let chunk = tokio::select! {
+            chunk = response.chunk() => chunk,
+            _ = subscription_tx.closed() => {
+                // The subscription was dropped.
+                return;
+            },
+        };
+        let chunk = match chunk {
+            Ok(Some(chunk)) => chunk,
+            Ok(None) => {
+                tracing::debug!("SSE event stream closed by the server");
+                return;
+            },
+            Err(e) => {
+                let _ = subscription_tx.send(Err(Error::http(e)));
+                return;
+            },
+        };
+        let messages = match decoder.decode(&chunk) {
+            Ok(messages) => messages,
+            Err(e) => {
+                let _ = subscription_tx.send(Err(e));
+                return;
+            },
+        };
+        for message in messages {
+            if subscription_tx.send(message.into_event(compat)).is_err() {
+                // The subscription was dropped.
+                return;
+            }
+        }
+    }
+}
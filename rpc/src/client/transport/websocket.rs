@@ -1,5 +1,7 @@
 //! WebSocket-based clients for accessing Tendermint RPC functionality.
 
+mod dialer;
+
 use alloc::{borrow::Cow, collections::BTreeMap as HashMap, fmt};
 use core::{
     convert::{TryFrom, TryInto},
@@ -9,7 +11,7 @@ use core::{
 
 use async_trait::async_trait;
 use async_tungstenite::{
-    tokio::ConnectStream,
+    tokio::ClientStream,
     tungstenite::{
         protocol::{frame::coding::CloseCode, CloseFrame},
         Message,
@@ -24,10 +26,11 @@ use tracing::{debug, error};
 use tendermint::{block::Height, Hash};
 use tendermint_config::net;
 
-use super::router::{SubscriptionId, SubscriptionIdRef};
+use super::router::{SubscriptionId, SubscriptionIdRef, SubscriptionQuery};
 use crate::{
     client::{
-        subscription::SubscriptionTx,
+        monitor::{HealthMonitor, HealthMonitorConfig},
+        subscription::{Release, SubscriptionTx},
         sync::{ChannelRx, ChannelTx},
         transport::router::{PublishResult, SubscriptionRouter},
         Client, CompatMode,
@@ -59,6 +62,12 @@ const PING_INTERVAL: Duration = Duration::from_secs((RECV_TIMEOUT_SECONDS * 9) /
 /// Low-level WebSocket configuration
 pub use async_tungstenite::tungstenite::protocol::WebSocketConfig;
 
+pub use self::dialer::{DialedStream, Dialer, HttpConnectDialer, TcpDialer};
+
+// The stream underlying the WebSocket connection, as opened by the dialer and
+// possibly wrapped in a TLS session.
+type ConnectStream = ClientStream<Box<dyn DialedStream>>;
+
 /// Tendermint RPC client that provides access to all RPC functionality
 /// (including [`Event`] subscription) over a WebSocket connection.
 ///
@@ -71,11 +80,18 @@ pub use async_tungstenite::tungstenite::protocol::WebSocketConfig;
 /// It is the caller's responsibility to spawn an asynchronous task in which to
 /// execute the [`WebSocketClientDriver::run`] method. See the example below.
 ///
+/// Subscriptions to the same query share a single subscription on the remote
+/// RPC endpoint, whose events are delivered to all the [`Subscription`]s to
+/// that query, even when they are initiated concurrently.
+///
 /// Dropping [`Subscription`]s will automatically terminate them (the
-/// `WebSocketClientDriver` detects a disconnected channel and removes the
-/// subscription from its internal routing table). When all subscriptions to a
-/// particular query have disconnected, the driver will automatically issue an
-/// unsubscribe request to the remote RPC endpoint.
+/// `WebSocketClientDriver` is notified and removes the subscription from its
+/// internal routing table). When all subscriptions to a particular query have
+/// been dropped, the driver will automatically issue an unsubscribe request to
+/// the remote RPC endpoint. Similarly, [`Subscription::unsubscribe`] only
+/// terminates the remote subscription once it is the last subscription to its
+/// query, whereas [`SubscriptionClient::unsubscribe`] terminates all the
+/// subscriptions to a query.
 ///
 /// ### Timeouts
 ///
@@ -94,6 +110,13 @@ pub use async_tungstenite::tungstenite::protocol::WebSocketConfig;
 ///
 /// This is not configurable at present.
 ///
+/// ### Dialing
+///
+/// The connection underlying the WebSocket is opened by a [`Dialer`], which
+/// connects directly to the RPC endpoint by default. Use
+/// [`Builder::dialer`] to route the connection through an HTTP proxy with
+/// [`HttpConnectDialer`], or any other transport.
+///
 /// ## Examples
 ///
 /// ```rust,ignore
@@ -149,6 +172,7 @@ pub struct Builder {
     url: WebSocketClientUrl,
     compat: CompatMode,
     transport_config: Option<WebSocketConfig>,
+    dialer: Box<dyn Dialer>,
 }
 
 impl Builder {
@@ -166,14 +190,27 @@ impl Builder {
         self
     }
 
+    /// Use the specified dialer to open the connection underlying the
+    /// WebSocket.
+    ///
+    /// The default is [`TcpDialer`], which connects directly to the RPC
+    /// endpoint. For secure endpoints, the TLS session is established over
+    /// the connection opened by the dialer.
+    pub fn dialer(mut self, dialer: impl Dialer + 'static) -> Self {
+        self.dialer = Box::new(dialer);
+        self
+    }
+
     /// Try to create a client with the options specified for this builder.
     pub async fn build(self) -> Result<(WebSocketClient, WebSocketClientDriver), Error> {
         let url = self.url.0;
         let compat = self.compat;
+        let dialer = self.dialer.as_ref();
         let (inner, driver) = if url.is_secure() {
-            sealed::WebSocketClient::new_secure(url, compat, self.transport_config).await?
+            sealed::WebSocketClient::new_secure(url, compat, self.transport_config, dialer).await?
         } else {
-            sealed::WebSocketClient::new_unsecure(url, compat, self.transport_config).await?
+            sealed::WebSocketClient::new_unsecure(url, compat, self.transport_config, dialer)
+                .await?
         };
 
         Ok((WebSocketClient { inner, compat }, driver))
@@ -217,9 +254,16 @@ impl WebSocketClient {
             url,
             compat: Default::default(),
             transport_config: Default::default(),
+            dialer: Box::new(TcpDialer::default()),
         }
     }
 
+    /// Start monitoring the health of the node this client is connected to
+    /// in a background task. See [`HealthMonitor`] for details.
+    pub fn health_monitor(&self, config: HealthMonitorConfig) -> HealthMonitor {
+        HealthMonitor::spawn(self.clone(), config)
+    }
+
     async fn perform_with_dialect<R, S>(&self, request: R, dialect: S) -> Result<R::Output, Error>
     where
         R: SimpleRequest<S>,
@@ -402,17 +446,18 @@ impl From<WebSocketClientUrl> for Url {
 
 mod sealed {
     use async_tungstenite::{
-        tokio::{connect_async_with_config, connect_async_with_tls_connector_and_config},
-        tungstenite::client::IntoClientRequest,
+        tokio::client_async_tls_with_connector_and_config, tungstenite::client::IntoClientRequest,
+        WebSocketStream,
     };
     use tracing::debug;
 
     use super::{
-        DriverCommand, SimpleRequestCommand, SubscribeCommand, UnsubscribeCommand,
-        WebSocketClientDriver, WebSocketConfig,
+        ConnectStream, Dialer, DriverCommand, SimpleRequestCommand, SubscribeCommand,
+        UnsubscribeCommand, WebSocketClientDriver, WebSocketConfig,
     };
     use crate::{
         client::{
+            subscription::ReleaseTx,
             sync::{unbounded, ChannelTx},
             transport::auth::authorize,
             CompatMode,
@@ -444,6 +489,7 @@ mod sealed {
     #[derive(Debug, Clone)]
     pub struct AsyncTungsteniteClient<C> {
         cmd_tx: ChannelTx<DriverCommand>,
+        release_tx: ReleaseTx,
         _client_type: core::marker::PhantomData<C>,
     }
 
@@ -460,17 +506,18 @@ mod sealed {
             url: Url,
             compat: CompatMode,
             config: Option<WebSocketConfig>,
+            dialer: &dyn Dialer,
         ) -> Result<(Self, WebSocketClientDriver), Error> {
             debug!("Connecting to unsecure WebSocket endpoint: {}", url);
 
-            let (stream, _response) = connect_async_with_config(url, config)
-                .await
-                .map_err(Error::tungstenite)?;
+            let stream = connect(url, config, dialer).await?;
 
             let (cmd_tx, cmd_rx) = unbounded();
-            let driver = WebSocketClientDriver::new(stream, cmd_rx, compat);
+            let (release_tx, release_rx) = unbounded();
+            let driver = WebSocketClientDriver::new(stream, cmd_rx, release_rx, compat);
             let client = Self {
                 cmd_tx,
+                release_tx,
                 _client_type: Default::default(),
             };
 
@@ -492,20 +539,18 @@ mod sealed {
             url: Url,
             compat: CompatMode,
             config: Option<WebSocketConfig>,
+            dialer: &dyn Dialer,
         ) -> Result<(Self, WebSocketClientDriver), Error> {
             debug!("Connecting to secure WebSocket endpoint: {}", url);
 
-            // Not supplying a connector means async_tungstenite will create the
-            // connector for us.
-            let (stream, _response) =
-                connect_async_with_tls_connector_and_config(url, None, config)
-                    .await
-                    .map_err(Error::tungstenite)?;
+            let stream = connect(url, config, dialer).await?;
 
             let (cmd_tx, cmd_rx) = unbounded();
-            let driver = WebSocketClientDriver::new(stream, cmd_rx, compat);
+            let (release_tx, release_rx) = unbounded();
+            let driver = WebSocketClientDriver::new(stream, cmd_rx, release_rx, compat);
             let client = Self {
                 cmd_tx,
+                release_tx,
                 _client_type: Default::default(),
             };
 
@@ -513,6 +558,29 @@ mod sealed {
         }
     }
 
+    // Opens a connection to the endpoint with the dialer, then performs the
+    // WebSocket handshake over it, after the TLS handshake for secure
+    // endpoints.
+    async fn connect(
+        url: Url,
+        config: Option<WebSocketConfig>,
+        dialer: &dyn Dialer,
+    ) -> Result<WebSocketStream<ConnectStream>, Error> {
+        let socket = dialer
+            .dial(url.host(), url.port())
+            .await
+            .map_err(Error::io)?;
+
+        // Not supplying a connector means async_tungstenite will create the
+        // connector for us.
+        let (stream, _response) =
+            client_async_tls_with_connector_and_config(url, socket, None, config)
+                .await
+                .map_err(Error::tungstenite)?;
+
+        Ok(stream)
+    }
+
     impl<C> AsyncTungsteniteClient<C> {
         fn send_cmd(&self, cmd: DriverCommand) -> Result<(), Error> {
             self.cmd_tx.send(cmd)
@@ -568,7 +636,7 @@ mod sealed {
             response_rx.recv().await.ok_or_else(|| {
                 Error::client_internal("failed to hear back from WebSocket driver".to_string())
             })??;
-            Ok(Subscription::new(id, query, subscription_rx))
+            Ok(Subscription::new(id, query, subscription_rx).with_release(self.release_tx.clone()))
         }
 
         pub async fn unsubscribe(&self, query: Query) -> Result<(), Error> {
@@ -597,9 +665,10 @@ mod sealed {
             url: Url,
             compat: CompatMode,
             config: Option<WebSocketConfig>,
+            dialer: &dyn Dialer,
         ) -> Result<(Self, WebSocketClientDriver), Error> {
             let (client, driver) =
-                AsyncTungsteniteClient::<Unsecure>::new(url, compat, config).await?;
+                AsyncTungsteniteClient::<Unsecure>::new(url, compat, config, dialer).await?;
             Ok((Self::Unsecure(client), driver))
         }
 
@@ -607,9 +676,10 @@ mod sealed {
             url: Url,
             compat: CompatMode,
             config: Option<WebSocketConfig>,
+            dialer: &dyn Dialer,
         ) -> Result<(Self, WebSocketClientDriver), Error> {
             let (client, driver) =
-                AsyncTungsteniteClient::<Secure>::new(url, compat, config).await?;
+                AsyncTungsteniteClient::<Secure>::new(url, compat, config, dialer).await?;
             Ok((Self::Secure(client), driver))
         }
 
@@ -740,9 +810,14 @@ pub struct WebSocketClientDriver {
     router: SubscriptionRouter,
     // How we receive incoming commands from the WebSocketClient.
     cmd_rx: ChannelRx<DriverCommand>,
+    // How we receive the IDs of the subscriptions that have been dropped.
+    release_rx: ChannelRx<Release>,
     // Commands we've received but have not yet completed, indexed by their ID.
     // A Terminate command is executed immediately.
     pending_commands: HashMap<SubscriptionId, DriverCommand>,
+    // Subscribe commands for queries whose subscription request is in flight,
+    // which will share the resulting subscription.
+    pending_subscriptions: HashMap<SubscriptionQuery, Vec<SubscribeCommand>>,
     // The compatibility mode directing how to parse subscription events.
     compat: CompatMode,
 }
@@ -751,13 +826,16 @@ impl WebSocketClientDriver {
     fn new(
         stream: WebSocketStream<ConnectStream>,
         cmd_rx: ChannelRx<DriverCommand>,
+        release_rx: ChannelRx<Release>,
         compat: CompatMode,
     ) -> Self {
         Self {
             stream,
             router: SubscriptionRouter::default(),
             cmd_rx,
+            release_rx,
             pending_commands: HashMap::new(),
+            pending_subscriptions: HashMap::new(),
             compat,
         }
     }
@@ -812,6 +890,7 @@ impl WebSocketClientDriver {
                     DriverCommand::SimpleRequest(req_cmd) => self.simple_request(req_cmd).await?,
                     DriverCommand::Terminate => return self.close().await,
                 },
+                Some(release) = self.release_rx.recv() => self.release(release).await?,
                 _ = ping_interval.tick() => self.ping().await?,
                 _ = &mut recv_timeout => {
                     return Err(Error::web_socket_timeout(RECV_TIMEOUT));
@@ -841,6 +920,13 @@ impl WebSocketClientDriver {
             return response_tx.send(Ok(()));
         }
 
+        // If a subscription request for the given query is in flight, this
+        // subscription will be added to the router along with it.
+        if let Some(waiting) = self.pending_subscriptions.get_mut(&cmd.query) {
+            waiting.push(cmd);
+            return Ok(());
+        }
+
         // Otherwise, we need to initiate a subscription request.
         let wrapper = Wrapper::new_with_id(
             Id::Str(cmd.id.clone()),
@@ -850,6 +936,8 @@ impl WebSocketClientDriver {
             cmd.response_tx.send(Err(e.clone()))?;
             return Err(e);
         }
+        self.pending_subscriptions
+            .insert(cmd.query.clone(), Vec::new());
         self.pending_commands
             .insert(cmd.id.clone(), DriverCommand::Subscribe(cmd));
         Ok(())
@@ -865,7 +953,10 @@ impl WebSocketClientDriver {
             cmd.response_tx.send(Ok(()))?;
             return Ok(());
         }
+        self.send_unsubscribe(cmd).await
+    }
 
+    async fn send_unsubscribe(&mut self, cmd: UnsubscribeCommand) -> Result<(), Error> {
         // Unsubscribe requests can (and probably should) have distinct
         // JSON-RPC IDs as compared to their subscription IDs.
         let wrapper = Wrapper::new(unsubscribe::Request::new(cmd.query.clone()));
@@ -932,39 +1023,53 @@ impl WebSocketClientDriver {
 
     async fn publish_error(&mut self, id: SubscriptionIdRef<'_>, err: Error) {
         if let PublishResult::AllDisconnected(query) = self.router.publish_error(id, err) {
-            debug!(
-                "All subscribers for query \"{}\" have disconnected. Unsubscribing from query...",
-                query
-            );
-
-            // If all subscribers have disconnected for this query, we need to
-            // unsubscribe from it. We issue a fire-and-forget unsubscribe
-            // message.
-            if let Err(e) = self
-                .send_request(Wrapper::new(unsubscribe::Request::new(query)))
-                .await
-            {
-                error!("Failed to send unsubscribe request: {}", e);
-            }
+            self.all_disconnected(query).await;
         }
     }
 
     async fn publish_event(&mut self, ev: Event) {
         if let PublishResult::AllDisconnected(query) = self.router.publish_event(ev) {
-            debug!(
-                "All subscribers for query \"{}\" have disconnected. Unsubscribing from query...",
-                query
-            );
+            self.all_disconnected(query).await;
+        }
+    }
 
-            // If all subscribers have disconnected for this query, we need to
-            // unsubscribe from it. We issue a fire-and-forget unsubscribe
-            // message.
-            if let Err(e) = self
-                .send_request(Wrapper::new(unsubscribe::Request::new(query)))
-                .await
-            {
-                error!("Failed to send unsubscribe request: {}", e);
-            }
+    async fn release(&mut self, release: Release) -> Result<(), Error> {
+        let query = self.router.remove(&release.id);
+        match (query, release.response_tx) {
+            // The last subscription to the query was dropped.
+            (Some(query), None) => {
+                self.all_disconnected(query).await;
+                Ok(())
+            },
+            // The last subscription to the query was unsubscribed, which
+            // waits for the remote endpoint to confirm.
+            (Some(query), Some(response_tx)) => {
+                self.send_unsubscribe(UnsubscribeCommand { query, response_tx })
+                    .await
+            },
+            // The caller may have given up waiting.
+            (None, Some(response_tx)) => {
+                let _ = response_tx.send(Ok(()));
+                Ok(())
+            },
+            (None, None) => Ok(()),
+        }
+    }
+
+    async fn all_disconnected(&mut self, query: SubscriptionQuery) {
+        debug!(
+            "All subscribers for query \"{}\" have disconnected. Unsubscribing from query...",
+            query
+        );
+
+        // If all subscribers have disconnected for this query, we need to
+        // unsubscribe from it. We issue a fire-and-forget unsubscribe
+        // message.
+        if let Err(e) = self
+            .send_request(Wrapper::new(unsubscribe::Request::new(query)))
+            .await
+        {
+            error!("Failed to send unsubscribe request: {}", e);
         }
     }
 
@@ -975,10 +1080,23 @@ impl WebSocketClientDriver {
     ) -> Result<(), Error> {
         match pending_cmd {
             DriverCommand::Subscribe(cmd) => {
-                let (id, query, subscription_tx, response_tx) =
-                    (cmd.id, cmd.query, cmd.subscription_tx, cmd.response_tx);
-                self.router.add(id, query, subscription_tx);
-                response_tx.send(Ok(()))
+                let waiting = self
+                    .pending_subscriptions
+                    .remove(&cmd.query)
+                    .unwrap_or_default();
+                for cmd in core::iter::once(cmd).chain(waiting) {
+                    let (id, query, subscription_tx, response_tx) =
+                        (cmd.id, cmd.query, cmd.subscription_tx, cmd.response_tx);
+                    // A subscriber which gave up waiting must neither be
+                    // routed events nor prevent the others from being
+                    // answered.
+                    if let Err(e) = response_tx.send(Ok(())) {
+                        debug!("Dropping subscription {} with no waiter: {}", id, e);
+                        continue;
+                    }
+                    self.router.add(id, query, subscription_tx);
+                }
+                Ok(())
             },
             DriverCommand::Unsubscribe(cmd) => cmd.response_tx.send(Ok(())),
             DriverCommand::SimpleRequest(cmd) => cmd.response_tx.send(Ok(response)),
@@ -1014,7 +1132,11 @@ impl WebSocketClientDriver {
 mod test {
     use alloc::collections::BTreeMap as HashMap;
     use core::str::FromStr;
-    use std::{path::PathBuf, println};
+    use std::{
+        path::PathBuf,
+        println,
+        sync::{Arc, Mutex},
+    };
 
     use async_tungstenite::{
         tokio::{accept_async, TokioAdapter},
@@ -1038,8 +1160,12 @@ mod test {
         driver_hdl: JoinHandle<Result<(), Error>>,
         terminate_tx: ChannelTx<Result<(), Error>>,
         event_tx: ChannelTx<Event>,
+        methods: ReceivedMethods,
     }
 
+    // The methods of the requests received by the test server, in order.
+    type ReceivedMethods = Arc<Mutex<Vec<String>>>;
+
     // A setting telling which of the CometBFT server versions to emulate
     // with the test server.
     #[derive(Copy, Clone)]
@@ -1060,16 +1186,24 @@ mod test {
             };
             let (terminate_tx, terminate_rx) = unbounded();
             let (event_tx, event_rx) = unbounded();
-            let driver = TestServerDriver::new(listener, version, event_rx, terminate_rx);
+            let methods = ReceivedMethods::default();
+            let driver =
+                TestServerDriver::new(listener, version, event_rx, terminate_rx, methods.clone());
             let driver_hdl = tokio::spawn(async move { driver.run().await });
             Self {
                 node_addr,
                 driver_hdl,
                 terminate_tx,
                 event_tx,
+                methods,
             }
         }
 
+        fn received_requests(&self, method: &str) -> usize {
+            let methods = self.methods.lock().unwrap();
+            methods.iter().filter(|m| *m == method).count()
+        }
+
         fn publish_event(&mut self, ev: Event) -> Result<(), Error> {
             self.event_tx.send(ev)
         }
@@ -1087,6 +1221,7 @@ mod test {
         event_rx: ChannelRx<Event>,
         terminate_rx: ChannelRx<Result<(), Error>>,
         handlers: Vec<TestServerHandler>,
+        methods: ReceivedMethods,
     }
 
     impl TestServerDriver {
@@ -1095,6 +1230,7 @@ mod test {
             version: TestRpcVersion,
             event_rx: ChannelRx<Event>,
             terminate_rx: ChannelRx<Result<(), Error>>,
+            methods: ReceivedMethods,
         ) -> Self {
             Self {
                 listener,
@@ -1102,6 +1238,7 @@ mod test {
                 event_rx,
                 terminate_rx,
                 handlers: Vec::new(),
+                methods,
             }
         }
 
@@ -1131,7 +1268,7 @@ mod test {
 
         async fn handle_incoming(&mut self, stream: TcpStream) {
             self.handlers
-                .push(TestServerHandler::new(stream, self.version).await);
+                .push(TestServerHandler::new(stream, self.version, self.methods.clone()).await);
         }
 
         async fn terminate(&mut self) {
@@ -1154,12 +1291,13 @@ mod test {
     }
 
     impl TestServerHandler {
-        async fn new(stream: TcpStream, version: TestRpcVersion) -> Self {
+        async fn new(stream: TcpStream, version: TestRpcVersion, methods: ReceivedMethods) -> Self {
             let conn: WebSocketStream<TokioAdapter<TcpStream>> =
                 accept_async(stream).await.unwrap();
             let (terminate_tx, terminate_rx) = unbounded();
             let (event_tx, event_rx) = unbounded();
-            let driver = TestServerHandlerDriver::new(conn, version, event_rx, terminate_rx);
+            let driver =
+                TestServerHandlerDriver::new(conn, version, event_rx, terminate_rx, methods);
             let driver_hdl = tokio::spawn(async move { driver.run().await });
             Self {
                 driver_hdl,
@@ -1187,6 +1325,7 @@ mod test {
         // A mapping of subscription queries to subscription IDs for this
         // connection.
         subscriptions: HashMap<String, String>,
+        methods: ReceivedMethods,
     }
 
     impl TestServerHandlerDriver {
@@ -1195,6 +1334,7 @@ mod test {
             version: TestRpcVersion,
             event_rx: ChannelRx<Event>,
             terminate_rx: ChannelRx<Result<(), Error>>,
+            methods: ReceivedMethods,
         ) -> Self {
             Self {
                 conn,
@@ -1202,6 +1342,7 @@ mod test {
                 event_rx,
                 terminate_rx,
                 subscriptions: HashMap::new(),
+                methods,
             }
         }
 
@@ -1262,6 +1403,8 @@ mod test {
             match serde_json::from_str::<serde_json::Value>(&msg) {
                 Ok(json_msg) => {
                     if let Some(json_method) = json_msg.get("method") {
+                        let method_name = json_method.as_str().unwrap().to_owned();
+                        self.methods.lock().unwrap().push(method_name);
                         match Method::from_str(json_method.as_str().unwrap()) {
                             Ok(method) => match method {
                                 Method::Subscribe => {
@@ -1549,6 +1692,254 @@ mod test {
                 );
             }
         }
+
+        #[tokio::test]
+        async fn websocket_client_shares_subscriptions() {
+            let event1 = read_event("subscribe_newblock_0").await;
+            let event2 = read_event("subscribe_newblock_1").await;
+
+            let mut server = TestServer::new("127.0.0.1:0", TestRpcVersion::V0_38).await;
+            let url = server.node_addr.clone().try_into().unwrap();
+            let (client, driver) = WebSocketClient::builder(url)
+                .compat_mode(CompatMode::V0_37)
+                .build()
+                .await
+                .unwrap();
+            let driver_handle = tokio::spawn(async move { driver.run().await });
+
+            // Concurrent subscriptions to the same query share a single
+            // subscription on the server.
+            let query: Query = EventType::NewBlock.into();
+            let (subs1, subs2) = tokio::join!(
+                client.subscribe(query.clone()),
+                client.subscribe(query.clone())
+            );
+            let (mut subs1, mut subs2) = (subs1.unwrap(), subs2.unwrap());
+            assert_eq!(server.received_requests("subscribe"), 1);
+
+            server.publish_event(event1.clone()).unwrap();
+            assert_eq!(subs1.next().await.unwrap().unwrap(), event1);
+            assert_eq!(subs2.next().await.unwrap().unwrap(), event1);
+
+            // The subscription on the server outlives the first subscription
+            // to be dropped.
+            drop(subs1);
+            server.publish_event(event2.clone()).unwrap();
+            assert_eq!(subs2.next().await.unwrap().unwrap(), event2);
+            assert_eq!(server.received_requests("unsubscribe"), 0);
+
+            // Dropping the last subscription unsubscribes from the server.
+            drop(subs2);
+            for _ in 0..100 {
+                if server.received_requests("unsubscribe") > 0 {
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            assert_eq!(server.received_requests("unsubscribe"), 1);
+
+            client.close().unwrap();
+            server.terminate().await.unwrap();
+            let _ = driver_handle.await.unwrap();
+        }
+
+        #[tokio::test]
+        async fn websocket_client_unsubscribes_shared_subscriptions() {
+            let event = read_event("subscribe_newblock_0").await;
+
+            let mut server = TestServer::new("127.0.0.1:0", TestRpcVersion::V0_38).await;
+            let url = server.node_addr.clone().try_into().unwrap();
+            let (client, driver) = WebSocketClient::builder(url)
+                .compat_mode(CompatMode::V0_37)
+                .build()
+                .await
+                .unwrap();
+            let driver_handle = tokio::spawn(async move { driver.run().await });
+
+            let query: Query = EventType::NewBlock.into();
+            let subs1 = client.subscribe(query.clone()).await.unwrap();
+            let mut subs2 = client.subscribe(query.clone()).await.unwrap();
+            assert_eq!(server.received_requests("subscribe"), 1);
+
+            // Unsubscribing one of the subscriptions keeps the other one.
+            subs1.unsubscribe().await.unwrap();
+            assert_eq!(server.received_requests("unsubscribe"), 0);
+            server.publish_event(event.clone()).unwrap();
+            assert_eq!(subs2.next().await.unwrap().unwrap(), event);
+
+            // Unsubscribing the last subscription waits for the server.
+            subs2.unsubscribe().await.unwrap();
+            assert_eq!(server.received_requests("unsubscribe"), 1);
+
+            client.close().unwrap();
+            server.terminate().await.unwrap();
+            let _ = driver_handle.await.unwrap();
+        }
+
+        #[tokio::test]
+        async fn websocket_client_answers_subscriptions_sharing_an_abandoned_one() {
+            use futures::FutureExt;
+
+            let event = read_event("subscribe_newblock_0").await;
+
+            let mut server = TestServer::new("127.0.0.1:0", TestRpcVersion::V0_38).await;
+            let url = server.node_addr.clone().try_into().unwrap();
+            let (client, driver) = WebSocketClient::builder(url)
+                .compat_mode(CompatMode::V0_37)
+                .build()
+                .await
+                .unwrap();
+            let driver_handle = tokio::spawn(async move { driver.run().await });
+
+            // The first subscriber gives up before the subscription is
+            // confirmed, while a second one waits on the same request.
+            let query: Query = EventType::NewBlock.into();
+            assert!(client.subscribe(query.clone()).now_or_never().is_none());
+            let mut subs = client.subscribe(query).await.unwrap();
+            assert_eq!(server.received_requests("subscribe"), 1);
+
+            server.publish_event(event.clone()).unwrap();
+            assert_eq!(subs.next().await.unwrap().unwrap(), event);
+
+            drop(subs);
+            client.close().unwrap();
+            server.terminate().await.unwrap();
+            driver_handle.await.unwrap().unwrap();
+        }
+    }
+
+    // A minimal HTTP proxy accepting a single CONNECT request, replying with
+    // the given status, and returning the head of the request.
+    async fn serve_connect_proxy(listener: TcpListener, status: &'static str) -> String {
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut head = String::new();
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).await.unwrap();
+            if line == "\r\n" {
+                break;
+            }
+            head.push_str(&line);
+        }
+        let target = head.split_whitespace().nth(1).unwrap().to_owned();
+        let mut client = reader.into_inner();
+        client
+            .write_all(format!("HTTP/1.1 {status}\r\n\r\n").as_bytes())
+            .await
+            .unwrap();
+        if status.starts_with("200") {
+            let mut upstream = TcpStream::connect(target).await.unwrap();
+            tokio::spawn(async move {
+                let _ = tokio::io::copy_bidirectional(&mut client, &mut upstream).await;
+            });
+        }
+        head
+    }
+
+    #[tokio::test]
+    async fn websocket_client_through_connect_proxy() {
+        let server = TestServer::new("127.0.0.1:0", TestRpcVersion::V0_38).await;
+        let proxy = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy_url =
+            Url::from_str(&format!("http://toto:tata@{}", proxy.local_addr().unwrap())).unwrap();
+        let proxy_hdl = tokio::spawn(serve_connect_proxy(proxy, "200 Connection established"));
+
+        let url: WebSocketClientUrl = server.node_addr.clone().try_into().unwrap();
+        let (client, driver) = WebSocketClient::builder(url.clone())
+            .dialer(HttpConnectDialer::new(proxy_url))
+            .build()
+            .await
+            .unwrap();
+        let driver_handle = tokio::spawn(async move { driver.run().await });
+
+        let head = proxy_hdl.await.unwrap();
+        let url = Url::from(url);
+        let authority = format!("{}:{}", url.host(), url.port());
+        assert!(head.starts_with(&format!("CONNECT {authority} HTTP/1.1\r\n")));
+        assert!(head.contains("Proxy-Authorization: Basic dG90bzp0YXRh\r\n"));
+
+        client.close().unwrap();
+        let _ = driver_handle.await.unwrap();
+        server.terminate().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn websocket_client_refused_by_connect_proxy() {
+        let server = TestServer::new("127.0.0.1:0", TestRpcVersion::V0_38).await;
+        let proxy = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy_url = Url::from_str(&format!("http://{}", proxy.local_addr().unwrap())).unwrap();
+        let proxy_hdl = tokio::spawn(serve_connect_proxy(
+            proxy,
+            "407 Proxy Authentication Required",
+        ));
+
+        let url = server.node_addr.clone().try_into().unwrap();
+        let result = WebSocketClient::builder(url)
+            .dialer(HttpConnectDialer::new(proxy_url))
+            .build()
+            .await;
+        assert!(result.is_err());
+
+        let head = proxy_hdl.await.unwrap();
+        assert!(!head.contains("Proxy-Authorization"));
+        server.terminate().await.unwrap();
+    }
+
+    async fn serve_proxy_response(listener: TcpListener, response: Vec<u8>) -> String {
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line).await.unwrap();
+        // The client may give up before the whole response is written.
+        let _ = reader.into_inner().write_all(&response).await;
+        request_line
+    }
+
+    #[tokio::test]
+    async fn connect_proxy_dialer_brackets_ipv6_hosts() {
+        let proxy = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy_url = Url::from_str(&format!("http://{}", proxy.local_addr().unwrap())).unwrap();
+        let proxy_hdl = tokio::spawn(serve_proxy_response(
+            proxy,
+            b"HTTP/1.1 200 Connection established\r\n\r\n".to_vec(),
+        ));
+
+        HttpConnectDialer::new(proxy_url)
+            .dial("::1", 26657)
+            .await
+            .unwrap();
+        let request_line = proxy_hdl.await.unwrap();
+        assert_eq!(request_line, "CONNECT [::1]:26657 HTTP/1.1\r\n");
+    }
+
+    #[tokio::test]
+    async fn connect_proxy_dialer_bounds_the_response() {
+        let long_line = format!("HTTP/1.1 200 {}\r\n\r\n", "a".repeat(16 * 1024));
+        let many_lines = format!(
+            "HTTP/1.1 200 OK\r\n{}\r\n",
+            "X-Padding: aaaaaaaaaaaaaaaaaaaa\r\n".repeat(4096)
+        );
+        for response in [long_line, many_lines] {
+            let proxy = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let proxy_url =
+                Url::from_str(&format!("http://{}", proxy.local_addr().unwrap())).unwrap();
+            let proxy_hdl = tokio::spawn(serve_proxy_response(proxy, response.into_bytes()));
+
+            let err = match HttpConnectDialer::new(proxy_url)
+                .dial("127.0.0.1", 26657)
+                .await
+            {
+                Ok(_) => panic!("expected the response of the proxy to be refused"),
+                Err(e) => e,
+            };
+            assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+            proxy_hdl.await.unwrap();
+        }
     }
 
     fn authorization(req: &http::Request<()>) -> Option<&str> {
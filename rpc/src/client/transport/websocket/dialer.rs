@@ -0,0 +1,208 @@
+//! Pluggable establishment of the connections underlying WebSocket clients.
+
+use alloc::sync::Arc;
+use core::fmt;
+use std::io;
+
+use async_trait::async_trait;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader},
+    net::TcpStream,
+};
+
+use crate::{
+    client::transport::{
+        auth::authorize,
+        dns::{HappyEyeballsConfig, Resolver, SystemResolver},
+    },
+    prelude::*,
+    Url,
+};
+
+/// A bidirectional byte stream over which a WebSocket connection (and, for
+/// secure endpoints, the TLS session carrying it) can be established.
+pub trait DialedStream: AsyncRead + AsyncWrite + Unpin + Send + 'static {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send + 'static> DialedStream for T {}
+
+/// Opens the connections underlying [`WebSocketClient`]s.
+///
+/// The default dialer, [`TcpDialer`], connects directly to the RPC endpoint.
+/// Custom dialers allow routing the connections through HTTP proxies (see
+/// [`HttpConnectDialer`]), Tor, or in-memory transports for tests.
+///
+/// [`WebSocketClient`]: super::WebSocketClient
+#[async_trait]
+pub trait Dialer: Send + Sync {
+    /// Open a connection to the given host and port.
+    async fn dial(&self, host: &str, port: u16) -> io::Result<Box<dyn DialedStream>>;
+}
+
+/// A [`Dialer`] opening a direct TCP connection to the RPC endpoint.
+///
+/// The host of the endpoint is resolved with the configured [`Resolver`],
+/// and the resolved addresses are raced as described in the
+/// [`dns`](crate::client::dns) module, so that unreachable addresses of
+/// dual-stack hosts do not stall the connection.
+#[derive(Clone)]
+pub struct TcpDialer {
+    resolver: Arc<dyn Resolver>,
+    happy_eyeballs: HappyEyeballsConfig,
+}
+
+impl TcpDialer {
+    /// Construct a dialer resolving host names with the [`SystemResolver`],
+    /// and the default [`HappyEyeballsConfig`].
+    pub fn new() -> Self {
+        Self {
+            resolver: Arc::new(SystemResolver),
+            happy_eyeballs: HappyEyeballsConfig::default(),
+        }
+    }
+
+    /// Resolve host names with the given resolver.
+    pub fn resolver(mut self, resolver: impl Resolver + 'static) -> Self {
+        self.resolver = Arc::new(resolver);
+        self
+    }
+
+    /// Race the connection attempts to the resolved addresses with the given
+    /// configuration.
+    pub fn happy_eyeballs(mut self, config: HappyEyeballsConfig) -> Self {
+        self.happy_eyeballs = config;
+        self
+    }
+}
+
+impl Default for TcpDialer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for TcpDialer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TcpDialer")
+            .field("happy_eyeballs", &self.happy_eyeballs)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl Dialer for TcpDialer {
+    async fn dial(&self, host: &str, port: u16) -> io::Result<Box<dyn DialedStream>> {
+        let stream = self
+            .happy_eyeballs
+            .connect(self.resolver.as_ref(), host, port)
+            .await?;
+        Ok(Box::new(stream))
+    }
+}
+
+/// The maximum size of a line of the response of a proxy to a `CONNECT`
+/// request.
+const MAX_PROXY_LINE_SIZE: usize = 8 * 1024;
+
+/// The maximum size of the response of a proxy to a `CONNECT` request, up to
+/// the end of its headers.
+const MAX_PROXY_RESPONSE_SIZE: usize = 64 * 1024;
+
+/// A [`Dialer`] tunneling the connections through an HTTP proxy, with the
+/// `CONNECT` method.
+///
+/// Credentials in the URL of the proxy are supplied to the proxy with HTTP
+/// Basic authentication. Responses of the proxy with lines longer than 8 KiB,
+/// or headers larger than 64 KiB, are refused.
+#[derive(Clone, Debug)]
+pub struct HttpConnectDialer {
+    proxy_url: Url,
+}
+
+impl HttpConnectDialer {
+    /// Construct a dialer tunneling the connections through the HTTP proxy
+    /// at the given URL.
+    pub fn new(proxy_url: Url) -> Self {
+        Self { proxy_url }
+    }
+
+    /// The URL of the proxy.
+    pub fn proxy_url(&self) -> &Url {
+        &self.proxy_url
+    }
+}
+
+#[async_trait]
+impl Dialer for HttpConnectDialer {
+    async fn dial(&self, host: &str, port: u16) -> io::Result<Box<dyn DialedStream>> {
+        let mut stream = TcpStream::connect((self.proxy_url.host(), self.proxy_url.port())).await?;
+
+        let authority = if host.contains(':') && !host.starts_with('[') {
+            // IPv6 literals are enclosed in brackets in authorities.
+            format!("[{host}]:{port}")
+        } else {
+            format!("{host}:{port}")
+        };
+        let mut request = format!("CONNECT {authority} HTTP/1.1\r\nHost: {authority}\r\n");
+        if let Some(auth) = authorize(self.proxy_url.as_ref()) {
+            request.push_str(&format!("Proxy-Authorization: {auth}\r\n"));
+        }
+        request.push_str("\r\n");
+        stream.write_all(request.as_bytes()).await?;
+
+        // Read the response of the proxy up to the end of its headers, without
+        // consuming anything past them, which belongs to the tunnel.
+        let mut reader = BufReader::with_capacity(1, stream);
+        let mut remaining = MAX_PROXY_RESPONSE_SIZE;
+        let status_line = read_proxy_line(&mut reader, &mut remaining).await?;
+        let status = status_line.split_whitespace().nth(1);
+        if !status_line.starts_with("HTTP/1.") || status.is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid response from proxy: {:?}", status_line.trim_end()),
+            ));
+        }
+        if status != Some("200") {
+            return Err(io::Error::new(
+                io::ErrorKind::ConnectionRefused,
+                format!(
+                    "proxy refused to connect to {authority}: {}",
+                    status_line.trim_end()
+                ),
+            ));
+        }
+        loop {
+            let line = read_proxy_line(&mut reader, &mut remaining).await?;
+            if line == "\r\n" || line == "\n" {
+                break;
+            }
+        }
+
+        Ok(Box::new(reader.into_inner()))
+    }
+}
+
+/// Read a line of the response of a proxy, of at most
+/// [`MAX_PROXY_LINE_SIZE`] bytes, nor more than the given remaining size of
+/// the response, which is updated.
+async fn read_proxy_line(
+    reader: &mut BufReader<TcpStream>,
+    remaining: &mut usize,
+) -> io::Result<String> {
+    let limit = MAX_PROXY_LINE_SIZE.min(*remaining);
+    let mut line = String::new();
+    let n = reader.take(limit as u64).read_line(&mut line).await?;
+    if n == 0 {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    if !line.ends_with('\n') {
+        if n < limit {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "response from proxy is too large",
+        ));
+    }
+    *remaining -= n;
+    Ok(line)
+}
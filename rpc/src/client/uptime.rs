@@ -0,0 +1,343 @@
+//! Tracking of the signing performance of validators.
+//!
+//! An [`UptimeTracker`] is fed with the commits of successive blocks, and
+//! the validator sets which signed them, and maintains, for each validator,
+//! the number of blocks it signed and missed overall and within a sliding
+//! window of recent blocks, as well as its streaks of missed blocks.
+//!
+//! As in the slashing module of the Cosmos SDK, a validator voting `nil` is
+//! considered to have signed the block; only absent votes count as missed.
+
+use alloc::collections::{BTreeMap, VecDeque};
+
+use tendermint::{
+    account,
+    block::{Commit, CommitSig, Height},
+    validator,
+};
+
+use crate::{client::Client, prelude::*, Error, Paging};
+
+/// Configuration of an [`UptimeTracker`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct UptimeConfig {
+    /// The number of most recent blocks over which the signing statistics
+    /// of the validators are windowed.
+    pub window: usize,
+}
+
+impl Default for UptimeConfig {
+    fn default() -> Self {
+        Self { window: 100 }
+    }
+}
+
+/// The signing statistics of a validator, over the blocks recorded while it
+/// was in the validator set.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SigningStats {
+    /// The number of blocks signed by the validator.
+    pub signed: u64,
+    /// The number of blocks the validator signed with a `nil` vote, which
+    /// are also counted as signed.
+    pub nil_votes: u64,
+    /// The number of blocks missed by the validator.
+    pub missed: u64,
+    /// The number of blocks in the window which were missed by the
+    /// validator.
+    pub missed_in_window: usize,
+    /// The number of blocks in the window, which is smaller than the
+    /// configured window until enough blocks were recorded.
+    pub window_len: usize,
+    /// The number of consecutive blocks missed by the validator, up to the
+    /// latest recorded block.
+    pub missed_streak: u64,
+    /// The longest run of consecutive blocks missed by the validator.
+    pub longest_missed_streak: u64,
+    /// The height of the latest block signed by the validator.
+    pub last_signed: Option<Height>,
+}
+
+impl SigningStats {
+    /// The fraction of the blocks in the window signed by the validator, or
+    /// `None` if no block was recorded yet.
+    pub fn uptime(&self) -> Option<f64> {
+        if self.window_len == 0 {
+            return None;
+        }
+        let signed = self.window_len - self.missed_in_window;
+        Some(signed as f64 / self.window_len as f64)
+    }
+}
+
+#[derive(Debug, Default)]
+struct ValidatorRecord {
+    stats: SigningStats,
+    /// Whether each block of the window was signed, oldest first.
+    window: VecDeque<bool>,
+}
+
+impl ValidatorRecord {
+    fn record(&mut self, height: Height, signed: bool, window: usize) {
+        let stats = &mut self.stats;
+        if signed {
+            stats.signed += 1;
+            stats.missed_streak = 0;
+            stats.last_signed = Some(height);
+        } else {
+            stats.missed += 1;
+            stats.missed_streak += 1;
+            stats.longest_missed_streak = stats.longest_missed_streak.max(stats.missed_streak);
+            stats.missed_in_window += 1;
+        }
+
+        self.window.push_back(signed);
+        while self.window.len() > window {
+            if self.window.pop_front() == Some(false) {
+                stats.missed_in_window -= 1;
+            }
+        }
+        stats.window_len = self.window.len();
+    }
+}
+
+/// Maintains the signing statistics of validators over successive commits.
+///
+/// ## Examples
+///
+/// ```rust,ignore
+/// use tendermint_rpc::{client::uptime::{UptimeConfig, UptimeTracker}, HttpClient};
+///
+/// let client = HttpClient::new("http://127.0.0.1:26657").unwrap();
+/// let mut tracker = UptimeTracker::new(UptimeConfig::default());
+/// for height in 1..=100_u32 {
+///     tracker.fetch_and_record(&client, height.into()).await?;
+/// }
+/// for (address, stats) in tracker.iter() {
+///     println!("{address}: {:?}", stats.uptime());
+/// }
+/// ```
+#[derive(Debug)]
+pub struct UptimeTracker {
+    config: UptimeConfig,
+    validators: BTreeMap<account::Id, ValidatorRecord>,
+    latest_height: Option<Height>,
+}
+
+impl UptimeTracker {
+    /// Create a tracker which did not record any commit yet.
+    pub fn new(config: UptimeConfig) -> Self {
+        Self {
+            config,
+            validators: BTreeMap::new(),
+            latest_height: None,
+        }
+    }
+
+    /// Record the signatures of the given commit, made by the given
+    /// validators, in the order of the commit signatures.
+    ///
+    /// Commits are expected in increasing order of height; a commit at a
+    /// height which is not higher than the latest recorded one is ignored,
+    /// and `false` is returned.
+    ///
+    /// Fails, without recording anything, if the validators do not match
+    /// the signatures of the commit: if their numbers differ, or if the
+    /// address of a signature is not the one of its validator. Absent votes
+    /// carry no address.
+    pub fn record_commit(
+        &mut self,
+        commit: &Commit,
+        validators: &[validator::Info],
+    ) -> Result<bool, Error> {
+        if let Some(latest) = self.latest_height {
+            if commit.height <= latest {
+                return Ok(false);
+            }
+        }
+        let mismatch = |detail| Error::commit_validators_mismatch(commit.height, detail);
+        if validators.len() != commit.signatures.len() {
+            return Err(mismatch(format!(
+                "{} validators for {} signatures",
+                validators.len(),
+                commit.signatures.len()
+            )));
+        }
+        for (index, (validator, signature)) in validators.iter().zip(&commit.signatures).enumerate()
+        {
+            if let Some(address) = signature.validator_address() {
+                if address != validator.address {
+                    return Err(mismatch(format!(
+                        "signature {index} is by {address}, not by {}",
+                        validator.address
+                    )));
+                }
+            }
+        }
+        self.latest_height = Some(commit.height);
+
+        for (validator, signature) in validators.iter().zip(&commit.signatures) {
+            let record = self.validators.entry(validator.address).or_default();
+            let signed = match signature {
+                CommitSig::BlockIdFlagAbsent => false,
+                CommitSig::BlockIdFlagCommit { .. } => true,
+                CommitSig::BlockIdFlagNil { .. } => {
+                    record.stats.nil_votes += 1;
+                    true
+                },
+            };
+            record.record(commit.height, signed, self.config.window);
+        }
+        Ok(true)
+    }
+
+    /// Fetch the commit of the block at the given height, and the validators
+    /// which signed it, and record them.
+    pub async fn fetch_and_record<C>(&mut self, client: &C, height: Height) -> Result<bool, Error>
+    where
+        C: Client + Sync,
+    {
+        let commit = client.commit(height).await?.signed_header.commit;
+        let validators = client.validators(height, Paging::All).await?.validators;
+        self.record_commit(&commit, &validators)
+    }
+
+    /// The height of the latest recorded commit.
+    pub fn latest_height(&self) -> Option<Height> {
+        self.latest_height
+    }
+
+    /// The signing statistics of the validator with the given address, if it
+    /// was in the validator set of any recorded commit.
+    pub fn stats(&self, address: &account::Id) -> Option<&SigningStats> {
+        self.validators.get(address).map(|record| &record.stats)
+    }
+
+    /// The signing statistics of all the validators which were in the
+    /// validator set of any recorded commit, by address.
+    pub fn iter(&self) -> impl Iterator<Item = (&account::Id, &SigningStats)> {
+        self.validators
+            .iter()
+            .map(|(address, record)| (address, &record.stats))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tendermint::{block, PublicKey, Time};
+
+    use super::*;
+
+    fn validators(count: u8) -> Vec<validator::Info> {
+        (1..=count)
+            .map(|i| {
+                let pk = PublicKey::from_raw_ed25519(&[i; 32]).unwrap();
+                validator::Info::new(pk, 10_u32.into())
+            })
+            .collect()
+    }
+
+    fn commit(height: u32, validators: &[validator::Info], flags: &[char]) -> Commit {
+        let signatures = validators
+            .iter()
+            .zip(flags)
+            .map(|(validator, flag)| match flag {
+                'c' => CommitSig::BlockIdFlagCommit {
+                    validator_address: validator.address,
+                    timestamp: Time::unix_epoch(),
+                    signature: None,
+                },
+                'n' => CommitSig::BlockIdFlagNil {
+                    validator_address: validator.address,
+                    timestamp: Time::unix_epoch(),
+                    signature: None,
+                },
+                _ => CommitSig::BlockIdFlagAbsent,
+            })
+            .collect();
+        Commit {
+            height: height.into(),
+            round: Default::default(),
+            block_id: block::Id::default(),
+            signatures,
+        }
+    }
+
+    #[test]
+    fn tracks_missed_blocks_and_streaks() {
+        let validators = validators(2);
+        let mut tracker = UptimeTracker::new(UptimeConfig { window: 3 });
+        let rounds = [['c', 'a'], ['a', 'a'], ['a', 'n'], ['c', 'a'], ['c', 'c']];
+        for (i, flags) in rounds.iter().enumerate() {
+            assert!(tracker
+                .record_commit(&commit(i as u32 + 1, &validators, flags), &validators)
+                .unwrap());
+        }
+
+        let first = tracker.stats(&validators[0].address).unwrap();
+        assert_eq!(first.signed, 3);
+        assert_eq!(first.missed, 2);
+        assert_eq!(first.longest_missed_streak, 2);
+        assert_eq!(first.missed_streak, 0);
+        assert_eq!(first.last_signed, Some(5_u32.into()));
+        // The window holds the blocks at heights 3 to 5.
+        assert_eq!(first.window_len, 3);
+        assert_eq!(first.missed_in_window, 1);
+
+        let second = tracker.stats(&validators[1].address).unwrap();
+        assert_eq!(second.signed, 2);
+        assert_eq!(second.nil_votes, 1);
+        assert_eq!(second.missed, 3);
+        assert_eq!(second.longest_missed_streak, 2);
+        assert_eq!(second.missed_in_window, 1);
+        assert_eq!(second.uptime(), Some(2.0 / 3.0));
+
+        assert_eq!(tracker.iter().count(), 2);
+        assert_eq!(tracker.latest_height(), Some(5_u32.into()));
+    }
+
+    #[test]
+    fn ignores_commits_out_of_order() {
+        let validators = validators(1);
+        let mut tracker = UptimeTracker::new(UptimeConfig::default());
+        let mut record = |height, flag| {
+            tracker
+                .record_commit(&commit(height, &validators, &[flag]), &validators)
+                .unwrap()
+        };
+        assert!(record(2, 'a'));
+        assert!(!record(2, 'c'));
+        assert!(!record(1, 'c'));
+
+        let stats = tracker.stats(&validators[0].address).unwrap();
+        assert_eq!(stats.missed, 1);
+        assert_eq!(stats.signed, 0);
+        assert_eq!(stats.uptime(), Some(0.0));
+        assert_eq!(stats.last_signed, None);
+    }
+
+    #[test]
+    fn rejects_mismatching_validators() {
+        let validators = validators(3);
+        let mut tracker = UptimeTracker::new(UptimeConfig::default());
+        let commit = commit(1, &validators, &['c', 'a', 'n']);
+        assert!(tracker.record_commit(&commit, &validators[..2]).is_err());
+
+        let swapped = [
+            validators[0].clone(),
+            validators[2].clone(),
+            validators[1].clone(),
+        ];
+        assert!(tracker.record_commit(&commit, &swapped).is_err());
+        // The second validator is absent, and its address is not checked.
+        let absent_swapped = [
+            validators[0].clone(),
+            validators[0].clone(),
+            validators[2].clone(),
+        ];
+        assert!(tracker.record_commit(&commit, &absent_swapped).unwrap());
+
+        assert_eq!(tracker.latest_height(), Some(1_u32.into()));
+        assert!(tracker.stats(&validators[1].address).is_none());
+    }
+}
@@ -0,0 +1,110 @@
+//! Verification of the commits served by a full node.
+
+use tendermint::{block::signed_header::SignedHeader, validator};
+use tendermint_light_client_verifier::{
+    errors::VerificationError,
+    operations::{ProdCommitValidator, ProdVotingPowerCalculator, VotingPowerCalculator},
+    predicates::{ProdPredicates, VerificationPredicates},
+    types::TrustThreshold,
+};
+
+/// Verify that the `signed_header` carries a valid commit by the `validators`
+/// at its height, and that it has been signed by more than 2/3 of the voting
+/// power of the `trusted` validator set.
+///
+/// The `validators` are typically retrieved from the same, untrusted, full node
+/// as the signed header, while the `trusted` validator set is provided by the
+/// caller. The two sets can differ, as long as enough of the trusted voting
+/// power signed the commit.
+pub fn verify_commit(
+    signed_header: &SignedHeader,
+    validators: &validator::Set,
+    trusted: &validator::Set,
+) -> Result<(), VerificationError> {
+    let predicates = ProdPredicates;
+    let calculator = ProdVotingPowerCalculator::default();
+
+    predicates.validator_sets_match(validators, signed_header.header.validators_hash)?;
+    predicates.header_matches_commit(&signed_header.header, signed_header.commit.block_id.hash)?;
+    predicates.valid_commit(signed_header, validators, &ProdCommitValidator)?;
+
+    if trusted.hash() != validators.hash() {
+        calculator.check_signers_overlap(signed_header, validators)?;
+    }
+    calculator.check_enough_trust(signed_header, trusted, TrustThreshold::TWO_THIRDS)
+}
+
+#[cfg(test)]
+mod tests {
+    use tendermint_testgen::{
+        helpers::get_time, light_block::TmLightBlock, Commit, Generator, Header, LightBlock,
+        Validator,
+    };
+
+    use super::*;
+
+    fn light_block(validators: &[Validator]) -> TmLightBlock {
+        let header = Header::new(validators)
+            .next_validators(validators)
+            .height(1)
+            .time(get_time(1).unwrap());
+        let commit = Commit::new(header.clone(), 1);
+        LightBlock::new(header, commit).generate().unwrap()
+    }
+
+    fn validator_set(validators: &[Validator]) -> validator::Set {
+        light_block(validators).validators
+    }
+
+    #[test]
+    fn verify_commit_by_trusted_validators() {
+        let validators = [
+            Validator::new("a").voting_power(10),
+            Validator::new("b").voting_power(10),
+            Validator::new("c").voting_power(10),
+        ];
+        let lb = light_block(&validators);
+        verify_commit(&lb.signed_header, &lb.validators, &lb.validators).unwrap();
+
+        // A different trusted set is fine, as long as it signed enough of the commit
+        let trusted = validator_set(&[
+            Validator::new("a").voting_power(10),
+            Validator::new("b").voting_power(10),
+            Validator::new("c").voting_power(10),
+            Validator::new("d").voting_power(1),
+        ]);
+        verify_commit(&lb.signed_header, &lb.validators, &trusted).unwrap();
+    }
+
+    #[test]
+    fn reject_commit_by_untrusted_validators() {
+        let lb = light_block(&[
+            Validator::new("a").voting_power(10),
+            Validator::new("b").voting_power(10),
+            Validator::new("c").voting_power(10),
+        ]);
+        let trusted = validator_set(&[
+            Validator::new("a").voting_power(10),
+            Validator::new("d").voting_power(10),
+            Validator::new("e").voting_power(10),
+        ]);
+        assert!(verify_commit(&lb.signed_header, &lb.validators, &trusted).is_err());
+    }
+
+    #[test]
+    fn reject_commit_with_mismatched_validators() {
+        let lb = light_block(&[
+            Validator::new("a").voting_power(10),
+            Validator::new("b").voting_power(10),
+        ]);
+        let other = validator_set(&[
+            Validator::new("a").voting_power(10),
+            Validator::new("c").voting_power(10),
+        ]);
+        assert!(verify_commit(&lb.signed_header, &other, &lb.validators).is_err());
+
+        let mut signed_header = lb.signed_header.clone();
+        signed_header.header.chain_id = "other-chain".parse().unwrap();
+        assert!(verify_commit(&signed_header, &lb.validators, &lb.validators).is_err());
+    }
+}
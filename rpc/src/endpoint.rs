@@ -17,9 +17,11 @@ pub mod header;
 pub mod header_by_hash;
 pub mod health;
 pub mod net_info;
+pub mod num_unconfirmed_txs;
 pub mod status;
 pub mod subscribe;
 pub mod tx;
 pub mod tx_search;
+pub mod unconfirmed_txs;
 pub mod unsubscribe;
 pub mod validators;
@@ -3,9 +3,11 @@
 use serde::{Deserialize, Serialize};
 use tendermint::{abci, block, consensus, serializers, validator, AppHash};
 
+use crate::client::CompatMode;
 use crate::dialect::{self, Dialect};
 use crate::prelude::*;
 use crate::request::RequestMessage;
+use crate::Error;
 
 /// Get ABCI results at a given height.
 #[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
@@ -92,6 +94,49 @@ pub struct Response {
 
 impl crate::Response for Response {}
 
+impl Response {
+    /// Deserialize a `/block_results` response in the JSON format of the
+    /// given protocol dialect.
+    ///
+    /// The events of the block are found in [`begin_block_events`] and
+    /// [`end_block_events`] for nodes running versions of CometBFT prior to
+    /// 0.38, and in [`finalize_block_events`] since then, irrespective of the
+    /// compatibility mode. [`block_events`] iterates over all of them.
+    ///
+    /// [`begin_block_events`]: Self::begin_block_events
+    /// [`end_block_events`]: Self::end_block_events
+    /// [`finalize_block_events`]: Self::finalize_block_events
+    /// [`block_events`]: Self::block_events
+    pub fn from_string_with_compat(
+        response: impl AsRef<[u8]>,
+        compat: CompatMode,
+    ) -> Result<Self, Error> {
+        use crate::Response as _;
+
+        match compat {
+            CompatMode::V0_34 => v0_34::DialectResponse::from_string(response).map(Into::into),
+            CompatMode::V0_37 => Self::from_string(response),
+        }
+    }
+
+    /// The results of the transactions of the block, in block order.
+    pub fn tx_results(&self) -> &[abci::types::ExecTxResult] {
+        self.txs_results.as_deref().unwrap_or_default()
+    }
+
+    /// The events emitted by the application for the block itself, rather
+    /// than for its transactions, in the order they were emitted: the events
+    /// of `BeginBlock`, `FinalizeBlock` and `EndBlock`, depending on the
+    /// protocol version of the node.
+    pub fn block_events(&self) -> impl Iterator<Item = &abci::Event> {
+        let begin_block_events = self.begin_block_events.iter().flatten();
+        let end_block_events = self.end_block_events.iter().flatten();
+        begin_block_events
+            .chain(&self.finalize_block_events)
+            .chain(end_block_events)
+    }
+}
+
 /// Serialization for /block_results endpoint format in Tendermint 0.34
 pub mod v0_34 {
     use super::Response;
@@ -147,3 +192,108 @@ pub mod v0_34 {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const V0_34_RESPONSE: &str = r#"{
+        "jsonrpc": "2.0",
+        "id": 1,
+        "result": {
+            "height": "12",
+            "txs_results": [
+                {
+                    "code": 0,
+                    "data": null,
+                    "log": "",
+                    "info": "",
+                    "gas_wanted": "10",
+                    "gas_used": "5",
+                    "events": [
+                        {
+                            "type": "app",
+                            "attributes": [
+                                { "key": "a2V5", "value": "dmFsdWU=", "index": true }
+                            ]
+                        }
+                    ],
+                    "codespace": ""
+                }
+            ],
+            "begin_block_events": [
+                {
+                    "type": "begin",
+                    "attributes": [
+                        { "key": "a2V5", "value": "dmFsdWU=", "index": true }
+                    ]
+                }
+            ],
+            "end_block_events": [
+                { "type": "end", "attributes": [] }
+            ],
+            "validator_updates": null,
+            "consensus_param_updates": null
+        }
+    }"#;
+
+    const V0_38_RESPONSE: &str = r#"{
+        "jsonrpc": "2.0",
+        "id": 1,
+        "result": {
+            "height": "12",
+            "txs_results": [
+                {
+                    "code": 0,
+                    "data": null,
+                    "log": "",
+                    "info": "",
+                    "gas_wanted": "10",
+                    "gas_used": "5",
+                    "events": [
+                        {
+                            "type": "app",
+                            "attributes": [
+                                { "key": "key", "value": "value", "index": true }
+                            ]
+                        }
+                    ],
+                    "codespace": ""
+                }
+            ],
+            "finalize_block_events": [
+                {
+                    "type": "begin",
+                    "attributes": [
+                        { "key": "key", "value": "value", "index": true }
+                    ]
+                },
+                { "type": "end", "attributes": [] }
+            ],
+            "validator_updates": [],
+            "consensus_param_updates": null
+        }
+    }"#;
+
+    #[test]
+    fn unifies_response_shapes() {
+        let v0_34 = Response::from_string_with_compat(V0_34_RESPONSE, CompatMode::V0_34).unwrap();
+        let v0_38 = Response::from_string_with_compat(V0_38_RESPONSE, CompatMode::V0_37).unwrap();
+
+        for response in [&v0_34, &v0_38] {
+            assert_eq!(response.height.value(), 12);
+            assert_eq!(response.tx_results().len(), 1);
+            assert_eq!(response.tx_results()[0].gas_used, 5);
+            assert_eq!(response.tx_results()[0].events[0].attributes[0].key, "key");
+
+            let kinds: Vec<_> = response.block_events().map(|e| e.kind.as_str()).collect();
+            assert_eq!(kinds, ["begin", "end"]);
+            assert_eq!(
+                response.block_events().next().unwrap().attributes[0].value,
+                "value"
+            );
+        }
+        assert!(v0_38.begin_block_events.is_none());
+        assert!(v0_34.finalize_block_events.is_empty());
+    }
+}
@@ -0,0 +1,79 @@
+//! `/num_unconfirmed_txs` endpoint JSON-RPC wrapper
+
+use serde::{Deserialize, Serialize};
+use tendermint::serializers;
+
+use crate::prelude::*;
+use crate::{dialect::Dialect, request::RequestMessage};
+
+/// Get the number and total size of the transactions in the mempool of a node
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Request;
+
+impl RequestMessage for Request {
+    fn method(&self) -> crate::Method {
+        crate::Method::NumUnconfirmedTxs
+    }
+}
+
+impl<S: Dialect> crate::Request<S> for Request {
+    type Response = Response;
+}
+
+impl<S: Dialect> crate::SimpleRequest<S> for Request {
+    type Output = Response;
+}
+
+/// Mempool size responses
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Response {
+    /// Number of transactions returned, always 0 for this endpoint
+    #[serde(with = "serializers::from_str")]
+    pub n_txs: u64,
+
+    /// Number of transactions in the mempool
+    #[serde(with = "serializers::from_str")]
+    pub total: u64,
+
+    /// Total size of the transactions in the mempool, in bytes
+    #[serde(with = "serializers::from_str")]
+    pub total_bytes: u64,
+
+    /// Transactions returned, always empty for this endpoint
+    #[serde(with = "serializers::txs")]
+    pub txs: Vec<Vec<u8>>,
+}
+
+impl crate::Response for Response {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Response as _;
+
+    #[test]
+    fn parses_response() {
+        let response = Response::from_string(
+            r#"{"jsonrpc":"2.0","id":-1,"result":{"n_txs":"0","total":"42","total_bytes":"3150","txs":null}}"#,
+        )
+        .unwrap();
+        assert_eq!(response.total, 42);
+        assert_eq!(response.total_bytes, 3150);
+        assert!(response.txs.is_empty());
+    }
+
+    #[test]
+    fn parses_numbers() {
+        let response = Response::from_string(
+            r#"{"jsonrpc":"2.0","id":-1,"result":{"n_txs":0,"total":42,"total_bytes":18446744073709551615,"txs":null}}"#,
+        )
+        .unwrap();
+        assert_eq!(response.total, 42);
+        assert_eq!(response.total_bytes, u64::MAX);
+
+        assert!(Response::from_string(
+            r#"{"jsonrpc":"2.0","id":-1,"result":{"n_txs":0,"total":-1,"total_bytes":0,"txs":null}}"#,
+        )
+        .is_err());
+    }
+}
@@ -1,7 +1,11 @@
 //! `/tx` endpoint JSON-RPC wrapper
 
 use serde::{Deserialize, Serialize};
-use tendermint::{abci, block, tx, Hash};
+use tendermint::{
+    abci, block,
+    tx::{self, DecodedTx, TxDecoder},
+    Hash,
+};
 
 use crate::dialect::{self, Dialect};
 use crate::{prelude::*, request::RequestMessage, serializers, Method};
@@ -68,6 +72,13 @@ pub struct Response {
 
 impl crate::Response for Response {}
 
+impl Response {
+    /// Decode the transaction with the given decoder.
+    pub fn decode_tx<D: TxDecoder>(&self, decoder: &D) -> Result<DecodedTx<D::Tx>, D::Error> {
+        decoder.decode_raw(&self.tx)
+    }
+}
+
 /// Serialization for /tx endpoint format in Tendermint 0.34
 pub mod v0_34 {
     use super::Response;
@@ -1,6 +1,7 @@
 //! `/tx_search` endpoint JSON-RPC wrapper
 
 use serde::{Deserialize, Serialize};
+use tendermint::tx::{DecodedTx, TxDecoder};
 
 use crate::{
     dialect::{self, Dialect},
@@ -73,6 +74,17 @@ pub struct Response {
 
 impl crate::Response for Response {}
 
+impl Response {
+    /// Decode the transactions found with the given decoder, in the order
+    /// of the results.
+    pub fn decode_txs<'a, D: TxDecoder>(
+        &'a self,
+        decoder: &'a D,
+    ) -> impl Iterator<Item = Result<DecodedTx<D::Tx>, D::Error>> + 'a {
+        self.txs.iter().map(move |tx| tx.decode_tx(decoder))
+    }
+}
+
 /// Serialization for /tx_search endpoint format in Tendermint 0.34
 pub mod v0_34 {
     use super::{tx, Response};
@@ -0,0 +1,84 @@
+//! `/unconfirmed_txs` endpoint JSON-RPC wrapper
+
+use serde::{Deserialize, Serialize};
+use tendermint::serializers;
+
+use crate::prelude::*;
+use crate::{dialect::Dialect, request::RequestMessage};
+
+/// List the transactions in the mempool of a node, along with their number
+/// and total size
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Request {
+    /// The maximum number of transactions to return. Nodes default to 30,
+    /// and cap it at 100.
+    #[serde(with = "serializers::optional_from_str")]
+    pub limit: Option<u32>,
+}
+
+impl Request {
+    /// Constructor.
+    pub fn new(limit: Option<u32>) -> Self {
+        Self { limit }
+    }
+}
+
+impl RequestMessage for Request {
+    fn method(&self) -> crate::Method {
+        crate::Method::UnconfirmedTxs
+    }
+}
+
+impl<S: Dialect> crate::Request<S> for Request {
+    type Response = Response;
+}
+
+impl<S: Dialect> crate::SimpleRequest<S> for Request {
+    type Output = Response;
+}
+
+/// Mempool transactions responses
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Response {
+    /// Number of transactions returned
+    #[serde(with = "serializers::from_str")]
+    pub n_txs: u64,
+
+    /// Number of transactions in the mempool
+    #[serde(with = "serializers::from_str")]
+    pub total: u64,
+
+    /// Total size of the transactions in the mempool, in bytes
+    #[serde(with = "serializers::from_str")]
+    pub total_bytes: u64,
+
+    /// Transactions returned, in the order they entered the mempool
+    #[serde(with = "serializers::txs")]
+    pub txs: Vec<Vec<u8>>,
+}
+
+impl crate::Response for Response {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Response as _;
+
+    #[test]
+    fn serializes_request() {
+        let request = serde_json::to_value(Request::new(Some(100))).unwrap();
+        assert_eq!(request, serde_json::json!({ "limit": "100" }));
+    }
+
+    #[test]
+    fn parses_response() {
+        let response = Response::from_string(
+            r#"{"jsonrpc":"2.0","id":-1,"result":{"n_txs":"2","total":"42","total_bytes":"3150","txs":["YWJj","ZGVm"]}}"#,
+        )
+        .unwrap();
+        assert_eq!(response.n_txs, 2);
+        assert_eq!(response.total, 42);
+        assert_eq!(response.total_bytes, 3150);
+        assert_eq!(response.txs, [b"abc".to_vec(), b"def".to_vec()]);
+    }
+}
@@ -4,7 +4,12 @@ use core::time::Duration;
 
 use flex_error::{define_error, DefaultTracer, DisplayError, DisplayOnly, ErrorMessageTracer};
 
-use crate::{prelude::*, response_error::ResponseError, rpc_url::Url};
+use crate::{
+    prelude::*,
+    response_error::{Code, ResponseError},
+    rpc_url::Url,
+    strict::FieldReport,
+};
 
 #[cfg(feature = "reqwest")]
 type ReqwestError = flex_error::DisplayOnly<reqwest::Error>;
@@ -30,6 +35,13 @@ type TungsteniteError = flex_error::DisplayOnly<async_tungstenite::tungstenite::
 #[cfg(not(feature = "async-tungstenite"))]
 type TungsteniteError = flex_error::NoSource;
 
+#[cfg(feature = "verifier")]
+type VerificationError =
+    flex_error::DisplayOnly<tendermint_light_client_verifier::errors::VerificationError>;
+
+#[cfg(not(feature = "verifier"))]
+type VerificationError = flex_error::NoSource;
+
 define_error! {
     #[derive(Debug, Clone, PartialEq, Eq)]
     Error {
@@ -216,6 +228,84 @@ define_error! {
             | e | {
                 format_args!("unsupported Tendermint version reported by the node: {}", e.version)
             },
+
+        InvalidCommit
+            {
+                height: tendermint::block::Height,
+            }
+            [ VerificationError ]
+            | e | {
+                format_args!("commit at height {} failed verification", e.height)
+            },
+
+        CommitValidatorsMismatch
+            {
+                height: tendermint::block::Height,
+                detail: String,
+            }
+            | e | {
+                format_args!("validators do not match the commit at height {}: {}",
+                    e.height, e.detail)
+            },
+
+        StrictValidation
+            {
+                report: FieldReport,
+            }
+            | e | {
+                format_args!("response does not match the expected format: {}", e.report)
+            },
+
+        ChainIdMismatch
+            {
+                expected: tendermint::chain::Id,
+                actual: tendermint::chain::Id,
+            }
+            | e | {
+                format_args!("node is on chain {}, expected {}", e.actual, e.expected)
+            },
+
+        NoRpcEndpoint
+            {
+                chain_name: String,
+            }
+            | e | {
+                format_args!("no RPC endpoint available for chain {}", e.chain_name)
+            },
+
+        DownloadTooLarge
+            {
+                url: String,
+                limit: usize,
+            }
+            | e | {
+                format_args!("download of {} exceeds the limit of {} bytes", e.url, e.limit)
+            },
+
+        ResponseTooLarge
+            {
+                method: crate::Method,
+                limit: usize,
+            }
+            | e | {
+                format_args!("response to {} request exceeds the limit of {} bytes", e.method, e.limit)
+            },
+
+        Sink
+            {
+                detail: String,
+            }
+            | e | {
+                format_args!("failed to deliver events to the sink: {}", e.detail)
+            },
+
+        Checkpoint
+            {
+                detail: String,
+            }
+            | e | {
+                format_args!("failed to access the checkpoint store: {}", e.detail)
+            },
     }
 }
 
@@ -228,9 +318,204 @@ impl Clone for Error {
     }
 }
 
+/// The broad category of an [`Error`], as returned by [`Error::kind`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The connection to the node failed, or was interrupted.
+    Transport,
+    /// The node did not respond in time.
+    Timeout,
+    /// The node processed the request, and responded with an error.
+    ///
+    /// Errors reported through a non-200 HTTP status code have the
+    /// [`Code::HttpError`] code.
+    Server { code: Code },
+    /// The response of the node could not be decoded.
+    Deserialization,
+    /// The node rejected the credentials of the client.
+    Auth,
+    /// The request could not be made, because of invalid parameters or
+    /// client configuration, or an unsupported node version.
+    Request,
+    /// The data served by the node failed verification.
+    Verification,
+    /// A resource of the client failed, such as an event sink or a
+    /// checkpoint store, rather than the node or the connection to it.
+    Local,
+}
+
+impl Error {
+    /// The broad category of the error, telling apart failures of the
+    /// connection to the node, node-side errors, and permanent request errors.
+    pub fn kind(&self) -> ErrorKind {
+        match self.detail() {
+            ErrorDetail::Io(_)
+            | ErrorDetail::Http(_)
+            | ErrorDetail::WebSocket(_)
+            | ErrorDetail::Tungstenite(_)
+            | ErrorDetail::ChannelSend(_)
+            | ErrorDetail::Join(_) => ErrorKind::Transport,
+
+            ErrorDetail::Sink(_) | ErrorDetail::Checkpoint(_) => ErrorKind::Local,
+
+            ErrorDetail::WebSocketTimeout(_) | ErrorDetail::Timeout(_) => ErrorKind::Timeout,
+
+            ErrorDetail::HttpRequestFailed(e) => match status_code(&e.status) {
+                401 | 403 => ErrorKind::Auth,
+                408 | 504 => ErrorKind::Timeout,
+                _ => ErrorKind::Server {
+                    code: Code::HttpError,
+                },
+            },
+            ErrorDetail::Response(e) => match e.source.code() {
+                Code::HttpError | Code::WebSocketError => ErrorKind::Transport,
+                Code::ClientInternalError => ErrorKind::Request,
+                code => ErrorKind::Server { code },
+            },
+            ErrorDetail::MethodNotFound(_) => ErrorKind::Server {
+                code: Code::MethodNotFound,
+            },
+            ErrorDetail::Server(_) => ErrorKind::Server {
+                code: Code::ServerError,
+            },
+
+            ErrorDetail::Parse(_)
+            | ErrorDetail::Serde(_)
+            | ErrorDetail::MalformedJson(_)
+            | ErrorDetail::MismatchResponse(_)
+            | ErrorDetail::UnrecognizedEventType(_)
+            | ErrorDetail::Tendermint(_)
+            | ErrorDetail::ParseInt(_)
+            | ErrorDetail::OutOfRange(_)
+            | ErrorDetail::StrictValidation(_) => ErrorKind::Deserialization,
+
+            ErrorDetail::InvalidProxy(_)
+            | ErrorDetail::ResponseTooLarge(_)
+            | ErrorDetail::InvalidParams(_)
+            | ErrorDetail::ClientInternal(_)
+            | ErrorDetail::InvalidUrl(_)
+            | ErrorDetail::InvalidNetworkAddress(_)
+            | ErrorDetail::ParseUrl(_)
+            | ErrorDetail::UnsupportedScheme(_)
+            | ErrorDetail::UnsupportedRpcVersion(_)
+            | ErrorDetail::InvalidTendermintVersion(_)
+            | ErrorDetail::UnsupportedTendermintVersion(_)
+            | ErrorDetail::NoRpcEndpoint(_)
+            | ErrorDetail::DownloadTooLarge(_) => ErrorKind::Request,
+
+            ErrorDetail::InvalidCommit(_)
+            | ErrorDetail::CommitValidatorsMismatch(_)
+            | ErrorDetail::ChainIdMismatch(_) => ErrorKind::Verification,
+        }
+    }
+
+    /// Whether the same request may succeed if it is tried again later.
+    ///
+    /// This holds for transport failures and timeouts, as well as for
+    /// server errors which are usually transient, such as internal errors
+    /// or HTTP statuses signaling that the node is overloaded or unavailable.
+    pub fn is_retryable(&self) -> bool {
+        match self.kind() {
+            ErrorKind::Transport | ErrorKind::Timeout => true,
+            ErrorKind::Server {
+                code: Code::HttpError,
+            } => match self.detail() {
+                ErrorDetail::HttpRequestFailed(e) => {
+                    let status = status_code(&e.status);
+                    status == 429 || status >= 500
+                },
+                _ => true,
+            },
+            ErrorKind::Server { code } => {
+                matches!(code, Code::InternalError | Code::ServerError)
+            },
+            ErrorKind::Deserialization
+            | ErrorKind::Auth
+            | ErrorKind::Request
+            | ErrorKind::Verification
+            | ErrorKind::Local => false,
+        }
+    }
+}
+
+#[cfg(feature = "reqwest")]
+fn status_code(status: &HttpStatusCode) -> u16 {
+    status.as_u16()
+}
+
+#[cfg(not(feature = "reqwest"))]
+fn status_code(status: &HttpStatusCode) -> u16 {
+    status.get()
+}
+
 #[cfg(feature = "tokio")]
 impl Error {
     pub fn send<T>(_: tokio::sync::mpsc::error::SendError<T>) -> Error {
         Error::channel_send()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn error_kinds() {
+        assert_eq!(
+            Error::timeout(Duration::from_secs(1)).kind(),
+            ErrorKind::Timeout
+        );
+        assert_eq!(Error::channel_send().kind(), ErrorKind::Transport);
+        assert_eq!(Error::malformed_json().kind(), ErrorKind::Deserialization);
+        assert_eq!(
+            Error::invalid_params("height".to_owned()).kind(),
+            ErrorKind::Request
+        );
+        assert_eq!(
+            Error::response(ResponseError::new(Code::InvalidParams, None)).kind(),
+            ErrorKind::Server {
+                code: Code::InvalidParams
+            }
+        );
+        assert_eq!(
+            Error::response(ResponseError::http_error("connection reset")).kind(),
+            ErrorKind::Transport
+        );
+        assert_eq!(Error::sink("disk full".to_owned()).kind(), ErrorKind::Local);
+        assert_eq!(
+            Error::checkpoint("permission denied".to_owned()).kind(),
+            ErrorKind::Local
+        );
+    }
+
+    #[test]
+    fn retryable_errors() {
+        assert!(Error::channel_send().is_retryable());
+        assert!(Error::timeout(Duration::from_secs(1)).is_retryable());
+        assert!(Error::response(ResponseError::new(Code::InternalError, None)).is_retryable());
+        assert!(!Error::response(ResponseError::new(Code::MethodNotFound, None)).is_retryable());
+        assert!(!Error::malformed_json().is_retryable());
+        assert!(!Error::unsupported_scheme("ftp".to_owned()).is_retryable());
+        assert!(!Error::sink("disk full".to_owned()).is_retryable());
+        assert!(!Error::checkpoint("permission denied".to_owned()).is_retryable());
+    }
+
+    #[cfg(feature = "reqwest")]
+    #[test]
+    fn http_status_errors() {
+        use reqwest::StatusCode;
+
+        let failed = |status| Error::http_request_failed(status);
+        assert_eq!(failed(StatusCode::UNAUTHORIZED).kind(), ErrorKind::Auth);
+        assert_eq!(
+            failed(StatusCode::GATEWAY_TIMEOUT).kind(),
+            ErrorKind::Timeout
+        );
+        assert!(failed(StatusCode::SERVICE_UNAVAILABLE).is_retryable());
+        assert!(failed(StatusCode::TOO_MANY_REQUESTS).is_retryable());
+        assert!(!failed(StatusCode::NOT_FOUND).is_retryable());
+        assert!(!failed(StatusCode::FORBIDDEN).is_retryable());
+    }
+}
@@ -2,9 +2,16 @@
 
 use alloc::collections::BTreeMap as HashMap;
 
-use tendermint::{abci, block, Block};
+use tendermint::{
+    abci, block,
+    tx::{DecodedTx, TxDecoder},
+    Block,
+};
 
-use crate::{prelude::*, query::EventType};
+use crate::{
+    prelude::*,
+    query::{self, EventType, Query},
+};
 
 /// An incoming event produced by a [`Subscription`].
 ///
@@ -30,6 +37,45 @@ impl Event {
             _ => None,
         }
     }
+
+    /// The height of the block of a `NewBlock` event, or of the transaction
+    /// of a `Tx` event.
+    pub fn height(&self) -> Option<block::Height> {
+        match &self.data {
+            EventData::NewBlock { block, .. } | EventData::LegacyNewBlock { block, .. } => {
+                block.as_ref().map(|block| block.header.height)
+            },
+            EventData::Tx { tx_result } => block::Height::try_from(tx_result.height).ok(),
+            EventData::GenericJsonEvent(_) => None,
+        }
+    }
+
+    /// Checks whether this event matches the given query.
+    ///
+    /// The attributes of the event are taken from the `events` map when it is
+    /// present, and otherwise collected from the ABCI events in the event data.
+    pub fn matches(&self, query: &Query) -> bool {
+        match &self.events {
+            Some(events) => query.matches(events),
+            None => query.matches(&self.data.attributes()),
+        }
+    }
+
+    /// Decode the transactions carried by this event with the given decoder:
+    /// the transaction of a `Tx` event, or the transactions of the block of
+    /// a `NewBlock` event.
+    pub fn decode_txs<D: TxDecoder>(&self, decoder: &D) -> Vec<Result<DecodedTx<D::Tx>, D::Error>> {
+        match &self.data {
+            EventData::NewBlock {
+                block: Some(block), ..
+            }
+            | EventData::LegacyNewBlock {
+                block: Some(block), ..
+            } => block.decode_txs(decoder).collect(),
+            EventData::Tx { tx_result } => vec![decoder.decode_raw(&tx_result.tx)],
+            _ => Vec::new(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -56,6 +102,84 @@ pub enum EventData {
     GenericJsonEvent(serde_json::Value),
 }
 
+impl EventData {
+    /// Collects the attributes of the ABCI events in the event data, keyed by
+    /// their composite `<event type>.<attribute key>` name, together with the
+    /// event type under the `tm.event` key and the attributes added by the
+    /// node: `block.height` for blocks, and `tx.height` and `tx.hash` for
+    /// transactions.
+    ///
+    /// The hash of a transaction is only computed with the `rust-crypto`
+    /// feature.
+    pub fn attributes(&self) -> HashMap<String, Vec<String>> {
+        let (event_type, mut attributes) = match self {
+            EventData::NewBlock {
+                result_finalize_block,
+                ..
+            } => (
+                Some(EventType::NewBlock),
+                result_finalize_block
+                    .as_ref()
+                    .map(|result| query::event_attributes(&result.events))
+                    .unwrap_or_default(),
+            ),
+            EventData::LegacyNewBlock {
+                result_begin_block,
+                result_end_block,
+                ..
+            } => {
+                let events: Vec<abci::Event> = result_begin_block
+                    .iter()
+                    .flat_map(|result| result.events.iter())
+                    .chain(
+                        result_end_block
+                            .iter()
+                            .flat_map(|result| result.events.iter()),
+                    )
+                    .cloned()
+                    .collect();
+                (Some(EventType::NewBlock), query::event_attributes(&events))
+            },
+            EventData::Tx { tx_result } => {
+                let mut attributes = query::event_attributes(&tx_result.result.events);
+                attributes.insert("tx.height".to_owned(), vec![tx_result.height.to_string()]);
+                #[cfg(feature = "rust-crypto")]
+                attributes.insert("tx.hash".to_owned(), vec![tx_hash(&tx_result.tx)]);
+                (Some(EventType::Tx), attributes)
+            },
+            EventData::GenericJsonEvent(_) => (None, HashMap::new()),
+        };
+        if let EventData::NewBlock {
+            block: Some(block), ..
+        }
+        | EventData::LegacyNewBlock {
+            block: Some(block), ..
+        } = self
+        {
+            attributes.insert(
+                "block.height".to_owned(),
+                vec![block.header.height.to_string()],
+            );
+        }
+        if let Some(event_type) = event_type {
+            attributes.insert("tm.event".to_owned(), vec![event_type.to_string()]);
+        }
+        attributes
+    }
+}
+
+/// The hash of a transaction, as reported by the node: the upper-case hex
+/// encoding of its SHA-256 digest.
+#[cfg(feature = "rust-crypto")]
+fn tx_hash(tx: &[u8]) -> String {
+    use tendermint::{
+        crypto::{default::Sha256, Sha256 as _},
+        Hash,
+    };
+
+    Hash::Sha256(Sha256::digest(tx)).to_string()
+}
+
 /// Transaction result info.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct TxInfo {
@@ -561,3 +685,46 @@ pub mod v0_38 {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Response;
+
+    fn fixture(name: &str) -> Event {
+        let json = std::fs::read_to_string(format!(
+            "./tests/kvstore_fixtures/v0_38/incoming/{name}.json"
+        ))
+        .unwrap();
+        v0_38::DeEvent::from_string(json).unwrap().into()
+    }
+
+    #[test]
+    fn new_block_attributes() {
+        let event = fixture("subscribe_newblock_0");
+        let attributes = event.data.attributes();
+        assert_eq!(
+            attributes["block.height"],
+            vec![event.height().unwrap().to_string()]
+        );
+        assert_eq!(attributes["tm.event"], vec!["NewBlock".to_owned()]);
+    }
+
+    #[test]
+    fn tx_attributes() {
+        let event = fixture("subscribe_txs_0");
+        let attributes = event.data.attributes();
+        let events = event.events.as_ref().unwrap();
+        assert_eq!(attributes["tx.height"], events["tx.height"]);
+        #[cfg(feature = "rust-crypto")]
+        {
+            assert_eq!(attributes["tx.hash"], events["tx.hash"]);
+            let query = format!("tx.hash = '{}'", events["tx.hash"][0]);
+            let event = Event {
+                events: None,
+                ..event.clone()
+            };
+            assert!(event.matches(&query.parse().unwrap()));
+        }
+    }
+}
@@ -32,15 +32,16 @@ extern crate std;
 
 mod prelude;
 
+pub mod app_error;
 pub mod client;
 
 #[cfg(any(feature = "http-client", feature = "websocket-client"))]
 pub use client::{
-    Client, MockClient, MockRequestMatcher, MockRequestMethodMatcher, Subscription,
-    SubscriptionClient,
+    Client, FaultConfig, FaultyClient, MockClient, MockRequestMatcher, MockRequestMethodMatcher,
+    PollingClient, PollingClientDriver, Subscription, SubscriptionClient,
 };
 #[cfg(feature = "http-client")]
-pub use client::{HttpClient, HttpClientUrl};
+pub use client::{HttpClient, HttpClientUrl, SseClient};
 #[cfg(feature = "websocket-client")]
 pub use client::{WebSocketClient, WebSocketClientDriver, WebSocketClientUrl, WebSocketConfig};
 
@@ -58,10 +59,14 @@ pub mod response;
 pub mod response_error;
 mod rpc_url;
 pub mod serializers;
+pub mod sse;
+pub mod strict;
 mod utils;
+#[cfg(feature = "rust-crypto")]
+pub mod validator_cache;
 mod version;
 
-pub use error::Error;
+pub use error::{Error, ErrorKind};
 pub use id::Id;
 pub use method::Method;
 pub use order::Order;
@@ -70,4 +75,6 @@ pub use request::{Request, SimpleRequest};
 pub use response::Response;
 pub use response_error::{Code, ResponseError};
 pub use rpc_url::{Scheme, Url};
+#[cfg(feature = "rust-crypto")]
+pub use validator_cache::ValidatorSetCache;
 pub use version::Version;
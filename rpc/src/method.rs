@@ -68,6 +68,9 @@ pub enum Method {
     /// Get network info
     NetInfo,
 
+    /// Get the number and total size of the transactions in the mempool
+    NumUnconfirmedTxs,
+
     /// Get node status
     Status,
 
@@ -77,6 +80,9 @@ pub enum Method {
     /// Search for transactions with their results
     TxSearch,
 
+    /// List the transactions in the mempool
+    UnconfirmedTxs,
+
     /// Get validator info for a block
     Validators,
 
@@ -113,10 +119,12 @@ impl Method {
             Method::HeaderByHash => "header_by_hash",
             Method::Health => "health",
             Method::NetInfo => "net_info",
+            Method::NumUnconfirmedTxs => "num_unconfirmed_txs",
             Method::Status => "status",
             Method::Subscribe => "subscribe",
             Method::Tx => "tx",
             Method::TxSearch => "tx_search",
+            Method::UnconfirmedTxs => "unconfirmed_txs",
             Method::Unsubscribe => "unsubscribe",
             Method::Validators => "validators",
         }
@@ -147,10 +155,12 @@ impl FromStr for Method {
             "genesis" => Method::Genesis,
             "health" => Method::Health,
             "net_info" => Method::NetInfo,
+            "num_unconfirmed_txs" => Method::NumUnconfirmedTxs,
             "status" => Method::Status,
             "subscribe" => Method::Subscribe,
             "tx" => Method::Tx,
             "tx_search" => Method::TxSearch,
+            "unconfirmed_txs" => Method::UnconfirmedTxs,
             "unsubscribe" => Method::Unsubscribe,
             "validators" => Method::Validators,
             other => return Err(Error::method_not_found(other.to_string())),
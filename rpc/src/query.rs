@@ -4,7 +4,8 @@
 //!
 //! [`Query`]: struct.Query.html
 
-use core::{fmt, str::FromStr};
+use alloc::collections::BTreeMap;
+use core::{cmp::Ordering, fmt, str::FromStr};
 
 use time::{
     format_description::well_known::Rfc3339,
@@ -12,6 +13,8 @@ use time::{
     Date, OffsetDateTime,
 };
 
+use tendermint::abci;
+
 use crate::{prelude::*, serializers::timestamp, Error};
 
 /// A structured query for use in interacting with the Tendermint RPC event
@@ -169,6 +172,49 @@ impl Query {
         self.conditions.push(Condition::exists(key.to_string()));
         self
     }
+
+    /// Check whether the query matches the given event attributes.
+    ///
+    /// The attributes are keyed by their composite `<event type>.<attribute key>`
+    /// name, as in the `events` map of an RPC [`Event`](crate::event::Event),
+    /// and the event type is expected to be found under the `tm.event` key.
+    /// A condition is satisfied if any of the values for its key satisfies it,
+    /// following the semantics of the CometBFT event query language.
+    pub fn matches(&self, events: &BTreeMap<String, Vec<String>>) -> bool {
+        if let Some(event_type) = &self.event_type {
+            let event_type = event_type.to_string();
+            let matches_type = events
+                .get("tm.event")
+                .is_some_and(|types| types.contains(&event_type));
+            if !matches_type {
+                return false;
+            }
+        }
+        self.conditions.iter().all(|c| c.matches(events))
+    }
+
+    /// Check whether the query matches the given ABCI events, emitted as part of
+    /// an event of the given type.
+    pub fn matches_abci_events(&self, event_type: &EventType, events: &[abci::Event]) -> bool {
+        let mut attributes = event_attributes(events);
+        attributes.insert("tm.event".to_owned(), vec![event_type.to_string()]);
+        self.matches(&attributes)
+    }
+}
+
+/// Collect the attributes of the given ABCI events, keyed by their composite
+/// `<event type>.<attribute key>` name.
+pub fn event_attributes(events: &[abci::Event]) -> BTreeMap<String, Vec<String>> {
+    let mut attributes = BTreeMap::<String, Vec<String>>::new();
+    for event in events {
+        for attribute in &event.attributes {
+            attributes
+                .entry(format!("{}.{}", event.kind, attribute.key))
+                .or_default()
+                .push(attribute.value.clone());
+        }
+    }
+    attributes
 }
 
 impl Default for Query {
@@ -470,6 +516,36 @@ impl Condition {
     pub fn exists(key: String) -> Self {
         Self::new(key, Operation::Exists)
     }
+
+    /// Check whether any of the values for the key in the given event
+    /// attributes satisfies this condition.
+    pub fn matches(&self, events: &BTreeMap<String, Vec<String>>) -> bool {
+        let values = match events.get(&self.key) {
+            Some(values) => values,
+            None => return false,
+        };
+        values.iter().any(|value| self.operation.matches(value))
+    }
+}
+
+impl Operation {
+    /// Check whether the given attribute value satisfies this operation.
+    pub fn matches(&self, value: &str) -> bool {
+        match self {
+            Operation::Eq(Operand::String(s)) => value == s,
+            Operation::Eq(op) => op.compare(value) == Some(Ordering::Equal),
+            Operation::Lt(op) => op.compare(value) == Some(Ordering::Less),
+            Operation::Lte(op) => {
+                matches!(op.compare(value), Some(Ordering::Less | Ordering::Equal))
+            },
+            Operation::Gt(op) => op.compare(value) == Some(Ordering::Greater),
+            Operation::Gte(op) => {
+                matches!(op.compare(value), Some(Ordering::Greater | Ordering::Equal))
+            },
+            Operation::Contains(s) => value.contains(s.as_str()),
+            Operation::Exists => true,
+        }
+    }
 }
 
 impl fmt::Display for Condition {
@@ -547,6 +623,78 @@ impl fmt::Display for Operand {
     }
 }
 
+impl Operand {
+    /// Compare the given attribute value to this operand.
+    ///
+    /// For numeric operands, the value is compared by its leading number,
+    /// so that e.g. `100stake` compares as `100`. Strings are not ordered,
+    /// and values which cannot be interpreted as the type of the operand
+    /// are not comparable to it.
+    fn compare(&self, value: &str) -> Option<Ordering> {
+        match self {
+            Operand::String(_) => None,
+            Operand::Signed(i) => compare_numbers(value, *i as i128, *i as f64),
+            Operand::Unsigned(u) => compare_numbers(value, *u as i128, *u as f64),
+            Operand::Float(f) => parse_number(value)?.as_f64().partial_cmp(f),
+            Operand::Date(d) => {
+                let date = Date::parse(value, &format_description!("[year]-[month]-[day]"))
+                    .ok()
+                    .or_else(|| parse_datetime(value).map(|dt| dt.date()))?;
+                Some(date.cmp(d))
+            },
+            Operand::DateTime(dt) => Some(parse_datetime(value)?.cmp(dt)),
+        }
+    }
+}
+
+/// A number found at the start of an attribute value.
+enum Number {
+    Integer(i128),
+    Float(f64),
+}
+
+impl Number {
+    fn as_f64(&self) -> f64 {
+        match self {
+            Number::Integer(i) => *i as f64,
+            Number::Float(f) => *f,
+        }
+    }
+}
+
+fn parse_number(value: &str) -> Option<Number> {
+    let value = value.trim_start();
+    let digits = |s: &str| s.bytes().take_while(u8::is_ascii_digit).count();
+
+    let sign = usize::from(value.starts_with('-'));
+    let mut end = sign + digits(&value[sign..]);
+    if end == sign {
+        return None;
+    }
+    let integer = &value[..end];
+    if value[end..].starts_with('.') {
+        let fraction = digits(&value[end + 1..]);
+        if fraction > 0 {
+            end += 1 + fraction;
+            return f64::from_str(&value[..end]).ok().map(Number::Float);
+        }
+    }
+    i128::from_str(integer).ok().map(Number::Integer)
+}
+
+fn compare_numbers(value: &str, integer: i128, float: f64) -> Option<Ordering> {
+    match parse_number(value)? {
+        Number::Integer(i) => Some(i.cmp(&integer)),
+        Number::Float(f) => f.partial_cmp(&float),
+    }
+}
+
+fn parse_datetime(value: &str) -> Option<OffsetDateTime> {
+    OffsetDateTime::parse(value, &Rfc3339)
+        .ok()
+        .map(|dt| dt.to_offset(offset!(UTC)))
+}
+
 fn fmt_date(d: Date, mut f: impl fmt::Write) -> fmt::Result {
     write!(f, "{:04}-{:02}-{:02}", d.year(), d.month() as u8, d.day())
 }
@@ -997,4 +1145,80 @@ mod test {
             }
         );
     }
+
+    fn transfer_events() -> Vec<abci::Event> {
+        vec![
+            abci::Event::new(
+                "transfer",
+                [
+                    ("sender", "alice"),
+                    ("recipient", "bob"),
+                    ("amount", "150stake"),
+                ],
+            ),
+            abci::Event::new("transfer", [("amount", "20stake")]),
+            abci::Event::new("block", [("time", "2020-09-24T10:17:23.5Z")]),
+        ]
+    }
+
+    fn matches(query: &str) -> bool {
+        Query::from_str(query)
+            .unwrap()
+            .matches_abci_events(&EventType::Tx, &transfer_events())
+    }
+
+    #[test]
+    fn query_matching_event_type() {
+        assert!(Query::default().matches_abci_events(&EventType::NewBlock, &[]));
+        assert!(matches("tm.event = 'Tx'"));
+        assert!(!matches("tm.event = 'NewBlock'"));
+        assert!(!Query::from(EventType::Tx).matches(&BTreeMap::new()));
+    }
+
+    #[test]
+    fn query_matching_strings() {
+        assert!(matches("tm.event = 'Tx' AND transfer.sender = 'alice'"));
+        assert!(!matches("transfer.sender = 'bob'"));
+        assert!(matches("transfer.recipient CONTAINS 'ob'"));
+        assert!(!matches("transfer.recipient CONTAINS 'alice'"));
+        assert!(matches("transfer.sender EXISTS"));
+        assert!(!matches("transfer.fee EXISTS"));
+        // strings are not ordered
+        assert!(!matches("transfer.sender > 'a'"));
+    }
+
+    #[test]
+    fn query_matching_numbers() {
+        assert!(matches("tm.event = 'Tx' AND transfer.amount > 100"));
+        assert!(matches("transfer.amount < 100"));
+        assert!(matches("transfer.amount = 150"));
+        assert!(matches("transfer.amount >= 150 AND transfer.amount <= 20"));
+        assert!(!matches("transfer.amount > 150"));
+        assert!(matches("transfer.amount > 149.5"));
+        assert!(matches("transfer.amount > -1"));
+        assert!(!matches("transfer.sender > 0"));
+        assert!(!matches("transfer.fee < 1000"));
+    }
+
+    #[test]
+    fn query_matching_dates() {
+        assert!(matches("block.time > TIME 2020-09-24T10:17:23Z"));
+        assert!(!matches("block.time < TIME 2020-09-24T10:17:23Z"));
+        assert!(matches("block.time = DATE 2020-09-24"));
+        assert!(matches("block.time >= DATE 2020-09-01"));
+        assert!(!matches("transfer.amount > DATE 2020-09-01"));
+    }
+
+    #[test]
+    fn number_parsing() {
+        assert!(matches!(parse_number("42"), Some(Number::Integer(42))));
+        assert!(matches!(
+            parse_number("-42uatom"),
+            Some(Number::Integer(-42))
+        ));
+        assert!(matches!(parse_number("4.5"), Some(Number::Float(f)) if f == 4.5));
+        assert!(matches!(parse_number("4."), Some(Number::Integer(4))));
+        assert!(parse_number("-").is_none());
+        assert!(parse_number("stake").is_none());
+    }
 }
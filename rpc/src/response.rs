@@ -4,7 +4,12 @@ use std::io::Read;
 
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
-use crate::{response_error::ResponseError, Error, Id, Version};
+use crate::{
+    prelude::*,
+    response_error::ResponseError,
+    strict::{self, FieldReport},
+    Error, Id, Version,
+};
 
 /// JSON-RPC responses
 pub trait Response: DeserializeOwned + Sized {
@@ -20,6 +25,18 @@ pub trait Response: DeserializeOwned + Sized {
         let wrapper: Wrapper<Self> = serde_json::from_reader(reader).map_err(Error::serde)?;
         wrapper.into_result()
     }
+
+    /// Parse a JSON-RPC response from a JSON string, reporting the fields
+    /// of the response which were unknown or defaulted
+    fn from_string_strict(response: impl AsRef<[u8]>) -> Result<(Self, FieldReport), Error> {
+        let (wrapper, mut report): (Wrapper<Self>, _) =
+            strict::from_slice(response.as_ref()).map_err(Error::serde)?;
+        // Only one of the result and error fields is expected in the envelope.
+        report
+            .defaulted
+            .retain(|field| field != "result" && field != "error");
+        Ok((wrapper.into_result()?, report))
+    }
 }
 
 /// JSON-RPC response wrapper (i.e. message envelope)
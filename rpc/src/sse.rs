@@ -0,0 +1,317 @@
+//! Encoding and decoding of subscription events as [Server-Sent Events].
+//!
+//! Server-Sent Events offer a simpler alternative to websockets to deliver
+//! events to browsers, and pass through HTTP proxies which do not support
+//! connection upgrades. Each event is sent as a message whose `event` field
+//! holds the event type (e.g. `NewBlock` or `Tx`), and whose `data` field
+//! holds the JSON serialization of the event in the given protocol dialect.
+//!
+//! A server (such as an event proxy) encodes the events with
+//! [`SseMessage::from_event`], and writes them to the response stream in the
+//! format produced by the [`Display`] implementation of [`SseMessage`].
+//! Clients decode the response stream with an [`SseDecoder`].
+//!
+//! [Server-Sent Events]: https://html.spec.whatwg.org/multipage/server-sent-events.html
+//! [`Display`]: core::fmt::Display
+
+use core::fmt;
+
+use crate::{
+    client::CompatMode,
+    event::{self, Event, EventData},
+    prelude::*,
+    Error,
+};
+
+/// The MIME type of Server-Sent Events response streams.
+pub const CONTENT_TYPE: &str = "text/event-stream";
+
+/// The name of the query parameter holding the subscription query in requests
+/// to an SSE endpoint.
+pub const QUERY_PARAM: &str = "query";
+
+/// A single Server-Sent Events message.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SseMessage {
+    /// The identifier of the message, if any.
+    pub id: Option<String>,
+    /// The type of the message, if any.
+    pub event: Option<String>,
+    /// The payload of the message.
+    pub data: String,
+}
+
+impl SseMessage {
+    /// Encode a subscription event in the JSON format of the given protocol
+    /// dialect.
+    pub fn from_event(ev: Event, compat: CompatMode) -> Result<Self, Error> {
+        let event_type = ev.event_type().map(|t| t.to_string());
+        let data = match compat {
+            CompatMode::V0_34 => serde_json::to_string(&event::v0_34::SerEvent::from(ev)),
+            CompatMode::V0_37 => match ev.data {
+                EventData::LegacyNewBlock { .. } => {
+                    serde_json::to_string(&event::v0_37::SerEvent::from(ev))
+                },
+                _ => serde_json::to_string(&event::v0_38::SerEvent::from(ev)),
+            },
+        }
+        .map_err(Error::serde)?;
+        Ok(Self {
+            id: None,
+            event: event_type,
+            data,
+        })
+    }
+
+    /// Set the identifier of the message.
+    pub fn with_id(mut self, id: impl ToString) -> Self {
+        self.id = Some(id.to_string());
+        self
+    }
+
+    /// Decode the subscription event carried by this message, in the JSON
+    /// format of the given protocol dialect.
+    pub fn into_event(self, compat: CompatMode) -> Result<Event, Error> {
+        match compat {
+            CompatMode::V0_34 => serde_json::from_str::<event::v0_34::DeEvent>(&self.data)
+                .map(Into::into)
+                .map_err(Error::serde),
+            CompatMode::V0_37 => serde_json::from_str::<event::v0_37::DeEvent>(&self.data)
+                .map(Into::into)
+                .map_err(Error::serde),
+        }
+    }
+}
+
+impl fmt::Display for SseMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(id) = &self.id {
+            writeln!(f, "id: {id}")?;
+        }
+        if let Some(event) = &self.event {
+            writeln!(f, "event: {event}")?;
+        }
+        for line in self.data.lines() {
+            writeln!(f, "data: {line}")?;
+        }
+        writeln!(f)
+    }
+}
+
+/// The default maximum size of the messages decoded by an [`SseDecoder`].
+pub const DEFAULT_MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
+
+/// Incremental decoder of a Server-Sent Events stream.
+///
+/// The stream may be fed in chunks of arbitrary sizes, which need not be
+/// aligned with line or message boundaries. Lines may end with either a line
+/// feed, a carriage return, or both.
+#[derive(Debug)]
+pub struct SseDecoder {
+    buffer: Vec<u8>,
+    message: SseMessage,
+    has_data: bool,
+    // Whether the last line ended the buffer with a carriage return, which
+    // is followed by a line feed in the case of a CRLF line ending.
+    after_cr: bool,
+    max_message_size: usize,
+}
+
+impl Default for SseDecoder {
+    fn default() -> Self {
+        Self {
+            buffer: Vec::new(),
+            message: SseMessage::default(),
+            has_data: false,
+            after_cr: false,
+            max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
+        }
+    }
+}
+
+impl SseDecoder {
+    /// Create a decoder at the start of a stream.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum size, in bytes, of the data of a message, and of any
+    /// of its lines, which defaults to [`DEFAULT_MAX_MESSAGE_SIZE`].
+    pub fn with_max_message_size(mut self, max_message_size: usize) -> Self {
+        self.max_message_size = max_message_size;
+        self
+    }
+
+    /// Feed the next chunk of the stream to the decoder, returning the
+    /// messages completed by the chunk.
+    ///
+    /// Fails if a message exceeds the maximum size, after which the stream
+    /// cannot be decoded any further.
+    pub fn decode(&mut self, mut chunk: &[u8]) -> Result<Vec<SseMessage>, Error> {
+        if self.after_cr && !chunk.is_empty() {
+            if chunk[0] == b'\n' {
+                chunk = &chunk[1..];
+            }
+            self.after_cr = false;
+        }
+        self.buffer.extend_from_slice(chunk);
+
+        let mut messages = Vec::new();
+        let mut start = 0;
+        while let Some(offset) = self.buffer[start..]
+            .iter()
+            .position(|b| *b == b'\n' || *b == b'\r')
+        {
+            let end = start + offset;
+            let line = String::from_utf8_lossy(&self.buffer[start..end]).into_owned();
+            start = end + 1;
+            if self.buffer[end] == b'\r' {
+                match self.buffer.get(start) {
+                    Some(b'\n') => start += 1,
+                    Some(_) => {},
+                    None => self.after_cr = true,
+                }
+            }
+            if let Some(message) = self.process_line(&line) {
+                messages.push(message);
+            }
+            if self.message.data.len() > self.max_message_size {
+                return Err(self.too_large());
+            }
+        }
+        self.buffer.drain(..start);
+        if self.buffer.len() > self.max_message_size {
+            return Err(self.too_large());
+        }
+        Ok(messages)
+    }
+
+    fn too_large(&self) -> Error {
+        Error::response_too_large(crate::Method::Subscribe, self.max_message_size)
+    }
+
+    fn process_line(&mut self, line: &str) -> Option<SseMessage> {
+        if line.is_empty() {
+            // Messages without data are discarded, as per the specification.
+            let message = core::mem::take(&mut self.message);
+            return core::mem::take(&mut self.has_data).then_some(message);
+        }
+        if line.starts_with(':') {
+            // Comment line, used to keep connections alive.
+            return None;
+        }
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+        match field {
+            "data" => {
+                if self.has_data {
+                    self.message.data.push('\n');
+                }
+                self.message.data.push_str(value);
+                self.has_data = true;
+            },
+            "event" => self.message.event = Some(value.to_owned()),
+            "id" => self.message.id = Some(value.to_owned()),
+            // Other fields, such as `retry`, are ignored.
+            _ => {},
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{event::v0_37::DeEvent, Response};
+
+    fn read_event(name: &str) -> Event {
+        let path = format!("./tests/kvstore_fixtures/v0_37/incoming/{name}.json");
+        let fixture = std::fs::read_to_string(path).unwrap();
+        DeEvent::from_string(fixture).unwrap().into()
+    }
+
+    #[test]
+    fn encodes_messages() {
+        let message = SseMessage {
+            id: None,
+            event: Some("Tx".to_owned()),
+            data: "{\n}".to_owned(),
+        }
+        .with_id(7);
+        assert_eq!(
+            message.to_string(),
+            "id: 7\nevent: Tx\ndata: {\ndata: }\n\n"
+        );
+    }
+
+    #[test]
+    fn decodes_chunked_stream() {
+        let stream = b": keep-alive\n\nid: 1\r\nevent: NewBlock\r\ndata: a\r\ndata:b\r\n\r\nretry: 10\n\ndata\n\n";
+        let mut decoder = SseDecoder::new();
+        let messages: Vec<_> = stream
+            .chunks(3)
+            .flat_map(|chunk| decoder.decode(chunk).unwrap())
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                SseMessage {
+                    id: Some("1".to_owned()),
+                    event: Some("NewBlock".to_owned()),
+                    data: "a\nb".to_owned(),
+                },
+                SseMessage::default(),
+            ]
+        );
+    }
+
+    #[test]
+    fn decodes_carriage_return_line_endings() {
+        let stream = b"data: a\rdata: b\r\rdata: c\r\n\r\n";
+        for size in 1..stream.len() {
+            let mut decoder = SseDecoder::new();
+            let messages: Vec<_> = stream
+                .chunks(size)
+                .flat_map(|chunk| decoder.decode(chunk).unwrap())
+                .map(|message| message.data)
+                .collect();
+            assert_eq!(messages, vec!["a\nb", "c"], "chunks of {size} bytes");
+        }
+    }
+
+    #[test]
+    fn bounds_the_message_size() {
+        let mut decoder = SseDecoder::new().with_max_message_size(8);
+        assert!(decoder.decode(b"data: 1234\ndata: 5678\n").is_err());
+
+        // Lines without an ending are bounded as well.
+        let mut decoder = SseDecoder::new().with_max_message_size(8);
+        assert!(decoder.decode(b"data: 12").unwrap().is_empty());
+        assert!(decoder.decode(b"345").is_err());
+
+        let mut decoder = SseDecoder::new().with_max_message_size(8);
+        let messages = decoder.decode(b"data: 12345678\n\n").unwrap();
+        assert_eq!(messages[0].data, "12345678");
+    }
+
+    #[test]
+    fn event_round_trip() {
+        for name in ["subscribe_newblock_0", "subscribe_txs_0"] {
+            let event = read_event(name);
+            let message = SseMessage::from_event(event.clone(), CompatMode::V0_37).unwrap();
+            assert_eq!(message.event, event.event_type().map(|t| t.to_string()));
+
+            let mut decoder = SseDecoder::new();
+            let mut messages = decoder.decode(message.to_string().as_bytes()).unwrap();
+            assert_eq!(messages.len(), 1);
+            let decoded = messages
+                .pop()
+                .unwrap()
+                .into_event(CompatMode::V0_37)
+                .unwrap();
+            assert_eq!(decoded, event);
+        }
+    }
+}
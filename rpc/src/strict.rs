@@ -0,0 +1,378 @@
+//! Strict deserialization of responses, keeping track of the fields which
+//! were ignored or missing.
+//!
+//! Response types ignore fields they do not know about, and many of them
+//! fall back to default values for missing fields, so as to stay compatible
+//! with other versions of the node. This makes version mismatches easy to
+//! miss, which is why responses may also be deserialized with [`from_slice`]
+//! when debugging, to report the fields which were not deserialized as such.
+
+use core::{cell::RefCell, fmt};
+
+use serde::de::{
+    self, value::StringDeserializer, DeserializeSeed, IntoDeserializer, MapAccess, SeqAccess,
+    Visitor,
+};
+use serde_json::Value;
+
+use crate::prelude::*;
+
+/// The fields of a response which were not deserialized as such, identified
+/// by their path in the JSON document (e.g. `result.sync_info.catching_up`).
+///
+/// Fields which can be provided under alternative names are reported as
+/// missing under the names which were not used.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FieldReport {
+    /// Fields present in the response, but unknown to the response type.
+    pub unknown: Vec<String>,
+    /// Fields of the response type which were missing from the response,
+    /// and were left to their default value.
+    pub defaulted: Vec<String>,
+}
+
+impl FieldReport {
+    /// Whether all the fields of the response were deserialized as such.
+    pub fn is_empty(&self) -> bool {
+        self.unknown.is_empty() && self.defaulted.is_empty()
+    }
+}
+
+impl fmt::Display for FieldReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unknown fields: [{}], defaulted fields: [{}]",
+            self.unknown.join(", "),
+            self.defaulted.join(", ")
+        )
+    }
+}
+
+/// Deserialize the given JSON document, reporting the fields which were
+/// ignored or missing.
+pub fn from_slice<T>(json: &[u8]) -> Result<(T, FieldReport), serde_json::Error>
+where
+    T: de::DeserializeOwned,
+{
+    let value: Value = serde_json::from_slice(json)?;
+    let report = RefCell::new(FieldReport::default());
+    let result = T::deserialize(Tracked {
+        value,
+        path: String::new(),
+        report: &report,
+    })?;
+    Ok((result, report.into_inner()))
+}
+
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_owned()
+    } else {
+        format!("{path}.{key}")
+    }
+}
+
+/// A deserializer of JSON values, recording which fields of the structures
+/// are unknown or missing.
+struct Tracked<'a> {
+    value: Value,
+    path: String,
+    report: &'a RefCell<FieldReport>,
+}
+
+macro_rules! forward_to_value {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                self.value.$method(visitor)
+            }
+        )*
+    };
+}
+
+impl<'de, 'a> de::Deserializer<'de> for Tracked<'a> {
+    type Error = serde_json::Error;
+
+    forward_to_value! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_i128 deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64
+        deserialize_u128 deserialize_f32 deserialize_f64 deserialize_char deserialize_str
+        deserialize_string deserialize_bytes deserialize_byte_buf deserialize_unit
+        deserialize_identifier
+    }
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let Tracked {
+            value,
+            path,
+            report,
+        } = self;
+        match value {
+            Value::Object(object) => visitor.visit_map(TrackedMap {
+                entries: object.into_iter(),
+                value: None,
+                path,
+                report,
+            }),
+            Value::Array(array) => visitor.visit_seq(TrackedSeq {
+                elements: array.into_iter().enumerate(),
+                path,
+                report,
+            }),
+            value => value.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            Value::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_unit_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.value.deserialize_unit_struct(name, visitor)
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            Value::Array(_) => self.deserialize_any(visitor),
+            value => value.deserialize_seq(visitor),
+        }
+    }
+
+    fn deserialize_tuple<V>(self, _len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            Value::Object(_) => self.deserialize_any(visitor),
+            value => value.deserialize_map(visitor),
+        }
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let object = match &self.value {
+            Value::Object(object) => object,
+            _ => return self.value.deserialize_struct(name, fields, visitor),
+        };
+        let mut report = self.report.borrow_mut();
+        for key in object.keys() {
+            if !fields.contains(&key.as_str()) {
+                report.unknown.push(join(&self.path, key));
+            }
+        }
+        for field in fields {
+            if !object.contains_key(*field) {
+                report.defaulted.push(join(&self.path, field));
+            }
+        }
+        drop(report);
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        // The contents of enumerations are not tracked.
+        self.value.deserialize_enum(name, variants, visitor)
+    }
+
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+}
+
+struct TrackedMap<'a> {
+    entries: serde_json::map::IntoIter,
+    value: Option<(String, Value)>,
+    path: String,
+    report: &'a RefCell<FieldReport>,
+}
+
+impl<'de, 'a> MapAccess<'de> for TrackedMap<'a> {
+    type Error = serde_json::Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        match self.entries.next() {
+            Some((key, value)) => {
+                let deserializer: StringDeserializer<serde_json::Error> =
+                    key.clone().into_deserializer();
+                self.value = Some((key, value));
+                seed.deserialize(deserializer).map(Some)
+            },
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let (key, value) = self
+            .value
+            .take()
+            .ok_or_else(|| de::Error::custom("value is missing"))?;
+        seed.deserialize(Tracked {
+            value,
+            path: join(&self.path, &key),
+            report: self.report,
+        })
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.entries.len())
+    }
+}
+
+struct TrackedSeq<'a> {
+    elements: core::iter::Enumerate<alloc::vec::IntoIter<Value>>,
+    path: String,
+    report: &'a RefCell<FieldReport>,
+}
+
+impl<'de, 'a> SeqAccess<'de> for TrackedSeq<'a> {
+    type Error = serde_json::Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.elements.next() {
+            Some((index, value)) => seed
+                .deserialize(Tracked {
+                    value,
+                    path: format!("{}[{}]", self.path, index),
+                    report: self.report,
+                })
+                .map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.elements.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::{endpoint, Response};
+
+    #[derive(Debug, Deserialize)]
+    struct Outer {
+        name: String,
+        #[serde(default)]
+        items: Vec<Inner>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Inner {
+        id: u64,
+        label: Option<String>,
+    }
+
+    #[test]
+    fn reports_unknown_and_defaulted_fields() {
+        let json =
+            br#"{"name":"a","items":[{"id":1,"label":"x"},{"id":2,"extra":true}],"version":3}"#;
+        let (outer, report) = from_slice::<Outer>(json).unwrap();
+        assert_eq!(outer.name, "a");
+        assert_eq!(outer.items[0].label.as_deref(), Some("x"));
+        assert_eq!(outer.items[1].id, 2);
+        assert_eq!(report.unknown, vec!["version", "items[1].extra"]);
+        assert_eq!(report.defaulted, vec!["items[1].label"]);
+
+        let (outer, report) = from_slice::<Outer>(br#"{"name":"b"}"#).unwrap();
+        assert!(outer.items.is_empty());
+        assert_eq!(report.defaulted, vec!["items"]);
+    }
+
+    #[test]
+    fn strict_response_parsing() {
+        let fixture = include_str!("../tests/kvstore_fixtures/v0_37/incoming/abci_info.json");
+        let (response, report) =
+            endpoint::abci_info::Response::from_string_strict(fixture).unwrap();
+        assert_eq!(
+            response.response.data,
+            endpoint::abci_info::Response::from_string(fixture)
+                .unwrap()
+                .response
+                .data
+        );
+        assert!(report.unknown.is_empty(), "{report}");
+    }
+}
@@ -0,0 +1,210 @@
+//! Cache of historical validator sets.
+//!
+//! Validator sets rarely change from one height to the next, and clients
+//! verifying headers over long ranges of heights, such as the light client
+//! during bisection, end up fetching and hashing the same validator set
+//! again and again. A [`ValidatorSetCache`] keeps the validator sets already
+//! fetched, indexed by chain ID and hash, so that they are served from the
+//! cache at any height where the expected hash is known, e.g. from the
+//! `validators_hash` of the header at that height.
+//!
+//! The cached sets are stored along with their memoized hash, which is not
+//! recomputed when they are served from the cache. The proposer priorities
+//! of their validators are the ones at the height they were fetched at,
+//! which do not enter their hash. The storage is pluggable
+//! through the [`ValidatorSetStore`] trait, and defaults to a bounded
+//! in-memory [`MemoryStore`].
+
+use alloc::{collections::VecDeque, sync::Arc};
+use core::fmt;
+use std::{collections::HashMap, sync::Mutex};
+
+use tendermint::{chain, validator, Hash};
+
+use crate::prelude::*;
+
+/// The key under which a validator set is cached.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ValidatorSetKey {
+    /// The ID of the chain
+    pub chain_id: chain::Id,
+    /// The hash of the validator set
+    pub hash: Hash,
+}
+
+/// Storage backing a [`ValidatorSetCache`].
+pub trait ValidatorSetStore: Send + Sync {
+    /// Get the validator set stored under the given key, if any.
+    fn get(&self, key: &ValidatorSetKey) -> Option<validator::Set>;
+
+    /// Store the given validator set under the given key.
+    fn insert(&self, key: ValidatorSetKey, validators: validator::Set);
+}
+
+/// A [`ValidatorSetStore`] keeping up to a given number of validator sets in
+/// memory, evicting the oldest inserted ones first.
+#[derive(Debug)]
+pub struct MemoryStore {
+    capacity: usize,
+    entries: Mutex<MemoryEntries>,
+}
+
+#[derive(Debug, Default)]
+struct MemoryEntries {
+    sets: HashMap<ValidatorSetKey, validator::Set>,
+    insertion_order: VecDeque<ValidatorSetKey>,
+}
+
+impl MemoryStore {
+    /// The default number of validator sets kept in memory.
+    pub const DEFAULT_CAPACITY: usize = 1024;
+
+    /// Create a store keeping up to the given number of validator sets.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Mutex::default(),
+        }
+    }
+
+    /// The number of validator sets currently stored.
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().sets.len()
+    }
+
+    /// Whether no validator set is currently stored.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for MemoryStore {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_CAPACITY)
+    }
+}
+
+impl ValidatorSetStore for MemoryStore {
+    fn get(&self, key: &ValidatorSetKey) -> Option<validator::Set> {
+        self.entries.lock().unwrap().sets.get(key).cloned()
+    }
+
+    fn insert(&self, key: ValidatorSetKey, validators: validator::Set) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        if entries.sets.insert(key.clone(), validators).is_some() {
+            return;
+        }
+        entries.insertion_order.push_back(key);
+        while entries.sets.len() > self.capacity {
+            let Some(oldest) = entries.insertion_order.pop_front() else {
+                break;
+            };
+            entries.sets.remove(&oldest);
+        }
+    }
+}
+
+/// A cache of validator sets, shared between its clones.
+///
+/// See the [module documentation](self) for details.
+#[derive(Clone)]
+pub struct ValidatorSetCache {
+    store: Arc<dyn ValidatorSetStore>,
+}
+
+impl ValidatorSetCache {
+    /// Create a cache backed by the given store.
+    pub fn new<S: ValidatorSetStore + 'static>(store: S) -> Self {
+        Self {
+            store: Arc::new(store),
+        }
+    }
+
+    /// Create a cache keeping up to the given number of validator sets in
+    /// memory.
+    pub fn in_memory(capacity: usize) -> Self {
+        Self::new(MemoryStore::new(capacity))
+    }
+
+    /// Get the validator set of the given chain with the given hash, if it is
+    /// cached.
+    pub fn get(&self, chain_id: &chain::Id, hash: Hash) -> Option<validator::Set> {
+        self.store.get(&ValidatorSetKey {
+            chain_id: chain_id.clone(),
+            hash,
+        })
+    }
+
+    /// Cache the given validator set of the given chain, and return its hash.
+    pub fn insert(&self, chain_id: &chain::Id, validators: validator::Set) -> Hash {
+        let hash = validators.hash();
+        let key = ValidatorSetKey {
+            chain_id: chain_id.clone(),
+            hash,
+        };
+        self.store.insert(key, validators);
+        hash
+    }
+}
+
+impl Default for ValidatorSetCache {
+    fn default() -> Self {
+        Self::new(MemoryStore::default())
+    }
+}
+
+impl fmt::Debug for ValidatorSetCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ValidatorSetCache").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tendermint::{validator::Info, vote, PublicKey};
+
+    use super::*;
+
+    fn validator_set(seed: u8) -> validator::Set {
+        let pub_key = PublicKey::from_raw_ed25519(&[seed; 32]).unwrap();
+        validator::Set::without_proposer(vec![Info::new(pub_key, vote::Power::from(10_u32))])
+    }
+
+    #[test]
+    fn serves_cached_sets_by_hash() {
+        let cache = ValidatorSetCache::in_memory(2);
+        let chain_id: chain::Id = "test-chain".parse().unwrap();
+
+        let hash = cache.insert(&chain_id, validator_set(1));
+        assert_eq!(hash, validator_set(1).hash());
+        assert_eq!(cache.get(&chain_id, hash), Some(validator_set(1)));
+
+        // Other hashes and chains are not served
+        let other_hash = validator_set(2).hash();
+        assert_eq!(cache.get(&chain_id, other_hash), None);
+        assert_eq!(cache.get(&"other-chain".parse().unwrap(), hash), None);
+    }
+
+    #[test]
+    fn memory_store_evicts_oldest_sets() {
+        let store = MemoryStore::new(2);
+        let key = |seed: u8| ValidatorSetKey {
+            chain_id: "test-chain".parse().unwrap(),
+            hash: validator_set(seed).hash(),
+        };
+
+        store.insert(key(1), validator_set(1));
+        store.insert(key(2), validator_set(2));
+        store.insert(key(1), validator_set(1));
+        assert_eq!(store.len(), 2);
+
+        store.insert(key(3), validator_set(3));
+        assert_eq!(store.len(), 2);
+        assert!(store.get(&key(1)).is_none());
+        assert!(store.get(&key(2)).is_some());
+        assert!(store.get(&key(3)).is_some());
+    }
+}
@@ -475,6 +475,14 @@ fn incoming_fixtures() {
 
                         check_vote(&dup.vote_a);
                         check_vote(&dup.vote_b);
+                        dup.validate_basic().unwrap();
+                        assert!(dup.signed_votes(&block.block.header.chain_id).is_some());
+
+                        assert_eq!(evidence.height().value(), 8009);
+                        let misbehavior = evidence.misbehavior();
+                        assert_eq!(misbehavior.len(), 1);
+                        assert_eq!(misbehavior[0].validator.power.value(), 1);
+                        assert_eq!(misbehavior[0].total_voting_power.value(), 121);
                     } else {
                         panic!("not a duplicate vote: {evidence:?}");
                     }
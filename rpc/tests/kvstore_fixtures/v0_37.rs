@@ -1378,6 +1378,27 @@ fn incoming_fixtures() {
     }
 }
 
+#[test]
+fn incoming_decoded_txs() {
+    // The kvstore transactions are `key=value` pairs.
+    let decoder = |raw: &[u8]| {
+        let tx = core::str::from_utf8(raw).map_err(|_| "not UTF-8")?;
+        tx.split_once('=')
+            .map(|(k, v)| (k.to_owned(), v.to_owned()))
+            .ok_or("missing '='")
+    };
+    let json_file = find_fixtures("v0_37", "incoming")
+        .into_iter()
+        .find(|path| path.ends_with("tx_search_no_prove.json"))
+        .unwrap();
+    let content = fs::read_to_string(json_file).unwrap();
+    let result = endpoint::tx_search::Response::from_string(content).unwrap();
+    let decoded: Vec<_> = result.decode_txs(&decoder).map(Result::unwrap).collect();
+    assert_eq!(decoded.len(), result.txs.len());
+    assert_eq!(decoded[0].tx, ("async-key".to_owned(), "value".to_owned()));
+    assert_eq!(decoded[0].raw, result.txs[0].tx);
+}
+
 fn check_event_attrs(events: &HashMap<String, Vec<String>>, app_key: &str, height: i64) {
     for (k, v) in events {
         match k.as_str() {
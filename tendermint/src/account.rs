@@ -9,7 +9,7 @@ use core::{
 use bytes::Bytes;
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use subtle::{self, ConstantTimeEq};
-use subtle_encoding::hex;
+use subtle_encoding::{bech32, hex};
 
 use tendermint_proto::Protobuf;
 
@@ -56,6 +56,12 @@ impl TryFrom<Bytes> for Id {
     }
 }
 
+impl From<Id> for [u8; LENGTH] {
+    fn from(value: Id) -> Self {
+        value.0
+    }
+}
+
 impl From<Id> for Bytes {
     fn from(value: Id) -> Self {
         Bytes::copy_from_slice(value.as_bytes())
@@ -72,6 +78,44 @@ impl Id {
     pub fn as_bytes(&self) -> &[u8] {
         &self.0[..]
     }
+
+    /// Serialize the account ID as uppercase hexadecimal, as done by the node
+    pub fn to_hex(&self) -> String {
+        self.to_string()
+    }
+
+    /// Serialize the account ID as Bech32 with the given human readable
+    /// prefix (e.g. `cosmosvalcons` for the consensus address of a validator)
+    pub fn to_bech32(&self, hrp: &str) -> String {
+        bech32::encode(hrp, self.as_bytes())
+    }
+
+    /// Decode an account ID from Bech32, checking that its human readable
+    /// prefix is the given one.
+    pub fn from_bech32(encoded: &str, hrp: &str) -> Result<Id, Error> {
+        let (found, id) = Self::decode_bech32(encoded)?;
+        if found != hrp.to_lowercase() {
+            return Err(Error::bech32_prefix_mismatch(hrp.to_owned(), found));
+        }
+        Ok(id)
+    }
+
+    /// Decode an account ID from Bech32, returning it along with its
+    /// (lowercase) human readable prefix.
+    ///
+    /// The checksum of the encoded string is always verified. As per
+    /// BIP-173, the string may be either all lowercase or all uppercase.
+    pub fn decode_bech32(encoded: &str) -> Result<(String, Id), Error> {
+        let has_lower = encoded.chars().any(|c| c.is_ascii_lowercase());
+        let has_upper = encoded.chars().any(|c| c.is_ascii_uppercase());
+        let (hrp, bytes) = match (has_lower, has_upper) {
+            (true, true) => return Err(Error::bech32_mixed_case()),
+            (false, true) => bech32::decode_upper(encoded),
+            _ => bech32::decode(encoded),
+        }
+        .map_err(Error::subtle_encoding)?;
+        Ok((hrp, bytes.try_into()?))
+    }
 }
 
 impl AsRef<[u8]> for Id {
@@ -206,6 +250,32 @@ mod tests {
         assert_eq!(id_bytes.ct_eq(&id).unwrap_u8(), 1);
     }
 
+    #[test]
+    fn test_bech32() {
+        let id = Id::from_str("0CDA3F47EF3C4906693B170EF650EB968C5F4B2C").unwrap();
+        let encoded = "cosmosvalcons1pndr73l083ysv6fmzu80v58tj6x97jevx96zhe";
+        assert_eq!(id.to_bech32("cosmosvalcons"), encoded);
+        assert_eq!(Id::from_bech32(encoded, "cosmosvalcons").unwrap(), id);
+        let pubkey_bytes =
+            hex::decode_upper("14253D61EF42D166D02E68D540D07FDF8D65A9AF0ACAA46302688E788A8521E2")
+                .unwrap();
+        let pubkey = crate::PublicKey::from_raw_ed25519(&pubkey_bytes).unwrap();
+        assert_eq!(pubkey.to_bech32_address("cosmosvalcons"), encoded);
+        assert_eq!(
+            Id::decode_bech32(&encoded.to_uppercase()).unwrap(),
+            ("cosmosvalcons".to_owned(), id)
+        );
+
+        // Wrong prefix
+        assert!(Id::from_bech32(encoded, "cosmos").is_err());
+        // Invalid checksum
+        assert!(Id::decode_bech32("cosmosvalcons1pndr73l083ysv6fmzu80v58tj6x97jevx96zhq").is_err());
+        // Mixed case
+        assert!(Id::decode_bech32("cosmosvalcons1PNDR73l083ysv6fmzu80v58tj6x97jevx96zhe").is_err());
+        // Wrong length
+        assert!(Id::decode_bech32(&bech32::encode("cosmos", [0u8; 32])).is_err());
+    }
+
     #[test]
     #[cfg(feature = "secp256k1")]
     fn test_secp_id() {
@@ -11,6 +11,7 @@ pub mod parts;
 mod round;
 pub mod signed_header;
 mod size;
+pub mod store;
 
 use serde::{Deserialize, Serialize};
 use tendermint_proto::v0_37::types::Block as RawBlock;
@@ -26,7 +27,12 @@ pub use self::{
     round::*,
     size::Size,
 };
-use crate::{error::Error, evidence, prelude::*};
+use crate::{
+    error::Error,
+    evidence,
+    prelude::*,
+    tx::{DecodedTx, TxDecoder},
+};
 
 /// Blocks consist of a header, transactions, votes (the commit), and a list of
 /// evidence of malfeasance (i.e. signing conflicting votes).
@@ -147,4 +153,13 @@ impl Block {
     pub fn last_commit(&self) -> &Option<Commit> {
         &self.last_commit
     }
+
+    /// Decode the transactions of the block with the given decoder, in the
+    /// order of the block.
+    pub fn decode_txs<'a, D: TxDecoder>(
+        &'a self,
+        decoder: &'a D,
+    ) -> impl Iterator<Item = Result<DecodedTx<D::Tx>, D::Error>> + 'a {
+        self.data.iter().map(move |raw| decoder.decode_raw(raw))
+    }
 }
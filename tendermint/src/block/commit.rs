@@ -38,6 +38,8 @@ tendermint_pb_modules! {
     };
     use pb::types::Commit as RawCommit;
 
+    audited_protobuf!(RawCommit, Commit);
+
     impl TryFrom<RawCommit> for Commit {
         type Error = Error;
 
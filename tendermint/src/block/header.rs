@@ -12,9 +12,16 @@ use crate::{
     crypto::Sha256,
     merkle::{self, MerkleHash},
     prelude::*,
-    AppHash, Hash, Time,
+    AppHash, Error, Hash, Time,
 };
 
+// The root of an empty Merkle tree, i.e. the SHA-256 hash of no data, which
+// the hashes of the last commit and results of the first block commit to.
+const EMPTY_MERKLE_ROOT: [u8; 32] = [
+    0xe3, 0xb0, 0xc4, 0x42, 0x98, 0xfc, 0x1c, 0x14, 0x9a, 0xfb, 0xf4, 0xc8, 0x99, 0x6f, 0xb9, 0x24,
+    0x27, 0xae, 0x41, 0xe4, 0x64, 0x9b, 0x93, 0x4c, 0xa4, 0x95, 0x99, 0x1b, 0x78, 0x52, 0xb8, 0x55,
+];
+
 /// Block `Header` values contain metadata about the block and about the
 /// consensus, as well as commitments to the data in the current block, the
 /// previous block, and the results returned by the application.
@@ -102,6 +109,46 @@ impl Header {
 
         Hash::Sha256(merkle::simple_hash_from_byte_vectors::<H>(&fields_bytes))
     }
+
+    /// Check the header against the rules depending on the initial height of
+    /// the chain, which is greater than 1 for chains restarted after a hard
+    /// fork (see the `initial_height` of the [`Genesis`](crate::Genesis)).
+    ///
+    /// The first block of a chain, at its initial height, has no last block,
+    /// and its last commit and results are empty. Any later block must refer
+    /// to the block preceding it. Blocks cannot be below the initial height.
+    pub fn validate_initial_height(&self, initial_height: block::Height) -> Result<(), Error> {
+        let is_empty = |hash: Option<Hash>| match hash {
+            Some(hash) => hash.is_empty() || hash.as_bytes() == EMPTY_MERKLE_ROOT,
+            None => true,
+        };
+
+        if self.height < initial_height {
+            return Err(Error::invalid_block(format!(
+                "height {} is below the initial height {} of the chain",
+                self.height, initial_height
+            )));
+        }
+        if self.height > initial_height {
+            return match self.last_block_id {
+                Some(_) => Ok(()),
+                None => Err(Error::invalid_block(format!(
+                    "missing last block ID at height {}, above the initial height {}",
+                    self.height, initial_height
+                ))),
+            };
+        }
+
+        if self.last_block_id.is_some() {
+            return Err(Error::invalid_first_header());
+        }
+        if !is_empty(self.last_commit_hash) || !is_empty(self.last_results_hash) {
+            return Err(Error::invalid_block(format!(
+                "non-empty last commit or results at the initial height {initial_height}"
+            )));
+        }
+        Ok(())
+    }
 }
 
 /// `Version` contains the protocol version for the blockchain and the
@@ -129,7 +176,7 @@ tendermint_pb_modules! {
         version::Consensus as RawConsensusVersion,
     };
 
-    impl Protobuf<RawHeader> for Header {}
+    audited_protobuf!(RawHeader, Header);
 
     impl TryFrom<RawHeader> for Header {
         type Error = Error;
@@ -253,7 +300,7 @@ tendermint_pb_modules! {
 #[cfg(test)]
 mod tests {
     use super::Header;
-    use crate::test::test_serialization_roundtrip;
+    use crate::{prelude::*, test::test_serialization_roundtrip, Hash};
 
     #[test]
     fn serialization_roundtrip() {
@@ -261,6 +308,36 @@ mod tests {
         test_serialization_roundtrip::<Header>(json_data);
     }
 
+    #[test]
+    fn validates_initial_height() {
+        let header: Header = serde_json::from_str(include_str!(
+            "../../tests/support/serialization/block/header.json"
+        ))
+        .unwrap();
+        assert_eq!(header.height.value(), 15);
+
+        // The header has a last block, and can be above the initial height.
+        header.validate_initial_height(1_u32.into()).unwrap();
+        header.validate_initial_height(14_u32.into()).unwrap();
+        assert!(header.validate_initial_height(15_u32.into()).is_err());
+        assert!(header.validate_initial_height(16_u32.into()).is_err());
+
+        let first = Header {
+            last_block_id: None,
+            last_commit_hash: Some(Hash::Sha256(super::EMPTY_MERKLE_ROOT)),
+            last_results_hash: None,
+            ..header
+        };
+        first.validate_initial_height(15_u32.into()).unwrap();
+        assert!(first.validate_initial_height(14_u32.into()).is_err());
+
+        let first = Header {
+            last_results_hash: Some(Hash::Sha256([1; 32])),
+            ..first
+        };
+        assert!(first.validate_initial_height(15_u32.into()).is_err());
+    }
+
     #[cfg(feature = "rust-crypto")]
     mod crypto {
         use super::*;
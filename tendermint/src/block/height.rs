@@ -7,7 +7,7 @@ use core::{
 use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
 use tendermint_proto::Protobuf;
 
-use crate::{error::Error, prelude::*};
+use crate::{error::Error, prelude::*, serializers::str_or_int::StrOrInt};
 
 /// Block height for a particular chain (i.e. number of blocks created since
 /// the chain began)
@@ -110,7 +110,7 @@ impl FromStr for Height {
 
 impl<'de> Deserialize<'de> for Height {
     fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
-        Self::from_str(&String::deserialize(deserializer)?)
+        Self::from_str(StrOrInt::deserialize(deserializer)?.as_str())
             .map_err(|e| D::Error::custom(format!("{e}")))
     }
 }
@@ -143,4 +143,18 @@ mod tests {
             Height::from(2_u32).value()
         );
     }
+
+    #[test]
+    fn deserialize_strings_and_numbers() {
+        let height: Height = serde_json::from_str(r#""9007199254740993""#).unwrap();
+        assert_eq!(height.value(), 9_007_199_254_740_993);
+        let height: Height = serde_json::from_str("9007199254740993").unwrap();
+        assert_eq!(height.value(), 9_007_199_254_740_993);
+
+        // Heights are at most `i64::MAX`, whether they are strings or numbers.
+        assert!(serde_json::from_str::<Height>(r#""9223372036854775808""#).is_err());
+        assert!(serde_json::from_str::<Height>("9223372036854775808").is_err());
+        assert!(serde_json::from_str::<Height>("-1").is_err());
+        assert!(serde_json::from_str::<Height>("1.5").is_err());
+    }
 }
@@ -6,7 +6,7 @@ use core::{
 
 use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
 
-use crate::{error::Error, prelude::*};
+use crate::{error::Error, prelude::*, serializers::str_or_int::StrOrInt};
 
 /// Block round for a particular chain
 #[derive(Copy, Clone, Default, Eq, Hash, PartialEq, PartialOrd, Ord)]
@@ -91,7 +91,7 @@ impl FromStr for Round {
 
 impl<'de> Deserialize<'de> for Round {
     fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
-        Self::from_str(&String::deserialize(deserializer)?)
+        Self::from_str(StrOrInt::deserialize(deserializer)?.as_str())
             .map_err(|e| D::Error::custom(format!("{e}")))
     }
 }
@@ -48,7 +48,7 @@ tendermint_pb_modules! {
         }
     }
 
-    impl Protobuf<RawSignedHeader> for SignedHeader {}
+    audited_protobuf!(RawSignedHeader, SignedHeader);
 }
 
 impl SignedHeader {
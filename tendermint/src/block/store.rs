@@ -0,0 +1,401 @@
+//! Reading of the block store of a CometBFT node.
+//!
+//! A node keeps the blocks it committed in its block store, the
+//! `blockstore.db` database of its data directory, which maps the following
+//! keys to Protobuf encoded values:
+//!
+//! | Key               | Value                                             |
+//! |-------------------|---------------------------------------------------|
+//! | `blockStore`      | the lowest and highest stored heights             |
+//! | `H:<height>`      | the metadata of the block, including its header   |
+//! | `P:<height>:<i>`  | the `i`-th part of the encoded block              |
+//! | `C:<height>`      | the commit of the block, stored with the next one |
+//! | `SC:<height>`     | the commit the node saw for the latest block      |
+//! | `BH:<hash>`       | the height of the block with the given hash       |
+//!
+//! A [`BlockStore`] decodes these values into domain types, from any
+//! [`KeyValueStore`] giving access to the database. With the `leveldb`
+//! feature, [`leveldb::LevelDb`] reads the `blockstore.db` directory of a
+//! stopped node using the default goleveldb backend. The databases of the
+//! other backends take a binding implementing [`KeyValueStore`].
+
+#[cfg(feature = "leveldb")]
+#[cfg_attr(docsrs, doc(cfg(feature = "leveldb")))]
+pub mod leveldb;
+
+use alloc::collections::BTreeMap;
+use core::{fmt, ops::RangeInclusive};
+
+use prost::Message;
+use tendermint_proto::v0_38::{
+    store::BlockStoreState as RawBlockStoreState,
+    types::{Block as RawBlock, BlockMeta as RawMeta, Commit as RawCommit, Part as RawPart},
+};
+
+use super::{Block, Commit, Height, Meta};
+use crate::{
+    crypto::Sha256,
+    error::Error,
+    merkle::{simple_hash_from_byte_vectors, MerkleHash},
+    prelude::*,
+    Hash,
+};
+
+/// The maximum size of an encoded block, as set by CometBFT.
+const MAX_BLOCK_SIZE: usize = 100 * 1024 * 1024;
+
+/// Read access to the key-value database backing a block store.
+pub trait KeyValueStore {
+    /// The error returned when accessing the database fails.
+    type Error: fmt::Display;
+
+    /// Get the value of the given key, if any.
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error>;
+}
+
+impl KeyValueStore for BTreeMap<Vec<u8>, Vec<u8>> {
+    type Error = core::convert::Infallible;
+
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+        Ok(BTreeMap::get(self, key).cloned())
+    }
+}
+
+/// Reads the blocks of the block store of a node.
+///
+/// ## Examples
+///
+/// ```rust,ignore
+/// use tendermint::block::store::{leveldb::LevelDb, BlockStore};
+///
+/// let store = BlockStore::new(LevelDb::open("data/blockstore.db")?);
+/// if let Some(heights) = store.range()? {
+///     for block in store.blocks(heights) {
+///         index(block?);
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct BlockStore<S> {
+    db: S,
+}
+
+impl<S: KeyValueStore> BlockStore<S> {
+    /// Read the block store in the given database.
+    pub fn new(db: S) -> Self {
+        Self { db }
+    }
+
+    /// Get back the underlying database.
+    pub fn into_inner(self) -> S {
+        self.db
+    }
+
+    /// The range of the heights of the stored blocks, or `None` if no block
+    /// is stored.
+    ///
+    /// The blocks below the lowest height were pruned.
+    pub fn range(&self) -> Result<Option<RangeInclusive<Height>>, Error> {
+        let key = "blockStore";
+        let Some(bytes) = self.get(key)? else {
+            return Ok(None);
+        };
+        let state = RawBlockStoreState::decode(bytes.as_slice())
+            .map_err(|e| Error::invalid_block_store_entry(key.to_string(), e.to_string()))?;
+        if state.height == 0 {
+            return Ok(None);
+        }
+        // Block stores written before pruning was introduced have no base.
+        let base = state.base.max(1);
+        Ok(Some(base.try_into()?..=state.height.try_into()?))
+    }
+
+    /// The metadata of the block at the given height, if it is stored.
+    pub fn block_meta(&self, height: Height) -> Result<Option<Meta>, Error> {
+        self.load::<RawMeta, _>(&format!("H:{height}"))
+    }
+
+    /// The block at the given height, if it is stored.
+    ///
+    /// The parts of the block are verified against the hash of the part set
+    /// recorded in the metadata of the block, and the block against the
+    /// recorded block hash.
+    #[cfg(feature = "rust-crypto")]
+    pub fn block(&self, height: Height) -> Result<Option<Block>, Error> {
+        self.block_with::<crate::crypto::default::Sha256>(height)
+    }
+
+    /// The block at the given height, if it is stored, verified with the
+    /// given hasher, as in [`BlockStore::block`].
+    pub fn block_with<H>(&self, height: Height) -> Result<Option<Block>, Error>
+    where
+        H: MerkleHash + Sha256 + Default,
+    {
+        let Some(meta) = self.block_meta(height)? else {
+            return Ok(None);
+        };
+        let invalid = |detail: &str| {
+            Error::invalid_block_store_entry(format!("P:{height}:*"), detail.to_string())
+        };
+        // The size is read from the database, and only trusted up to the
+        // maximum size of a block.
+        let size = usize::try_from(meta.block_size).unwrap_or_default();
+        let mut bytes = Vec::with_capacity(size.min(MAX_BLOCK_SIZE));
+        let mut parts = Vec::new();
+        for index in 0..meta.block_id.part_set_header.total {
+            let key = format!("P:{height}:{index}");
+            let part = self
+                .get(&key)?
+                .ok_or_else(|| Error::missing_block_store_entry(key.clone()))?;
+            let part = RawPart::decode(part.as_slice())
+                .map_err(|e| Error::invalid_block_store_entry(key, e.to_string()))?;
+            if bytes.len() + part.bytes.len() > MAX_BLOCK_SIZE {
+                return Err(invalid("block exceeds the maximum size"));
+            }
+            bytes.extend_from_slice(&part.bytes);
+            parts.push(part.bytes);
+        }
+        let parts_hash = Hash::Sha256(simple_hash_from_byte_vectors::<H>(&parts));
+        if parts_hash != meta.block_id.part_set_header.hash {
+            return Err(invalid("parts do not match the part set hash"));
+        }
+        let block = Block::try_from(
+            RawBlock::decode(bytes.as_slice()).map_err(|e| invalid(&e.to_string()))?,
+        )?;
+        if block.header.hash_with::<H>() != meta.block_id.hash {
+            return Err(invalid("block does not match the block hash"));
+        }
+        Ok(Some(block))
+    }
+
+    /// The commit of the block at the given height, which is stored along
+    /// with the block at the next height, as its last commit.
+    pub fn block_commit(&self, height: Height) -> Result<Option<Commit>, Error> {
+        self.load::<RawCommit, _>(&format!("C:{height}"))
+    }
+
+    /// The commit the node saw for the block at the given height. It is only
+    /// kept for the latest block.
+    pub fn seen_commit(&self, height: Height) -> Result<Option<Commit>, Error> {
+        self.load::<RawCommit, _>(&format!("SC:{height}"))
+    }
+
+    /// The height of the block with the given hash, if it is stored.
+    pub fn block_height_by_hash(&self, hash: &Hash) -> Result<Option<Height>, Error> {
+        let key = format!("BH:{}", hex_lower(hash.as_bytes()));
+        let Some(bytes) = self.get(&key)? else {
+            return Ok(None);
+        };
+        let height = core::str::from_utf8(&bytes)
+            .map_err(|e| e.to_string())
+            .and_then(|height| height.parse::<Height>().map_err(|e| e.to_string()))
+            .map_err(|e| Error::invalid_block_store_entry(key, e))?;
+        Ok(Some(height))
+    }
+
+    /// The block with the given hash, if it is stored.
+    #[cfg(feature = "rust-crypto")]
+    pub fn block_by_hash(&self, hash: &Hash) -> Result<Option<Block>, Error> {
+        match self.block_height_by_hash(hash)? {
+            Some(height) => self.block(height),
+            None => Ok(None),
+        }
+    }
+
+    /// Iterate over the blocks in the given range of heights, in increasing
+    /// order.
+    ///
+    /// A block missing from the store, e.g. because it was pruned, is
+    /// reported as an error.
+    #[cfg(feature = "rust-crypto")]
+    pub fn blocks(
+        &self,
+        heights: RangeInclusive<Height>,
+    ) -> impl Iterator<Item = Result<Block, Error>> + '_ {
+        (heights.start().value()..=heights.end().value()).map(move |height| {
+            let height = Height::try_from(height)?;
+            self.block(height)?
+                .ok_or_else(|| Error::missing_block_store_entry(format!("H:{height}")))
+        })
+    }
+
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>, Error> {
+        self.db
+            .get(key.as_bytes())
+            .map_err(|e| Error::block_store(e.to_string()))
+    }
+
+    fn load<Raw, T>(&self, key: &str) -> Result<Option<T>, Error>
+    where
+        Raw: Message + Default,
+        T: TryFrom<Raw, Error = Error>,
+    {
+        let Some(bytes) = self.get(key)? else {
+            return Ok(None);
+        };
+        let raw = Raw::decode(bytes.as_slice())
+            .map_err(|e| Error::invalid_block_store_entry(key.to_string(), e.to_string()))?;
+        T::try_from(raw).map(Some)
+    }
+}
+
+/// Format bytes as lowercase hexadecimal, as in the block hash keys.
+fn hex_lower(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+#[cfg(all(test, feature = "rust-crypto"))]
+mod tests {
+    use tendermint_proto::v0_38::types::{BlockId as RawBlockId, PartSetHeader};
+
+    use super::*;
+    use crate::{block::Header, crypto::default::Sha256};
+
+    const PART_SIZE: usize = 64;
+
+    pub(super) fn block() -> Block {
+        let mut header: Header = serde_json::from_str(include_str!(
+            "../../tests/support/serialization/block/header.json"
+        ))
+        .unwrap();
+        header.height = Height::from(1_u32);
+        header.last_block_id = None;
+        Block::new(
+            header,
+            vec![vec![7; 100], vec![8; 50]],
+            Default::default(),
+            None,
+        )
+        .unwrap()
+    }
+
+    /// The entries of a block store holding the given block.
+    pub(super) fn store(block: &Block) -> BTreeMap<Vec<u8>, Vec<u8>> {
+        let bytes = RawBlock::from(block.clone()).encode_to_vec();
+        let mut db = BTreeMap::new();
+        let parts: Vec<_> = bytes.chunks(PART_SIZE).collect();
+        for (index, chunk) in parts.iter().enumerate() {
+            let part = RawPart {
+                index: index as u32,
+                bytes: chunk.to_vec(),
+                proof: None,
+            };
+            db.insert(format!("P:1:{index}").into_bytes(), part.encode_to_vec());
+        }
+        let hash = block.header.hash();
+        let meta = RawMeta {
+            block_id: Some(RawBlockId {
+                hash: hash.as_bytes().to_vec(),
+                part_set_header: Some(PartSetHeader {
+                    total: parts.len() as u32,
+                    hash: simple_hash_from_byte_vectors::<Sha256>(&parts).to_vec(),
+                }),
+            }),
+            block_size: bytes.len() as i64,
+            header: Some(block.header.clone().into()),
+            num_txs: block.data.len() as i64,
+        };
+        db.insert(b"H:1".to_vec(), meta.encode_to_vec());
+        db.insert(
+            format!("BH:{}", hex_lower(hash.as_bytes())).into_bytes(),
+            b"1".to_vec(),
+        );
+        let state = RawBlockStoreState { base: 1, height: 1 };
+        db.insert(b"blockStore".to_vec(), state.encode_to_vec());
+        db
+    }
+
+    fn update_meta(db: &mut BTreeMap<Vec<u8>, Vec<u8>>, update: impl FnOnce(&mut RawMeta)) {
+        let mut meta = RawMeta::decode(db[b"H:1".as_slice()].as_slice()).unwrap();
+        update(&mut meta);
+        db.insert(b"H:1".to_vec(), meta.encode_to_vec());
+    }
+
+    fn invalid_entry(err: &Error) -> Option<&str> {
+        match err.detail() {
+            crate::error::ErrorDetail::InvalidBlockStoreEntry(e) => Some(&e.detail),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn reads_blocks() {
+        let block = block();
+        let hash = block.header.hash();
+        let store = BlockStore::new(store(&block));
+        let one = Height::from(1_u32);
+
+        assert_eq!(store.range().unwrap(), Some(one..=one));
+        let meta = store.block_meta(one).unwrap().unwrap();
+        assert_eq!(meta.num_txs, 2);
+        assert!(meta.block_id.part_set_header.total > 1);
+        assert_eq!(store.block(one).unwrap(), Some(block.clone()));
+        assert_eq!(store.block_by_hash(&hash).unwrap(), Some(block.clone()));
+        assert_eq!(store.block(Height::from(2_u32)).unwrap(), None);
+        assert_eq!(store.block_commit(one).unwrap(), None);
+
+        let blocks: Vec<_> = store.blocks(one..=Height::from(2_u32)).collect();
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].as_ref().unwrap(), &block);
+        assert!(blocks[1].is_err());
+    }
+
+    #[test]
+    fn reports_missing_parts() {
+        let mut db = store(&block());
+        db.remove(b"P:1:1".as_slice());
+        let err = BlockStore::new(db).block(Height::from(1_u32)).unwrap_err();
+        assert!(matches!(
+            err.detail(),
+            crate::error::ErrorDetail::MissingBlockStoreEntry(e) if e.key == "P:1:1"
+        ));
+    }
+
+    #[test]
+    fn verifies_the_parts_and_the_block() {
+        let one = Height::from(1_u32);
+
+        // A transaction byte of the block is altered in its last part.
+        let mut db = store(&block());
+        let key = db
+            .keys()
+            .rfind(|key| key.starts_with(b"P:"))
+            .unwrap()
+            .clone();
+        let mut part = RawPart::decode(db[&key].as_slice()).unwrap();
+        let index = part.bytes.iter().rposition(|byte| *byte == 8).unwrap();
+        part.bytes[index] = 9;
+        db.insert(key, part.encode_to_vec());
+        let err = BlockStore::new(db).block(one).unwrap_err();
+        assert_eq!(
+            invalid_entry(&err),
+            Some("parts do not match the part set hash")
+        );
+
+        let mut db = store(&block());
+        update_meta(&mut db, |meta| {
+            meta.block_id.as_mut().unwrap().hash = vec![3; 32];
+        });
+        let err = BlockStore::new(db).block(one).unwrap_err();
+        assert_eq!(
+            invalid_entry(&err),
+            Some("block does not match the block hash")
+        );
+    }
+
+    #[test]
+    fn bounds_the_recorded_block_size() {
+        let block = block();
+        let mut db = store(&block);
+        update_meta(&mut db, |meta| meta.block_size = i64::MAX);
+        let store = BlockStore::new(db);
+        assert_eq!(store.block(Height::from(1_u32)).unwrap(), Some(block));
+    }
+
+    #[test]
+    fn empty_store() {
+        let store = BlockStore::new(BTreeMap::new());
+        assert_eq!(store.range().unwrap(), None);
+        assert_eq!(store.block(Height::from(1_u32)).unwrap(), None);
+    }
+}
@@ -0,0 +1,873 @@
+//! Reading of the LevelDB databases of nodes.
+//!
+//! Nodes write their databases with goleveldb, into the files of the LevelDB
+//! format: the sorted tables holding most of the entries, the manifest
+//! listing the tables, and the logs of the latest writes, which were not
+//! compacted into tables yet. [`LevelDb`] reads these files directly,
+//! checking their checksums and decompressing the blocks of the tables
+//! compressed with Snappy.
+//!
+//! The database must not be written to while it is read, so the node must
+//! be stopped, or the database copied first.
+
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+use super::KeyValueStore;
+use crate::prelude::*;
+
+/// The magic number ending the tables.
+const TABLE_MAGIC: u64 = 0xdb47_7524_8b80_fb57;
+
+/// The size of the footer of a table.
+const FOOTER_SIZE: usize = 48;
+
+/// The size of the blocks of the logs.
+const LOG_BLOCK_SIZE: usize = 32 * 1024;
+
+/// The size of the header of the records of the logs.
+const LOG_HEADER_SIZE: usize = 7;
+
+/// The maximum size of a block of a table, above which it is considered
+/// corrupted. The blocks written by goleveldb are of a few kilobytes, except
+/// for the blocks of single large entries.
+const MAX_TABLE_BLOCK_SIZE: usize = 256 * 1024 * 1024;
+
+/// The comparator of the keys of the databases written by nodes.
+const BYTEWISE_COMPARATOR: &[u8] = b"leveldb.BytewiseComparator";
+
+/// A table of the database, as listed in the manifest.
+#[derive(Clone, Debug)]
+struct Table {
+    number: u64,
+    smallest: Vec<u8>,
+    largest: Vec<u8>,
+}
+
+/// A LevelDB database, opened read-only.
+///
+/// The entries of the logs are loaded when the database is opened, and the
+/// entries of the tables are read from disk on each access.
+///
+/// ## Examples
+///
+/// ```rust,ignore
+/// use tendermint::block::store::{leveldb::LevelDb, BlockStore};
+///
+/// let store = BlockStore::new(LevelDb::open("data/blockstore.db")?);
+/// ```
+#[derive(Clone, Debug)]
+pub struct LevelDb {
+    dir: PathBuf,
+    /// The latest values written to the logs, with `None` for the deleted
+    /// keys.
+    log_entries: BTreeMap<Vec<u8>, Option<Vec<u8>>>,
+    /// The tables of each level. The tables of level 0, which may overlap,
+    /// are sorted from the newest, and the tables of the other levels by
+    /// their keys.
+    levels: Vec<Vec<Table>>,
+}
+
+impl LevelDb {
+    /// Open the database in the given directory.
+    pub fn open(dir: impl AsRef<Path>) -> io::Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        let current = read_file(&dir.join("CURRENT"))?;
+        let manifest = core::str::from_utf8(&current)
+            .ok()
+            .map(str::trim_end)
+            .filter(|name| name.starts_with("MANIFEST-") && !name.contains(['/', '\\']))
+            .ok_or_else(|| invalid_data(&dir.join("CURRENT"), "no manifest"))?;
+
+        let manifest = dir.join(manifest);
+        let mut tables = BTreeMap::new();
+        let mut log_number = 0;
+        let mut prev_log_number = 0;
+        for edit in log_records(&read_file(&manifest)?) {
+            apply_edit(&edit, &mut tables, &mut log_number, &mut prev_log_number)
+                .map_err(|e| invalid_data(&manifest, &e))?;
+        }
+        let mut levels: Vec<Vec<Table>> = Vec::new();
+        for ((level, _), table) in tables {
+            let level = usize::try_from(level).unwrap_or(usize::MAX);
+            if level >= levels.len() {
+                levels.resize(level.saturating_add(1).min(64), Vec::new());
+            }
+            levels
+                .get_mut(level)
+                .ok_or_else(|| invalid_data(&manifest, "invalid table level"))?
+                .push(table);
+        }
+        if let Some(level0) = levels.first_mut() {
+            level0.sort_by_key(|table| core::cmp::Reverse(table.number));
+        }
+        for level in levels.iter_mut().skip(1) {
+            level.sort_by(|a, b| a.smallest.cmp(&b.smallest));
+        }
+
+        // The logs written since the last compaction, in the order they were
+        // written.
+        let mut logs = Vec::new();
+        for entry in std::fs::read_dir(&dir)? {
+            let name = entry?.file_name();
+            let number = name
+                .to_str()
+                .and_then(|name| name.strip_suffix(".log"))
+                .and_then(|number| number.parse::<u64>().ok());
+            if let Some(number) = number {
+                if number >= log_number || number == prev_log_number {
+                    logs.push(number);
+                }
+            }
+        }
+        logs.sort_unstable();
+        let mut log_entries = BTreeMap::new();
+        for number in logs {
+            let path = dir.join(format!("{number:06}.log"));
+            for batch in log_records(&read_file(&path)?) {
+                apply_batch(&batch, &mut log_entries).map_err(|e| invalid_data(&path, &e))?;
+            }
+        }
+
+        Ok(Self {
+            dir,
+            log_entries,
+            levels,
+        })
+    }
+
+    /// The tables which may hold the given key, in the order they are to be
+    /// searched.
+    fn tables_for<'a>(&'a self, key: &'a [u8]) -> impl Iterator<Item = &'a Table> + 'a {
+        let level0 = self
+            .levels
+            .first()
+            .into_iter()
+            .flatten()
+            .filter(move |table| {
+                table.smallest.as_slice() <= key && key <= table.largest.as_slice()
+            });
+        let others = self.levels.iter().skip(1).filter_map(move |level| {
+            let index = level.partition_point(|table| table.largest.as_slice() < key);
+            level
+                .get(index)
+                .filter(|table| table.smallest.as_slice() <= key)
+        });
+        level0.chain(others)
+    }
+
+    /// The value of the given key in the given table, if the table has an
+    /// entry for it, which is `None` if the key was deleted.
+    fn table_get(&self, table: &Table, key: &[u8]) -> io::Result<Option<Option<Vec<u8>>>> {
+        let mut path = self.dir.join(format!("{:06}.ldb", table.number));
+        if !path.exists() {
+            // The extension of the tables written by older versions.
+            path.set_extension("sst");
+        }
+        let invalid = |detail: &str| invalid_data(&path, detail);
+        let mut file = File::open(&path)?;
+        let size = file.metadata()?.len();
+        if size < FOOTER_SIZE as u64 {
+            return Err(invalid("truncated table"));
+        }
+        let footer = read_at(&mut file, size - FOOTER_SIZE as u64, FOOTER_SIZE)?;
+        if footer[40..] != TABLE_MAGIC.to_le_bytes() {
+            return Err(invalid("not a table"));
+        }
+        let mut reader = Reader::new(&footer);
+        let _meta_index = BlockHandle::read(&mut reader).map_err(|e| invalid(&e))?;
+        let index = BlockHandle::read(&mut reader).map_err(|e| invalid(&e))?;
+
+        // The index maps keys, which are at least as large as the last key
+        // of each data block and smaller than the first key of the next one,
+        // to the data blocks.
+        let index = read_block(&mut file, index).map_err(|e| invalid(&e))?;
+        let mut data = None;
+        for (separator, handle) in block_entries(&index).map_err(|e| invalid(&e))? {
+            let (separator, _) = split_internal_key(&separator).map_err(|e| invalid(&e))?;
+            if separator >= key {
+                data = Some(BlockHandle::read(&mut Reader::new(&handle)).map_err(|e| invalid(&e))?);
+                break;
+            }
+        }
+        let Some(data) = data else {
+            return Ok(None);
+        };
+
+        // The entries of a key are sorted from the latest.
+        let data = read_block(&mut file, data).map_err(|e| invalid(&e))?;
+        for (internal_key, value) in block_entries(&data).map_err(|e| invalid(&e))? {
+            let (user_key, kind) = split_internal_key(&internal_key).map_err(|e| invalid(&e))?;
+            if user_key == key {
+                return match kind {
+                    0 => Ok(Some(None)),
+                    1 => Ok(Some(Some(value))),
+                    _ => Err(invalid("invalid entry type")),
+                };
+            }
+            if user_key > key {
+                break;
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl KeyValueStore for LevelDb {
+    type Error = io::Error;
+
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+        if let Some(value) = self.log_entries.get(key) {
+            return Ok(value.clone());
+        }
+        for table in self.tables_for(key) {
+            if let Some(value) = self.table_get(table, key)? {
+                return Ok(value);
+            }
+        }
+        Ok(None)
+    }
+}
+
+fn invalid_data(path: &Path, detail: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{}: {}", path.display(), detail),
+    )
+}
+
+fn read_file(path: &Path) -> io::Result<Vec<u8>> {
+    std::fs::read(path).map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))
+}
+
+fn read_at(file: &mut File, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+    let mut bytes = vec![0; len];
+    file.seek(SeekFrom::Start(offset))?;
+    file.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// The records of a log, i.e. of a log of writes or of a manifest.
+///
+/// A log ending with a truncated or corrupted record, as written by a node
+/// which crashed, is read up to that record.
+fn log_records(log: &[u8]) -> Vec<Vec<u8>> {
+    let mut records = Vec::new();
+    let mut record: Option<Vec<u8>> = None;
+    let mut pos = 0;
+    while pos < log.len() {
+        let block_left = LOG_BLOCK_SIZE - pos % LOG_BLOCK_SIZE;
+        if block_left < LOG_HEADER_SIZE {
+            // The end of a block too short for a header is zero padding.
+            pos += block_left;
+            continue;
+        }
+        let Some(header) = log.get(pos..pos + LOG_HEADER_SIZE) else {
+            break;
+        };
+        let checksum = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+        let len = usize::from(u16::from_le_bytes([header[4], header[5]]));
+        let kind = header[6];
+        if kind == 0 && len == 0 {
+            // The rest of a preallocated block.
+            pos += block_left;
+            continue;
+        }
+        let start = pos + LOG_HEADER_SIZE;
+        let Some(fragment) = log.get(start..start + len) else {
+            break;
+        };
+        if len > block_left - LOG_HEADER_SIZE
+            || unmask(checksum) != crc32c(&log[pos + 6..start + len])
+        {
+            break;
+        }
+        pos = start + len;
+        match (kind, record.as_mut()) {
+            (1, _) => {
+                records.push(fragment.to_vec());
+                record = None;
+            },
+            (2, _) => record = Some(fragment.to_vec()),
+            (3, Some(record)) => record.extend_from_slice(fragment),
+            (4, Some(_)) => {
+                let mut record = record.take().unwrap_or_default();
+                record.extend_from_slice(fragment);
+                records.push(record);
+            },
+            _ => break,
+        }
+    }
+    records
+}
+
+/// Apply an edit of the manifest to the tables of the database, keyed by
+/// level and number.
+fn apply_edit(
+    edit: &[u8],
+    tables: &mut BTreeMap<(u64, u64), Table>,
+    log_number: &mut u64,
+    prev_log_number: &mut u64,
+) -> Result<(), String> {
+    let mut reader = Reader::new(edit);
+    while !reader.is_empty() {
+        match reader.varint()? {
+            // The comparator.
+            1 => {
+                if reader.length_prefixed()? != BYTEWISE_COMPARATOR {
+                    return Err("unsupported comparator".to_string());
+                }
+            },
+            2 => *log_number = reader.varint()?,
+            // The next file number and the last sequence number.
+            3 | 4 => {
+                reader.varint()?;
+            },
+            // A compaction pointer.
+            5 => {
+                reader.varint()?;
+                reader.length_prefixed()?;
+            },
+            // A deleted table.
+            6 => {
+                let level = reader.varint()?;
+                let number = reader.varint()?;
+                tables.remove(&(level, number));
+            },
+            // A new table.
+            7 => {
+                let level = reader.varint()?;
+                let number = reader.varint()?;
+                let _size = reader.varint()?;
+                let (smallest, _) = split_internal_key(reader.length_prefixed()?)?;
+                let (largest, _) = split_internal_key(reader.length_prefixed()?)?;
+                let table = Table {
+                    number,
+                    smallest: smallest.to_vec(),
+                    largest: largest.to_vec(),
+                };
+                tables.insert((level, number), table);
+            },
+            9 => *prev_log_number = reader.varint()?,
+            tag => return Err(format!("unknown manifest tag {tag}")),
+        }
+    }
+    Ok(())
+}
+
+/// Apply a batch of writes of a log to the given entries.
+fn apply_batch(
+    batch: &[u8],
+    entries: &mut BTreeMap<Vec<u8>, Option<Vec<u8>>>,
+) -> Result<(), String> {
+    let mut reader = Reader::new(batch);
+    let _sequence = reader.bytes(8)?;
+    let count = reader.bytes(4)?;
+    let count = u32::from_le_bytes([count[0], count[1], count[2], count[3]]);
+    for _ in 0..count {
+        match reader.bytes(1)?[0] {
+            0 => {
+                entries.insert(reader.length_prefixed()?.to_vec(), None);
+            },
+            1 => {
+                let key = reader.length_prefixed()?.to_vec();
+                let value = reader.length_prefixed()?.to_vec();
+                entries.insert(key, Some(value));
+            },
+            _ => return Err("invalid batch entry type".to_string()),
+        }
+    }
+    Ok(())
+}
+
+/// Split an internal key into its user key and the type of its entry.
+fn split_internal_key(key: &[u8]) -> Result<(&[u8], u8), String> {
+    if key.len() < 8 {
+        return Err("invalid internal key".to_string());
+    }
+    let (user_key, trailer) = key.split_at(key.len() - 8);
+    Ok((user_key, trailer[0]))
+}
+
+/// The location of a block in a table.
+#[derive(Copy, Clone, Debug)]
+struct BlockHandle {
+    offset: u64,
+    size: u64,
+}
+
+impl BlockHandle {
+    fn read(reader: &mut Reader<'_>) -> Result<Self, String> {
+        Ok(Self {
+            offset: reader.varint()?,
+            size: reader.varint()?,
+        })
+    }
+}
+
+/// Read the block of a table, followed by its compression type and checksum.
+fn read_block(file: &mut File, handle: BlockHandle) -> Result<Vec<u8>, String> {
+    let size = usize::try_from(handle.size)
+        .ok()
+        .filter(|size| *size <= MAX_TABLE_BLOCK_SIZE)
+        .ok_or("block exceeds the maximum size")?;
+    let bytes = read_at(file, handle.offset, size + 5).map_err(|e| e.to_string())?;
+    let checksum = u32::from_le_bytes([
+        bytes[size + 1],
+        bytes[size + 2],
+        bytes[size + 3],
+        bytes[size + 4],
+    ]);
+    if unmask(checksum) != crc32c(&bytes[..size + 1]) {
+        return Err("block checksum mismatch".to_string());
+    }
+    match bytes[size] {
+        0 => Ok(bytes[..size].to_vec()),
+        1 => snappy_decompress(&bytes[..size]),
+        _ => Err("unsupported block compression".to_string()),
+    }
+}
+
+/// A key of a table and its value.
+type Entry = (Vec<u8>, Vec<u8>);
+
+/// The keys and values of a block of a table.
+fn block_entries(block: &[u8]) -> Result<Vec<Entry>, String> {
+    let invalid = || "invalid block".to_string();
+    let restarts = block.len().checked_sub(4).ok_or_else(invalid)?;
+    let restarts = u32::from_le_bytes(block[restarts..].try_into().map_err(|_| invalid())?);
+    let end = usize::try_from(restarts)
+        .ok()
+        .and_then(|restarts| restarts.checked_add(1)?.checked_mul(4))
+        .and_then(|trailer| block.len().checked_sub(trailer))
+        .ok_or_else(invalid)?;
+
+    // Each key is stored as the length of the prefix it shares with the
+    // previous key, followed by the rest of it.
+    let mut entries = Vec::new();
+    let mut key = Vec::new();
+    let mut reader = Reader::new(&block[..end]);
+    while !reader.is_empty() {
+        let shared = reader.length()?;
+        let non_shared = reader.length()?;
+        let value_len = reader.length()?;
+        if shared > key.len() {
+            return Err(invalid());
+        }
+        key.truncate(shared);
+        key.extend_from_slice(reader.bytes(non_shared)?);
+        entries.push((key.clone(), reader.bytes(value_len)?.to_vec()));
+    }
+    Ok(entries)
+}
+
+/// Decompress a block compressed with Snappy.
+fn snappy_decompress(input: &[u8]) -> Result<Vec<u8>, String> {
+    let invalid = || "invalid Snappy data".to_string();
+    let mut reader = Reader::new(input);
+    let len = reader.length()?;
+    if len > MAX_TABLE_BLOCK_SIZE {
+        return Err("block exceeds the maximum size".to_string());
+    }
+    let mut output = Vec::with_capacity(len);
+    while !reader.is_empty() {
+        let tag = reader.bytes(1)?[0];
+        let (offset, copy_len) = match tag & 3 {
+            0 => {
+                let literal_len = match usize::from(tag >> 2) {
+                    short @ 0..=59 => short + 1,
+                    long => {
+                        let mut bytes = [0; 4];
+                        bytes[..long - 59].copy_from_slice(reader.bytes(long - 59)?);
+                        usize::try_from(u32::from_le_bytes(bytes))
+                            .ok()
+                            .and_then(|len| len.checked_add(1))
+                            .ok_or_else(invalid)?
+                    },
+                };
+                if output.len() + literal_len > len {
+                    return Err(invalid());
+                }
+                output.extend_from_slice(reader.bytes(literal_len)?);
+                continue;
+            },
+            1 => {
+                let offset = (usize::from(tag >> 5) << 8) | usize::from(reader.bytes(1)?[0]);
+                (offset, usize::from((tag >> 2) & 7) + 4)
+            },
+            2 => {
+                let bytes = reader.bytes(2)?;
+                let offset = u16::from_le_bytes([bytes[0], bytes[1]]);
+                (usize::from(offset), usize::from(tag >> 2) + 1)
+            },
+            _ => {
+                let bytes = reader.bytes(4)?;
+                let offset = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                let offset = usize::try_from(offset).map_err(|_| invalid())?;
+                (offset, usize::from(tag >> 2) + 1)
+            },
+        };
+        if offset == 0 || offset > output.len() || output.len() + copy_len > len {
+            return Err(invalid());
+        }
+        // The copied bytes may overlap the bytes being appended.
+        for _ in 0..copy_len {
+            output.push(output[output.len() - offset]);
+        }
+    }
+    if output.len() != len {
+        return Err(invalid());
+    }
+    Ok(output)
+}
+
+/// The CRC-32C checksum of the given bytes.
+fn crc32c(bytes: &[u8]) -> u32 {
+    let mut crc = !0_u32;
+    for byte in bytes {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0x82f6_3b78
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// Unmask a checksum stored by LevelDB, which are masked so that the
+/// checksums of data holding checksums are not computed over them.
+fn unmask(masked: u32) -> u32 {
+    let rotated = masked.wrapping_sub(0xa282_ead8);
+    rotated.rotate_left(15)
+}
+
+/// A reader of the fields of the LevelDB encodings.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if len > self.bytes.len() {
+            return Err("unexpected end of data".to_string());
+        }
+        let (bytes, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(bytes)
+    }
+
+    fn varint(&mut self) -> Result<u64, String> {
+        let mut value = 0_u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.bytes(1)?[0];
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err("invalid varint".to_string())
+    }
+
+    fn length(&mut self) -> Result<usize, String> {
+        usize::try_from(self.varint()?).map_err(|_| "invalid length".to_string())
+    }
+
+    fn length_prefixed(&mut self) -> Result<&'a [u8], String> {
+        let len = self.length()?;
+        self.bytes(len)
+    }
+}
+
+#[cfg(all(test, feature = "rust-crypto"))]
+mod tests {
+    use std::fs;
+
+    use super::{
+        super::{tests, BlockStore},
+        *,
+    };
+    use crate::block::Height;
+
+    fn mask(crc: u32) -> u32 {
+        crc.rotate_right(15).wrapping_add(0xa282_ead8)
+    }
+
+    fn varint(mut value: u64, out: &mut Vec<u8>) {
+        while value >= 0x80 {
+            out.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        out.push(value as u8);
+    }
+
+    fn length_prefixed(bytes: &[u8], out: &mut Vec<u8>) {
+        varint(bytes.len() as u64, out);
+        out.extend_from_slice(bytes);
+    }
+
+    fn internal_key(key: &[u8], sequence: u64, kind: u8) -> Vec<u8> {
+        let mut internal = key.to_vec();
+        internal.extend_from_slice(&((sequence << 8) | u64::from(kind)).to_le_bytes());
+        internal
+    }
+
+    /// Write the given records into a log, fragmented across its blocks.
+    fn log(records: &[Vec<u8>]) -> Vec<u8> {
+        let mut out = Vec::new();
+        for record in records {
+            let mut rest = record.as_slice();
+            let mut first = true;
+            loop {
+                let block_left = LOG_BLOCK_SIZE - out.len() % LOG_BLOCK_SIZE;
+                if block_left < LOG_HEADER_SIZE {
+                    out.resize(out.len() + block_left, 0);
+                    continue;
+                }
+                let len = rest.len().min(block_left - LOG_HEADER_SIZE);
+                let last = len == rest.len();
+                let kind = match (first, last) {
+                    (true, true) => 1,
+                    (true, false) => 2,
+                    (false, false) => 3,
+                    (false, true) => 4,
+                };
+                let mut checked = vec![kind];
+                checked.extend_from_slice(&rest[..len]);
+                out.extend_from_slice(&mask(crc32c(&checked)).to_le_bytes());
+                out.extend_from_slice(&(len as u16).to_le_bytes());
+                out.extend_from_slice(&checked);
+                rest = &rest[len..];
+                first = false;
+                if last {
+                    break;
+                }
+            }
+        }
+        out
+    }
+
+    fn batch(sequence: u64, writes: &[(&[u8], Option<&[u8]>)]) -> Vec<u8> {
+        let mut out = sequence.to_le_bytes().to_vec();
+        out.extend_from_slice(&(writes.len() as u32).to_le_bytes());
+        for (key, value) in writes {
+            out.push(value.is_some().into());
+            length_prefixed(key, &mut out);
+            if let Some(value) = value {
+                length_prefixed(value, &mut out);
+            }
+        }
+        out
+    }
+
+    /// Compress the given bytes with Snappy, as literals only.
+    fn snappy_literals(bytes: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        varint(bytes.len() as u64, &mut out);
+        for chunk in bytes.chunks(1000) {
+            let len = (chunk.len() - 1) as u16;
+            if len < 60 {
+                out.push((len as u8) << 2);
+            } else {
+                out.push(61 << 2);
+                out.extend_from_slice(&len.to_le_bytes());
+            }
+            out.extend_from_slice(chunk);
+        }
+        out
+    }
+
+    fn block(entries: &[Entry]) -> Vec<u8> {
+        const RESTART_INTERVAL: usize = 4;
+        let mut out = Vec::new();
+        let mut restarts = Vec::new();
+        let mut previous: &[u8] = &[];
+        for (index, (key, value)) in entries.iter().enumerate() {
+            let shared = if index % RESTART_INTERVAL == 0 {
+                restarts.push(out.len() as u32);
+                0
+            } else {
+                key.iter().zip(previous).take_while(|(a, b)| a == b).count()
+            };
+            varint(shared as u64, &mut out);
+            varint((key.len() - shared) as u64, &mut out);
+            varint(value.len() as u64, &mut out);
+            out.extend_from_slice(&key[shared..]);
+            out.extend_from_slice(value);
+            previous = key;
+        }
+        for restart in &restarts {
+            out.extend_from_slice(&restart.to_le_bytes());
+        }
+        out.extend_from_slice(&(restarts.len() as u32).to_le_bytes());
+        out
+    }
+
+    fn write_block(table: &mut Vec<u8>, block: &[u8], compress: bool) -> Vec<u8> {
+        let (contents, kind) = if compress {
+            (snappy_literals(block), 1)
+        } else {
+            (block.to_vec(), 0)
+        };
+        let mut handle = Vec::new();
+        varint(table.len() as u64, &mut handle);
+        varint(contents.len() as u64, &mut handle);
+        let mut checked = contents;
+        checked.push(kind);
+        let checksum = mask(crc32c(&checked));
+        table.extend_from_slice(&checked);
+        table.extend_from_slice(&checksum.to_le_bytes());
+        handle
+    }
+
+    /// Write a table holding the given entries, sorted by internal key, in
+    /// data blocks of two entries.
+    fn table(entries: &[Entry], compress: bool) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut index = Vec::new();
+        for entries in entries.chunks(2) {
+            let handle = write_block(&mut out, &block(entries), compress);
+            index.push((entries.last().unwrap().0.clone(), handle));
+        }
+        let mut footer = write_block(&mut out, &block(&[]), compress);
+        footer.extend(write_block(&mut out, &block(&index), compress));
+        footer.resize(40, 0);
+        footer.extend_from_slice(&TABLE_MAGIC.to_le_bytes());
+        out.extend(footer);
+        out
+    }
+
+    fn new_table(level: u64, number: u64, entries: &[Entry]) -> Vec<u8> {
+        let mut edit = Vec::new();
+        varint(7, &mut edit);
+        varint(level, &mut edit);
+        varint(number, &mut edit);
+        varint(0, &mut edit);
+        length_prefixed(&entries.first().unwrap().0, &mut edit);
+        length_prefixed(&entries.last().unwrap().0, &mut edit);
+        edit
+    }
+
+    /// Write a database holding the entries of a block store, spread over
+    /// the logs and the tables of two levels, with stale entries.
+    fn write_database(dir: &Path) {
+        let entries = tests::store(&tests::block());
+        let mut keys: Vec<_> = entries.keys().cloned().collect();
+        let meta = keys.iter().position(|key| key == b"H:1").unwrap();
+        let meta = keys.remove(meta);
+
+        // Most entries, and a stale block meta, are in a level 1 table.
+        let mut level1: Vec<_> = keys
+            .iter()
+            .map(|key| (internal_key(key, 1, 1), entries[key].clone()))
+            .chain([
+                (internal_key(&meta, 1, 1), b"stale".to_vec()),
+                (internal_key(b"deleted", 1, 1), b"value".to_vec()),
+            ])
+            .collect();
+        // Internal keys are sorted by user key, then from the latest entry.
+        level1.sort_by(|(a, _), (b, _)| {
+            let (a, b) = (a.split_at(a.len() - 8), b.split_at(b.len() - 8));
+            a.0.cmp(b.0).then_with(|| b.1.cmp(a.1))
+        });
+        fs::write(dir.join("000005.ldb"), table(&level1, true)).unwrap();
+
+        // The block meta is overwritten in a newer level 0 table.
+        let level0 = vec![(internal_key(&meta, 2, 1), entries[&meta].clone())];
+        fs::write(dir.join("000007.ldb"), table(&level0, false)).unwrap();
+
+        // The block store state is overwritten, and an entry deleted, in the
+        // log written since.
+        let state = b"blockStore";
+        let large = vec![1; LOG_BLOCK_SIZE * 2];
+        let batches = [
+            batch(3, &[(state, Some(b"stale")), (b"large", Some(&large))]),
+            batch(
+                5,
+                &[
+                    (state, Some(&entries[state.as_slice()])),
+                    (b"deleted", None),
+                ],
+            ),
+        ];
+        fs::write(dir.join("000008.log"), log(&batches)).unwrap();
+        // An older log, compacted into the tables.
+        fs::write(dir.join("000004.log"), log(&[batch(1, &[(state, None)])])).unwrap();
+
+        let mut edit = Vec::new();
+        varint(1, &mut edit);
+        length_prefixed(BYTEWISE_COMPARATOR, &mut edit);
+        let mut log_number = Vec::new();
+        varint(2, &mut log_number);
+        varint(8, &mut log_number);
+        let edits = [
+            edit,
+            new_table(1, 5, &level1),
+            new_table(0, 7, &level0),
+            log_number,
+        ];
+        fs::write(dir.join("MANIFEST-000002"), log(&edits)).unwrap();
+        fs::write(dir.join("CURRENT"), "MANIFEST-000002\n").unwrap();
+    }
+
+    #[test]
+    fn reads_block_stores() {
+        let dir = tempfile::tempdir().unwrap();
+        write_database(dir.path());
+        let db = LevelDb::open(dir.path()).unwrap();
+        assert_eq!(db.get(b"deleted").unwrap(), None);
+        assert_eq!(db.get(b"large").unwrap(), Some(vec![1; LOG_BLOCK_SIZE * 2]));
+        assert_eq!(db.get(b"missing").unwrap(), None);
+
+        let block = tests::block();
+        let one = Height::from(1_u32);
+        let store = BlockStore::new(db);
+        assert_eq!(store.range().unwrap(), Some(one..=one));
+        assert_eq!(store.block(one).unwrap(), Some(block.clone()));
+        assert_eq!(
+            store.block_by_hash(&block.header.hash()).unwrap(),
+            Some(block)
+        );
+    }
+
+    #[test]
+    fn checks_the_checksums() {
+        let dir = tempfile::tempdir().unwrap();
+        write_database(dir.path());
+        let path = dir.path().join("000007.ldb");
+        let mut table = fs::read(&path).unwrap();
+        table[10] ^= 1;
+        fs::write(&path, table).unwrap();
+        let db = LevelDb::open(dir.path()).unwrap();
+        let err = db.get(b"H:1").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn decompresses_snappy_copies() {
+        // A literal followed by a copy overlapping the bytes it appends.
+        let compressed = [9, 2 << 2, b'a', b'b', b'c', ((6 - 4) << 2) | 1, 3];
+        assert_eq!(snappy_decompress(&compressed).unwrap(), b"abcabcabc");
+        assert!(snappy_decompress(&[9, 2 << 2, b'a', b'b', b'c', (2 << 2) | 1, 4]).is_err());
+    }
+
+    #[test]
+    fn computes_crc32c() {
+        assert_eq!(crc32c(b"123456789"), 0xe306_9283);
+        assert_eq!(unmask(mask(0x1234_5678)), 0x1234_5678);
+    }
+}
@@ -2,5 +2,6 @@
 
 pub mod params;
 pub mod state;
+pub mod wal;
 
 pub use self::{params::Params, state::State};
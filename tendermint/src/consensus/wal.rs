@@ -0,0 +1,564 @@
+//! Reading of the consensus write-ahead log (WAL) of CometBFT.
+//!
+//! Before acting on a consensus message, CometBFT writes it to a WAL, which
+//! is replayed to recover the state of consensus after a crash. This makes
+//! the WAL the first place to look at when consensus stalls or a validator
+//! misbehaves. It is a sequence of records, each made of:
+//!
+//! 1. the CRC-32C (Castagnoli) checksum of the message, as a 4-byte
+//!    big-endian integer,
+//! 2. the length of the message, as a 4-byte big-endian integer, which may
+//!    not exceed [`MAX_MSG_SIZE_BYTES`],
+//! 3. the message, a Protobuf encoded `TimedWALMessage`.
+//!
+//! The WAL is split over several files as it grows: the head file `wal`
+//! holds the latest records, and the rotated files `wal.000`, `wal.001`,
+//! etc. hold the previous ones, oldest first. The WAL is the concatenation
+//! of these files, in this order.
+
+use core::{fmt, str::FromStr, time::Duration};
+
+use prost::Message;
+use tendermint_proto::v0_38::consensus::{
+    message::Sum as RawMessageSum, wal_message::Sum as RawWalMessageSum,
+    Message as RawConsensusMessage, TimedWalMessage as RawTimedWalMessage,
+    WalMessage as RawWalMessage,
+};
+
+use crate::{
+    block::{Height, Round},
+    error::Error,
+    node,
+    prelude::*,
+    Proposal, Time, Vote,
+};
+
+/// The maximum length of a message in the WAL, in bytes.
+pub const MAX_MSG_SIZE_BYTES: usize = 1024 * 1024;
+
+/// The length of the header of a record, made of the checksum and the
+/// length of the message.
+const HEADER_LEN: usize = 8;
+
+/// A message of the WAL, with the time it was written.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimedWalMessage {
+    pub time: Time,
+    pub msg: WalMessage,
+}
+
+/// A message of the WAL.
+#[derive(Clone, Debug, PartialEq)]
+pub enum WalMessage {
+    /// The consensus state machine entered a new step.
+    RoundState {
+        height: Height,
+        round: Round,
+        step: RoundStep,
+    },
+    /// A consensus message was received from a peer, or produced by the
+    /// node itself, in which case there is no peer.
+    MsgInfo {
+        msg: ConsensusMessage,
+        peer_id: Option<node::Id>,
+    },
+    /// A timeout was scheduled in the given step.
+    Timeout {
+        duration: Duration,
+        height: Height,
+        round: Round,
+        step: RoundStep,
+    },
+    /// The block at the given height was committed.
+    ///
+    /// A new WAL starts with the end of height 0.
+    EndHeight(Height),
+}
+
+/// A consensus message recorded in the WAL.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConsensusMessage {
+    /// A proposal for a block.
+    Proposal(Proposal),
+    /// A prevote or a precommit.
+    Vote(Vote),
+    /// The other messages, e.g. the parts of proposed blocks, in their raw
+    /// form.
+    Other(RawConsensusMessage),
+}
+
+/// The steps of a round of consensus.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RoundStep {
+    NewHeight = 1,
+    NewRound = 2,
+    Propose = 3,
+    Prevote = 4,
+    PrevoteWait = 5,
+    Precommit = 6,
+    PrecommitWait = 7,
+    Commit = 8,
+}
+
+impl RoundStep {
+    /// The name of the step, as written by CometBFT.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::NewHeight => "RoundStepNewHeight",
+            Self::NewRound => "RoundStepNewRound",
+            Self::Propose => "RoundStepPropose",
+            Self::Prevote => "RoundStepPrevote",
+            Self::PrevoteWait => "RoundStepPrevoteWait",
+            Self::Precommit => "RoundStepPrecommit",
+            Self::PrecommitWait => "RoundStepPrecommitWait",
+            Self::Commit => "RoundStepCommit",
+        }
+    }
+}
+
+impl TryFrom<u32> for RoundStep {
+    type Error = Error;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(Self::NewHeight),
+            2 => Ok(Self::NewRound),
+            3 => Ok(Self::Propose),
+            4 => Ok(Self::Prevote),
+            5 => Ok(Self::PrevoteWait),
+            6 => Ok(Self::Precommit),
+            7 => Ok(Self::PrecommitWait),
+            8 => Ok(Self::Commit),
+            _ => Err(Error::invalid_wal_message(format!(
+                "unknown round step: {value}"
+            ))),
+        }
+    }
+}
+
+impl FromStr for RoundStep {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        (1..=8)
+            .map(|value| Self::try_from(value).unwrap())
+            .find(|step| step.as_str() == s)
+            .ok_or_else(|| Error::invalid_wal_message(format!("unknown round step: {s}")))
+    }
+}
+
+impl fmt::Display for RoundStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl TryFrom<RawTimedWalMessage> for TimedWalMessage {
+    type Error = Error;
+
+    fn try_from(value: RawTimedWalMessage) -> Result<Self, Self::Error> {
+        Ok(Self {
+            time: value
+                .time
+                .ok_or_else(Error::missing_timestamp)?
+                .try_into()?,
+            msg: value
+                .msg
+                .ok_or_else(|| Error::invalid_wal_message("missing message".to_string()))?
+                .try_into()?,
+        })
+    }
+}
+
+impl TryFrom<RawWalMessage> for WalMessage {
+    type Error = Error;
+
+    fn try_from(value: RawWalMessage) -> Result<Self, Self::Error> {
+        match value.sum {
+            Some(RawWalMessageSum::EventDataRoundState(state)) => Ok(Self::RoundState {
+                height: state.height.try_into()?,
+                round: state.round.try_into()?,
+                step: state.step.parse()?,
+            }),
+            Some(RawWalMessageSum::MsgInfo(info)) => Ok(Self::MsgInfo {
+                msg: info
+                    .msg
+                    .ok_or_else(|| {
+                        Error::invalid_wal_message("missing consensus message".to_string())
+                    })?
+                    .try_into()?,
+                peer_id: match info.peer_id.as_str() {
+                    "" => None,
+                    peer_id => Some(peer_id.parse()?),
+                },
+            }),
+            Some(RawWalMessageSum::TimeoutInfo(timeout)) => Ok(Self::Timeout {
+                duration: crate::evidence::Duration::try_from(
+                    timeout.duration.unwrap_or_default(),
+                )?
+                .into(),
+                height: timeout.height.try_into()?,
+                round: timeout.round.try_into()?,
+                step: timeout.step.try_into()?,
+            }),
+            Some(RawWalMessageSum::EndHeight(end)) => Ok(Self::EndHeight(end.height.try_into()?)),
+            None => Err(Error::invalid_wal_message("empty message".to_string())),
+        }
+    }
+}
+
+impl TryFrom<RawConsensusMessage> for ConsensusMessage {
+    type Error = Error;
+
+    fn try_from(value: RawConsensusMessage) -> Result<Self, Self::Error> {
+        match value.sum {
+            Some(RawMessageSum::Proposal(proposal)) => Ok(Self::Proposal(
+                proposal
+                    .proposal
+                    .ok_or_else(Error::no_proposal_found)?
+                    .try_into()?,
+            )),
+            Some(RawMessageSum::Vote(vote)) => Ok(Self::Vote(
+                vote.vote.ok_or_else(Error::no_vote_found)?.try_into()?,
+            )),
+            Some(sum) => Ok(Self::Other(RawConsensusMessage { sum: Some(sum) })),
+            None => Err(Error::invalid_wal_message(
+                "empty consensus message".to_string(),
+            )),
+        }
+    }
+}
+
+/// Decode the record at the start of the given bytes, returning its
+/// message and the length of the record.
+pub fn decode_record(bytes: &[u8]) -> Result<(TimedWalMessage, usize), Error> {
+    let header = bytes.get(..HEADER_LEN).ok_or_else(Error::wal_truncated)?;
+    let (checksum, length) = decode_header(header.try_into().unwrap())?;
+    let data = bytes
+        .get(HEADER_LEN..HEADER_LEN + length)
+        .ok_or_else(Error::wal_truncated)?;
+    Ok((decode_message(checksum, data)?, HEADER_LEN + length))
+}
+
+/// Encode a message as a record of the WAL.
+pub fn encode_record(msg: &RawTimedWalMessage) -> Vec<u8> {
+    let data = msg.encode_to_vec();
+    let mut record = Vec::with_capacity(HEADER_LEN + data.len());
+    record.extend_from_slice(&crc32c(&data).to_be_bytes());
+    record.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    record.extend_from_slice(&data);
+    record
+}
+
+fn decode_header(header: &[u8; HEADER_LEN]) -> Result<(u32, usize), Error> {
+    let checksum = u32::from_be_bytes(header[..4].try_into().unwrap());
+    let length = u32::from_be_bytes(header[4..].try_into().unwrap()) as usize;
+    if length > MAX_MSG_SIZE_BYTES {
+        return Err(Error::wal_message_too_large(length, MAX_MSG_SIZE_BYTES));
+    }
+    Ok((checksum, length))
+}
+
+fn decode_message(checksum: u32, data: &[u8]) -> Result<TimedWalMessage, Error> {
+    let actual = crc32c(data);
+    if actual != checksum {
+        return Err(Error::wal_checksum_mismatch(checksum, actual));
+    }
+    RawTimedWalMessage::decode(data)
+        .map_err(|e| Error::invalid_wal_message(e.to_string()))?
+        .try_into()
+}
+
+/// Reads the messages of a WAL.
+///
+/// Reading stops at the first error, e.g. a record which was only partly
+/// written when the node crashed.
+///
+/// ## Examples
+///
+/// ```rust,ignore
+/// use std::fs::File;
+/// use tendermint::consensus::wal::{WalMessage, WalReader};
+///
+/// let file = File::open("data/cs.wal/wal")?;
+/// for msg in WalReader::new(std::io::BufReader::new(file)) {
+///     let msg = msg?;
+///     if let WalMessage::EndHeight(height) = msg.msg {
+///         println!("{}: committed {height}", msg.time);
+///     }
+/// }
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct WalReader<R> {
+    reader: R,
+    buf: Vec<u8>,
+    failed: bool,
+}
+
+#[cfg(feature = "std")]
+impl<R: std::io::Read> WalReader<R> {
+    /// Read the messages of the WAL from the given reader.
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buf: Vec::new(),
+            failed: false,
+        }
+    }
+
+    /// Get back the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    fn read_message(&mut self) -> Result<Option<TimedWalMessage>, Error> {
+        let mut header = [0; HEADER_LEN];
+        if !self.fill(&mut header)? {
+            return Ok(None);
+        }
+        let (checksum, length) = decode_header(&header)?;
+        let mut data = core::mem::take(&mut self.buf);
+        data.resize(length, 0);
+        let msg = match self.fill(&mut data) {
+            Ok(true) => decode_message(checksum, &data).map(Some),
+            // The header of the record was read, so its message must follow.
+            Ok(false) => Err(Error::wal_truncated()),
+            Err(e) => Err(e),
+        };
+        self.buf = data;
+        msg
+    }
+
+    /// Fill the given buffer, returning `false` if the end of the WAL was
+    /// reached before any byte was read.
+    fn fill(&mut self, buf: &mut [u8]) -> Result<bool, Error> {
+        use std::io::ErrorKind;
+
+        let mut read = 0;
+        while read < buf.len() {
+            match self.reader.read(&mut buf[read..]) {
+                Ok(0) if read == 0 => return Ok(false),
+                Ok(0) => return Err(Error::wal_truncated()),
+                Ok(n) => read += n,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {},
+                Err(e) => return Err(Error::wal_io(e.to_string())),
+            }
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: std::io::Read> Iterator for WalReader<R> {
+    type Item = Result<TimedWalMessage, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        match self.read_message() {
+            Ok(msg) => msg.map(Ok),
+            Err(e) => {
+                self.failed = true;
+                Some(Err(e))
+            },
+        }
+    }
+}
+
+/// The lookup table of the CRC-32C checksum, for the reversed Castagnoli
+/// polynomial.
+const CRC32C_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0x82f6_3b78
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+fn crc32c(data: &[u8]) -> u32 {
+    !data.iter().fold(!0, |crc, &byte| {
+        CRC32C_TABLE[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use tendermint_proto::{
+        google::protobuf::Timestamp,
+        v0_38::{
+            consensus::{BlockPart, EndHeight, MsgInfo, TimeoutInfo, Vote as RawVoteMessage},
+            types::{EventDataRoundState, Vote as RawVote},
+        },
+    };
+
+    use super::*;
+
+    fn timed(sum: RawWalMessageSum) -> RawTimedWalMessage {
+        RawTimedWalMessage {
+            time: Some(Timestamp {
+                seconds: 1_700_000_000,
+                nanos: 0,
+            }),
+            msg: Some(RawWalMessage { sum: Some(sum) }),
+        }
+    }
+
+    fn records() -> Vec<RawTimedWalMessage> {
+        vec![
+            timed(RawWalMessageSum::EndHeight(EndHeight { height: 0 })),
+            timed(RawWalMessageSum::EventDataRoundState(EventDataRoundState {
+                height: 1,
+                round: 0,
+                step: "RoundStepPropose".to_string(),
+            })),
+            timed(RawWalMessageSum::TimeoutInfo(TimeoutInfo {
+                duration: Some(tendermint_proto::google::protobuf::Duration {
+                    seconds: 3,
+                    nanos: 0,
+                }),
+                height: 1,
+                round: 0,
+                step: 3,
+            })),
+            timed(RawWalMessageSum::MsgInfo(MsgInfo {
+                msg: Some(RawConsensusMessage {
+                    sum: Some(RawMessageSum::Vote(RawVoteMessage {
+                        vote: Some(RawVote {
+                            r#type: 1,
+                            height: 1,
+                            validator_address: vec![0xa; 20],
+                            timestamp: Some(Timestamp::default()),
+                            ..Default::default()
+                        }),
+                    })),
+                }),
+                peer_id: "a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0".to_string(),
+            })),
+            timed(RawWalMessageSum::MsgInfo(MsgInfo {
+                msg: Some(RawConsensusMessage {
+                    sum: Some(RawMessageSum::BlockPart(BlockPart {
+                        height: 1,
+                        round: 0,
+                        part: None,
+                    })),
+                }),
+                peer_id: String::new(),
+            })),
+        ]
+    }
+
+    #[test]
+    fn crc32c_check_value() {
+        assert_eq!(crc32c(b"123456789"), 0xe306_9283);
+        assert_eq!(crc32c(b""), 0);
+    }
+
+    #[test]
+    fn decodes_records() {
+        let bytes: Vec<u8> = records().iter().flat_map(encode_record).collect();
+        let mut msgs = vec![];
+        let mut rest = &bytes[..];
+        while !rest.is_empty() {
+            let (msg, len) = decode_record(rest).unwrap();
+            msgs.push(msg.msg);
+            rest = &rest[len..];
+        }
+
+        assert_eq!(msgs.len(), 5);
+        assert_eq!(msgs[0], WalMessage::EndHeight(Height::from(0_u32)));
+        assert_eq!(
+            msgs[1],
+            WalMessage::RoundState {
+                height: Height::from(1_u32),
+                round: Round::from(0_u8),
+                step: RoundStep::Propose,
+            }
+        );
+        assert_eq!(
+            msgs[2],
+            WalMessage::Timeout {
+                duration: Duration::from_secs(3),
+                height: Height::from(1_u32),
+                round: Round::from(0_u8),
+                step: RoundStep::Propose,
+            }
+        );
+        match &msgs[3] {
+            WalMessage::MsgInfo {
+                msg: ConsensusMessage::Vote(vote),
+                peer_id: Some(peer_id),
+            } => {
+                assert_eq!(vote.height, Height::from(1_u32));
+                assert_eq!(peer_id.to_string(), "a0".repeat(20));
+            },
+            other => panic!("unexpected message: {other:?}"),
+        }
+        assert!(matches!(
+            msgs[4],
+            WalMessage::MsgInfo {
+                msg: ConsensusMessage::Other(_),
+                peer_id: None,
+            }
+        ));
+    }
+
+    #[test]
+    fn rejects_corrupted_records() {
+        let mut record = encode_record(&records()[1]);
+        let last = record.len() - 1;
+        record[last] ^= 1;
+        assert!(matches!(
+            decode_record(&record).unwrap_err().detail(),
+            crate::error::ErrorDetail::WalChecksumMismatch(_)
+        ));
+
+        let record = encode_record(&records()[1]);
+        assert!(matches!(
+            decode_record(&record[..record.len() - 1])
+                .unwrap_err()
+                .detail(),
+            crate::error::ErrorDetail::WalTruncated(_)
+        ));
+
+        let mut record = encode_record(&records()[1]);
+        record[4..8].copy_from_slice(&(MAX_MSG_SIZE_BYTES as u32 + 1).to_be_bytes());
+        assert!(matches!(
+            decode_record(&record).unwrap_err().detail(),
+            crate::error::ErrorDetail::WalMessageTooLarge(_)
+        ));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn reads_until_partial_record() {
+        let mut bytes: Vec<u8> = records().iter().flat_map(encode_record).collect();
+        let mut reader = WalReader::new(&bytes[..]);
+        assert_eq!(reader.by_ref().map(Result::unwrap).count(), 5);
+        assert!(reader.next().is_none());
+
+        // A node crashing while writing a record leaves it partly written.
+        let partial = encode_record(&records()[0]);
+        bytes.extend_from_slice(&partial[..partial.len() / 2]);
+        let msgs: Vec<_> = WalReader::new(&bytes[..]).collect();
+        assert_eq!(msgs.len(), 6);
+        assert!(msgs[..5].iter().all(Result::is_ok));
+        assert!(matches!(
+            msgs[5].as_ref().unwrap_err().detail(),
+            crate::error::ErrorDetail::WalTruncated(_)
+        ));
+    }
+}
@@ -18,6 +18,11 @@ impl SigningKey {
         let pubkey_bytes = pubkey.to_bytes();
         VerificationKey::new(pubkey_bytes)
     }
+
+    #[cfg(feature = "rust-crypto")]
+    pub fn sign(&self, msg: &[u8]) -> crate::Signature {
+        ed25519_consensus::SigningKey::from(self.0).sign(msg).into()
+    }
 }
 
 impl TryFrom<&'_ [u8]> for SigningKey {
@@ -74,6 +74,13 @@ define_error! {
         InvalidAccountIdLength
             |_| { format_args!("invalid account ID length") },
 
+        Bech32PrefixMismatch
+            { expected: String, found: String }
+            |e| { format_args!("invalid bech32 prefix: expected '{}', found '{}'", e.expected, e.found) },
+
+        Bech32MixedCase
+            |_| { format_args!("bech32 string mixes upper and lower case characters") },
+
         InvalidSignatureIdLength
             |_| { format_args!("invalid signature ID length") },
 
@@ -157,6 +164,10 @@ define_error! {
         InvalidEvidence
             |_| { format_args!("invalid evidence") },
 
+        InvalidDuplicateVoteEvidence
+            { detail: String }
+            |e| { format_args!("invalid duplicate vote evidence: {}", e.detail) },
+
         InvalidValidatorParams
             |_| { format_args!("invalid validator parameters") },
 
@@ -235,6 +246,37 @@ define_error! {
 
         TotalVotingPowerOverflow
             |_| { "total voting power in validator set exceeds the allowed maximum" },
+
+        InvalidWalMessage
+            { detail: String }
+            |e| { format_args!("invalid WAL message: {}", e.detail) },
+
+        WalChecksumMismatch
+            { expected: u32, actual: u32 }
+            |e| { format_args!("WAL message checksum mismatch: expected {:#010x}, got {:#010x}", e.expected, e.actual) },
+
+        WalMessageTooLarge
+            { length: usize, max: usize }
+            |e| { format_args!("WAL message of {} bytes exceeds the maximum of {} bytes", e.length, e.max) },
+
+        WalTruncated
+            |_| { "WAL ends in the middle of a message" },
+
+        WalIo
+            { detail: String }
+            |e| { format_args!("error reading the WAL: {}", e.detail) },
+
+        BlockStore
+            { detail: String }
+            |e| { format_args!("error reading the block store: {}", e.detail) },
+
+        MissingBlockStoreEntry
+            { key: String }
+            |e| { format_args!("missing block store entry: {}", e.key) },
+
+        InvalidBlockStoreEntry
+            { key: String, detail: String }
+            |e| { format_args!("invalid block store entry {}: {}", e.key, e.detail) },
     }
 }
 
@@ -7,14 +7,18 @@ use core::{
 
 use serde::{Deserialize, Serialize};
 use tendermint_proto::google::protobuf::Duration as RawDuration;
+use tendermint_proto::v0_38::types::PartSetHeader as RawPartSetHeader;
 use tendermint_proto::Protobuf;
 
 use crate::{
-    block::{signed_header::SignedHeader, Height},
+    abci::types::{Misbehavior, MisbehaviorKind, Validator},
+    block::{self, signed_header::SignedHeader, Height},
+    chain,
+    crypto::signature::Verifier,
     error::Error,
     prelude::*,
     serializers, validator,
-    vote::Power,
+    vote::{Power, SignedVote},
     Time, Vote,
 };
 
@@ -28,6 +32,57 @@ pub enum Evidence {
     LightClientAttack(Box<LightClientAttackEvidence>),
 }
 
+impl Evidence {
+    /// The height at which the offense occurred, i.e. the height of the
+    /// conflicting votes, or the common height of a light client attack.
+    pub fn height(&self) -> Height {
+        match self {
+            Self::DuplicateVote(ev) => ev.vote_a.height,
+            Self::LightClientAttack(ev) => ev.common_height,
+        }
+    }
+
+    /// The time at which the offense occurred, i.e. the time of the block at
+    /// [`height`](Self::height).
+    pub fn time(&self) -> Time {
+        match self {
+            Self::DuplicateVote(ev) => ev.timestamp,
+            Self::LightClientAttack(ev) => ev.timestamp,
+        }
+    }
+
+    /// The misbehaviors reported to the application for this evidence, when
+    /// it is committed in a block: one for every offending validator.
+    pub fn misbehavior(&self) -> Vec<Misbehavior> {
+        match self {
+            Self::DuplicateVote(ev) => vec![Misbehavior {
+                kind: MisbehaviorKind::DuplicateVote,
+                validator: Validator {
+                    address: ev.vote_a.validator_address.into(),
+                    power: ev.validator_power,
+                },
+                height: ev.vote_a.height,
+                time: ev.timestamp,
+                total_voting_power: ev.total_voting_power,
+            }],
+            Self::LightClientAttack(ev) => ev
+                .byzantine_validators
+                .iter()
+                .map(|validator| Misbehavior {
+                    kind: MisbehaviorKind::LightClientAttack,
+                    validator: Validator {
+                        address: validator.address.into(),
+                        power: validator.power,
+                    },
+                    height: ev.common_height,
+                    time: ev.timestamp,
+                    total_voting_power: ev.total_voting_power,
+                })
+                .collect(),
+        }
+    }
+}
+
 impl From<LightClientAttackEvidence> for Evidence {
     fn from(ev: LightClientAttackEvidence) -> Self {
         Self::LightClientAttack(Box::new(ev))
@@ -71,6 +126,114 @@ impl DuplicateVoteEvidence {
     pub fn votes(&self) -> (&Vote, &Vote) {
         (&self.vote_a, &self.vote_b)
     }
+
+    /// Get the votes along with the bytes they were signed over on the given
+    /// chain, ready to be verified against the public key of the validator.
+    ///
+    /// Returns `None` if either vote is not signed.
+    pub fn signed_votes(&self, chain_id: &chain::Id) -> Option<(SignedVote, SignedVote)> {
+        Some((
+            SignedVote::from_vote(self.vote_a.clone(), chain_id.clone())?,
+            SignedVote::from_vote(self.vote_b.clone(), chain_id.clone())?,
+        ))
+    }
+
+    /// Check the consistency of the evidence, without checking the
+    /// signatures of the votes.
+    ///
+    /// As in CometBFT, both votes must be for the same height, round and
+    /// step, by the same validator, for different blocks, and ordered by the
+    /// key of their block ID.
+    pub fn validate_basic(&self) -> Result<(), Error> {
+        let (a, b) = (&self.vote_a, &self.vote_b);
+        if a.height != b.height || a.round != b.round || a.vote_type != b.vote_type {
+            return Err(Error::invalid_duplicate_vote_evidence(format!(
+                "h/r/s does not match: {}/{}/{} vs {}/{}/{}",
+                a.height, a.round, a.vote_type, b.height, b.round, b.vote_type
+            )));
+        }
+        if a.validator_address != b.validator_address {
+            return Err(Error::invalid_duplicate_vote_evidence(format!(
+                "validator addresses do not match: {} vs {}",
+                a.validator_address, b.validator_address
+            )));
+        }
+        if a.validator_index != b.validator_index {
+            return Err(Error::invalid_duplicate_vote_evidence(format!(
+                "validator indices do not match: {} vs {}",
+                a.validator_index, b.validator_index
+            )));
+        }
+        if a.block_id == b.block_id {
+            return Err(Error::invalid_duplicate_vote_evidence(
+                "block IDs are the same, not a real duplicate vote".to_string(),
+            ));
+        }
+        if block_id_key(a.block_id.as_ref()) >= block_id_key(b.block_id.as_ref()) {
+            return Err(Error::invalid_duplicate_vote_evidence(
+                "duplicate votes in invalid order".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Verify the evidence against the validator set at its height on the
+    /// given chain, as CometBFT does before accepting the evidence.
+    ///
+    /// On top of the checks of [`validate_basic`](Self::validate_basic), the
+    /// offending validator must belong to the set, with the voting powers
+    /// recorded in the evidence, and both votes must be signed by it.
+    pub fn verify<V: Verifier>(
+        &self,
+        chain_id: &chain::Id,
+        validators: &validator::Set,
+    ) -> Result<(), Error> {
+        self.validate_basic()?;
+
+        let address = self.vote_a.validator_address;
+        let validator = validators.validator(address).ok_or_else(|| {
+            Error::invalid_duplicate_vote_evidence(format!(
+                "address {} was not a validator at height {}",
+                address, self.vote_a.height
+            ))
+        })?;
+        if validator.power != self.validator_power {
+            return Err(Error::invalid_duplicate_vote_evidence(format!(
+                "validator power from evidence and our validator set does not match ({} != {})",
+                self.validator_power, validator.power
+            )));
+        }
+        if validators.total_voting_power() != self.total_voting_power {
+            return Err(Error::invalid_duplicate_vote_evidence(format!(
+                "total voting power from the evidence and our validator set does not match ({} != {})",
+                self.total_voting_power,
+                validators.total_voting_power()
+            )));
+        }
+
+        let (vote_a, vote_b) = self.signed_votes(chain_id).ok_or_else(|| {
+            Error::invalid_duplicate_vote_evidence("missing vote signature".to_string())
+        })?;
+        for vote in [vote_a, vote_b] {
+            validator.verify_signature::<V>(&vote.sign_bytes(), vote.signature())?;
+        }
+        Ok(())
+    }
+}
+
+/// The key by which the votes of duplicate vote evidence are ordered in
+/// CometBFT: the block hash followed by the encoded part set header.
+fn block_id_key(block_id: Option<&block::Id>) -> Vec<u8> {
+    match block_id {
+        Some(block_id) => {
+            let mut key = block_id.hash.as_bytes().to_vec();
+            key.extend(Protobuf::<RawPartSetHeader>::encode_vec(
+                block_id.part_set_header,
+            ));
+            key
+        },
+        None => Vec::new(),
+    }
 }
 
 /// Conflicting block detected in light client attack
@@ -217,7 +380,7 @@ tendermint_pb_modules! {
                 vote_a: Some(value.vote_a.into()),
                 vote_b: Some(value.vote_b.into()),
                 total_voting_power: value.total_voting_power.into(),
-                validator_power: value.total_voting_power.into(),
+                validator_power: value.validator_power.into(),
                 timestamp: Some(value.timestamp.into()),
             }
         }
@@ -383,3 +546,134 @@ impl From<Duration> for RawDuration {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use tendermint_proto::v0_38::types::DuplicateVoteEvidence as RawDuplicateVoteEvidence;
+
+    use super::*;
+    use crate::{
+        account,
+        vote::{Type, ValidatorIndex},
+    };
+
+    fn vote(round: u32) -> Vote {
+        Vote {
+            vote_type: Type::Prevote,
+            height: Height::from(10_u32),
+            round: round.try_into().unwrap(),
+            block_id: None,
+            timestamp: Some(Time::unix_epoch()),
+            validator_address: account::Id::new([1; account::LENGTH]),
+            validator_index: ValidatorIndex::try_from(0_u32).unwrap(),
+            signature: None,
+            extension: Default::default(),
+            extension_signature: None,
+        }
+    }
+
+    #[test]
+    fn duplicate_vote_evidence_encodes_validator_power() {
+        let evidence = DuplicateVoteEvidence {
+            vote_a: vote(0),
+            vote_b: vote(1),
+            total_voting_power: Power::from(100_u32),
+            validator_power: Power::from(10_u32),
+            timestamp: Time::unix_epoch(),
+        };
+        let raw = RawDuplicateVoteEvidence::from(evidence.clone());
+        assert_eq!(raw.total_voting_power, 100);
+        assert_eq!(raw.validator_power, 10);
+        assert_eq!(DuplicateVoteEvidence::try_from(raw).unwrap(), evidence);
+    }
+
+    #[cfg(feature = "rust-crypto")]
+    mod verify {
+        use super::*;
+        use crate::{
+            block::{parts::Header as PartSetHeader, Round},
+            crypto::default::signature::Verifier as DefaultVerifier,
+            hash::{Algorithm, Hash},
+            private_key::PrivateKey,
+        };
+
+        fn signed_vote(key: &PrivateKey, chain_id: &chain::Id, block_hash: Option<u8>) -> Vote {
+            let validator = validator::Info::new(key.public_key(), Power::from(10_u32));
+            let mut vote = Vote {
+                vote_type: Type::Prevote,
+                height: Height::from(7_u32),
+                round: Round::from(1_u16),
+                block_id: block_hash.map(|byte| {
+                    let hash = Hash::from_bytes(Algorithm::Sha256, &[byte; 32]).unwrap();
+                    block::Id {
+                        hash,
+                        part_set_header: PartSetHeader::new(1, hash).unwrap(),
+                    }
+                }),
+                timestamp: Some(Time::unix_epoch()),
+                validator_address: validator.address,
+                validator_index: ValidatorIndex::try_from(0_u32).unwrap(),
+                signature: None,
+                extension: vec![],
+                extension_signature: None,
+            };
+            vote.signature = Some(key.sign(&vote.clone().into_signable_vec(chain_id.clone())));
+            vote
+        }
+
+        #[test]
+        fn verifies_duplicate_votes() {
+            let key = PrivateKey::from_ed25519_seed(&[1; 32]).unwrap();
+            let chain_id: chain::Id = "test-chain".parse().unwrap();
+            let validators = validator::Set::without_proposer(vec![validator::Info::new(
+                key.public_key(),
+                Power::from(10_u32),
+            )]);
+
+            let mut evidence = DuplicateVoteEvidence {
+                vote_a: signed_vote(&key, &chain_id, None),
+                vote_b: signed_vote(&key, &chain_id, Some(1)),
+                total_voting_power: Power::from(10_u32),
+                validator_power: Power::from(10_u32),
+                timestamp: Time::unix_epoch(),
+            };
+            evidence
+                .verify::<DefaultVerifier>(&chain_id, &validators)
+                .unwrap();
+
+            let misbehavior = Evidence::from(evidence.clone()).misbehavior();
+            assert_eq!(misbehavior.len(), 1);
+            assert_eq!(misbehavior[0].kind, MisbehaviorKind::DuplicateVote);
+            assert_eq!(misbehavior[0].height, Height::from(7_u32));
+            assert_eq!(
+                misbehavior[0].validator.address,
+                <[u8; 20]>::from(evidence.vote_a.validator_address)
+            );
+
+            // Signed on another chain
+            let other_chain: chain::Id = "other-chain".parse().unwrap();
+            assert!(evidence
+                .verify::<DefaultVerifier>(&other_chain, &validators)
+                .is_err());
+
+            // Votes in the wrong order
+            let swapped = DuplicateVoteEvidence {
+                vote_a: evidence.vote_b.clone(),
+                vote_b: evidence.vote_a.clone(),
+                ..evidence.clone()
+            };
+            assert!(swapped.validate_basic().is_err());
+
+            // Mismatching voting power
+            evidence.validator_power = Power::from(5_u32);
+            assert!(evidence.validate_basic().is_ok());
+            assert!(evidence
+                .verify::<DefaultVerifier>(&chain_id, &validators)
+                .is_err());
+
+            // Votes for the same block
+            evidence.vote_b = evidence.vote_a.clone();
+            assert!(evidence.validate_basic().is_err());
+        }
+    }
+}
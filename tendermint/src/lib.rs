@@ -41,11 +41,16 @@ pub mod hash;
 pub mod merkle;
 mod moniker;
 pub mod node;
+#[cfg(feature = "proptest")]
+#[cfg_attr(docsrs, doc(cfg(feature = "proptest")))]
+pub mod pbt;
 mod prelude;
 pub mod private_key;
 pub mod privval;
 pub mod proposal;
 pub mod public_key;
+#[cfg(feature = "serialization-audit")]
+pub mod serialization_audit;
 pub mod serializers;
 pub mod signature;
 pub mod time;
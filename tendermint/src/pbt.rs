@@ -0,0 +1,515 @@
+//! [proptest](https://github.com/AltSysrq/proptest) strategies for the core domain types.
+//!
+//! The generated values respect the structural invariants enforced by the domain types
+//! and by the verification logic: validator sets are sorted and within the voting power
+//! limits, headers reference the hashes of their validator sets and a proposer from them,
+//! and commits, votes and evidence carry valid signatures for the generated chain id.
+//! This makes the strategies suitable for fuzzing serializers as well as verifiers.
+//!
+//! Enabled with the "proptest" feature.
+
+use core::convert::TryFrom;
+
+use ed25519_consensus::SigningKey;
+use proptest::{collection, prelude::*};
+use tendermint_pbt_gen as pbt;
+
+use crate::{
+    block::{self, parts, signed_header::SignedHeader, CommitSig, Height, Round},
+    chain,
+    evidence::{ConflictingBlock, DuplicateVoteEvidence, Evidence, LightClientAttackEvidence},
+    prelude::*,
+    validator,
+    vote::{self, ValidatorIndex},
+    AppHash, Hash, PublicKey, Signature, Time, Vote,
+};
+
+/// The maximum voting power of a single generated validator.
+///
+/// Kept low enough that sets of any size supported by the strategies of this module
+/// remain below [`validator::Set::MAX_TOTAL_VOTING_POWER`].
+pub const MAX_VALIDATOR_POWER: u64 = 1 << 32;
+
+/// The maximum number of validators in generated validator sets.
+pub const MAX_VALIDATORS: usize = 16;
+
+/// A validator together with the key it signs with.
+#[derive(Clone, Debug)]
+pub struct Signer {
+    /// The signing key of the validator.
+    pub key: SigningKey,
+    /// The validator info derived from the key.
+    pub info: validator::Info,
+}
+
+impl Signer {
+    /// Signs the given bytes with the validator key.
+    pub fn sign(&self, msg: &[u8]) -> Signature {
+        self.key.sign(msg).into()
+    }
+
+    /// Signs the vote for the given chain, filling in its signature.
+    pub fn sign_vote(&self, mut vote: Vote, chain_id: &chain::Id) -> Vote {
+        vote.validator_address = self.info.address;
+        vote.signature = None;
+        let sign_bytes = vote.clone().into_signable_vec(chain_id.clone());
+        vote.signature = Some(self.sign(&sign_bytes));
+        vote
+    }
+}
+
+/// A validator set, together with the signers of its validators,
+/// in the same order as [`validator::Set::validators`].
+#[derive(Clone, Debug)]
+pub struct SignerSet {
+    /// The validator set.
+    pub set: validator::Set,
+    /// The signers of the validators in the set.
+    pub signers: Vec<Signer>,
+}
+
+prop_compose! {
+    /// An arbitrary block [`Height`], strictly positive.
+    pub fn arb_height()(height in 1..=i64::MAX as u64) -> Height {
+        Height::try_from(height).unwrap()
+    }
+}
+
+prop_compose! {
+    /// An arbitrary block [`Round`].
+    pub fn arb_round()(round in 0..=i32::MAX as u32) -> Round {
+        Round::try_from(round).unwrap()
+    }
+}
+
+prop_compose! {
+    /// An arbitrary [`Time`] that has a valid protobuf representation.
+    pub fn arb_time()(datetime in pbt::time::arb_protobuf_safe_datetime()) -> Time {
+        Time::try_from(datetime).unwrap()
+    }
+}
+
+prop_compose! {
+    /// An arbitrary [`chain::Id`], within the maximum chain id length.
+    pub fn arb_chain_id()(id in "[a-z][a-z0-9-]{0,49}") -> chain::Id {
+        chain::Id::try_from(id).unwrap()
+    }
+}
+
+prop_compose! {
+    /// An arbitrary, non-empty SHA256 [`Hash`].
+    pub fn arb_hash()(bytes in any::<[u8; 32]>()) -> Hash {
+        Hash::Sha256(bytes)
+    }
+}
+
+prop_compose! {
+    /// An arbitrary [`AppHash`] of up to 32 bytes.
+    pub fn arb_app_hash()(bytes in collection::vec(any::<u8>(), 0..=32)) -> AppHash {
+        AppHash::try_from(bytes).unwrap()
+    }
+}
+
+prop_compose! {
+    /// An arbitrary [`block::Id`] with a non-empty part set header.
+    pub fn arb_block_id()(
+        hash in arb_hash(),
+        total in 1..=u32::MAX,
+        parts_hash in arb_hash(),
+    ) -> block::Id {
+        block::Id {
+            hash,
+            part_set_header: parts::Header::new(total, parts_hash).unwrap(),
+        }
+    }
+}
+
+prop_compose! {
+    /// An arbitrary validator with its signing key.
+    pub fn arb_signer()(
+        seed in any::<[u8; 32]>(),
+        power in 1..=MAX_VALIDATOR_POWER,
+    ) -> Signer {
+        make_signer(seed, power)
+    }
+}
+
+prop_compose! {
+    /// An arbitrary [`validator::Info`].
+    pub fn arb_validator()(signer in arb_signer()) -> validator::Info {
+        signer.info
+    }
+}
+
+prop_compose! {
+    /// An arbitrary, non-empty validator set with distinct validators,
+    /// together with their signing keys.
+    pub fn arb_signer_set()(
+        validators in collection::btree_map(
+            any::<[u8; 32]>(),
+            1..=MAX_VALIDATOR_POWER,
+            1..=MAX_VALIDATORS,
+        ),
+    ) -> SignerSet {
+        let signers: Vec<Signer> = validators
+            .into_iter()
+            .map(|(seed, power)| make_signer(seed, power))
+            .collect();
+        let set = validator::Set::without_proposer(
+            signers.iter().map(|s| s.info.clone()).collect(),
+        );
+        // Order the signers the same way as the validator set does.
+        let signers = set
+            .validators()
+            .iter()
+            .map(|info| {
+                signers
+                    .iter()
+                    .find(|s| s.info.address == info.address)
+                    .cloned()
+                    .unwrap()
+            })
+            .collect();
+        SignerSet { set, signers }
+    }
+}
+
+prop_compose! {
+    /// An arbitrary, non-empty [`validator::Set`] with distinct validators.
+    pub fn arb_validator_set()(signers in arb_signer_set()) -> validator::Set {
+        signers.set
+    }
+}
+
+prop_compose! {
+    /// An arbitrary [`block::Header`] at the given height, for the given validators.
+    ///
+    /// The header references the hashes of the validator sets, and its proposer
+    /// is one of the validators. The last block id is only set above height 1.
+    pub fn arb_header_for(
+        chain_id: chain::Id,
+        height: Height,
+        validators: validator::Set,
+        next_validators: validator::Set,
+    )(
+        app_version in any::<u64>(),
+        time in arb_time(),
+        last_block_id in arb_block_id(),
+        last_commit_hash in arb_hash(),
+        data_hash in arb_hash(),
+        consensus_hash in arb_hash(),
+        app_hash in arb_app_hash(),
+        last_results_hash in arb_hash(),
+        evidence_hash in arb_hash(),
+        proposer in any::<prop::sample::Index>(),
+    ) -> block::Header {
+        let first = height.value() == 1;
+        block::Header {
+            version: block::header::Version {
+                block: 11,
+                app: app_version,
+            },
+            chain_id: chain_id.clone(),
+            height,
+            time,
+            last_block_id: (!first).then_some(last_block_id),
+            last_commit_hash: (!first).then_some(last_commit_hash),
+            data_hash: Some(data_hash),
+            validators_hash: validators.hash(),
+            next_validators_hash: next_validators.hash(),
+            consensus_hash,
+            app_hash,
+            last_results_hash: (!first).then_some(last_results_hash),
+            evidence_hash: Some(evidence_hash),
+            proposer_address: proposer.get(validators.validators()).address,
+        }
+    }
+}
+
+prop_compose! {
+    /// An arbitrary [`block::Header`].
+    pub fn arb_header()(
+        chain_id in arb_chain_id(),
+        height in arb_height(),
+        validators in arb_validator_set(),
+        next_validators in arb_validator_set(),
+    )(
+        header in arb_header_for(chain_id, height, validators, next_validators),
+    ) -> block::Header {
+        header
+    }
+}
+
+prop_compose! {
+    /// An arbitrary [`block::Commit`] for the given header, signed by the given signers.
+    ///
+    /// Every validator either signs for the block, signs for nil, or is absent,
+    /// such that the validators signing for the block have more than 2/3 of the
+    /// total voting power.
+    pub fn arb_commit_for(header: block::Header, signers: Vec<Signer>)(
+        round in arb_round(),
+        block_parts in 1..=u32::MAX,
+        flags in collection::vec(0..3u8, signers.len()),
+        timestamps in collection::vec(arb_time(), signers.len()),
+    ) -> block::Commit {
+        let block_id = block::Id {
+            hash: header.hash(),
+            part_set_header: parts::Header::new(block_parts, header.hash()).unwrap(),
+        };
+        let total: u64 = signers.iter().map(|s| s.info.power()).sum();
+        let mut committed = 0;
+        let signatures = signers
+            .iter()
+            .zip(flags)
+            .zip(timestamps)
+            .enumerate()
+            .map(|(index, ((signer, flag), timestamp))| {
+                // Sign for the block as long as the quorum is not reached yet.
+                let flag = if committed * 3 <= total * 2 { 0 } else { flag };
+                let vote = Vote {
+                    vote_type: vote::Type::Precommit,
+                    height: header.height,
+                    round,
+                    block_id: (flag == 0).then_some(block_id),
+                    timestamp: Some(timestamp),
+                    validator_address: signer.info.address,
+                    validator_index: ValidatorIndex::try_from(index).unwrap(),
+                    signature: None,
+                    extension: vec![],
+                    extension_signature: None,
+                };
+                let vote = signer.sign_vote(vote, &header.chain_id);
+                match flag {
+                    0 => {
+                        committed += signer.info.power();
+                        CommitSig::BlockIdFlagCommit {
+                            validator_address: signer.info.address,
+                            timestamp,
+                            signature: vote.signature,
+                        }
+                    },
+                    1 => CommitSig::BlockIdFlagNil {
+                        validator_address: signer.info.address,
+                        timestamp,
+                        signature: vote.signature,
+                    },
+                    _ => CommitSig::BlockIdFlagAbsent,
+                }
+            })
+            .collect();
+        block::Commit {
+            height: header.height,
+            round,
+            block_id,
+            signatures,
+        }
+    }
+}
+
+/// An arbitrary [`SignedHeader`], together with the validator set that signed it.
+pub fn arb_signed_header() -> impl Strategy<Value = (SignedHeader, validator::Set)> {
+    (
+        arb_chain_id(),
+        arb_height(),
+        arb_signer_set(),
+        arb_validator_set(),
+    )
+        .prop_flat_map(|(chain_id, height, validators, next_validators)| {
+            let header = arb_header_for(chain_id, height, validators.set.clone(), next_validators);
+            (header, Just(validators))
+        })
+        .prop_flat_map(|(header, validators)| {
+            let commit = arb_commit_for(header.clone(), validators.signers.clone());
+            (commit, Just(header), Just(validators.set))
+        })
+        .prop_map(|(commit, header, validators)| {
+            (SignedHeader::new(header, commit).unwrap(), validators)
+        })
+}
+
+prop_compose! {
+    /// An arbitrary [`block::Commit`].
+    pub fn arb_commit()(signed_header in arb_signed_header()) -> block::Commit {
+        signed_header.0.commit
+    }
+}
+
+prop_compose! {
+    /// An arbitrary [`Vote`] by the given signer, signed for the given chain.
+    pub fn arb_vote_by(signer: Signer, chain_id: chain::Id)(
+        vote_type in prop_oneof![Just(vote::Type::Prevote), Just(vote::Type::Precommit)],
+        height in arb_height(),
+        round in arb_round(),
+        block_id in prop::option::of(arb_block_id()),
+        timestamp in arb_time(),
+        validator_index in 0..=i32::MAX as u32,
+    ) -> Vote {
+        let vote = Vote {
+            vote_type,
+            height,
+            round,
+            block_id,
+            timestamp: Some(timestamp),
+            validator_address: signer.info.address,
+            validator_index: ValidatorIndex::try_from(validator_index).unwrap(),
+            signature: None,
+            extension: vec![],
+            extension_signature: None,
+        };
+        signer.sign_vote(vote, &chain_id)
+    }
+}
+
+prop_compose! {
+    /// An arbitrary signed [`Vote`].
+    pub fn arb_vote()(
+        signer in arb_signer(),
+        chain_id in arb_chain_id(),
+    )(
+        vote in arb_vote_by(signer, chain_id),
+    ) -> Vote {
+        vote
+    }
+}
+
+prop_compose! {
+    /// An arbitrary [`DuplicateVoteEvidence`]: two votes of the same validator
+    /// for the same height, round and step, but for different blocks.
+    pub fn arb_duplicate_vote_evidence()(
+        validators in arb_signer_set(),
+        chain_id in arb_chain_id(),
+    )(
+        index in any::<prop::sample::Index>(),
+        vote in arb_vote_by(validators.signers[0].clone(), chain_id.clone()),
+        other_block_id in arb_block_id(),
+        timestamp in arb_time(),
+        validators in Just(validators),
+        chain_id in Just(chain_id),
+    ) -> DuplicateVoteEvidence {
+        let signer = index.get(&validators.signers);
+        let vote_a = signer.sign_vote(vote.clone(), &chain_id);
+        let mut vote_b = vote;
+        vote_b.block_id = match vote_a.block_id {
+            Some(block_id) if block_id == other_block_id => None,
+            _ => Some(other_block_id),
+        };
+        let vote_b = signer.sign_vote(vote_b, &chain_id);
+        DuplicateVoteEvidence {
+            vote_a,
+            vote_b,
+            total_voting_power: validators.set.total_voting_power(),
+            validator_power: signer.info.power,
+            timestamp,
+        }
+    }
+}
+
+prop_compose! {
+    /// An arbitrary [`LightClientAttackEvidence`], with a conflicting block
+    /// signed by its own validator set.
+    pub fn arb_light_client_attack_evidence()(
+        (signed_header, validator_set) in arb_signed_header(),
+        common_height in arb_height(),
+        timestamp in arb_time(),
+    ) -> LightClientAttackEvidence {
+        let height = signed_header.header.height.value();
+        let common_height = Height::try_from(common_height.value() % height + 1).unwrap();
+        let byzantine_validators = validator_set
+            .validators()
+            .iter()
+            .filter(|v| {
+                signed_header
+                    .commit
+                    .signatures
+                    .iter()
+                    .any(|sig| sig.is_commit() && sig.validator_address() == Some(v.address))
+            })
+            .cloned()
+            .collect();
+        LightClientAttackEvidence {
+            total_voting_power: validator_set.total_voting_power(),
+            conflicting_block: ConflictingBlock {
+                signed_header,
+                validator_set,
+            },
+            common_height,
+            byzantine_validators,
+            timestamp,
+        }
+    }
+}
+
+/// An arbitrary piece of [`Evidence`].
+pub fn arb_evidence() -> impl Strategy<Value = Evidence> {
+    prop_oneof![
+        arb_duplicate_vote_evidence().prop_map(|ev| Evidence::DuplicateVote(Box::new(ev))),
+        arb_light_client_attack_evidence().prop_map(Evidence::from),
+    ]
+}
+
+fn make_signer(seed: [u8; 32], power: u64) -> Signer {
+    let key = SigningKey::from(seed);
+    let pub_key = PublicKey::from_raw_ed25519(key.verification_key().as_bytes()).unwrap();
+    let info = validator::Info::new(pub_key, vote::Power::try_from(power).unwrap());
+    Signer { key, info }
+}
+
+#[cfg(test)]
+mod tests {
+    use tendermint_proto::{v0_38::types as pb, Protobuf};
+
+    use super::*;
+    use crate::{crypto::default::signature::Verifier, vote::SignedVote};
+
+    proptest! {
+        #[test]
+        fn signed_header_roundtrips_and_verifies((signed_header, validators) in arb_signed_header()) {
+            let raw: pb::SignedHeader = signed_header.clone().into();
+            prop_assert_eq!(SignedHeader::try_from(raw).unwrap(), signed_header.clone());
+
+            prop_assert_eq!(signed_header.header.validators_hash, validators.hash());
+            let mut signed_power = 0;
+            for (sig, info) in signed_header.commit.signatures.iter().zip(validators.validators()) {
+                let (timestamp, signature) = match sig {
+                    CommitSig::BlockIdFlagCommit { timestamp, signature, .. }
+                    | CommitSig::BlockIdFlagNil { timestamp, signature, .. } => (*timestamp, signature.clone()),
+                    CommitSig::BlockIdFlagAbsent => continue,
+                };
+                let vote = Vote {
+                    vote_type: vote::Type::Precommit,
+                    height: signed_header.commit.height,
+                    round: signed_header.commit.round,
+                    block_id: sig.is_commit().then_some(signed_header.commit.block_id),
+                    timestamp: Some(timestamp),
+                    validator_address: info.address,
+                    validator_index: ValidatorIndex::try_from(0).unwrap(),
+                    signature,
+                    extension: vec![],
+                    extension_signature: None,
+                };
+                let signed_vote =
+                    SignedVote::from_vote(vote, signed_header.header.chain_id.clone()).unwrap();
+                prop_assert!(info
+                    .verify_signature::<Verifier>(&signed_vote.sign_bytes(), signed_vote.signature())
+                    .is_ok());
+                if sig.is_commit() {
+                    signed_power += info.power();
+                }
+            }
+            prop_assert!(signed_power * 3 > validators.total_voting_power().value() * 2);
+        }
+
+        #[test]
+        fn vote_roundtrips(vote in arb_vote()) {
+            let bytes = Protobuf::<pb::Vote>::encode_vec(vote.clone());
+            prop_assert_eq!(<Vote as Protobuf<pb::Vote>>::decode_vec(&bytes).unwrap(), vote);
+        }
+
+        #[test]
+        fn evidence_roundtrips(evidence in arb_evidence()) {
+            let bytes = Protobuf::<pb::Evidence>::encode_vec(evidence.clone());
+            let decoded = <Evidence as Protobuf<pb::Evidence>>::decode_vec(&bytes).unwrap();
+            prop_assert_eq!(decoded, evidence);
+        }
+    }
+}
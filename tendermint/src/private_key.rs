@@ -4,16 +4,21 @@ pub use crate::crypto::ed25519::SigningKey as Ed25519;
 #[cfg(feature = "secp256k1")]
 pub use k256::ecdsa::SigningKey as Secp256k1;
 
-use crate::prelude::*;
+#[cfg(feature = "keystore")]
+#[cfg_attr(docsrs, doc(cfg(feature = "keystore")))]
+pub mod keystore;
+#[cfg(feature = "pkcs8")]
+mod pkcs8;
+
+use crate::{prelude::*, public_key::Algorithm, Error};
 
 #[cfg(feature = "rust-crypto")]
-use crate::public_key::PublicKey;
+use crate::{public_key::PublicKey, Signature};
 
 #[cfg(feature = "rust-crypto")]
 use serde::{de, ser, Deserialize, Serialize};
 #[cfg(feature = "rust-crypto")]
 use subtle_encoding::{Base64, Encoding};
-#[cfg(feature = "rust-crypto")]
 use zeroize::Zeroizing;
 
 pub const ED25519_KEYPAIR_SIZE: usize = 64;
@@ -59,6 +64,35 @@ impl PrivateKey {
         }
     }
 
+    /// Get the algorithm of this private key
+    pub fn algorithm(&self) -> Algorithm {
+        match self {
+            PrivateKey::Ed25519(_) => Algorithm::Ed25519,
+
+            #[cfg(feature = "secp256k1")]
+            PrivateKey::Secp256k1(_) => Algorithm::Secp256k1,
+        }
+    }
+
+    /// Sign the given message with this private key, as expected by
+    /// CometBFT for the key's algorithm: a plain Ed25519 signature, or a
+    /// low-S ECDSA signature over the SHA-256 digest of the message for
+    /// Secp256k1 keys.
+    #[cfg(feature = "rust-crypto")]
+    pub fn sign(&self, msg: &[u8]) -> Signature {
+        match self {
+            PrivateKey::Ed25519(signing_key) => signing_key.sign(msg),
+
+            #[cfg(feature = "secp256k1")]
+            PrivateKey::Secp256k1(signing_key) => {
+                use k256::ecdsa::signature::Signer as _;
+
+                let signature: k256::ecdsa::Signature = signing_key.sign(msg);
+                signature.into()
+            },
+        }
+    }
+
     /// If applicable, borrow the Ed25519 keypair
     pub fn ed25519_signing_key(&self) -> Option<&Ed25519> {
         match self {
@@ -68,6 +102,73 @@ impl PrivateKey {
             PrivateKey::Secp256k1(_signing_key) => None,
         }
     }
+
+    /// Construct an Ed25519 private key from its raw 32-byte seed.
+    pub fn from_ed25519_seed(seed: &[u8]) -> Result<Self, Error> {
+        Ed25519::try_from(seed).map(PrivateKey::Ed25519)
+    }
+
+    /// Construct an Ed25519 private key from the 64-byte encoding used in
+    /// CometBFT keyfiles, i.e. the seed followed by the public key.
+    ///
+    /// Fails if the public key half does not match the one derived from the
+    /// seed.
+    #[cfg(feature = "rust-crypto")]
+    pub fn from_ed25519_keypair_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() != ED25519_KEYPAIR_SIZE {
+            return Err(Error::invalid_key("invalid Ed25519 keypair size".into()));
+        }
+        let signing_key = Ed25519::try_from(&bytes[0..32])?;
+        if &bytes[32..64] != signing_key.verification_key().as_bytes() {
+            return Err(Error::invalid_key("keypair mismatch".into()));
+        }
+        Ok(PrivateKey::Ed25519(signing_key))
+    }
+
+    /// Construct a Secp256k1 private key from its raw 32-byte scalar.
+    #[cfg(feature = "secp256k1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "secp256k1")))]
+    pub fn from_secp256k1_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Secp256k1::from_slice(bytes)
+            .map(PrivateKey::Secp256k1)
+            .map_err(|_| Error::invalid_key("invalid secp256k1 private key".into()))
+    }
+
+    /// Export the raw secret bytes of this key: the 32-byte seed of an
+    /// Ed25519 key, or the 32-byte scalar of a Secp256k1 key.
+    pub fn to_raw_bytes(&self) -> Zeroizing<Vec<u8>> {
+        match self {
+            PrivateKey::Ed25519(signing_key) => Zeroizing::new(signing_key.as_bytes().to_vec()),
+
+            #[cfg(feature = "secp256k1")]
+            PrivateKey::Secp256k1(signing_key) => Zeroizing::new(signing_key.to_bytes().to_vec()),
+        }
+    }
+
+    /// Export an Ed25519 key in the 64-byte encoding used in CometBFT
+    /// keyfiles, i.e. the seed followed by the public key.
+    #[cfg(feature = "rust-crypto")]
+    pub fn to_ed25519_keypair_bytes(&self) -> Option<Zeroizing<[u8; ED25519_KEYPAIR_SIZE]>> {
+        let signing_key = self.ed25519_signing_key()?;
+        let mut keypair_bytes = Zeroizing::new([0u8; ED25519_KEYPAIR_SIZE]);
+        keypair_bytes[0..32].copy_from_slice(signing_key.as_bytes());
+        keypair_bytes[32..64].copy_from_slice(signing_key.verification_key().as_bytes());
+        Some(keypair_bytes)
+    }
+
+    /// Parse a private key from its CometBFT JSON encoding, as found in the
+    /// `priv_key` field of `priv_validator_key.json` and `node_key.json`.
+    #[cfg(feature = "rust-crypto")]
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        // The errors of serde_json may quote the input, i.e. the key.
+        serde_json::from_str(json).map_err(|_| Error::invalid_key("malformed JSON key".into()))
+    }
+
+    /// Serialize this private key into its CometBFT JSON encoding.
+    #[cfg(feature = "rust-crypto")]
+    pub fn to_json(&self) -> Zeroizing<String> {
+        Zeroizing::new(serde_json::to_string(self).expect("private key serialization"))
+    }
 }
 
 /// Serialize a Secp256k1 privkey as Base64
@@ -149,3 +250,57 @@ where
 
     Ok(signing_key)
 }
+
+#[cfg(all(test, feature = "rust-crypto"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ed25519_raw_and_json_round_trip() {
+        let key = PrivateKey::from_ed25519_seed(&[7; 32]).unwrap();
+        assert_eq!(key.to_raw_bytes().as_slice(), &[7; 32]);
+
+        let keypair_bytes = key.to_ed25519_keypair_bytes().unwrap();
+        let decoded = PrivateKey::from_ed25519_keypair_bytes(&keypair_bytes[..]).unwrap();
+        assert_eq!(decoded.public_key(), key.public_key());
+
+        let mut mismatched = *keypair_bytes;
+        mismatched[63] ^= 1;
+        assert!(PrivateKey::from_ed25519_keypair_bytes(&mismatched).is_err());
+        assert!(PrivateKey::from_ed25519_seed(&[7; 31]).is_err());
+
+        let json = key.to_json();
+        assert!(json.starts_with(r#"{"type":"tendermint/PrivKeyEd25519","value":"#));
+        let decoded = PrivateKey::from_json(&json).unwrap();
+        assert_eq!(decoded.to_raw_bytes(), key.to_raw_bytes());
+
+        // The errors do not quote the key.
+        let json = json.replace("PrivKeyEd25519", "PrivKeySecret");
+        let err = PrivateKey::from_json(&json).err().unwrap().to_string();
+        assert!(!err.contains("PrivKeySecret"), "{err}");
+    }
+
+    #[test]
+    fn signatures_verify_with_public_key() {
+        use crate::crypto::{default::signature::Verifier, signature::Verifier as _};
+
+        let keys = [
+            PrivateKey::from_ed25519_seed(&[7; 32]).unwrap(),
+            #[cfg(feature = "secp256k1")]
+            PrivateKey::from_secp256k1_bytes(&[7; 32]).unwrap(),
+        ];
+
+        for key in keys {
+            let public_key = key.public_key();
+            assert_eq!(key.algorithm(), public_key.algorithm());
+
+            let signature = key.sign(b"sign bytes");
+            assert_eq!(
+                signature.as_bytes().len(),
+                crate::signature::SIGNATURE_LENGTH
+            );
+            Verifier::verify(public_key, b"sign bytes", &signature).unwrap();
+            assert!(Verifier::verify(public_key, b"other bytes", &signature).is_err());
+        }
+    }
+}
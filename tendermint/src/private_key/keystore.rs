@@ -0,0 +1,248 @@
+//! Password-encrypted keystores for private keys.
+//!
+//! The private key is serialized in its CometBFT JSON encoding, and
+//! encrypted with XChaCha20-Poly1305 under a key derived from the password
+//! with Argon2id. The resulting [`Keystore`] is itself serializable, e.g. to
+//! JSON for storage on disk.
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit},
+    XChaCha20Poly1305, XNonce,
+};
+use rand_core::CryptoRngCore;
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use super::PrivateKey;
+use crate::{prelude::*, serializers::bytes::base64string, Error};
+
+/// The current version of the keystore format.
+pub const KEYSTORE_VERSION: u32 = 1;
+
+const KDF_NAME: &str = "argon2id";
+const CIPHER_NAME: &str = "xchacha20poly1305";
+const SALT_SIZE: usize = 16;
+const NONCE_SIZE: usize = 24;
+
+/// Maximum memory size of the key derivation, in KiB, so that a crafted
+/// keystore cannot exhaust the memory.
+pub const MAX_M_COST: u32 = 1024 * 1024;
+/// Maximum number of iterations of the key derivation.
+pub const MAX_T_COST: u32 = 16;
+/// Maximum degree of parallelism of the key derivation.
+pub const MAX_P_COST: u32 = 16;
+
+/// Cost parameters of the Argon2id key derivation, at most [`MAX_M_COST`],
+/// [`MAX_T_COST`] and [`MAX_P_COST`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfParams {
+    /// Memory size, in KiB.
+    pub m_cost: u32,
+    /// Number of iterations.
+    pub t_cost: u32,
+    /// Degree of parallelism.
+    pub p_cost: u32,
+}
+
+impl Default for KdfParams {
+    /// The defaults recommended by OWASP for Argon2id.
+    fn default() -> Self {
+        Self {
+            m_cost: Params::DEFAULT_M_COST,
+            t_cost: Params::DEFAULT_T_COST,
+            p_cost: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+/// A private key encrypted under a password.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Keystore {
+    /// Version of the keystore format.
+    pub version: u32,
+    /// Name of the key derivation function.
+    pub kdf: String,
+    /// Parameters of the key derivation function.
+    pub kdf_params: KdfParams,
+    /// Salt of the key derivation.
+    #[serde(with = "base64string")]
+    pub salt: Vec<u8>,
+    /// Name of the cipher.
+    pub cipher: String,
+    /// Nonce used for encryption.
+    #[serde(with = "base64string")]
+    pub nonce: Vec<u8>,
+    /// The encrypted private key.
+    #[serde(with = "base64string")]
+    pub ciphertext: Vec<u8>,
+}
+
+impl Keystore {
+    /// Encrypt the given private key under the given password, using the
+    /// default key derivation parameters.
+    pub fn encrypt<R: CryptoRngCore>(
+        key: &PrivateKey,
+        password: &[u8],
+        rng: &mut R,
+    ) -> Result<Self, Error> {
+        Self::encrypt_with_params(key, password, KdfParams::default(), rng)
+    }
+
+    /// Encrypt the given private key under the given password, using the
+    /// given key derivation parameters.
+    pub fn encrypt_with_params<R: CryptoRngCore>(
+        key: &PrivateKey,
+        password: &[u8],
+        kdf_params: KdfParams,
+        rng: &mut R,
+    ) -> Result<Self, Error> {
+        let mut salt = vec![0u8; SALT_SIZE];
+        rng.fill_bytes(&mut salt);
+        let mut nonce = vec![0u8; NONCE_SIZE];
+        rng.fill_bytes(&mut nonce);
+
+        let cipher = cipher(password, &salt, kdf_params)?;
+        let plaintext = key.to_json();
+        let ciphertext = cipher
+            .encrypt(XNonce::from_slice(&nonce), plaintext.as_bytes())
+            .map_err(|_| Error::crypto())?;
+
+        Ok(Self {
+            version: KEYSTORE_VERSION,
+            kdf: KDF_NAME.to_string(),
+            kdf_params,
+            salt,
+            cipher: CIPHER_NAME.to_string(),
+            nonce,
+            ciphertext,
+        })
+    }
+
+    /// Decrypt the private key stored in this keystore with the given
+    /// password.
+    pub fn decrypt(&self, password: &[u8]) -> Result<PrivateKey, Error> {
+        if self.version != KEYSTORE_VERSION || self.kdf != KDF_NAME || self.cipher != CIPHER_NAME {
+            return Err(Error::invalid_key(format!(
+                "unsupported keystore: version {}, kdf {}, cipher {}",
+                self.version, self.kdf, self.cipher
+            )));
+        }
+        if self.nonce.len() != NONCE_SIZE {
+            return Err(Error::invalid_key("invalid keystore nonce size".into()));
+        }
+        if self.salt.len() < SALT_SIZE {
+            return Err(Error::invalid_key("invalid keystore salt size".into()));
+        }
+
+        let cipher = cipher(password, &self.salt, self.kdf_params)?;
+        let plaintext = Zeroizing::new(
+            cipher
+                .decrypt(XNonce::from_slice(&self.nonce), self.ciphertext.as_slice())
+                .map_err(|_| Error::invalid_key("wrong password or corrupted keystore".into()))?,
+        );
+        let json = core::str::from_utf8(&plaintext)
+            .map_err(|_| Error::invalid_key("malformed keystore contents".into()))?;
+        PrivateKey::from_json(json)
+    }
+}
+
+/// Derive the encryption key from the password, and set up the cipher.
+fn cipher(password: &[u8], salt: &[u8], kdf_params: KdfParams) -> Result<XChaCha20Poly1305, Error> {
+    if kdf_params.m_cost > MAX_M_COST
+        || kdf_params.t_cost > MAX_T_COST
+        || kdf_params.p_cost > MAX_P_COST
+    {
+        return Err(Error::invalid_key(format!(
+            "key derivation parameters exceed the maximum costs: {kdf_params:?}"
+        )));
+    }
+    let params = Params::new(
+        kdf_params.m_cost,
+        kdf_params.t_cost,
+        kdf_params.p_cost,
+        None,
+    )
+    .map_err(|e| Error::invalid_key(format!("invalid key derivation parameters: {e}")))?;
+    let mut key = Zeroizing::new([0u8; 32]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(password, salt, &mut *key)
+        .map_err(|e| Error::invalid_key(format!("key derivation failed: {e}")))?;
+    Ok(XChaCha20Poly1305::new(key.as_ref().into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use rand_core::{CryptoRng, RngCore};
+
+    use super::*;
+
+    /// Deterministic byte source, good enough for tests.
+    struct TestRng(u8);
+
+    impl RngCore for TestRng {
+        fn next_u32(&mut self) -> u32 {
+            rand_core::impls::next_u32_via_fill(self)
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            rand_core::impls::next_u64_via_fill(self)
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            for byte in dest {
+                self.0 = self.0.wrapping_add(1);
+                *byte = self.0;
+            }
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+            self.fill_bytes(dest);
+            Ok(())
+        }
+    }
+
+    impl CryptoRng for TestRng {}
+
+    const CHEAP_PARAMS: KdfParams = KdfParams {
+        m_cost: 64,
+        t_cost: 1,
+        p_cost: 1,
+    };
+
+    #[test]
+    fn encrypt_decrypt_round_trip() {
+        let key = PrivateKey::from_ed25519_seed(&[42; 32]).unwrap();
+        let keystore =
+            Keystore::encrypt_with_params(&key, b"hunter2", CHEAP_PARAMS, &mut TestRng(0)).unwrap();
+
+        let json = serde_json::to_string(&keystore).unwrap();
+        let keystore: Keystore = serde_json::from_str(&json).unwrap();
+        let decrypted = keystore.decrypt(b"hunter2").unwrap();
+        assert_eq!(decrypted.to_raw_bytes(), key.to_raw_bytes());
+
+        assert!(keystore.decrypt(b"hunter3").is_err());
+    }
+
+    #[test]
+    fn rejects_expensive_or_weak_parameters() {
+        let key = PrivateKey::from_ed25519_seed(&[42; 32]).unwrap();
+        let keystore =
+            Keystore::encrypt_with_params(&key, b"hunter2", CHEAP_PARAMS, &mut TestRng(0)).unwrap();
+
+        let mut expensive = keystore.clone();
+        expensive.kdf_params.m_cost = MAX_M_COST + 1;
+        let err = expensive.decrypt(b"hunter2").err().unwrap();
+        assert!(err.to_string().contains("maximum costs"), "{err}");
+        expensive.kdf_params = KdfParams {
+            t_cost: MAX_T_COST + 1,
+            ..CHEAP_PARAMS
+        };
+        assert!(expensive.decrypt(b"hunter2").is_err());
+
+        let mut short_salt = keystore;
+        short_salt.salt.truncate(SALT_SIZE - 1);
+        let err = short_salt.decrypt(b"hunter2").err().unwrap();
+        assert!(err.to_string().contains("salt size"), "{err}");
+    }
+}
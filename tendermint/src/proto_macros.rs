@@ -27,3 +27,24 @@ macro_rules! tendermint_pb_modules {
         }
     };
 }
+
+/// Implement [`Protobuf`](tendermint_proto::Protobuf) for a domain type,
+/// auditing the decoded messages when the `serialization-audit` feature is
+/// enabled.
+macro_rules! audited_protobuf {
+    ($raw:ty, $domain:ty) => {
+        impl Protobuf<$raw> for $domain {
+            #[cfg(feature = "serialization-audit")]
+            fn decode<B: bytes::Buf>(buf: B) -> Result<Self, tendermint_proto::Error> {
+                crate::serialization_audit::decode::<$raw, Self, B>(buf, false)
+            }
+
+            #[cfg(feature = "serialization-audit")]
+            fn decode_length_delimited<B: bytes::Buf>(
+                buf: B,
+            ) -> Result<Self, tendermint_proto::Error> {
+                crate::serialization_audit::decode::<$raw, Self, B>(buf, true)
+            }
+        }
+    };
+}
@@ -176,6 +176,15 @@ impl PublicKey {
         Ed25519::try_from(bytes).map(PublicKey::Ed25519).ok()
     }
 
+    /// Get the algorithm of this public key
+    pub fn algorithm(&self) -> Algorithm {
+        match self {
+            PublicKey::Ed25519(_) => Algorithm::Ed25519,
+            #[cfg(feature = "secp256k1")]
+            PublicKey::Secp256k1(_) => Algorithm::Secp256k1,
+        }
+    }
+
     /// Get Ed25519 public key
     pub fn ed25519(self) -> Option<Ed25519> {
         #[allow(unreachable_patterns)]
@@ -222,6 +231,13 @@ impl PublicKey {
         bech32::encode(hrp, backward_compatible_amino_prefixed_pubkey)
     }
 
+    /// Derive the address of this key, serialized as Bech32 with the given
+    /// human readable prefix (e.g. `cosmosvalcons` for consensus keys)
+    #[cfg(feature = "rust-crypto")]
+    pub fn to_bech32_address(self, hrp: &str) -> String {
+        crate::account::Id::from(self).to_bech32(hrp)
+    }
+
     /// Serialize this key as hexadecimal
     pub fn to_hex(self) -> String {
         String::from_utf8(hex::encode_upper(self.to_bytes())).unwrap()
@@ -0,0 +1,231 @@
+//! Auditing of the Protobuf serialization of consensus types.
+//!
+//! The hashes and signatures of consensus structures are computed over their
+//! Protobuf encoding, so the domain types of this crate must encode back to
+//! exactly the bytes they were decoded from. Fields the domain types do not
+//! know about, e.g. added by a newer version of CometBFT or by a fork, are
+//! silently dropped otherwise.
+//!
+//! With the `serialization-audit` feature, every [`Header`], [`SignedHeader`],
+//! [`Commit`] and [`Vote`] decoded with [`Protobuf::decode`] (or any of the
+//! other decoding methods of the trait) is encoded again, and compared byte
+//! for byte to the decoded message. Mismatches are logged as `tracing`
+//! events at the error level, and passed to the handler set with
+//! [`set_mismatch_handler`], if any, e.g. to abort on mismatches.
+//!
+//! [`Header`]: crate::block::Header
+//! [`SignedHeader`]: crate::block::signed_header::SignedHeader
+//! [`Commit`]: crate::block::Commit
+//! [`Vote`]: crate::Vote
+//! [`Protobuf::decode`]: tendermint_proto::Protobuf::decode
+
+use core::{any::type_name, fmt};
+
+use bytes::Buf;
+use once_cell::race::OnceBox;
+use prost::Message;
+use subtle_encoding::hex;
+use tendermint_proto::Error;
+
+use crate::prelude::*;
+
+/// A decoded message which does not encode back to the same bytes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mismatch {
+    /// The name of the domain type the message was decoded into
+    pub type_name: &'static str,
+    /// The bytes of the decoded message
+    pub decoded: Vec<u8>,
+    /// The bytes of the message encoded again from the domain type
+    pub reencoded: Vec<u8>,
+}
+
+impl Mismatch {
+    /// The offset of the first byte differing between the decoded and the
+    /// re-encoded message.
+    pub fn first_difference(&self) -> usize {
+        self.decoded
+            .iter()
+            .zip(&self.reencoded)
+            .position(|(a, b)| a != b)
+            .unwrap_or_else(|| self.decoded.len().min(self.reencoded.len()))
+    }
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} does not round-trip: decoded {} bytes, re-encoded {} bytes, first difference at offset {}",
+            self.type_name,
+            self.decoded.len(),
+            self.reencoded.len(),
+            self.first_difference(),
+        )
+    }
+}
+
+/// A handler of serialization mismatches.
+pub type MismatchHandler = fn(&Mismatch);
+
+static HANDLER: OnceBox<MismatchHandler> = OnceBox::new();
+
+/// Set the handler to which the serialization mismatches are passed, once
+/// they are logged.
+///
+/// The handler can only be set once; the given handler is returned back if
+/// one was already set.
+pub fn set_mismatch_handler(handler: MismatchHandler) -> Result<(), MismatchHandler> {
+    HANDLER.set(Box::new(handler)).map_err(|handler| *handler)
+}
+
+fn report(mismatch: &Mismatch) {
+    tracing::error!(
+        type_name = mismatch.type_name,
+        first_difference = mismatch.first_difference(),
+        decoded = %String::from_utf8_lossy(&hex::encode(&mismatch.decoded)),
+        reencoded = %String::from_utf8_lossy(&hex::encode(&mismatch.reencoded)),
+        "serialization mismatch"
+    );
+    #[cfg(test)]
+    if let Some(handler) = tests::HANDLER.with(|handler| handler.get()) {
+        return handler(mismatch);
+    }
+    if let Some(handler) = HANDLER.get() {
+        handler(mismatch);
+    }
+}
+
+/// Decode a message as [`Protobuf::decode`] and
+/// [`Protobuf::decode_length_delimited`] do, and audit it.
+///
+/// [`Protobuf::decode`]: tendermint_proto::Protobuf::decode
+/// [`Protobuf::decode_length_delimited`]: tendermint_proto::Protobuf::decode_length_delimited
+pub(crate) fn decode<Raw, T, B>(mut buf: B, length_delimited: bool) -> Result<T, Error>
+where
+    Raw: Message + Default + From<T>,
+    T: Clone + TryFrom<Raw>,
+    T::Error: fmt::Display,
+    B: Buf,
+{
+    let mut bytes = buf.copy_to_bytes(buf.remaining());
+    if length_delimited {
+        let len = prost::decode_length_delimiter(bytes.clone()).map_err(Error::decode_message)?;
+        // Trailing bytes are ignored when decoding.
+        bytes.truncate(prost::length_delimiter_len(len) + len);
+    }
+    let raw = if length_delimited {
+        Raw::decode_length_delimited(bytes.clone())
+    } else {
+        Raw::decode(bytes.clone())
+    }
+    .map_err(Error::decode_message)?;
+    let value = T::try_from(raw).map_err(Error::try_from::<Raw, T, _>)?;
+
+    let raw = Raw::from(value.clone());
+    let reencoded = if length_delimited {
+        raw.encode_length_delimited_to_vec()
+    } else {
+        raw.encode_to_vec()
+    };
+    if reencoded != bytes {
+        report(&Mismatch {
+            type_name: type_name::<T>(),
+            decoded: bytes.to_vec(),
+            reencoded,
+        });
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::Cell;
+
+    use tendermint_proto::{v0_38::types::Vote as RawVote, Protobuf};
+
+    use super::*;
+    use crate::{vote::Type, Vote};
+
+    std::thread_local! {
+        /// The handler of the mismatches of the current test, used in place
+        /// of the one set for the process, so that tests do not interfere.
+        pub(super) static HANDLER: Cell<Option<MismatchHandler>> = const { Cell::new(None) };
+
+        static MISMATCHES: Cell<usize> = const { Cell::new(0) };
+    }
+
+    fn count_mismatch(mismatch: &Mismatch) {
+        assert!(mismatch.type_name.ends_with("Vote"));
+        MISMATCHES.with(|mismatches| mismatches.set(mismatches.get() + 1));
+    }
+
+    fn mismatches() -> usize {
+        MISMATCHES.with(Cell::get)
+    }
+
+    fn vote() -> Vote {
+        Vote {
+            vote_type: Type::Precommit,
+            height: 10_u32.into(),
+            round: 1_u16.into(),
+            block_id: None,
+            timestamp: Some("2019-01-01T00:00:00Z".parse().unwrap()),
+            validator_address: crate::account::Id::new([0xa5; 20]),
+            validator_index: 3_u32.try_into().unwrap(),
+            signature: crate::Signature::new([7; 64]).unwrap(),
+            extension: vec![],
+            extension_signature: None,
+        }
+    }
+
+    #[test]
+    fn reports_messages_not_round_tripping() {
+        HANDLER.with(|handler| handler.set(Some(count_mismatch)));
+
+        let bytes = Protobuf::<RawVote>::encode_vec(vote());
+        let decoded: Vote = Protobuf::<RawVote>::decode_vec(&bytes).unwrap();
+        assert_eq!(decoded, vote());
+        let delimited = Protobuf::<RawVote>::encode_length_delimited_vec(vote());
+        let decoded: Vote = Protobuf::<RawVote>::decode_length_delimited_vec(&delimited).unwrap();
+        assert_eq!(decoded, vote());
+        assert_eq!(mismatches(), 0);
+
+        // Append a field unknown to the domain type, which is dropped when
+        // decoding.
+        let mut bytes = bytes;
+        bytes.extend_from_slice(&[0xf8, 0x01, 0x01]);
+        let decoded: Vote = Protobuf::<RawVote>::decode_vec(&bytes).unwrap();
+        assert_eq!(decoded, vote());
+        assert_eq!(mismatches(), 1);
+        HANDLER.with(|handler| handler.set(None));
+    }
+
+    #[test]
+    fn logs_mismatches_by_default() {
+        let mut bytes = Protobuf::<RawVote>::encode_vec(vote());
+        bytes.extend_from_slice(&[0xf8, 0x01, 0x01]);
+        let decoded: Vote = Protobuf::<RawVote>::decode_vec(&bytes).unwrap();
+        assert_eq!(decoded, vote());
+    }
+
+    #[test]
+    fn locates_first_difference() {
+        let mismatch = Mismatch {
+            type_name: "Vote",
+            decoded: vec![1, 2, 3, 4],
+            reencoded: vec![1, 2, 4],
+        };
+        assert_eq!(mismatch.first_difference(), 2);
+        assert_eq!(
+            mismatch.to_string(),
+            "Vote does not round-trip: decoded 4 bytes, re-encoded 3 bytes, first difference at offset 2"
+        );
+
+        let truncated = Mismatch {
+            reencoded: vec![1, 2],
+            ..mismatch
+        };
+        assert_eq!(truncated.first_difference(), 2);
+    }
+}
@@ -1,3 +1,5 @@
+mod decoder;
 mod proof;
 
+pub use decoder::{DecodedTx, ProtobufTxDecoder, TxDecoder};
 pub use proof::Proof;
@@ -0,0 +1,124 @@
+use core::{fmt, marker::PhantomData};
+
+use crate::prelude::*;
+
+/// Decoder of the raw bytes of transactions into the transaction type of an
+/// application.
+///
+/// Transactions are opaque to Tendermint: blocks, RPC responses and events
+/// only carry their raw bytes. A decoder implementing this trait can be
+/// passed to the `decode_tx`/`decode_txs` methods of these types to obtain
+/// the transactions in their decoded form, along with their raw bytes.
+///
+/// Any function or closure from `&[u8]` to a `Result` is a decoder, and
+/// [`ProtobufTxDecoder`] decodes transactions encoded as Protobuf messages,
+/// such as the `TxRaw` messages of Cosmos SDK based chains.
+pub trait TxDecoder {
+    /// The decoded transaction type.
+    type Tx;
+    /// The error returned for transactions that cannot be decoded.
+    type Error;
+
+    /// Decode the raw bytes of a transaction.
+    fn decode(&self, raw: &[u8]) -> Result<Self::Tx, Self::Error>;
+
+    /// Decode the raw bytes of a transaction, keeping them alongside the
+    /// decoded transaction.
+    fn decode_raw(&self, raw: &[u8]) -> Result<DecodedTx<Self::Tx>, Self::Error> {
+        Ok(DecodedTx {
+            raw: raw.to_vec(),
+            tx: self.decode(raw)?,
+        })
+    }
+}
+
+impl<F, T, E> TxDecoder for F
+where
+    F: Fn(&[u8]) -> Result<T, E>,
+{
+    type Tx = T;
+    type Error = E;
+
+    fn decode(&self, raw: &[u8]) -> Result<T, E> {
+        self(raw)
+    }
+}
+
+/// A decoded transaction, along with its raw bytes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecodedTx<T> {
+    /// The raw bytes of the transaction, as included in the block.
+    pub raw: Vec<u8>,
+    /// The decoded transaction.
+    pub tx: T,
+}
+
+/// Decoder of transactions encoded as Protobuf messages of type `M`.
+pub struct ProtobufTxDecoder<M> {
+    message: PhantomData<fn() -> M>,
+}
+
+impl<M> ProtobufTxDecoder<M> {
+    /// Create a decoder of messages of type `M`.
+    pub fn new() -> Self {
+        Self {
+            message: PhantomData,
+        }
+    }
+}
+
+impl<M> Default for ProtobufTxDecoder<M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<M> Clone for ProtobufTxDecoder<M> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<M> Copy for ProtobufTxDecoder<M> {}
+
+impl<M> fmt::Debug for ProtobufTxDecoder<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProtobufTxDecoder")
+            .field("message", &core::any::type_name::<M>())
+            .finish()
+    }
+}
+
+impl<M> TxDecoder for ProtobufTxDecoder<M>
+where
+    M: prost::Message + Default,
+{
+    type Tx = M;
+    type Error = prost::DecodeError;
+
+    fn decode(&self, raw: &[u8]) -> Result<M, prost::DecodeError> {
+        M::decode(raw)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_protobuf_messages() {
+        use prost::Message;
+        use tendermint_proto::google::protobuf::Duration;
+
+        let message = Duration {
+            seconds: 7,
+            nanos: 42,
+        };
+        let raw = message.encode_to_vec();
+        let decoder = ProtobufTxDecoder::<Duration>::new();
+        let decoded = decoder.decode_raw(&raw).unwrap();
+        assert_eq!(decoded.tx, message);
+        assert_eq!(decoded.raw, raw);
+        assert!(decoder.decode(&[0xff]).is_err());
+    }
+}
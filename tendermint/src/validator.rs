@@ -1,5 +1,6 @@
 //! Tendermint validators
 
+use once_cell::race::OnceBox;
 use serde::{Deserialize, Serialize};
 use tendermint_proto::v0_38::types::{
     SimpleValidator as RawSimpleValidator, ValidatorSet as RawValidatorSet,
@@ -24,6 +25,8 @@ pub struct Set {
     validators: Vec<Info>,
     proposer: Option<Info>,
     total_voting_power: vote::Power,
+    #[serde(skip)]
+    hash: CachedHash,
 }
 
 impl Set {
@@ -67,6 +70,7 @@ impl Set {
             validators,
             proposer,
             total_voting_power,
+            hash: CachedHash::default(),
         })
     }
 
@@ -92,6 +96,16 @@ impl Set {
         Ok(Self::new(validators, Some(proposer)))
     }
 
+    /// Designate the validator with the given address as the proposer of
+    /// this set, keeping the memoized hash of the set.
+    pub fn into_with_proposer(mut self, proposer_address: account::Id) -> Result<Self, Error> {
+        self.proposer = Some(
+            self.validator(proposer_address)
+                .ok_or_else(|| Error::proposer_not_found(proposer_address))?,
+        );
+        Ok(self)
+    }
+
     /// Get Info of the underlying validators.
     pub fn validators(&self) -> &Vec<Info> {
         &self.validators
@@ -122,12 +136,20 @@ impl Set {
     }
 
     /// Compute the hash of this validator set.
+    ///
+    /// As validator sets are immutable, the hash is only computed on the first
+    /// call, and memoized for the subsequent calls.
     #[cfg(feature = "rust-crypto")]
     pub fn hash(&self) -> Hash {
-        self.hash_with::<crate::crypto::default::Sha256>()
+        *self
+            .hash
+            .0
+            .get_or_init(|| Box::new(self.hash_with::<crate::crypto::default::Sha256>()))
     }
 
-    /// Hash this header with a SHA256 hasher provided by a crypto provider.
+    /// Hash this validator set with a SHA256 hasher provided by a crypto provider.
+    ///
+    /// Unlike [`Set::hash`], the hash is computed on every call.
     pub fn hash_with<H>(&self) -> Hash
     where
         H: MerkleHash + Sha256 + Default,
@@ -142,6 +164,35 @@ impl Set {
     }
 }
 
+/// The memoized hash of a validator set with the default hasher, which is
+/// ignored when comparing sets.
+#[derive(Default)]
+struct CachedHash(OnceBox<Hash>);
+
+impl Clone for CachedHash {
+    fn clone(&self) -> Self {
+        let cached = Self::default();
+        if let Some(hash) = self.0.get() {
+            let _ = cached.0.set(Box::new(*hash));
+        }
+        cached
+    }
+}
+
+impl PartialEq for CachedHash {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for CachedHash {}
+
+impl core::fmt::Debug for CachedHash {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.0.get().fmt(f)
+    }
+}
+
 /// Validator information
 // Todo: Remove address and make it into a function that generates it on the fly from pub_key.
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -461,6 +512,14 @@ mod tests {
             let hash = val_set.hash();
             assert_eq!(hash_expect, hash.as_bytes().to_vec());
 
+            // the memoized hash is carried over by clones, and ignored by comparisons
+            let cloned = val_set.clone();
+            assert_eq!(cloned.hash(), hash);
+            assert_eq!(
+                Set::without_proposer(vec![v3.clone(), v2.clone(), v1.clone()]),
+                val_set
+            );
+
             let not_in_set = make_validator(
                 vec![
                     110, 147, 87, 120, 27, 218, 66, 209, 81, 4, 169, 153, 64, 163, 137, 89, 168,
@@ -478,6 +537,25 @@ mod tests {
                 148_151_478_422_287_875 + 158_095_448_483_785_107 + 770_561_664_770_006_272
             );
         }
+
+        #[test]
+        fn into_with_proposer_keeps_hash() {
+            let v1 = make_validator(vec![1; 32], 10);
+            let v2 = make_validator(vec![2; 32], 20);
+            let set = Set::without_proposer(vec![v1.clone(), v2.clone()]);
+            let hash = set.hash();
+
+            let with_proposer = set.clone().into_with_proposer(v1.address).unwrap();
+            assert_eq!(with_proposer.proposer(), &Some(v1.clone()));
+            assert_eq!(with_proposer.hash.0.get(), Some(&hash));
+            assert_eq!(
+                with_proposer,
+                Set::with_proposer(vec![v1.clone(), v2], v1.address).unwrap()
+            );
+
+            let not_in_set = make_validator(vec![3; 32], 30);
+            assert!(set.into_with_proposer(not_in_set.address).is_err());
+        }
     }
 
     #[test]
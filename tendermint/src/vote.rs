@@ -76,7 +76,7 @@ mod v0_34 {
     use tendermint_proto::v0_34::types::Vote as RawVote;
     use tendermint_proto::Protobuf;
 
-    impl Protobuf<RawVote> for Vote {}
+    audited_protobuf!(RawVote, Vote);
 
     impl TryFrom<RawVote> for Vote {
         type Error = Error;
@@ -127,7 +127,7 @@ mod v0_37 {
     use tendermint_proto::v0_37::types::Vote as RawVote;
     use tendermint_proto::Protobuf;
 
-    impl Protobuf<RawVote> for Vote {}
+    audited_protobuf!(RawVote, Vote);
 
     impl TryFrom<RawVote> for Vote {
         type Error = Error;
@@ -178,7 +178,7 @@ mod v0_38 {
     use tendermint_proto::v0_38::types::Vote as RawVote;
     use tendermint_proto::Protobuf;
 
-    impl Protobuf<RawVote> for Vote {}
+    audited_protobuf!(RawVote, Vote);
 
     impl TryFrom<RawVote> for Vote {
         type Error = Error;
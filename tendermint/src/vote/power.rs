@@ -7,7 +7,7 @@ use core::{
 
 use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
 
-use crate::{error::Error, prelude::*};
+use crate::{error::Error, prelude::*, serializers::str_or_int::StrOrInt};
 
 /// Voting power
 #[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord, Default)]
@@ -82,7 +82,8 @@ impl Power {
 impl<'de> Deserialize<'de> for Power {
     fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
         Ok(Power(
-            String::deserialize(deserializer)?
+            StrOrInt::deserialize(deserializer)?
+                .as_str()
                 .parse::<i64>()
                 .map_err(|e| D::Error::custom(format!("{e}")))?
                 .try_into()
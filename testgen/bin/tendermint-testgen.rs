@@ -1,6 +1,9 @@
 use gumdrop::Options;
 use simple_error::SimpleError;
-use tendermint_testgen::{helpers::*, Commit, Generator, Header, Time, Validator, Vote};
+use tendermint_testgen::{
+    helpers::*, AbciSession, Chain, Commit, Generator, Header, LightClientAttack, Time, Validator,
+    Vote,
+};
 
 const USAGE: &str = r#"
 This is a small utility for producing tendermint datastructures
@@ -67,6 +70,16 @@ enum Command {
     Commit(Commit),
     #[options(help = "produce timestamp from number of seconds since epoch")]
     Time(Time),
+    #[options(
+        help = "produce trusted, honest and conflicting light blocks of a light client attack"
+    )]
+    Attack(LightClientAttack),
+    #[options(
+        help = "produce light blocks of a chain from its length, validators and validator changes"
+    )]
+    Chain(Chain),
+    #[options(help = "produce ABCI request/response test vectors from blocks with txs and events")]
+    Abci(AbciSession),
 }
 
 fn encode_with_stdin<Opts: Generator<T> + Options, T: serde::Serialize>(
@@ -132,5 +145,8 @@ fn main() {
         Some(Command::Vote(cli)) => run_command(cli, opts.stdin),
         Some(Command::Commit(cli)) => run_command(cli, opts.stdin),
         Some(Command::Time(cli)) => run_command(cli, opts.stdin),
+        Some(Command::Attack(cli)) => run_command(cli, opts.stdin),
+        Some(Command::Chain(cli)) => run_command(cli, opts.stdin),
+        Some(Command::Abci(cli)) => run_command(cli, opts.stdin),
     }
 }
@@ -0,0 +1,529 @@
+//! Generation of ABCI request/response sequences, as exchanged between a node and
+//! an application over the lifecycle of a chain.
+//!
+//! The generated sequences can be stored as [`AbciVector`]s, which carry the
+//! protobuf encoding of each message, framed the same way as on an ABCI socket.
+//! This allows both `Application` implementations and the ABCI wire codec to be
+//! tested against stable fixtures.
+
+use std::str::FromStr;
+
+use gumdrop::Options;
+use serde::{Deserialize, Serialize};
+use simple_error::*;
+use tendermint::{
+    abci::{
+        response::ProcessProposal as ProposalStatus,
+        types::{
+            self, BlockSignatureInfo, CommitInfo, ExecTxResult, ExtendedCommitInfo,
+            ExtendedVoteInfo, VoteInfo,
+        },
+        Code, Event,
+    },
+    block::{BlockIdFlag, CommitSig},
+    crypto::{default::Sha256, Sha256 as _},
+    serializers::bytes::hexstring,
+    v0_38::abci::{request, response, Request, Response},
+    validator, AppHash,
+};
+use tendermint_proto::Protobuf;
+
+use crate::{
+    consensus::default_consensus_params, helpers::*, light_block::TmLightBlock,
+    validator::default_validators, BlockTimes, Chain, Generator, Validator,
+};
+
+/// An ABCI request, together with the response the application is expected to produce.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AbciExchange {
+    pub request: Request,
+    pub response: Response,
+}
+
+impl AbciExchange {
+    pub fn new(request: Request, response: Response) -> Self {
+        Self { request, response }
+    }
+
+    /// The name of the ABCI method, as used in the protobuf definitions.
+    pub fn method(&self) -> &'static str {
+        match self.request {
+            Request::Echo(_) => "echo",
+            Request::Flush => "flush",
+            Request::Info(_) => "info",
+            Request::InitChain(_) => "init_chain",
+            Request::Query(_) => "query",
+            Request::CheckTx(_) => "check_tx",
+            Request::Commit => "commit",
+            Request::ListSnapshots => "list_snapshots",
+            Request::OfferSnapshot(_) => "offer_snapshot",
+            Request::LoadSnapshotChunk(_) => "load_snapshot_chunk",
+            Request::ApplySnapshotChunk(_) => "apply_snapshot_chunk",
+            Request::PrepareProposal(_) => "prepare_proposal",
+            Request::ProcessProposal(_) => "process_proposal",
+            Request::ExtendVote(_) => "extend_vote",
+            Request::VerifyVoteExtension(_) => "verify_vote_extension",
+            Request::FinalizeBlock(_) => "finalize_block",
+        }
+    }
+
+    /// Encodes the exchange into a test vector.
+    pub fn to_vector(&self) -> AbciVector {
+        AbciVector {
+            method: self.method().to_string(),
+            request: self.request.clone().encode_length_delimited_vec(),
+            response: self.response.clone().encode_length_delimited_vec(),
+        }
+    }
+}
+
+/// An ABCI exchange in its wire format.
+///
+/// Both the request and the response are length-delimited protobuf messages
+/// (`tendermint.abci.Request` and `tendermint.abci.Response` of CometBFT 0.38),
+/// i.e. exactly the bytes sent over an ABCI socket connection.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AbciVector {
+    pub method: String,
+    #[serde(with = "hexstring")]
+    pub request: Vec<u8>,
+    #[serde(with = "hexstring")]
+    pub response: Vec<u8>,
+}
+
+impl AbciVector {
+    /// Decodes the request and the response of the test vector.
+    pub fn decode(&self) -> Result<AbciExchange, SimpleError> {
+        let exchange = AbciExchange {
+            request: try_with!(
+                Request::decode_length_delimited_vec(&self.request),
+                "failed to decode ABCI request"
+            ),
+            response: try_with!(
+                Response::decode_length_delimited_vec(&self.response),
+                "failed to decode ABCI response"
+            ),
+        };
+        if exchange.method() != self.method {
+            bail!(
+                "ABCI request for method {} found in a test vector for method {}",
+                exchange.method(),
+                self.method
+            )
+        }
+        Ok(exchange)
+    }
+}
+
+/// The contents of a block, and the events the application emits when executing it.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AbciBlock {
+    /// The transactions in the block.
+    #[serde(default)]
+    pub txs: Vec<String>,
+    /// The events emitted when executing each of the transactions.
+    #[serde(default)]
+    pub tx_events: Vec<Event>,
+    /// The events emitted when finalizing the block.
+    #[serde(default)]
+    pub events: Vec<Event>,
+}
+
+impl AbciBlock {
+    pub fn new(txs: &[&str]) -> Self {
+        Self {
+            txs: txs.iter().map(|tx| tx.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Adds an event emitted when executing each of the transactions.
+    pub fn tx_event(mut self, event: Event) -> Self {
+        self.tx_events.push(event);
+        self
+    }
+
+    /// Adds an event emitted when finalizing the block.
+    pub fn event(mut self, event: Event) -> Self {
+        self.events.push(event);
+        self
+    }
+}
+
+/// A session of ABCI calls made by a node to an application: the handshake,
+/// followed by a full consensus round for each of the blocks.
+///
+/// Each round consists of `PrepareProposal`, `ProcessProposal`, `FinalizeBlock`
+/// and `Commit` calls. The responses are those of a reference application, which
+/// accepts all proposals, executes all transactions successfully, and computes its
+/// app hash by chaining the SHA256 hashes of the executed transactions.
+#[derive(Debug, Options, Serialize, Deserialize, Clone)]
+pub struct AbciSession {
+    #[options(
+        help = "blocks (default: a single empty block), encoded as array of {\"txs\": [TX, ..], \"tx_events\": [EVENT, ..], \"events\": [EVENT, ..]} objects",
+        parse(try_from_str = "parse_as::<Vec<AbciBlock>>")
+    )]
+    pub blocks: Option<Vec<AbciBlock>>,
+    #[options(
+        help = "validators (default: 4 validators with equal voting power), encoded as array of 'validator' parameters",
+        parse(try_from_str = "parse_as::<Vec<Validator>>")
+    )]
+    pub validators: Option<Vec<Validator>>,
+    #[options(
+        help = "block times (default: one second apart, starting at 1 second since UNIX EPOCH), encoded as 'block times' JSON object",
+        parse(try_from_str = "parse_as::<BlockTimes>")
+    )]
+    pub times: Option<BlockTimes>,
+    #[options(help = "chain id (default: test-chain)")]
+    pub chain_id: Option<String>,
+}
+
+impl AbciSession {
+    pub fn new(blocks: &[AbciBlock]) -> Self {
+        Self {
+            blocks: Some(blocks.to_vec()),
+            validators: None,
+            times: None,
+            chain_id: None,
+        }
+    }
+    set_option!(blocks, &[AbciBlock], Some(blocks.to_vec()));
+    set_option!(validators, &[Validator], Some(validators.to_vec()));
+    set_option!(times, BlockTimes);
+    set_option!(chain_id, &str, Some(chain_id.to_string()));
+
+    /// Adds a block to the session.
+    pub fn block(mut self, block: AbciBlock) -> Self {
+        self.blocks.get_or_insert_with(Vec::new).push(block);
+        self
+    }
+
+    /// Produces the ABCI exchanges of the session, in the order they are made.
+    pub fn exchanges(&self) -> Result<Vec<AbciExchange>, SimpleError> {
+        let blocks = self
+            .blocks
+            .clone()
+            .unwrap_or_else(|| vec![AbciBlock::default()]);
+        if blocks.is_empty() {
+            bail!("ABCI session must contain at least one block")
+        }
+        let chain_id = self.chain_id.as_deref().unwrap_or("test-chain");
+        let validators = self.validators.clone().unwrap_or_else(default_validators);
+        let light_blocks = Chain::new(blocks.len() as u64)
+            .validators(&validators)
+            .times(self.times.clone().unwrap_or_default())
+            .chain_id(chain_id)
+            .generate()?;
+
+        let mut exchanges = handshake(chain_id, &light_blocks[0])?;
+        let mut app_hash = AppHash::default();
+        for (i, (block, light_block)) in blocks.iter().zip(&light_blocks).enumerate() {
+            let last_commit = match i {
+                0 => CommitInfo {
+                    round: Default::default(),
+                    votes: vec![],
+                },
+                _ => commit_info(&light_blocks[i - 1]),
+            };
+            app_hash = try_with!(
+                AppHash::try_from(next_app_hash(&app_hash, &block.txs).to_vec()),
+                "failed to compute app hash"
+            );
+            exchanges.extend(consensus_round(
+                block,
+                light_block,
+                last_commit,
+                app_hash.clone(),
+            ));
+        }
+        Ok(exchanges)
+    }
+
+    /// Produces the test vectors of the session, in the order they are exchanged.
+    pub fn vectors(&self) -> Result<Vec<AbciVector>, SimpleError> {
+        Ok(self
+            .exchanges()?
+            .iter()
+            .map(AbciExchange::to_vector)
+            .collect())
+    }
+}
+
+impl FromStr for AbciSession {
+    type Err = SimpleError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let session = match parse_as::<AbciSession>(s) {
+            Ok(input) => input,
+            Err(_) => AbciSession::new(&try_with!(
+                parse_as::<Vec<AbciBlock>>(s),
+                "failed to parse ABCI blocks"
+            )),
+        };
+        Ok(session)
+    }
+}
+
+impl Generator<Vec<AbciVector>> for AbciSession {
+    fn merge_with_default(self, default: Self) -> Self {
+        Self {
+            blocks: self.blocks.or(default.blocks),
+            validators: self.validators.or(default.validators),
+            times: self.times.or(default.times),
+            chain_id: self.chain_id.or(default.chain_id),
+        }
+    }
+
+    fn generate(&self) -> Result<Vec<AbciVector>, SimpleError> {
+        self.vectors()
+    }
+}
+
+fn handshake(chain_id: &str, genesis: &TmLightBlock) -> Result<Vec<AbciExchange>, SimpleError> {
+    let header = &genesis.signed_header.header;
+    let info = AbciExchange::new(
+        Request::Info(request::Info {
+            version: "0.38.0".to_string(),
+            block_version: header.version.block,
+            p2p_version: 8,
+            abci_version: "2.0.0".to_string(),
+        }),
+        Response::Info(response::Info {
+            data: "tendermint-testgen".to_string(),
+            version: "0.1.0".to_string(),
+            app_version: header.version.app,
+            last_block_height: Default::default(),
+            last_block_app_hash: AppHash::default(),
+        }),
+    );
+    let validators = genesis
+        .validators
+        .validators()
+        .iter()
+        .map(|v| validator::Update {
+            pub_key: v.pub_key,
+            power: v.power,
+        })
+        .collect();
+    let init_chain = AbciExchange::new(
+        Request::InitChain(request::InitChain {
+            time: header.time,
+            chain_id: chain_id.to_string(),
+            consensus_params: default_consensus_params(),
+            validators,
+            app_state_bytes: Default::default(),
+            initial_height: header.height,
+        }),
+        Response::InitChain(response::InitChain {
+            consensus_params: None,
+            validators: vec![],
+            app_hash: AppHash::default(),
+        }),
+    );
+    Ok(vec![info, init_chain])
+}
+
+fn consensus_round(
+    block: &AbciBlock,
+    light_block: &TmLightBlock,
+    last_commit: CommitInfo,
+    app_hash: AppHash,
+) -> Vec<AbciExchange> {
+    let header = &light_block.signed_header.header;
+    let txs: Vec<_> = block
+        .txs
+        .iter()
+        .map(|tx| tx.clone().into_bytes().into())
+        .collect();
+    let max_tx_bytes = default_consensus_params().block.max_bytes as i64;
+
+    let prepare_proposal = AbciExchange::new(
+        Request::PrepareProposal(request::PrepareProposal {
+            max_tx_bytes,
+            txs: txs.clone(),
+            local_last_commit: Some(ExtendedCommitInfo {
+                round: last_commit.round,
+                votes: last_commit
+                    .votes
+                    .iter()
+                    .map(|vote| ExtendedVoteInfo {
+                        validator: vote.validator.clone(),
+                        sig_info: vote.sig_info,
+                        vote_extension: Default::default(),
+                        extension_signature: None,
+                    })
+                    .collect(),
+            }),
+            misbehavior: vec![],
+            height: header.height,
+            time: header.time,
+            next_validators_hash: header.next_validators_hash,
+            proposer_address: header.proposer_address,
+        }),
+        Response::PrepareProposal(response::PrepareProposal { txs: txs.clone() }),
+    );
+    let process_proposal = AbciExchange::new(
+        Request::ProcessProposal(request::ProcessProposal {
+            txs: txs.clone(),
+            proposed_last_commit: Some(last_commit.clone()),
+            misbehavior: vec![],
+            hash: header.hash(),
+            height: header.height,
+            time: header.time,
+            next_validators_hash: header.next_validators_hash,
+            proposer_address: header.proposer_address,
+        }),
+        Response::ProcessProposal(ProposalStatus::Accept),
+    );
+    let tx_results = txs
+        .iter()
+        .map(|_| ExecTxResult {
+            code: Code::Ok,
+            events: block.tx_events.clone(),
+            ..Default::default()
+        })
+        .collect();
+    let finalize_block = AbciExchange::new(
+        Request::FinalizeBlock(request::FinalizeBlock {
+            txs,
+            decided_last_commit: last_commit,
+            misbehavior: vec![],
+            hash: header.hash(),
+            height: header.height,
+            time: header.time,
+            next_validators_hash: header.next_validators_hash,
+            proposer_address: header.proposer_address,
+        }),
+        Response::FinalizeBlock(response::FinalizeBlock {
+            events: block.events.clone(),
+            tx_results,
+            validator_updates: vec![],
+            consensus_param_updates: None,
+            app_hash,
+        }),
+    );
+    let commit = AbciExchange::new(
+        Request::Commit,
+        Response::Commit(response::Commit {
+            data: Default::default(),
+            retain_height: Default::default(),
+        }),
+    );
+    vec![prepare_proposal, process_proposal, finalize_block, commit]
+}
+
+/// Describes the votes in the commit of the given light block, as seen by the application.
+fn commit_info(light_block: &TmLightBlock) -> CommitInfo {
+    let commit = &light_block.signed_header.commit;
+    let votes = light_block
+        .validators
+        .validators()
+        .iter()
+        .zip(&commit.signatures)
+        .map(|(validator, sig)| {
+            let flag = match sig {
+                CommitSig::BlockIdFlagAbsent => BlockIdFlag::Absent,
+                CommitSig::BlockIdFlagCommit { .. } => BlockIdFlag::Commit,
+                CommitSig::BlockIdFlagNil { .. } => BlockIdFlag::Nil,
+            };
+            VoteInfo {
+                validator: types::Validator {
+                    address: validator
+                        .address
+                        .as_bytes()
+                        .try_into()
+                        .expect("account id is 20 bytes long"),
+                    power: validator.power,
+                },
+                sig_info: BlockSignatureInfo::Flag(flag),
+            }
+        })
+        .collect();
+    CommitInfo {
+        round: commit.round,
+        votes,
+    }
+}
+
+/// The app hash of the reference application: the previous app hash,
+/// hashed together with each of the executed transactions.
+fn next_app_hash(app_hash: &AppHash, txs: &[String]) -> [u8; 32] {
+    let mut data = app_hash.as_bytes().to_vec();
+    for tx in txs {
+        data.extend(Sha256::digest(tx));
+    }
+    Sha256::digest(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use tendermint::abci::EventAttributeIndexExt;
+
+    use super::*;
+
+    fn session() -> AbciSession {
+        let transfer = Event::new("transfer", [("amount", "10").index()]);
+        AbciSession::new(&[
+            AbciBlock::new(&["a=1", "b=2"]).tx_event(transfer),
+            AbciBlock::default().event(Event::new("begin", [("height", "2").no_index()])),
+            AbciBlock::new(&["c=3"]),
+        ])
+    }
+
+    #[test]
+    fn test_session_sequence() {
+        let exchanges = session().exchanges().unwrap();
+        let methods: Vec<_> = exchanges.iter().map(AbciExchange::method).collect();
+        let round = [
+            "prepare_proposal",
+            "process_proposal",
+            "finalize_block",
+            "commit",
+        ];
+        let mut expected = vec!["info", "init_chain"];
+        for _ in 0..3 {
+            expected.extend(round);
+        }
+        assert_eq!(methods, expected);
+
+        let mut app_hashes = vec![];
+        for (height, exchange) in exchanges
+            .iter()
+            .filter(|e| e.method() == "finalize_block")
+            .enumerate()
+        {
+            let (Request::FinalizeBlock(req), Response::FinalizeBlock(res)) =
+                (&exchange.request, &exchange.response)
+            else {
+                unreachable!()
+            };
+            assert_eq!(req.height.value(), height as u64 + 1);
+            assert_eq!(req.txs.len(), res.tx_results.len());
+            // the votes for the previous block are reported from the second block on
+            let votes = if height == 0 { 0 } else { 4 };
+            assert_eq!(req.decided_last_commit.votes.len(), votes);
+            app_hashes.push(res.app_hash.clone());
+        }
+        app_hashes.dedup();
+        assert_eq!(app_hashes.len(), 3);
+    }
+
+    #[test]
+    fn test_vectors_roundtrip() {
+        let session = session();
+        let exchanges = session.exchanges().unwrap();
+        let json = session.encode().unwrap();
+        let vectors: Vec<AbciVector> = serde_json::from_str(&json).unwrap();
+        let decoded: Vec<_> = vectors.iter().map(|v| v.decode().unwrap()).collect();
+        assert_eq!(decoded, exchanges);
+
+        // generation is deterministic
+        assert_eq!(session.encode().unwrap(), json);
+    }
+
+    #[test]
+    fn test_vector_method_mismatch() {
+        let mut vector = AbciSession::new(&[AbciBlock::default()]).vectors().unwrap()[0].clone();
+        vector.method = "commit".to_string();
+        assert!(vector.decode().is_err());
+    }
+}
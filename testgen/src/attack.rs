@@ -0,0 +1,361 @@
+//! Generation of conflicting light blocks, as produced by the attacks on the light client
+//! described in the [light client attack detection spec].
+//!
+//! Every scenario shares a trusted anchor with the honest chain, and produces a pair of
+//! light blocks at the same target height: one from the honest chain, and one conflicting
+//! with it that still carries enough signatures to be verified from the trusted anchor.
+//!
+//! [light client attack detection spec]: https://github.com/cometbft/cometbft/blob/main/spec/light-client/detection/detection_003_reviewed.md
+
+use std::{fmt, str::FromStr};
+
+use gumdrop::Options;
+use serde::{Deserialize, Serialize};
+use simple_error::*;
+use tendermint::AppHash;
+
+use crate::{
+    helpers::*,
+    light_block::{LightBlock, TmLightBlock},
+    validator::default_validators,
+    Commit, Generator, Header, Validator,
+};
+
+/// The kind of attack on the light client.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AttackKind {
+    /// More than 2/3 of the validator set sign a second, conflicting block in the same round.
+    Equivocation,
+    /// Less than 2/3 but more than 1/3 of the trusted validator set sign a block with
+    /// fabricated state, including a validator set consisting only of the faulty validators.
+    Lunatic,
+    /// More than 2/3 of the validator set sign a conflicting block in a different round,
+    /// "forgetting" about the lock they held in the round the honest block was committed in.
+    Amnesia,
+}
+
+impl fmt::Display for AttackKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AttackKind::Equivocation => write!(f, "equivocation"),
+            AttackKind::Lunatic => write!(f, "lunatic"),
+            AttackKind::Amnesia => write!(f, "amnesia"),
+        }
+    }
+}
+
+impl FromStr for AttackKind {
+    type Err = SimpleError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "equivocation" => Ok(AttackKind::Equivocation),
+            "lunatic" => Ok(AttackKind::Lunatic),
+            "amnesia" => Ok(AttackKind::Amnesia),
+            _ => bail!(
+                "unknown attack kind '{}' (expected equivocation, lunatic or amnesia)",
+                s
+            ),
+        }
+    }
+}
+
+/// A light client attack fixture: the trusted anchor, together with the honest and the
+/// conflicting light blocks at the target height.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttackFixture {
+    /// The kind of attack this fixture represents
+    pub kind: AttackKind,
+    /// Light block trusted by the light client, shared by the honest and the conflicting chain
+    pub trusted: TmLightBlock,
+    /// Light block of the honest chain at the target height
+    pub honest: TmLightBlock,
+    /// Light block at the target height conflicting with the honest one
+    pub conflicting: TmLightBlock,
+}
+
+/// Generator of [`AttackFixture`]s.
+#[derive(Debug, Options, Serialize, Deserialize, Clone)]
+pub struct LightClientAttack {
+    #[options(help = "attack kind (required): equivocation, lunatic or amnesia")]
+    pub kind: Option<AttackKind>,
+    #[options(
+        help = "validators (default: 4 validators with equal voting power), encoded as array of 'validator' parameters",
+        parse(try_from_str = "parse_as::<Vec<Validator>>")
+    )]
+    pub validators: Option<Vec<Validator>>,
+    #[options(
+        help = "validators signing the conflicting block (default: the smallest prefix of validators that suffices for the attack), encoded as array of 'validator' parameters",
+        parse(try_from_str = "parse_as::<Vec<Validator>>")
+    )]
+    pub faulty: Option<Vec<Validator>>,
+    #[options(help = "chain id (default: test-chain)")]
+    pub chain_id: Option<String>,
+    #[options(help = "height of the trusted block (default: 1)")]
+    pub trusted_height: Option<u64>,
+    #[options(
+        help = "height of the conflicting block (default: trusted height + 2 for lunatic attacks, trusted height + 1 otherwise)"
+    )]
+    pub target_height: Option<u64>,
+}
+
+impl LightClientAttack {
+    pub fn new(kind: AttackKind) -> Self {
+        Self {
+            kind: Some(kind),
+            validators: None,
+            faulty: None,
+            chain_id: None,
+            trusted_height: None,
+            target_height: None,
+        }
+    }
+    set_option!(kind, AttackKind);
+    set_option!(validators, &[Validator], Some(validators.to_vec()));
+    set_option!(faulty, &[Validator], Some(faulty.to_vec()));
+    set_option!(chain_id, &str, Some(chain_id.to_string()));
+    set_option!(trusted_height, u64);
+    set_option!(target_height, u64);
+}
+
+impl FromStr for LightClientAttack {
+    type Err = SimpleError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let attack = match parse_as::<LightClientAttack>(s) {
+            Ok(input) => input,
+            Err(_) => LightClientAttack::new(s.parse()?),
+        };
+        Ok(attack)
+    }
+}
+
+impl Generator<AttackFixture> for LightClientAttack {
+    fn merge_with_default(self, default: Self) -> Self {
+        Self {
+            kind: self.kind.or(default.kind),
+            validators: self.validators.or(default.validators),
+            faulty: self.faulty.or(default.faulty),
+            chain_id: self.chain_id.or(default.chain_id),
+            trusted_height: self.trusted_height.or(default.trusted_height),
+            target_height: self.target_height.or(default.target_height),
+        }
+    }
+
+    fn generate(&self) -> Result<AttackFixture, SimpleError> {
+        let kind = match self.kind {
+            None => bail!("attack kind is missing"),
+            Some(kind) => kind,
+        };
+        let validators = self.validators.clone().unwrap_or_else(default_validators);
+        if validators.is_empty() {
+            bail!("validator set of the attacked chain is empty")
+        }
+        let chain_id = self.chain_id.as_deref().unwrap_or("test-chain");
+        let trusted_height = self.trusted_height.unwrap_or(1);
+        // A lunatic block right after the trusted one would be rejected by sequential
+        // verification, as its validator set does not match the trusted next validators.
+        let min_target_height = match kind {
+            AttackKind::Lunatic => trusted_height + 2,
+            AttackKind::Equivocation | AttackKind::Amnesia => trusted_height + 1,
+        };
+        let target_height = self.target_height.unwrap_or(min_target_height);
+        if target_height < min_target_height {
+            bail!(
+                "{} attack cannot target height {} from trusted height {}",
+                kind,
+                target_height,
+                trusted_height
+            )
+        }
+
+        let faulty = match &self.faulty {
+            Some(faulty) => faulty.clone(),
+            None => {
+                // Lunatic attacks only need to pass the skipping verification
+                // trust threshold (1/3), the others need a full commit quorum (2/3).
+                let (num, den) = match kind {
+                    AttackKind::Lunatic => (1, 3),
+                    AttackKind::Equivocation | AttackKind::Amnesia => (2, 3),
+                };
+                smallest_quorum(&validators, num, den)
+            },
+        };
+
+        let trusted_header = Header::new(&validators)
+            .next_validators(&validators)
+            .chain_id(chain_id)
+            .height(trusted_height)
+            .time(get_time(trusted_height)?);
+        let trusted = LightBlock::new_default_with_header(trusted_header.clone());
+
+        // The header preceding the target height on the honest chain
+        let mut parent = trusted_header;
+        for _ in trusted_height + 1..target_height {
+            parent = parent.next();
+        }
+        let honest = LightBlock::new_default_with_header(parent.next());
+
+        let mut conflicting_header = parent.next().app_hash(conflicting_app_hash()?);
+        if kind == AttackKind::Lunatic {
+            conflicting_header = conflicting_header
+                .validators(&faulty)
+                .next_validators(&faulty);
+        }
+        let round = match kind {
+            AttackKind::Amnesia => 2,
+            AttackKind::Equivocation | AttackKind::Lunatic => 1,
+        };
+        let mut commit = Commit::new(conflicting_header.clone(), round);
+        if let Some(votes) = commit.votes.as_mut() {
+            votes.retain(|vote| faulty.iter().any(|v| Some(v) == vote.validator.as_ref()));
+        }
+        let conflicting = LightBlock::new(conflicting_header.clone(), commit)
+            .validators(conflicting_header.validators.as_ref().unwrap())
+            .next_validators(conflicting_header.validators.as_ref().unwrap())
+            .provider(&default_conflicting_peer_id());
+
+        Ok(AttackFixture {
+            kind,
+            trusted: trusted.generate()?,
+            honest: honest.generate()?,
+            conflicting: conflicting.generate()?,
+        })
+    }
+}
+
+/// The peer id of the node that provided the conflicting light block.
+pub fn default_conflicting_peer_id() -> String {
+    "DEADBEEFDEADBEEFDEADBEEFDEADBEEFDEADBEEF".to_string()
+}
+
+// Returns the smallest prefix of the validators whose voting power exceeds
+// the `num / den` fraction of the total voting power.
+fn smallest_quorum(validators: &[Validator], num: u64, den: u64) -> Vec<Validator> {
+    let total: u64 = validators.iter().map(|v| v.voting_power.unwrap_or(0)).sum();
+    let mut power = 0;
+    let mut quorum = vec![];
+    for validator in validators {
+        if power * den > total * num {
+            break;
+        }
+        power += validator.voting_power.unwrap_or(0);
+        quorum.push(validator.clone());
+    }
+    quorum
+}
+
+fn conflicting_app_hash() -> Result<AppHash, SimpleError> {
+    AppHash::try_from(vec![0xBA; 32]).map_err(|e| SimpleError::new(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generate(kind: AttackKind) -> AttackFixture {
+        LightClientAttack::new(kind).generate().unwrap()
+    }
+
+    #[test]
+    fn test_attacks_share_trusted_anchor() {
+        for kind in [
+            AttackKind::Equivocation,
+            AttackKind::Lunatic,
+            AttackKind::Amnesia,
+        ] {
+            let fixture = generate(kind);
+            let honest = &fixture.honest.signed_header;
+            let conflicting = &fixture.conflicting.signed_header;
+
+            assert_eq!(fixture.trusted.signed_header.header.height.value(), 1);
+            assert_ne!(fixture.honest.provider, fixture.conflicting.provider);
+            assert_eq!(honest.header.height, conflicting.header.height);
+            assert_ne!(honest.header.hash(), conflicting.header.hash());
+            assert_eq!(
+                honest.header.last_block_id,
+                conflicting.header.last_block_id
+            );
+            assert_eq!(
+                fixture.trusted.signed_header.header.next_validators_hash,
+                honest.header.validators_hash
+            );
+        }
+    }
+
+    #[test]
+    fn test_equivocation() {
+        let fixture = generate(AttackKind::Equivocation);
+        let honest = &fixture.honest.signed_header;
+        let conflicting = &fixture.conflicting.signed_header;
+
+        assert_eq!(
+            honest.header.validators_hash,
+            conflicting.header.validators_hash
+        );
+        assert_eq!(honest.commit.round, conflicting.commit.round);
+        // 3 out of 4 validators sign the conflicting block
+        let signers = conflicting
+            .commit
+            .signatures
+            .iter()
+            .filter(|sig| sig.is_commit())
+            .count();
+        assert_eq!(signers, 3);
+    }
+
+    #[test]
+    fn test_lunatic() {
+        let fixture = LightClientAttack::new(AttackKind::Lunatic)
+            .target_height(5)
+            .generate()
+            .unwrap();
+        let honest = &fixture.honest.signed_header;
+        let conflicting = &fixture.conflicting.signed_header;
+
+        assert_eq!(conflicting.header.height.value(), 5);
+        assert_ne!(
+            honest.header.validators_hash,
+            conflicting.header.validators_hash
+        );
+        // 2 out of 4 validators form the fabricated validator set
+        assert_eq!(fixture.conflicting.validators.validators().len(), 2);
+        assert_eq!(
+            conflicting.header.validators_hash,
+            fixture.conflicting.validators.hash()
+        );
+    }
+
+    #[test]
+    fn test_amnesia() {
+        let fixture = generate(AttackKind::Amnesia);
+        let honest = &fixture.honest.signed_header;
+        let conflicting = &fixture.conflicting.signed_header;
+
+        assert_eq!(
+            honest.header.validators_hash,
+            conflicting.header.validators_hash
+        );
+        assert_ne!(honest.commit.round, conflicting.commit.round);
+    }
+
+    #[test]
+    fn test_invalid_heights() {
+        assert!(LightClientAttack::new(AttackKind::Lunatic)
+            .trusted_height(3)
+            .target_height(3)
+            .generate()
+            .is_err());
+        assert!(LightClientAttack::new(AttackKind::Lunatic)
+            .target_height(2)
+            .generate()
+            .is_err());
+    }
+
+    #[test]
+    fn test_fixture_roundtrip() {
+        let attack: LightClientAttack = "lunatic".parse().unwrap();
+        let json = attack.encode().unwrap();
+        let fixture: AttackFixture = serde_json::from_str(&json).unwrap();
+        assert_eq!(fixture, attack.generate().unwrap());
+    }
+}
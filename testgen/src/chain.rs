@@ -0,0 +1,337 @@
+//! Generation of complete chains, with validators joining, leaving,
+//! and changing their voting power along the way.
+
+use std::{collections::BTreeMap, convert::TryFrom, str::FromStr};
+
+use gumdrop::Options;
+use serde::{Deserialize, Serialize};
+use simple_error::*;
+use tendermint::{block, chain::Info};
+
+use crate::time::BlockTimes;
+use crate::{
+    helpers::*, light_block::TmLightBlock, validator::default_validators, Commit, Generator,
+    Header, LightBlock, LightChain, Validator,
+};
+
+/// A set of validator updates taking effect at a given height.
+///
+/// The updates follow the semantics of ABCI validator updates: a validator with zero
+/// voting power leaves the set, a validator with an unknown identifier joins it, and
+/// a validator already in the set gets its voting power changed.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ValidatorChange {
+    /// The first height at which the updated validator set signs blocks.
+    pub height: u64,
+    /// The validator updates.
+    pub updates: Vec<Validator>,
+}
+
+impl ValidatorChange {
+    pub fn new(height: u64) -> Self {
+        Self {
+            height,
+            updates: vec![],
+        }
+    }
+
+    /// Adds a validator with the given voting power to the set.
+    pub fn join(mut self, id: &str, voting_power: u64) -> Self {
+        self.updates
+            .push(Validator::new(id).voting_power(voting_power));
+        self
+    }
+
+    /// Removes a validator from the set.
+    pub fn leave(mut self, id: &str) -> Self {
+        self.updates.push(Validator::new(id).voting_power(0));
+        self
+    }
+
+    /// Changes the voting power of a validator in the set.
+    pub fn power(self, id: &str, voting_power: u64) -> Self {
+        self.join(id, voting_power)
+    }
+
+    fn apply(&self, validators: &[Validator]) -> Result<Vec<Validator>, SimpleError> {
+        let mut validators = validators.to_vec();
+        for update in &self.updates {
+            let position = validators.iter().position(|v| v == update);
+            match (position, update.voting_power.unwrap_or(0)) {
+                (Some(i), 0) => {
+                    validators.remove(i);
+                },
+                (None, 0) => bail!(
+                    "validator {:?} cannot leave at height {}: not in the validator set",
+                    update.id,
+                    self.height
+                ),
+                (Some(i), _) => validators[i] = update.clone(),
+                (None, _) => validators.push(update.clone()),
+            }
+        }
+        if validators.is_empty() {
+            bail!("validator set is empty at height {}", self.height)
+        }
+        Ok(validators)
+    }
+}
+
+/// A chain of light blocks, whose validator set evolves over time.
+#[derive(Debug, Options, Serialize, Deserialize, Clone)]
+pub struct Chain {
+    #[options(help = "number of blocks in the chain (required)")]
+    pub length: Option<u64>,
+    #[options(
+        help = "validators at height 1 (default: 4 validators with equal voting power), encoded as array of 'validator' parameters",
+        parse(try_from_str = "parse_as::<Vec<Validator>>")
+    )]
+    pub validators: Option<Vec<Validator>>,
+    #[options(
+        help = "validator changes (default: none), encoded as array of {\"height\": HEIGHT, \"updates\": [VALIDATOR, ..]} objects",
+        parse(try_from_str = "parse_as::<Vec<ValidatorChange>>")
+    )]
+    pub changes: Option<Vec<ValidatorChange>>,
+    #[options(
+        help = "block times (default: one second apart, starting at 1 second since UNIX EPOCH), encoded as 'block times' JSON object",
+        parse(try_from_str = "parse_as::<BlockTimes>")
+    )]
+    pub times: Option<BlockTimes>,
+    #[options(help = "chain id (default: test-chain)")]
+    pub chain_id: Option<String>,
+}
+
+impl Chain {
+    pub fn new(length: u64) -> Self {
+        Self {
+            length: Some(length),
+            validators: None,
+            changes: None,
+            times: None,
+            chain_id: None,
+        }
+    }
+    set_option!(length, u64);
+    set_option!(validators, &[Validator], Some(validators.to_vec()));
+    set_option!(changes, &[ValidatorChange], Some(changes.to_vec()));
+    set_option!(times, BlockTimes);
+    set_option!(chain_id, &str, Some(chain_id.to_string()));
+
+    /// Adds a validator change to the chain
+    pub fn change(mut self, change: ValidatorChange) -> Self {
+        self.changes.get_or_insert_with(Vec::new).push(change);
+        self
+    }
+
+    /// Computes the validator sets at each height of the chain, and one past its end.
+    pub fn validator_sets(&self) -> Result<Vec<Vec<Validator>>, SimpleError> {
+        let length = match self.length {
+            None => bail!("chain length is missing"),
+            Some(0) => bail!("chain length must be positive"),
+            Some(length) => length,
+        };
+        let mut changes = BTreeMap::new();
+        for change in self.changes.iter().flatten() {
+            if change.height < 2 {
+                bail!(
+                    "validator change at height {} must be at height 2 or above",
+                    change.height
+                )
+            }
+            if change.height > length + 1 {
+                bail!(
+                    "validator change at height {} is past the end of the chain at height {}",
+                    change.height,
+                    length + 1
+                )
+            }
+            if changes.insert(change.height, change).is_some() {
+                bail!("duplicate validator change at height {}", change.height)
+            }
+        }
+
+        let initial = self.validators.clone().unwrap_or_else(default_validators);
+        if initial.is_empty() {
+            bail!("validator set is empty at height 1")
+        }
+        let mut sets = vec![initial];
+        for height in 2..=length + 1 {
+            let last = sets.last().unwrap();
+            let next = match changes.get(&height) {
+                Some(change) => change.apply(last)?,
+                None => last.clone(),
+            };
+            sets.push(next);
+        }
+        Ok(sets)
+    }
+
+    /// Produces the companion light blocks for each height of the chain.
+    pub fn light_chain(&self) -> Result<LightChain, SimpleError> {
+        let sets = self.validator_sets()?;
+        let chain_id = self.chain_id.as_deref().unwrap_or("test-chain");
+        let times = self.times.clone().unwrap_or_default();
+
+        let mut header = Header::new(&sets[0])
+            .next_validators(&sets[1])
+            .chain_id(chain_id)
+            .height(1)
+            .time(times.time_at(1)?);
+        let mut light_blocks = vec![];
+        for (i, window) in sets.windows(2).enumerate() {
+            if i > 0 {
+                let height = i as u64 + 1;
+                header = header
+                    .next()
+                    .next_validators(&window[1])
+                    .time(times.time_at(height)?);
+            }
+            let commit = Commit::new(header.clone(), 1);
+            let light_block = LightBlock::new(header.clone(), commit)
+                .validators(&window[0])
+                .next_validators(&window[1]);
+            light_blocks.push(light_block);
+        }
+
+        let last_block_id = block::Id {
+            hash: header.generate()?.hash(),
+            part_set_header: Default::default(),
+        };
+        let info = Info {
+            id: try_with!(chain_id.parse(), "invalid chain id"),
+            height: try_with!(
+                block::Height::try_from(light_blocks.len() as u64),
+                "invalid chain height"
+            ),
+            last_block_id: Some(last_block_id),
+            time: None,
+        };
+        Ok(LightChain::new(info, light_blocks))
+    }
+}
+
+impl FromStr for Chain {
+    type Err = SimpleError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let chain = match parse_as::<Chain>(s) {
+            Ok(input) => input,
+            Err(_) => Chain::new(try_with!(u64::from_str(s), "failed to parse chain length")),
+        };
+        Ok(chain)
+    }
+}
+
+impl Generator<Vec<TmLightBlock>> for Chain {
+    fn merge_with_default(self, default: Self) -> Self {
+        Self {
+            length: self.length.or(default.length),
+            validators: self.validators.or(default.validators),
+            changes: self.changes.or(default.changes),
+            times: self.times.or(default.times),
+            chain_id: self.chain_id.or(default.chain_id),
+        }
+    }
+
+    fn generate(&self) -> Result<Vec<TmLightBlock>, SimpleError> {
+        self.light_chain()?
+            .light_blocks
+            .iter()
+            .map(|lb| lb.generate())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tendermint::block::CommitSig;
+
+    use super::*;
+
+    #[test]
+    fn test_chain_without_changes() {
+        let blocks = Chain::new(3).generate().unwrap();
+        assert_eq!(blocks.len(), 3);
+        for block in &blocks {
+            assert_eq!(block.validators, block.next_validators);
+            assert_eq!(block.validators.validators().len(), 4);
+        }
+    }
+
+    #[test]
+    fn test_chain_with_churn() {
+        let chain = Chain::new(5)
+            .change(ValidatorChange::new(2).join("e", 50))
+            .change(ValidatorChange::new(4).leave("a").power("b", 100));
+        let blocks = chain.generate().unwrap();
+
+        let sizes: Vec<_> = blocks
+            .iter()
+            .map(|b| b.validators.validators().len())
+            .collect();
+        assert_eq!(sizes, vec![4, 5, 5, 4, 4]);
+        assert_eq!(blocks[3].validators.total_voting_power().value(), 250);
+
+        for (i, block) in blocks.iter().enumerate() {
+            let header = &block.signed_header.header;
+            assert_eq!(header.height.value(), i as u64 + 1);
+            assert_eq!(header.validators_hash, block.validators.hash());
+            assert_eq!(header.next_validators_hash, block.next_validators.hash());
+            assert_eq!(
+                block.signed_header.commit.signatures.len(),
+                block.validators.validators().len()
+            );
+            if i > 0 {
+                let prev = &blocks[i - 1];
+                assert_eq!(prev.next_validators, block.validators);
+                assert_eq!(
+                    header.last_block_id.map(|id| id.hash),
+                    Some(prev.signed_header.header.hash())
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_chain_with_block_times() {
+        let times = BlockTimes::new(100, 10).stall(3);
+        let blocks = Chain::new(3).times(times).generate().unwrap();
+        let secs: Vec<i64> = blocks
+            .iter()
+            .map(|b| time::OffsetDateTime::from(b.signed_header.header.time).unix_timestamp())
+            .collect();
+        assert_eq!(secs, vec![100, 110, 110]);
+        // votes are cast at the time of the block they commit
+        for block in &blocks {
+            for sig in &block.signed_header.commit.signatures {
+                if let CommitSig::BlockIdFlagCommit { timestamp, .. } = sig {
+                    assert_eq!(*timestamp, block.signed_header.header.time);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_changes() {
+        assert!(Chain::new(3)
+            .change(ValidatorChange::new(2).leave("z"))
+            .generate()
+            .is_err());
+        assert!(Chain::new(3)
+            .change(ValidatorChange::new(1).join("e", 10))
+            .generate()
+            .is_err());
+        let everyone_leaves = ["a", "b", "c", "d"]
+            .iter()
+            .fold(ValidatorChange::new(3), |change, id| change.leave(id));
+        assert!(Chain::new(3).change(everyone_leaves).generate().is_err());
+        // changes can be scheduled up to the next validators of the last block
+        assert!(Chain::new(3)
+            .change(ValidatorChange::new(4).join("e", 10))
+            .generate()
+            .is_ok());
+        assert!(Chain::new(3)
+            .change(ValidatorChange::new(5).join("e", 10))
+            .generate()
+            .is_err());
+    }
+}
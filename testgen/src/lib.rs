@@ -9,6 +9,9 @@
 pub mod helpers;
 
 /// Helper types for generating Tendermint datastructures
+pub mod abci;
+pub mod attack;
+pub mod chain;
 pub mod commit;
 pub mod consensus;
 pub mod generator;
@@ -20,6 +23,9 @@ pub mod validator;
 pub mod validator_set;
 pub mod vote;
 
+pub use abci::{AbciBlock, AbciExchange, AbciSession, AbciVector};
+pub use attack::{AttackFixture, AttackKind, LightClientAttack};
+pub use chain::{Chain, ValidatorChange};
 pub use commit::Commit;
 pub use generator::Generator;
 pub use header::Header;
@@ -29,7 +35,7 @@ pub use validator::Validator;
 pub use validator_set::ValidatorSet;
 pub use vote::Vote;
 
-pub use crate::time::Time;
+pub use crate::time::{BlockTimes, Time};
 
 /// Helpers for organizing and running the tests
 pub mod apalache;
@@ -1,5 +1,5 @@
 use gumdrop::Options;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use simple_error::*;
 
 use crate::{helpers::*, Generator};
@@ -44,6 +44,114 @@ impl Generator<tendermint::Time> for Time {
     }
 }
 
+/// A shift of the timestamp of the block at the given height, in seconds.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct TimeShift {
+    pub height: u64,
+    pub secs: i64,
+}
+
+/// The pattern of block timestamps along a chain.
+///
+/// Block times start at `start` seconds since UNIX EPOCH and advance by `interval` seconds
+/// per block; with a `drift`, the interval grows (or shrinks, down to zero) by that many
+/// seconds at each block. Individual blocks can then be shifted in time, e.g. to produce
+/// blocks with the same timestamp as their predecessor, blocks going back in time,
+/// or blocks from the future.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct BlockTimes {
+    /// Time of the block at height 1, in seconds since UNIX EPOCH (default: 1)
+    pub start: Option<u64>,
+    /// Initial number of seconds between blocks (default: 1)
+    pub interval: Option<u64>,
+    /// Change of the interval between blocks at each height, in seconds (default: 0)
+    pub drift: Option<i64>,
+    /// Shifts of individual block times (default: none)
+    pub shifts: Option<Vec<TimeShift>>,
+}
+
+impl BlockTimes {
+    pub fn new(start: u64, interval: u64) -> Self {
+        Self {
+            start: Some(start),
+            interval: Some(interval),
+            drift: None,
+            shifts: None,
+        }
+    }
+    set_option!(start, u64);
+    set_option!(interval, u64);
+    set_option!(drift, i64);
+
+    /// Shifts the time of the block at the given height by the given number of seconds.
+    pub fn shift(mut self, height: u64, secs: i64) -> Self {
+        self.shifts
+            .get_or_insert_with(Vec::new)
+            .push(TimeShift { height, secs });
+        self
+    }
+
+    /// Gives the block at the given height the same time as its predecessor,
+    /// the edge case of BFT time that must be rejected as non-monotonic.
+    pub fn stall(self, height: u64) -> Self {
+        // An interval which overflows fails when the block times are computed.
+        let interval = self.interval_at(height).unwrap_or(i64::MAX);
+        self.shift(height, -interval)
+    }
+
+    /// Number of seconds between the block at the given height and its predecessor,
+    /// before shifts are applied.
+    fn interval_at(&self, height: u64) -> Result<i64, SimpleError> {
+        let interval = i128::from(self.interval.unwrap_or(1));
+        let drift = i128::from(self.drift.unwrap_or(0));
+        drift
+            .checked_mul(i128::from(height) - 2)
+            .and_then(|change| change.checked_add(interval))
+            .and_then(|interval| i64::try_from(interval.max(0)).ok())
+            .ok_or_else(|| SimpleError::new(format!("block interval at height {height} overflows")))
+    }
+
+    /// Computes the time of the block at the given height, in seconds since UNIX EPOCH.
+    pub fn secs_at(&self, height: u64) -> Result<u64, SimpleError> {
+        if height == 0 {
+            bail!("block times start at height 1")
+        }
+        let overflow = || SimpleError::new(format!("block time at height {height} overflows"));
+        let interval = i128::from(self.interval.unwrap_or(1));
+        let drift = i128::from(self.drift.unwrap_or(0));
+        // The intervals form an arithmetic progression, until they shrink to zero.
+        let mut blocks = i128::from(height - 1);
+        if drift < 0 {
+            blocks = blocks.min(interval / -drift + 1);
+        }
+        let drifts = blocks
+            .checked_mul(blocks - 1)
+            .and_then(|pairs| (pairs / 2).checked_mul(drift))
+            .ok_or_else(overflow)?;
+        let mut secs = blocks
+            .checked_mul(interval)
+            .and_then(|secs| secs.checked_add(drifts))
+            .and_then(|secs| secs.checked_add(i128::from(self.start.unwrap_or(1))))
+            .ok_or_else(overflow)?;
+        for shift in self.shifts.iter().flatten() {
+            if shift.height == height {
+                secs = secs
+                    .checked_add(i128::from(shift.secs))
+                    .ok_or_else(overflow)?;
+            }
+        }
+        if secs < 0 {
+            bail!("block time at height {} is before UNIX EPOCH", height)
+        }
+        u64::try_from(secs).map_err(|_| overflow())
+    }
+
+    /// Computes the time of the block at the given height.
+    pub fn time_at(&self, height: u64) -> Result<tendermint::Time, SimpleError> {
+        get_time(self.secs_at(height)?)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -53,4 +161,31 @@ mod tests {
         let time = Time::new(0);
         assert_eq!(time.generate().unwrap(), tendermint::Time::unix_epoch());
     }
+
+    #[test]
+    fn test_block_times() {
+        let times = BlockTimes::default();
+        let secs: Vec<u64> = (1..=4).map(|h| times.secs_at(h).unwrap()).collect();
+        assert_eq!(secs, vec![1, 2, 3, 4]);
+
+        let times = BlockTimes::new(100, 10).drift(5);
+        let secs: Vec<u64> = (1..=4).map(|h| times.secs_at(h).unwrap()).collect();
+        assert_eq!(secs, vec![100, 110, 125, 145]);
+
+        let times = BlockTimes::new(100, 10).drift(-5);
+        let secs: Vec<u64> = (1..=5).map(|h| times.secs_at(h).unwrap()).collect();
+        assert_eq!(secs, vec![100, 110, 115, 115, 115]);
+
+        let times = BlockTimes::new(100, 10).stall(3).shift(4, 1000);
+        let secs: Vec<u64> = (1..=4).map(|h| times.secs_at(h).unwrap()).collect();
+        assert_eq!(secs, vec![100, 110, 110, 1130]);
+
+        assert!(BlockTimes::new(1, 1).shift(2, -10).time_at(2).is_err());
+
+        let times = BlockTimes::new(1, u64::MAX).drift(i64::MAX);
+        assert!(times.secs_at(u64::MAX).is_err());
+        assert!(times.stall(u64::MAX).secs_at(u64::MAX).is_err());
+        let times = BlockTimes::new(0, 3).drift(-1);
+        assert_eq!(times.secs_at(u64::MAX).unwrap(), 6);
+    }
 }
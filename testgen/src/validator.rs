@@ -119,6 +119,14 @@ impl Generator<validator::Info> for Validator {
     }
 }
 
+/// The validators used when none are specified: four validators with equal voting power.
+pub fn default_validators() -> Vec<Validator> {
+    ["a", "b", "c", "d"]
+        .iter()
+        .map(|id| Validator::new(id).voting_power(50))
+        .collect()
+}
+
 /// A helper function to generate multiple validators at once.
 pub fn generate_validators(vals: &[Validator]) -> Result<Vec<validator::Info>, SimpleError> {
     sort_validators(vals)
@@ -0,0 +1,412 @@
+//! The checks run against each node.
+
+use std::{
+    future::Future,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use futures::StreamExt;
+use tendermint::node;
+use tendermint_light_client::{
+    builder::LightClientBuilder,
+    components::io::{AtHeight, Io, ProdIo},
+    store::{memory::MemoryStore, LightStore},
+    verifier::{
+        options::Options as LightClientOptions,
+        types::{Height, Status, TrustThreshold},
+    },
+};
+use tendermint_rpc::{
+    client::CompatMode,
+    event::EventData,
+    query::{EventType, Query},
+    Client, HttpClient, Order, Paging, SubscriptionClient, Url, WebSocketClient,
+};
+use tokio::time;
+use tracing::{info, warn};
+
+use crate::matrix::{CheckResult, NodeReport, Outcome, Surface};
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// The name reported by the kvstore application of tendermint-abci.
+const KVSTORE_APP: &str = "kvstore-rs";
+
+/// Runs the checks against a node, and records their results.
+struct Runner {
+    report: NodeReport,
+    timeout: Duration,
+}
+
+impl Runner {
+    /// Run a check, returning its output if it passed.
+    async fn check<T, F>(&mut self, surface: Surface, name: &'static str, check: F) -> Option<T>
+    where
+        F: Future<Output = Result<T, BoxError>>,
+    {
+        let started = Instant::now();
+        let result = match time::timeout(self.timeout, check).await {
+            Ok(result) => result,
+            Err(_) => Err(format!("timed out after {:?}", self.timeout).into()),
+        };
+        let (outcome, detail, output) = match result {
+            Ok(output) => (Outcome::Pass, None, Some(output)),
+            Err(e) => (Outcome::Fail, Some(describe(&*e)), None),
+        };
+        self.record(surface, name, outcome, detail, started.elapsed());
+        output
+    }
+
+    /// Record a check which could not run.
+    fn skip(&mut self, surface: Surface, name: &'static str, reason: &str) {
+        self.record(
+            surface,
+            name,
+            Outcome::Skip,
+            Some(reason.to_string()),
+            Duration::ZERO,
+        );
+    }
+
+    fn record(
+        &mut self,
+        surface: Surface,
+        name: &'static str,
+        outcome: Outcome,
+        detail: Option<String>,
+        duration: Duration,
+    ) {
+        let label = &self.report.label;
+        match &detail {
+            None => info!(
+                "[{label}] {}/{name}: {}",
+                surface.as_str(),
+                outcome.as_str()
+            ),
+            Some(detail) => warn!(
+                "[{label}] {}/{name}: {}: {detail}",
+                surface.as_str(),
+                outcome.as_str()
+            ),
+        }
+        self.report.checks.push(CheckResult {
+            surface,
+            name,
+            outcome,
+            detail,
+            duration_ms: duration.as_millis(),
+        });
+    }
+}
+
+/// Describe an error on a single line, with its causes but without the
+/// location and backtrace traced by the errors of tendermint-rs.
+fn describe(e: &(dyn std::error::Error + 'static)) -> String {
+    let message = e.to_string();
+    let message = message.split("\n\nLocation:").next().unwrap_or_default();
+    message
+        .split('\n')
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && *line != "Caused by:")
+        .map(|line| line.trim_start_matches(|c: char| c.is_ascii_digit() || c == ':'))
+        .map(str::trim)
+        .collect::<Vec<_>>()
+        .join(": ")
+}
+
+fn ensure(condition: bool, message: impl FnOnce() -> String) -> Result<(), BoxError> {
+    if condition {
+        Ok(())
+    } else {
+        Err(message().into())
+    }
+}
+
+/// Run all the checks against the node with the given label and RPC URL.
+///
+/// If `abci_app` is set, the node is expected to run the kvstore application
+/// of tendermint-abci as its proxy app, instead of its built-in one.
+pub async fn run(label: String, url: Url, abci_app: bool, timeout: Duration) -> NodeReport {
+    let mut runner = Runner {
+        report: NodeReport::new(label, url.to_string()),
+        timeout,
+    };
+
+    // The version of the node selects the compatibility mode of the clients.
+    let status = runner
+        .check(Surface::Rpc, "status", async {
+            let client = HttpClient::new(url.clone())?;
+            Ok(client.status().await?)
+        })
+        .await;
+    let Some(status) = status else {
+        return runner.report;
+    };
+    let version = status.node_info.version.clone();
+    runner.report.version = Some(version.to_string());
+    let client = runner
+        .check(Surface::Rpc, "compat_mode", async {
+            let compat_mode = CompatMode::from_version(version)?;
+            let client = HttpClient::builder(url.clone().try_into()?)
+                .compat_mode(compat_mode)
+                .build()?;
+            Ok((compat_mode, client))
+        })
+        .await;
+    let Some((compat_mode, client)) = client else {
+        return runner.report;
+    };
+    runner.report.compat_mode = Some(compat_mode.to_string());
+
+    rpc_checks(&mut runner, &client).await;
+    abci_checks(&mut runner, &client, abci_app).await;
+    websocket_checks(&mut runner, &url, compat_mode).await;
+    light_client_checks(&mut runner, client, status.node_info.id).await;
+    runner.report
+}
+
+async fn rpc_checks(runner: &mut Runner, client: &HttpClient) {
+    runner
+        .check(Surface::Rpc, "health", async { Ok(client.health().await?) })
+        .await;
+    runner
+        .check(Surface::Rpc, "net_info", async {
+            Ok(client.net_info().await?)
+        })
+        .await;
+    runner
+        .check(Surface::Rpc, "genesis", async {
+            Ok(client.genesis::<serde_json::Value>().await?)
+        })
+        .await;
+    let latest = runner
+        .check(Surface::Rpc, "latest_block", async {
+            Ok(client.latest_block().await?.block.header.height)
+        })
+        .await;
+    runner
+        .check(Surface::Rpc, "latest_commit", async {
+            Ok(client.latest_commit().await?)
+        })
+        .await;
+    runner
+        .check(Surface::Rpc, "latest_block_results", async {
+            Ok(client.latest_block_results().await?)
+        })
+        .await;
+    runner
+        .check(Surface::Rpc, "consensus_params", async {
+            Ok(client.latest_consensus_params().await?)
+        })
+        .await;
+    runner
+        .check(Surface::Rpc, "num_unconfirmed_txs", async {
+            Ok(client.num_unconfirmed_txs().await?)
+        })
+        .await;
+    runner
+        .check(Surface::Rpc, "block_search", async {
+            let query = Query::gte("block.height", 1_u64);
+            let response = client.block_search(query, 1, 10, Order::Ascending).await?;
+            ensure(!response.blocks.is_empty(), || {
+                "no blocks found".to_string()
+            })
+        })
+        .await;
+
+    let Some(latest) = latest else {
+        for name in ["blockchain", "block", "header", "validators", "commit"] {
+            runner.skip(Surface::Rpc, name, "latest_block failed");
+        }
+        return;
+    };
+    runner
+        .check(Surface::Rpc, "blockchain", async {
+            let response = client.blockchain(Height::from(1_u32), latest).await?;
+            ensure(!response.block_metas.is_empty(), || "no blocks".to_string())
+        })
+        .await;
+    runner
+        .check(Surface::Rpc, "block", async {
+            let response = client.block(latest).await?;
+            ensure(response.block.header.height == latest, || {
+                format!("got block {}", response.block.header.height)
+            })
+        })
+        .await;
+    runner
+        .check(Surface::Rpc, "header", async {
+            Ok(client.header(latest).await?)
+        })
+        .await;
+    runner
+        .check(Surface::Rpc, "validators", async {
+            let response = client.validators(latest, Paging::All).await?;
+            ensure(!response.validators.is_empty(), || {
+                "no validators".to_string()
+            })
+        })
+        .await;
+    runner
+        .check(Surface::Rpc, "commit", async {
+            Ok(client.commit(latest).await?)
+        })
+        .await;
+}
+
+async fn abci_checks(runner: &mut Runner, client: &HttpClient, abci_app: bool) {
+    let info = runner
+        .check(Surface::Abci, "info", async {
+            Ok(client.abci_info().await?)
+        })
+        .await;
+    if !abci_app {
+        runner.skip(
+            Surface::Abci,
+            "tendermint_abci_app",
+            "the node runs its built-in kvstore application",
+        );
+    } else if let Some(info) = info {
+        // The transaction and query below then go through the ABCI server of
+        // tendermint-abci.
+        runner
+            .check(Surface::Abci, "tendermint_abci_app", async {
+                ensure(info.data == KVSTORE_APP, || {
+                    format!("the node runs the {:?} application", info.data)
+                })
+            })
+            .await;
+    } else {
+        runner.skip(Surface::Abci, "tendermint_abci_app", "info failed");
+    }
+
+    // A unique key, so that the harness can be run again against the same
+    // nodes.
+    let nonce = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let key = format!("compat-test-{nonce}");
+    let value = "compat-test-value";
+    let committed = runner
+        .check(Surface::Abci, "broadcast_tx_commit", async {
+            let response = client.broadcast_tx_commit(format!("{key}={value}")).await?;
+            ensure(response.check_tx.code.is_ok(), || {
+                format!("CheckTx failed: {}", response.check_tx.log)
+            })?;
+            ensure(response.tx_result.code.is_ok(), || {
+                format!("transaction failed: {}", response.tx_result.log)
+            })?;
+            Ok(response)
+        })
+        .await;
+    let Some(committed) = committed else {
+        runner.skip(Surface::Abci, "query", "broadcast_tx_commit failed");
+        runner.skip(Surface::Rpc, "tx", "broadcast_tx_commit failed");
+        runner.skip(Surface::Rpc, "tx_search", "broadcast_tx_commit failed");
+        return;
+    };
+    runner
+        .check(Surface::Abci, "query", async {
+            let response = client.abci_query(None, key.as_bytes(), None, false).await?;
+            ensure(response.value == value.as_bytes(), || {
+                format!("got value {:?}", String::from_utf8_lossy(&response.value))
+            })
+        })
+        .await;
+    runner
+        .check(Surface::Rpc, "tx", async {
+            let response = client.tx(committed.hash, false).await?;
+            ensure(response.height == committed.height, || {
+                format!("got height {}", response.height)
+            })
+        })
+        .await;
+    runner
+        .check(Surface::Rpc, "tx_search", async {
+            let query = Query::eq("tx.height", committed.height.value());
+            let response = client
+                .tx_search(query, false, 1, 10, Order::Ascending)
+                .await?;
+            ensure(
+                response.txs.iter().any(|tx| tx.hash == committed.hash),
+                || "transaction not found".to_string(),
+            )
+        })
+        .await;
+}
+
+async fn websocket_checks(runner: &mut Runner, url: &Url, compat_mode: CompatMode) {
+    runner
+        .check(Surface::Websocket, "subscribe_new_block", async {
+            let host = url.host();
+            let port = url.port();
+            let ws_url = format!("ws://{host}:{port}/websocket");
+            let (client, driver) = WebSocketClient::builder(ws_url.as_str().try_into()?)
+                .compat_mode(compat_mode)
+                .build()
+                .await?;
+            let driver = tokio::spawn(driver.run());
+            let mut subscription = client.subscribe(EventType::NewBlock.into()).await?;
+            let event = subscription.next().await.ok_or("subscription ended")??;
+            drop(subscription);
+            client.close()?;
+            driver.await??;
+            ensure(
+                matches!(
+                    event.data,
+                    EventData::NewBlock { block: Some(_), .. }
+                        | EventData::LegacyNewBlock { block: Some(_), .. }
+                ),
+                || format!("unexpected event: {:?}", event.data),
+            )
+        })
+        .await;
+}
+
+async fn light_client_checks(runner: &mut Runner, client: HttpClient, peer_id: node::Id) {
+    // The light client performs blocking requests.
+    let verify = |backward: bool| {
+        let client = client.clone();
+        async move { tokio::task::spawn_blocking(move || verify(client, peer_id, backward)).await? }
+    };
+    runner
+        .check(Surface::LightClient, "verify_forward", verify(false))
+        .await;
+    runner
+        .check(Surface::LightClient, "verify_backward", verify(true))
+        .await;
+}
+
+/// Verify the latest block from a trusted block in the past, or a block in
+/// the past from the latest trusted block.
+fn verify(client: HttpClient, peer_id: node::Id, backward: bool) -> Result<(), BoxError> {
+    let timeout = Some(Duration::from_secs(5));
+    let io = ProdIo::new(peer_id, client.clone(), timeout);
+    let latest = io.fetch_light_block(AtHeight::Highest)?;
+    let (trusted, target) = if backward {
+        let target = Height::try_from((latest.height().value() / 2).max(1))?;
+        (latest, target)
+    } else {
+        let trusted = (latest.height().value() / 2).max(1);
+        let trusted = io.fetch_light_block(AtHeight::At(Height::try_from(trusted)?))?;
+        (trusted, latest.height())
+    };
+    let mut light_store = Box::new(MemoryStore::new());
+    light_store.insert(trusted, Status::Trusted);
+    let options = LightClientOptions {
+        trust_threshold: TrustThreshold::ONE_THIRD,
+        trusting_period: Duration::from_secs(60 * 60),
+        clock_drift: Duration::from_secs(5 * 60),
+        initial_height: None,
+    };
+    let mut instance = LightClientBuilder::prod(peer_id, client, light_store, options, timeout)
+        .trust_from_store()?
+        .build();
+    let verified = instance
+        .light_client
+        .verify_to_target(target, &mut instance.state)?;
+    ensure(verified.height() == target, || {
+        format!("verified block {}", verified.height())
+    })
+}
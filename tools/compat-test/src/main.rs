@@ -0,0 +1,136 @@
+//! Compatibility test harness, running the RPC, light client and ABCI surface
+//! of tendermint-rs against nodes of several CometBFT versions.
+//!
+//! Each node runs a kvstore application, e.g. in the Docker containers started
+//! by:
+//!
+//!     cargo make
+//!
+//! which runs the checks against each of the supported versions, and writes
+//! the resulting compatibility matrix to `compat-matrix.json`. The harness
+//! exits with an error if any check fails against any node, so that the
+//! supported versions are enforced by CI.
+//!
+//! The nodes which speak the ABCI protocol of tendermint-abci (v0.38) run its
+//! kvstore application as their proxy app, so that the ABCI checks go through
+//! the ABCI server of tendermint-abci. The others run their built-in kvstore.
+//!
+//! It can also be run against nodes which are already running, with:
+//!
+//!     cargo run -- --node v0.34=http://127.0.0.1:26657 --node v0.38=http://127.0.0.1:26677 --abci-app v0.38
+
+mod checks;
+mod matrix;
+
+use std::{fs, path::PathBuf, str::FromStr, time::Duration};
+
+use clap::Parser;
+use tendermint_rpc::Url;
+use tracing::{error, info, level_filters::LevelFilter};
+
+use crate::matrix::Matrix;
+
+/// A node to check, given as `<label>=<RPC URL>`.
+#[derive(Clone, Debug)]
+struct Node {
+    label: String,
+    url: Url,
+}
+
+impl FromStr for Node {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (label, url) = s
+            .split_once('=')
+            .ok_or_else(|| format!("expected <label>=<url>, got {s}"))?;
+        let url = url.parse().map_err(|e| format!("invalid URL {url}: {e}"))?;
+        Ok(Node {
+            label: label.to_string(),
+            url,
+        })
+    }
+}
+
+#[derive(Debug, Parser)]
+/// A harness checking the compatibility of tendermint-rs with nodes of several
+/// CometBFT versions, each running a kvstore application.
+struct Opt {
+    /// A node to check, as `<label>=<RPC URL>`, e.g. `v0.37=http://127.0.0.1:26667`.
+    #[arg(short, long = "node", required = true)]
+    nodes: Vec<Node>,
+
+    /// The label of a node whose proxy app is the kvstore application of
+    /// tendermint-abci (`kvstore-rs`), rather than its built-in one.
+    #[arg(long = "abci-app")]
+    abci_apps: Vec<String>,
+
+    /// Where to write the compatibility matrix as JSON, instead of the
+    /// standard output.
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Where to write the compatibility matrix as a Markdown table.
+    #[arg(long)]
+    markdown: Option<PathBuf>,
+
+    /// The timeout of each check, in seconds.
+    #[arg(long, default_value = "30")]
+    timeout: u64,
+
+    #[arg(short, long)]
+    verbose: bool,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let opt = Opt::parse();
+    tracing_subscriber::fmt()
+        .with_max_level(if opt.verbose {
+            LevelFilter::DEBUG
+        } else {
+            LevelFilter::INFO
+        })
+        .with_writer(std::io::stderr)
+        .init();
+
+    if let Some(label) = opt
+        .abci_apps
+        .iter()
+        .find(|label| !opt.nodes.iter().any(|node| &node.label == *label))
+    {
+        return Err(format!("no node labelled {label}").into());
+    }
+
+    let mut matrix = Matrix { nodes: Vec::new() };
+    for node in opt.nodes {
+        info!("Checking node {} at {}", node.label, node.url);
+        let abci_app = opt.abci_apps.contains(&node.label);
+        let timeout = Duration::from_secs(opt.timeout);
+        let report = checks::run(node.label, node.url, abci_app, timeout).await;
+        matrix.nodes.push(report);
+    }
+
+    let json = serde_json::to_string_pretty(&matrix)?;
+    match &opt.output {
+        Some(path) => fs::write(path, json)?,
+        None => println!("{json}"),
+    }
+    if let Some(path) = &opt.markdown {
+        fs::write(path, matrix.to_markdown())?;
+    }
+
+    for node in &matrix.nodes {
+        let version = node.version.as_deref().unwrap_or("unknown version");
+        if node.is_compatible() {
+            info!("{} ({version}): compatible", node.label);
+        } else {
+            error!("{} ({version}): incompatible", node.label);
+        }
+    }
+    if matrix.is_compatible() {
+        Ok(())
+    } else {
+        Err("some checks failed".into())
+    }
+}
@@ -0,0 +1,139 @@
+//! The compatibility matrix produced by the harness.
+
+use serde::Serialize;
+
+/// The part of tendermint-rs exercised by a check.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Surface {
+    /// Requests to the RPC endpoints, over HTTP.
+    Rpc,
+    /// Event subscriptions, over a WebSocket connection.
+    Websocket,
+    /// Verification of light blocks by the light client.
+    LightClient,
+    /// Requests served by the ABCI application of the node.
+    Abci,
+}
+
+impl Surface {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Surface::Rpc => "rpc",
+            Surface::Websocket => "websocket",
+            Surface::LightClient => "light-client",
+            Surface::Abci => "abci",
+        }
+    }
+}
+
+/// The outcome of a check.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome {
+    Pass,
+    Fail,
+    /// The check could not run, because a check it depends on failed.
+    Skip,
+}
+
+impl Outcome {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Outcome::Pass => "pass",
+            Outcome::Fail => "fail",
+            Outcome::Skip => "skip",
+        }
+    }
+}
+
+/// The result of a check against a node.
+#[derive(Clone, Debug, Serialize)]
+pub struct CheckResult {
+    pub surface: Surface,
+    pub name: &'static str,
+    pub outcome: Outcome,
+    /// The reason of a failure or of a skip.
+    pub detail: Option<String>,
+    pub duration_ms: u128,
+}
+
+/// The results of the checks against a node.
+#[derive(Clone, Debug, Serialize)]
+pub struct NodeReport {
+    /// The label of the node given on the command line, e.g. `v0.37`.
+    pub label: String,
+    pub rpc_url: String,
+    /// The version reported by the node, if it responded.
+    pub version: Option<String>,
+    /// The compatibility mode selected for the reported version.
+    pub compat_mode: Option<String>,
+    pub checks: Vec<CheckResult>,
+}
+
+impl NodeReport {
+    pub fn new(label: String, rpc_url: String) -> Self {
+        Self {
+            label,
+            rpc_url,
+            version: None,
+            compat_mode: None,
+            checks: Vec::new(),
+        }
+    }
+
+    /// Whether all the checks passed.
+    pub fn is_compatible(&self) -> bool {
+        self.checks
+            .iter()
+            .all(|check| check.outcome == Outcome::Pass)
+    }
+
+    fn outcome(&self, surface: Surface, name: &str) -> Option<Outcome> {
+        self.checks
+            .iter()
+            .find(|check| check.surface == surface && check.name == name)
+            .map(|check| check.outcome)
+    }
+}
+
+/// The results of the checks against all the nodes.
+#[derive(Clone, Debug, Serialize)]
+pub struct Matrix {
+    pub nodes: Vec<NodeReport>,
+}
+
+impl Matrix {
+    /// Whether all the checks passed against all the nodes.
+    pub fn is_compatible(&self) -> bool {
+        self.nodes.iter().all(NodeReport::is_compatible)
+    }
+
+    /// Render the matrix as a Markdown table, with a row per check and a
+    /// column per node.
+    pub fn to_markdown(&self) -> String {
+        let mut checks: Vec<(Surface, &'static str)> = Vec::new();
+        for check in self.nodes.iter().flat_map(|node| &node.checks) {
+            if !checks.contains(&(check.surface, check.name)) {
+                checks.push((check.surface, check.name));
+            }
+        }
+
+        let mut table = String::from("| check |");
+        for node in &self.nodes {
+            let version = node.version.as_deref().unwrap_or("unknown");
+            table.push_str(&format!(" {} ({version}) |", node.label));
+        }
+        table.push_str("\n|---|");
+        table.push_str(&"---|".repeat(self.nodes.len()));
+        for (surface, name) in checks {
+            table.push_str(&format!("\n| {}/{name} |", surface.as_str()));
+            for node in &self.nodes {
+                let outcome = node.outcome(surface, name).map_or("-", Outcome::as_str);
+                table.push_str(&format!(" {outcome} |"));
+            }
+        }
+        table.push('\n');
+        table
+    }
+}
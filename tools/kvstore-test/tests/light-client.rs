@@ -61,6 +61,7 @@ fn make_primary() -> Instance {
         trust_threshold: TrustThreshold::new(1, 3).unwrap(),
         trusting_period: Duration::from_secs(60 * 60), // 60 minutes
         clock_drift: Duration::from_secs(5 * 60),      // 5 minutes
+        initial_height: None,
     };
 
     make_instance(primary, options, node_address)